@@ -1,6 +1,7 @@
 use std::sync::Arc;
 use ultimo::middleware::BoxedMiddleware;
 use ultimo::prelude::*;
+use ultimo::response::Response;
 
 #[derive(Deserialize, Validate)]
 struct CreateUser {
@@ -25,6 +26,7 @@ async fn main() -> Result<()> {
     let mut app = Ultimo::new();
 
     // Global middleware
+    app.use_middleware(middleware::builtin::request_id());
     app.use_middleware(middleware::builtin::logger());
     app.use_middleware(
         middleware::builtin::Cors::new()
@@ -84,8 +86,7 @@ a{color:#4f46e5}code{background:#f4f4f5;padding:0.15rem 0.4rem;border-radius:0.2
 
     // POST with validation
     app.post("/users", |ctx: Context| async move {
-        let input: CreateUser = ctx.req.json().await?;
-        validate(&input)?;
+        let input: CreateUser = ctx.req.json_validated().await?;
 
         let user = User {
             id: 1,
@@ -113,7 +114,7 @@ a{color:#4f46e5}code{background:#f4f4f5;padding:0.15rem 0.4rem;border-radius:0.2
     // Custom middleware example
     let custom_middleware: BoxedMiddleware = Arc::new(|ctx: Context, next| {
         Box::pin(async move {
-            ctx.set("request_id", "12345").await;
+            ctx.set("api_version", "v1").await;
             let result = next(ctx).await;
             result
         })
@@ -122,12 +123,19 @@ a{color:#4f46e5}code{background:#f4f4f5;padding:0.15rem 0.4rem;border-radius:0.2
 
     // Route that uses middleware data
     app.get("/info", |ctx: Context| async move {
+        // Set by the built-in request_id() middleware
         let request_id = ctx
             .get("request_id")
             .await
             .unwrap_or_else(|| "none".to_string());
+        // Set by the custom middleware above
+        let api_version = ctx
+            .get("api_version")
+            .await
+            .unwrap_or_else(|| "none".to_string());
         ctx.json(json!({
             "requestId": request_id,
+            "apiVersion": api_version,
             "framework": "Ultimo"
         }))
         .await
@@ -151,7 +159,7 @@ a{color:#4f46e5}code{background:#f4f4f5;padding:0.15rem 0.4rem;border-radius:0.2
 
     // Error handling example
     app.get("/error", |_ctx: Context| async move {
-        Err(UltimoError::BadRequest(
+        Err::<Response, _>(UltimoError::BadRequest(
             "This is an intentional error".to_string(),
         ))
     });
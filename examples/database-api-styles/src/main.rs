@@ -136,7 +136,7 @@ async fn main() -> ultimo::Result<()> {
 
     println!("🔌 Connecting to database: {}", database_url);
 
-    let pool = SqlxPool::connect(&database_url)
+    let pool = SqlxPool::<sqlx::Postgres>::connect(&database_url)
         .await
         .map_err(|e| UltimoError::Internal(format!("Failed to create pool: {}", e)))?;
 
@@ -176,11 +176,7 @@ async fn main() -> ultimo::Result<()> {
     });
 
     app.get("/rest/users/:id", |ctx: Context| async move {
-        let id: i32 = ctx
-            .req
-            .param("id")?
-            .parse()
-            .map_err(|_| UltimoError::BadRequest("Invalid user ID".to_string()))?;
+        let id: i32 = ctx.req.param_as("id")?;
         let db = ctx.sqlx::<sqlx::Postgres>()?;
         let user = db_operations::get_user(db, id).await?;
         ctx.json(user).await
@@ -194,11 +190,7 @@ async fn main() -> ultimo::Result<()> {
     });
 
     app.put("/rest/users/:id", |ctx: Context| async move {
-        let id: i32 = ctx
-            .req
-            .param("id")?
-            .parse()
-            .map_err(|_| UltimoError::BadRequest("Invalid user ID".to_string()))?;
+        let id: i32 = ctx.req.param_as("id")?;
         let input: UpdateUserInput = ctx.req.json().await?;
         let db = ctx.sqlx::<sqlx::Postgres>()?;
         let user = db_operations::update_user(db, id, input).await?;
@@ -206,11 +198,7 @@ async fn main() -> ultimo::Result<()> {
     });
 
     app.delete("/rest/users/:id", |ctx: Context| async move {
-        let id: i32 = ctx
-            .req
-            .param("id")?
-            .parse()
-            .map_err(|_| UltimoError::BadRequest("Invalid user ID".to_string()))?;
+        let id: i32 = ctx.req.param_as("id")?;
         let db = ctx.sqlx::<sqlx::Postgres>()?;
         db_operations::delete_user(db, id).await?;
         ctx.status(204).await;
@@ -81,17 +81,8 @@ async fn main() -> ultimo::Result<()> {
     let mut app = Ultimo::new();
     app.with_diesel(pool);
 
-    // Health check endpoint
-    app.get("/health", |ctx: Context| async move {
-        // Test database connection by getting a connection from the pool
-        let _conn = ctx.diesel::<diesel::PgConnection>()?;
-
-        ctx.json(serde_json::json!({
-            "status": "healthy",
-            "database": "connected"
-        }))
-        .await
-    });
+    // Health check endpoint (checks out a pooled connection; 503 if unreachable)
+    app.health_check("/health");
 
     // List all users
     app.get("/users", |ctx: Context| async move {
@@ -111,11 +102,7 @@ async fn main() -> ultimo::Result<()> {
 
     // Get user by ID
     app.get("/users/:id", |ctx: Context| async move {
-        let id: i32 = ctx
-            .req
-            .param("id")?
-            .parse()
-            .map_err(|_| UltimoError::BadRequest("Invalid id format".to_string()))?;
+        let id: i32 = ctx.req.param_as("id")?;
 
         let mut conn = ctx.diesel::<diesel::PgConnection>()?;
 
@@ -152,11 +139,7 @@ async fn main() -> ultimo::Result<()> {
 
     // Update user
     app.put("/users/:id", |ctx: Context| async move {
-        let id: i32 = ctx
-            .req
-            .param("id")?
-            .parse()
-            .map_err(|_| UltimoError::BadRequest("Invalid id format".to_string()))?;
+        let id: i32 = ctx.req.param_as("id")?;
 
         let input: UpdateUserInput = ctx.req.json().await?;
         let mut conn = ctx.diesel::<diesel::PgConnection>()?;
@@ -172,13 +155,35 @@ async fn main() -> ultimo::Result<()> {
         ctx.json(user).await
     });
 
+    // Partially update user: only the fields in the body change
+    app.patch("/users/:id", |ctx: Context| async move {
+        let id: i32 = ctx.req.param_as("id")?;
+        let mut conn = ctx.diesel::<diesel::PgConnection>()?;
+
+        let current = users::table
+            .find(id)
+            .select(User::as_select())
+            .first(&mut *conn)
+            .optional()
+            .map_err(|e| UltimoError::Internal(format!("Database query failed: {}", e)))?
+            .ok_or_else(|| UltimoError::NotFound(format!("User with id {} not found", id)))?;
+        let patched: User = ctx.req.json_patch(&current).await?;
+
+        let user = diesel::update(users::table.find(id))
+            .set((
+                users::name.eq(&patched.name),
+                users::email.eq(&patched.email),
+            ))
+            .returning(User::as_returning())
+            .get_result(&mut *conn)
+            .map_err(|e| UltimoError::Internal(format!("Database update failed: {}", e)))?;
+
+        ctx.json(user).await
+    });
+
     // Delete user
     app.delete("/users/:id", |ctx: Context| async move {
-        let id: i32 = ctx
-            .req
-            .param("id")?
-            .parse()
-            .map_err(|_| UltimoError::BadRequest("Invalid id format".to_string()))?;
+        let id: i32 = ctx.req.param_as("id")?;
 
         let mut conn = ctx.diesel::<diesel::PgConnection>()?;
 
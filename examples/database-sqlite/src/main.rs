@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::FromRow;
+use ultimo::database::sqlx::SqlxPool;
+use ultimo::prelude::*;
+
+#[derive(Debug, Serialize, FromRow)]
+struct User {
+    id: i64,
+    name: String,
+    email: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateUserInput {
+    name: String,
+    email: String,
+}
+
+/// Open the database and create the schema.
+///
+/// `sqlite::memory:` gives every connection its own empty database, so an
+/// in-memory pool is capped at one connection.
+async fn connect(database_url: &str) -> ultimo::Result<SqlxPool<sqlx::Sqlite>> {
+    let max_connections = if database_url.contains(":memory:") {
+        1
+    } else {
+        5
+    };
+    let pool = SqlxPool::<sqlx::Sqlite>::connect_with_options(
+        SqlitePoolOptions::new().max_connections(max_connections),
+        database_url,
+    )
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            email TEXT NOT NULL UNIQUE
+        )
+        "#,
+    )
+    .execute(pool.pool())
+    .await?;
+
+    Ok(pool)
+}
+
+fn build_app(pool: SqlxPool<sqlx::Sqlite>) -> Ultimo {
+    let mut app = Ultimo::new();
+    app.with_sqlx(pool);
+
+    // Health check endpoint (pings the database)
+    app.health_check("/health");
+
+    // List all users
+    app.get("/users", |ctx: Context| async move {
+        let db = ctx.sqlx::<sqlx::Sqlite>()?;
+        let users = sqlx::query_as::<_, User>("SELECT id, name, email FROM users ORDER BY id")
+            .fetch_all(db)
+            .await?;
+        ctx.json(json!({ "users": users, "total": users.len() }))
+            .await
+    });
+
+    // Get user by ID
+    app.get("/users/:id", |ctx: Context| async move {
+        let id: i64 = ctx.req.param_as("id")?;
+        let db = ctx.sqlx::<sqlx::Sqlite>()?;
+        let user = sqlx::query_as::<_, User>("SELECT id, name, email FROM users WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?
+            .ok_or_else(|| UltimoError::NotFound("User not found".to_string()))?;
+        ctx.json(user).await
+    });
+
+    // Create user
+    app.post("/users", |ctx: Context| async move {
+        let input: CreateUserInput = ctx.req.json().await?;
+        let db = ctx.sqlx::<sqlx::Sqlite>()?;
+        let user = sqlx::query_as::<_, User>(
+            "INSERT INTO users (name, email) VALUES (?, ?) RETURNING id, name, email",
+        )
+        .bind(&input.name)
+        .bind(&input.email)
+        .fetch_one(db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                UltimoError::Conflict("Email already exists".to_string())
+            }
+            other => other.into(),
+        })?;
+
+        ctx.status(201).await;
+        ctx.json(user).await
+    });
+
+    // Create several users atomically: all of them or none
+    app.post("/users/batch", |ctx: Context| async move {
+        let inputs: Vec<CreateUserInput> = ctx.req.json().await?;
+        let users = ctx
+            .transaction::<sqlx::Sqlite, _, _>(|tx| {
+                Box::pin(async move {
+                    let mut users = Vec::with_capacity(inputs.len());
+                    for input in inputs {
+                        let user = sqlx::query_as::<_, User>(
+                            "INSERT INTO users (name, email) VALUES (?, ?) RETURNING id, name, email",
+                        )
+                        .bind(input.name)
+                        .bind(input.email)
+                        .fetch_one(&mut **tx)
+                        .await?;
+                        users.push(user);
+                    }
+                    Ok(users)
+                })
+            })
+            .await?;
+
+        ctx.status(201).await;
+        ctx.json(json!({ "created": users.len(), "users": users }))
+            .await
+    });
+
+    // Delete user
+    app.delete("/users/:id", |ctx: Context| async move {
+        let id: i64 = ctx.req.param_as("id")?;
+        let db = ctx.sqlx::<sqlx::Sqlite>()?;
+        let result = sqlx::query("DELETE FROM users WHERE id = ?")
+            .bind(id)
+            .execute(db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(UltimoError::NotFound("User not found".to_string()));
+        }
+        ctx.status(204).await;
+        ctx.text("").await
+    });
+
+    app
+}
+
+#[tokio::main]
+async fn main() -> ultimo::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    // e.g. DATABASE_URL=sqlite:app.db?mode=rwc to persist to a file
+    let database_url =
+        std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".to_string());
+    println!("🔌 Opening database: {}", database_url);
+
+    let pool = connect(&database_url).await?;
+    let app = build_app(pool);
+
+    println!("🚀 Server running on http://127.0.0.1:3002");
+    println!();
+    println!("Try these endpoints:");
+    println!("  GET    http://127.0.0.1:3002/health");
+    println!("  GET    http://127.0.0.1:3002/users");
+    println!("  POST   http://127.0.0.1:3002/users");
+    println!("  POST   http://127.0.0.1:3002/users/batch");
+    println!("  GET    http://127.0.0.1:3002/users/1");
+    println!("  DELETE http://127.0.0.1:3002/users/1");
+    println!();
+
+    app.listen("127.0.0.1:3002").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultimo::testing::TestClient;
+
+    async fn client() -> TestClient {
+        TestClient::new(build_app(connect("sqlite::memory:").await.unwrap()))
+    }
+
+    #[tokio::test]
+    async fn health_reports_connected_database() {
+        client()
+            .await
+            .get("/health")
+            .send()
+            .await
+            .assert_ok()
+            .assert_json_eq(json!({ "status": "healthy", "database": "connected" }));
+    }
+
+    #[tokio::test]
+    async fn create_then_fetch_user() {
+        let client = client().await;
+        client
+            .post("/users")
+            .json(&json!({ "name": "Alice", "email": "alice@example.com" }))
+            .send()
+            .await
+            .assert_status(201)
+            .assert_json_eq(json!({ "id": 1, "name": "Alice", "email": "alice@example.com" }));
+
+        client
+            .get("/users/1")
+            .send()
+            .await
+            .assert_json_eq(json!({ "id": 1, "name": "Alice", "email": "alice@example.com" }));
+
+        client
+            .post("/users")
+            .json(&json!({ "name": "Alice 2", "email": "alice@example.com" }))
+            .send()
+            .await
+            .assert_status(409);
+    }
+
+    #[tokio::test]
+    async fn batch_insert_is_all_or_nothing() {
+        let client = client().await;
+        client
+            .post("/users/batch")
+            .json(&json!([
+                { "name": "Bob", "email": "bob@example.com" },
+                { "name": "Bobby", "email": "bob@example.com" }
+            ]))
+            .send()
+            .await
+            .assert_status(500);
+
+        client
+            .get("/users")
+            .send()
+            .await
+            .assert_json_eq(json!({ "users": [], "total": 0 }));
+    }
+}
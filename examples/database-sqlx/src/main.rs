@@ -3,7 +3,7 @@ use sqlx::FromRow;
 use ultimo::database::sqlx::SqlxPool;
 use ultimo::prelude::*;
 
-#[derive(Debug, Serialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow)]
 struct User {
     id: i32,
     name: String,
@@ -29,7 +29,7 @@ async fn main() -> ultimo::Result<()> {
 
     println!("🔌 Connecting to database: {}", database_url);
 
-    let pool = SqlxPool::connect(&database_url)
+    let pool = SqlxPool::<sqlx::Postgres>::connect(&database_url)
         .await
         .expect("Failed to connect to database");
 
@@ -57,22 +57,8 @@ async fn main() -> ultimo::Result<()> {
     // Add CORS middleware
     app.use_middleware(ultimo::middleware::builtin::cors());
 
-    // Health check endpoint
-    app.get("/health", |ctx: Context| async move {
-        let db = ctx.sqlx::<sqlx::Postgres>()?;
-
-        // Check database connection
-        sqlx::query("SELECT 1")
-            .execute(db)
-            .await
-            .map_err(|e| UltimoError::Internal(format!("Database health check failed: {}", e)))?;
-
-        ctx.json(json!({
-            "status": "healthy",
-            "database": "connected"
-        }))
-        .await
-    });
+    // Health check endpoint (pings the database; 503 if unreachable)
+    app.health_check("/health");
 
     // List all users
     app.get("/users", |ctx: Context| async move {
@@ -92,11 +78,7 @@ async fn main() -> ultimo::Result<()> {
 
     // Get user by ID
     app.get("/users/:id", |ctx: Context| async move {
-        let id: i32 = ctx
-            .req
-            .param("id")?
-            .parse()
-            .map_err(|_| UltimoError::BadRequest("Invalid user ID".to_string()))?;
+        let id: i32 = ctx.req.param_as("id")?;
         let db = ctx.sqlx::<sqlx::Postgres>()?;
 
         let user = sqlx::query_as::<_, User>("SELECT id, name, email FROM users WHERE id = $1")
@@ -131,17 +113,12 @@ async fn main() -> ultimo::Result<()> {
             }
         })?;
 
-        ctx.status(201).await;
-        ctx.json(user).await
+        ctx.created(user).await
     });
 
     // Update user
     app.put("/users/:id", |ctx: Context| async move {
-        let id: i32 = ctx
-            .req
-            .param("id")?
-            .parse()
-            .map_err(|_| UltimoError::BadRequest("Invalid user ID".to_string()))?;
+        let id: i32 = ctx.req.param_as("id")?;
         let input: CreateUserInput = ctx.req.json().await?;
         let db = ctx.sqlx::<sqlx::Postgres>()?;
 
@@ -161,13 +138,35 @@ async fn main() -> ultimo::Result<()> {
         }
     });
 
+    // Partially update user: only the fields in the body change
+    app.patch("/users/:id", |ctx: Context| async move {
+        let id: i32 = ctx.req.param_as("id")?;
+        let db = ctx.sqlx::<sqlx::Postgres>()?;
+
+        let current = sqlx::query_as::<_, User>("SELECT id, name, email FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(db)
+            .await
+            .map_err(|e| UltimoError::Internal(format!("Database query failed: {}", e)))?
+            .ok_or_else(|| UltimoError::NotFound("User not found".to_string()))?;
+        let patched: User = ctx.req.json_patch(&current).await?;
+
+        let user = sqlx::query_as::<_, User>(
+            "UPDATE users SET name = $1, email = $2 WHERE id = $3 RETURNING id, name, email",
+        )
+        .bind(&patched.name)
+        .bind(&patched.email)
+        .bind(id)
+        .fetch_one(db)
+        .await
+        .map_err(|e| UltimoError::Internal(format!("Database query failed: {}", e)))?;
+
+        ctx.json(user).await
+    });
+
     // Delete user
     app.delete("/users/:id", |ctx: Context| async move {
-        let id: i32 = ctx
-            .req
-            .param("id")?
-            .parse()
-            .map_err(|_| UltimoError::BadRequest("Invalid user ID".to_string()))?;
+        let id: i32 = ctx.req.param_as("id")?;
         let db = ctx.sqlx::<sqlx::Postgres>()?;
 
         let result = sqlx::query("DELETE FROM users WHERE id = $1")
@@ -180,8 +179,7 @@ async fn main() -> ultimo::Result<()> {
             return Err(UltimoError::NotFound("User not found".to_string()));
         }
 
-        ctx.status(204).await;
-        ctx.text("").await
+        ctx.no_content().await
     });
 
     // Transaction example: Transfer (multi-step operation)
@@ -194,35 +192,33 @@ async fn main() -> ultimo::Result<()> {
         }
 
         let input: TransferRequest = ctx.req.json().await?;
-        let db = ctx.sqlx::<sqlx::Postgres>()?;
 
-        // Start transaction
-        let mut tx = db
-            .begin()
-            .await
-            .map_err(|e| UltimoError::Internal(format!("Failed to start transaction: {}", e)))?;
-
-        // This is a simplified example - in production you'd have a credits/balance column
-        // For demo, we'll just verify both users exist
-        let from_user =
-            sqlx::query_as::<_, User>("SELECT id, name, email FROM users WHERE id = $1")
-                .bind(input.from_user_id)
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(|e| UltimoError::Internal(e.to_string()))?
-                .ok_or_else(|| UltimoError::NotFound("From user not found".to_string()))?;
-
-        let to_user = sqlx::query_as::<_, User>("SELECT id, name, email FROM users WHERE id = $1")
-            .bind(input.to_user_id)
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(|e| UltimoError::Internal(e.to_string()))?
-            .ok_or_else(|| UltimoError::NotFound("To user not found".to_string()))?;
-
-        // Commit transaction
-        tx.commit()
-            .await
-            .map_err(|e| UltimoError::Internal(format!("Failed to commit transaction: {}", e)))?;
+        // Commits if the closure returns Ok, rolls back on Err (or panic)
+        let (from_user, to_user) = ctx
+            .transaction::<sqlx::Postgres, _, _>(|tx| {
+                Box::pin(async move {
+                    // This is a simplified example - in production you'd have a credits/balance column
+                    // For demo, we'll just verify both users exist
+                    let from_user = sqlx::query_as::<_, User>(
+                        "SELECT id, name, email FROM users WHERE id = $1",
+                    )
+                    .bind(input.from_user_id)
+                    .fetch_optional(&mut **tx)
+                    .await?
+                    .ok_or_else(|| UltimoError::NotFound("From user not found".to_string()))?;
+
+                    let to_user = sqlx::query_as::<_, User>(
+                        "SELECT id, name, email FROM users WHERE id = $1",
+                    )
+                    .bind(input.to_user_id)
+                    .fetch_optional(&mut **tx)
+                    .await?
+                    .ok_or_else(|| UltimoError::NotFound("To user not found".to_string()))?;
+
+                    Ok((from_user, to_user))
+                })
+            })
+            .await?;
 
         ctx.json(json!({
             "success": true,
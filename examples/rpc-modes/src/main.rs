@@ -113,29 +113,12 @@ async fn main() -> ultimo::Result<()> {
     println!("   - POST /api/createUser");
     println!();
 
-    // Mount REST endpoints manually (in a real app, this would be automated)
-    let rpc_for_list = rest_rpc.clone();
-    rest_app.get("/api/listUsers", move |ctx: Context| {
-        let rpc = rpc_for_list.clone();
-        async move {
-            let params = serde_json::json!({});
-            let result = rpc.call("listUsers", params).await?;
-            ctx.json(result).await
-        }
-    });
-
-    let rpc_for_create = rest_rpc.clone();
-    rest_app.post("/api/createUser", move |ctx: Context| {
-        let rpc = rpc_for_create.clone();
-        async move {
-            let body: CreateUserInput = ctx.req.json().await?;
-            let result = rpc.call("createUser", serde_json::to_value(body)?).await?;
-            ctx.json(result).await
-        }
-    });
+    // Mount one route per procedure: GET for queries, POST for mutations
+    rest_app.mount_rpc("/api", rest_rpc.clone());
 
     println!("REST Mode: Would listen on http://localhost:3001");
     println!("  GET  /api/listUsers");
+    println!("  GET  /api/getUserById?id=1");
     println!("  POST /api/createUser");
     println!();
 
@@ -202,25 +185,7 @@ async fn main() -> ultimo::Result<()> {
     println!();
 
     // Mount JSON-RPC 2.0 endpoint (supports single, batch, and notifications)
-    let rpc_handler = jsonrpc_rpc.clone();
-    jsonrpc_app.post("/rpc", move |ctx: Context| {
-        let rpc = rpc_handler.clone();
-        async move {
-            let body = ctx.req.bytes().await?;
-            let output = rpc.handle_request(&body).await;
-            match output.into_body() {
-                Some(bytes) => {
-                    let value: serde_json::Value = serde_json::from_slice(&bytes)
-                        .map_err(|e| ultimo::UltimoError::Internal(e.to_string()))?;
-                    ctx.json(value).await
-                }
-                None => {
-                    ctx.status(204).await;
-                    ctx.text("").await
-                }
-            }
-        }
-    });
+    jsonrpc_app.mount_rpc("/rpc", jsonrpc_rpc.clone());
 
     println!("JSON-RPC Mode: Would listen on http://localhost:3000");
     println!();
@@ -1,5 +1,5 @@
 use ultimo::prelude::*;
-use ultimo::websocket::{Message, WebSocket, WebSocketConfig, WebSocketHandler};
+use ultimo::websocket::{CloseCode, Message, WebSocket, WebSocketConfig, WebSocketHandler};
 
 #[derive(Clone)]
 struct ChatHandler;
@@ -69,7 +69,7 @@ impl WebSocketHandler for ChatHandler {
         );
     }
 
-    async fn on_close(&self, _ws: &WebSocket<Self::Data>, code: u16, reason: &str) {
+    async fn on_close(&self, _ws: &WebSocket<Self::Data>, code: CloseCode, reason: &str) {
         println!("Client disconnected: {} - {}", code, reason);
     }
 }
@@ -3,7 +3,7 @@
 //! A simple chat room application demonstrating WebSocket support in Ultimo.
 //!
 //! Features:
-//! - Multiple users can connect
+//! - Multiple users can connect, each named by the `?name=` query parameter
 //! - Messages are broadcasted to all connected clients
 //! - Join/leave notifications
 //! - Custom configuration (Phase 2: ping/pong, message size limits)
@@ -17,7 +17,7 @@
 use async_trait::async_trait;
 use ultimo::{
     prelude::*,
-    websocket::{Message, WebSocket, WebSocketConfig, WebSocketHandler},
+    websocket::{CloseCode, Message, WebSocket, WebSocketConfig, WebSocketHandler},
 };
 
 /// Chat room handler
@@ -27,13 +27,16 @@ struct ChatHandler {
 
 #[async_trait]
 impl WebSocketHandler for ChatHandler {
-    type Data = ();
+    /// The user's name, read from the upgrade request
+    type Data = String;
 
     async fn on_open(&self, ws: &WebSocket<Self::Data>) {
-        tracing::info!("New client connected to {}", self.room);
+        tracing::info!("{} connected to {}", ws.data(), self.room);
 
         // Send welcome message to the new user
-        ws.send("Welcome to the chat room!").await.ok();
+        ws.send(format!("Welcome to the chat room, {}!", ws.data()))
+            .await
+            .ok();
 
         // Subscribe to chat room (after welcome, so we receive future messages)
         if let Err(e) = ws.subscribe(self.room).await {
@@ -47,7 +50,9 @@ impl WebSocketHandler for ChatHandler {
                 tracing::info!("Received message: {}", text);
 
                 // Parse message
-                if let Ok(msg_data) = serde_json::from_str::<serde_json::Value>(&text) {
+                if let Ok(mut msg_data) = serde_json::from_str::<serde_json::Value>(&text) {
+                    // Stamp the sender from the connection, not the client's claim
+                    msg_data["sender"] = ws.data().clone().into();
                     // Broadcast to all clients in the room
                     ws.publish(self.room, &msg_data).await.ok();
                 } else {
@@ -66,7 +71,7 @@ impl WebSocketHandler for ChatHandler {
         }
     }
 
-    async fn on_close(&self, _ws: &WebSocket<Self::Data>, code: u16, reason: &str) {
+    async fn on_close(&self, _ws: &WebSocket<Self::Data>, code: CloseCode, reason: &str) {
         tracing::info!("Client disconnected: {} - {}", code, reason);
     }
 
@@ -84,6 +89,7 @@ impl WebSocketHandler for ChatHandler {
 }
 
 #[tokio::main]
+#[allow(clippy::result_large_err)] // the guard's rejection is a full Response
 async fn main() -> Result<()> {
     // Initialize tracing
     tracing_subscriber::fmt()
@@ -112,7 +118,19 @@ async fn main() -> Result<()> {
         ..Default::default()
     };
 
-    app.websocket_with_config("/ws", ChatHandler { room: "lobby" }, config);
+    // Name each connection from `?name=` before `on_open` runs
+    app.websocket_route("/ws")
+        .guard(|req| {
+            let name = req
+                .uri()
+                .query()
+                .and_then(|q| q.split('&').find_map(|kv| kv.strip_prefix("name=")))
+                .filter(|name| !name.is_empty())
+                .unwrap_or("anonymous");
+            Ok(name.to_string())
+        })
+        .config(config)
+        .handler(ChatHandler { room: "lobby" });
 
     tracing::info!("Starting chat server on http://localhost:4000");
     tracing::info!("Open your browser and navigate to http://localhost:4000");
@@ -37,7 +37,13 @@ async fn start_server(port: u16) {
             }
         }
 
-        async fn on_close(&self, _ws: &WebSocket<Self::Data>, _code: u16, _reason: &str) {}
+        async fn on_close(
+            &self,
+            _ws: &WebSocket<Self::Data>,
+            _code: ultimo::websocket::CloseCode,
+            _reason: &str,
+        ) {
+        }
     }
 
     tokio::spawn(async move {
@@ -0,0 +1,179 @@
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directory inside the backend that the built frontend is copied to.
+const PUBLIC_DIR: &str = "public";
+
+/// File left in [`PUBLIC_DIR`] to mark it as output of a previous build, and
+/// so safe to replace.
+const BUILD_MARKER: &str = ".ultimo-build";
+
+/// Build the project for deployment.
+///
+/// Run from the project root. The backend is `backend/` (fullstack template)
+/// or the current directory; it is built with cargo under `profile`. If a
+/// `frontend/package.json` exists, the frontend is built with its package
+/// manager and `frontend/dist/` is copied to `<backend>/public/`, where the
+/// backend serves it with `serve_static` / `serve_spa`. Any failing step
+/// fails the command.
+pub async fn run(profile: String) -> Result<()> {
+    let backend = if Path::new("backend/Cargo.toml").exists() {
+        PathBuf::from("backend")
+    } else if Path::new("Cargo.toml").exists() {
+        PathBuf::from(".")
+    } else {
+        bail!("no Cargo.toml in the current directory or backend/; run `ultimo build` from your project root");
+    };
+
+    println!(
+        "{}",
+        format!("📦 Building for production ({} profile)", profile).bold()
+    );
+    println!();
+
+    let frontend = Path::new("frontend");
+    if frontend.join("package.json").exists() {
+        build_frontend(frontend)?;
+        let public = backend.join(PUBLIC_DIR);
+        copy_dist(&frontend.join("dist"), &public)?;
+        println!(
+            "{}",
+            format!("✅ Frontend copied to {}", public.display()).green()
+        );
+        println!();
+    }
+
+    build_backend(&backend, &profile)?;
+    println!(
+        "{}",
+        format!(
+            "✅ Backend built in {}",
+            backend.join("target").join(profile_dir(&profile)).display()
+        )
+        .green()
+    );
+
+    println!();
+    println!("{}", "✨ Build complete!".green().bold());
+    Ok(())
+}
+
+/// `cargo build` with the flag selecting `profile`.
+fn build_backend(backend: &Path, profile: &str) -> Result<()> {
+    println!("{}", "🦀 Building backend...".bold());
+
+    let mut cargo = Command::new("cargo");
+    cargo.arg("build").current_dir(backend);
+    match profile {
+        "debug" | "dev" => {}
+        "release" => {
+            cargo.arg("--release");
+        }
+        custom => {
+            cargo.args(["--profile", custom]);
+        }
+    }
+    let status = cargo.status().context("Failed to invoke `cargo build`")?;
+    if !status.success() {
+        bail!("backend build failed");
+    }
+    Ok(())
+}
+
+/// Directory under `target/` that cargo writes `profile` to.
+fn profile_dir(profile: &str) -> &str {
+    match profile {
+        "dev" => "debug",
+        other => other,
+    }
+}
+
+/// Install dependencies if needed, then run the `build` script.
+fn build_frontend(frontend: &Path) -> Result<()> {
+    let manager = package_manager(frontend);
+    println!(
+        "{}",
+        format!("🌐 Building frontend with {}...", manager).bold()
+    );
+
+    if !frontend.join("node_modules").exists() {
+        run_in(frontend, manager, &["install"])?;
+    }
+    run_in(frontend, manager, &["run", "build"])?;
+
+    if !frontend.join("dist").is_dir() {
+        bail!(
+            "frontend build did not produce {}",
+            frontend.join("dist").display()
+        );
+    }
+    Ok(())
+}
+
+/// The package manager whose lockfile is present, npm by default.
+fn package_manager(frontend: &Path) -> &'static str {
+    if frontend.join("pnpm-lock.yaml").exists() {
+        "pnpm"
+    } else if frontend.join("yarn.lock").exists() {
+        "yarn"
+    } else {
+        "npm"
+    }
+}
+
+fn run_in(dir: &Path, program: &str, args: &[&str]) -> Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .with_context(|| format!("Failed to invoke `{}`; is it installed?", program))?;
+    if !status.success() {
+        bail!(
+            "`{} {}` failed in {}",
+            program,
+            args.join(" "),
+            dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Replace `to` with a copy of `from`.
+///
+/// An existing `to` is only removed if it is empty or carries
+/// [`BUILD_MARKER`]; anything else may be hand-written files, so the build
+/// stops instead.
+fn copy_dist(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        let empty = std::fs::read_dir(to)
+            .with_context(|| format!("Failed to read {}", to.display()))?
+            .next()
+            .is_none();
+        if !empty && !to.join(BUILD_MARKER).exists() {
+            bail!(
+                "{} exists and was not created by `ultimo build`; move or delete it so the frontend can be copied there",
+                to.display()
+            );
+        }
+        std::fs::remove_dir_all(to).with_context(|| format!("Failed to clear {}", to.display()))?;
+    }
+    copy_dir(from, to).with_context(|| format!("Failed to copy {}", from.display()))?;
+    std::fs::write(to.join(BUILD_MARKER), "")
+        .with_context(|| format!("Failed to write {}", to.join(BUILD_MARKER).display()))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
@@ -1,17 +1,28 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use colored::Colorize;
 use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
-use std::path::Path;
-use std::time::Duration;
-use tokio::process::Command;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
 use tokio::signal;
 use tokio::sync::mpsc as tokio_mpsc;
 
 /// Run the development server with hot reload.
 ///
 /// Watches `.rs` files and `Cargo.toml` for changes, then recompiles and
-/// restarts the server automatically.
-pub async fn run(port: u16, host: String) -> Result<()> {
+/// restarts the server automatically. The built binary is run directly (not
+/// through `cargo run`) so killing it on a change really frees the port; its
+/// stdout/stderr are inherited. `PORT` and `HOST` are passed in the
+/// environment for the app to bind to. `bin` picks the binary when the
+/// package has several; see [`select_binary`] for the default.
+pub async fn run(port: u16, host: String, bin: Option<String>) -> Result<()> {
+    if !Path::new("Cargo.toml").exists() {
+        bail!("no Cargo.toml in the current directory; run `ultimo dev` from your project root");
+    }
+
     println!(
         "{}",
         format!("🔥 Starting dev server on {}:{}", host, port)
@@ -41,17 +52,39 @@ pub async fn run(port: u16, host: String) -> Result<()> {
         }
 
         // Watch Cargo.toml
-        let cargo_toml = Path::new("Cargo.toml");
-        if cargo_toml.exists() {
-            debouncer
-                .watcher()
-                .watch(cargo_toml, RecursiveMode::NonRecursive)?;
-        }
+        debouncer
+            .watcher()
+            .watch(Path::new("Cargo.toml"), RecursiveMode::NonRecursive)?;
 
-        // Bridge std channel to tokio channel in a background thread
+        // Bridge std channel to tokio channel in a background thread.
+        // Cargo itself opens the watched files while building, so only a
+        // changed modification time counts as a change.
         std::thread::spawn(move || {
-            while std_rx.recv().is_ok() {
-                let _ = tx.blocking_send(());
+            let mut seen = HashMap::new();
+            for path in watched_files(Path::new("src")) {
+                seen.insert(path.clone(), modified(&path));
+            }
+            seen.insert(
+                PathBuf::from("Cargo.toml"),
+                modified(Path::new("Cargo.toml")),
+            );
+
+            for events in std_rx.into_iter().flatten() {
+                let mut changed = false;
+                for event in events {
+                    let path = relative(&event.path);
+                    if !is_watched(&path) {
+                        continue;
+                    }
+                    let now = modified(&path);
+                    if seen.insert(path, now) != Some(now) {
+                        changed = true;
+                    }
+                }
+                if changed {
+                    // A full channel already has a restart queued.
+                    let _ = tx.try_send(());
+                }
             }
         });
 
@@ -59,20 +92,20 @@ pub async fn run(port: u16, host: String) -> Result<()> {
     };
 
     // Initial build and run
-    let mut child = spawn_server(port, &host).await?;
+    let mut child = spawn_server(port, &host, bin.as_deref()).await?;
 
     loop {
         tokio::select! {
             _ = signal::ctrl_c() => {
                 println!("\n{}", "⏹  Shutting down dev server...".yellow());
-                kill_child(&mut child).await;
+                kill_child(child.take()).await;
                 break;
             }
             _ = rx.recv() => {
                 println!();
                 println!("{}", "♻  Change detected, restarting...".cyan());
-                kill_child(&mut child).await;
-                child = spawn_server(port, &host).await?;
+                kill_child(child.take()).await;
+                child = spawn_server(port, &host, bin.as_deref()).await?;
             }
         }
     }
@@ -80,21 +113,18 @@ pub async fn run(port: u16, host: String) -> Result<()> {
     Ok(())
 }
 
-async fn spawn_server(port: u16, host: &str) -> Result<tokio::process::Child> {
+/// Build the project and start its binary. `None` if the build failed; the
+/// next change retries.
+async fn spawn_server(port: u16, host: &str, bin: Option<&str>) -> Result<Option<Child>> {
     println!("{}", "   Compiling...".dimmed());
 
-    // Build first so we can show compilation errors clearly
-    let build_status = Command::new("cargo").args(["build"]).status().await?;
-
-    if !build_status.success() {
+    let Some(binary) = build(bin).await? else {
         println!(
             "{}",
             "❌ Build failed. Waiting for file changes...".red().bold()
         );
-        // Return a dummy process that just sleeps (will be killed on next change)
-        let child = Command::new("sleep").arg("86400").spawn()?;
-        return Ok(child);
-    }
+        return Ok(None);
+    };
 
     println!(
         "{}",
@@ -102,16 +132,143 @@ async fn spawn_server(port: u16, host: &str) -> Result<tokio::process::Child> {
     );
     println!();
 
-    let child = Command::new("cargo")
-        .args(["run"])
+    let child = Command::new(&binary)
         .env("PORT", port.to_string())
         .env("HOST", host)
+        .kill_on_drop(true)
         .spawn()?;
 
-    Ok(child)
+    Ok(Some(child))
+}
+
+/// Run `cargo build` (only `bin`, if given) and return the path of the
+/// binary to run.
+///
+/// Compiler diagnostics are rendered to stderr as usual; the JSON messages on
+/// stdout are only read for the executables' paths.
+async fn build(bin: Option<&str>) -> Result<Option<PathBuf>> {
+    let mut args = vec!["build", "--message-format=json-render-diagnostics"];
+    if let Some(bin) = bin {
+        args.extend(["--bin", bin]);
+    }
+    let mut cargo = Command::new("cargo")
+        .args(args)
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let mut binaries = Vec::new();
+    let mut lines = BufReader::new(cargo.stdout.take().expect("stdout is piped")).lines();
+    while let Some(line) = lines.next_line().await? {
+        if let Some(binary) = executable_artifact(&line) {
+            binaries.push(binary);
+        }
+    }
+
+    if !cargo.wait().await?.success() {
+        return Ok(None);
+    }
+    select_binary(binaries).await.map(Some)
+}
+
+/// A binary built by cargo.
+struct Binary {
+    package_id: String,
+    name: String,
+    path: PathBuf,
 }
 
-async fn kill_child(child: &mut tokio::process::Child) {
-    let _ = child.kill().await;
-    let _ = child.wait().await;
+/// The binary from a `compiler-artifact` message, if it produced one.
+fn executable_artifact(line: &str) -> Option<Binary> {
+    let message: serde_json::Value = serde_json::from_str(line).ok()?;
+    if message["reason"] != "compiler-artifact" {
+        return None;
+    }
+    Some(Binary {
+        package_id: message["package_id"].as_str()?.to_string(),
+        name: message["target"]["name"].as_str()?.to_string(),
+        path: PathBuf::from(message["executable"].as_str()?),
+    })
+}
+
+/// Pick the binary to run: the only one built, else the one named by its
+/// package's `default-run`, else the one named after its package. Anything
+/// else asks for `--bin`.
+async fn select_binary(mut binaries: Vec<Binary>) -> Result<PathBuf> {
+    match binaries.len() {
+        0 => bail!("`cargo build` produced no binary; is this a binary crate?"),
+        1 => return Ok(binaries.remove(0).path),
+        _ => {}
+    }
+
+    let output = Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .stderr(Stdio::inherit())
+        .output()
+        .await?;
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap_or_default();
+    let packages = metadata["packages"].as_array().cloned().unwrap_or_default();
+    let is_default = |binary: &Binary| {
+        packages.iter().any(|package| {
+            package["id"] == binary.package_id.as_str()
+                && (package["default_run"] == binary.name.as_str()
+                    || package["default_run"].is_null() && package["name"] == binary.name.as_str())
+        })
+    };
+
+    let defaults: Vec<&Binary> = binaries
+        .iter()
+        .filter(|binary| is_default(binary))
+        .collect();
+    if let [binary] = defaults.as_slice() {
+        return Ok(binary.path.clone());
+    }
+    let mut names: Vec<&str> = binaries.iter().map(|binary| binary.name.as_str()).collect();
+    names.sort_unstable();
+    bail!(
+        "could not determine which binary to run; pass `--bin <name>` or set `default-run` in Cargo.toml (available: {})",
+        names.join(", ")
+    )
+}
+
+/// Whether a change to `path` should trigger a rebuild.
+fn is_watched(path: &Path) -> bool {
+    path == Path::new("Cargo.toml") || path.extension().is_some_and(|ext| ext == "rs")
+}
+
+/// `.rs` files under `dir`, recursively.
+fn watched_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(watched_files(&path));
+        } else if is_watched(&path) {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// `path` relative to the current directory, as the watch paths are.
+fn relative(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Modification time of `path`; `None` once it's deleted.
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Stop the running server, if any, and wait for it to exit.
+async fn kill_child(child: Option<Child>) {
+    if let Some(mut child) = child {
+        let _ = child.kill().await;
+        let _ = child.wait().await;
+    }
 }
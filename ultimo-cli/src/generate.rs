@@ -2,13 +2,18 @@ use anyhow::{Context, Result};
 use colored::Colorize;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use ultimo::rpc::RpcSchema;
 
-/// Run the project's `generate-client` binary to emit a TypeScript client.
+/// Generate a TypeScript client from the project's real RPC registrations.
 ///
-/// Convention: the project defines `src/bin/generate-client.rs` which builds its
-/// `RpcRegistry` and calls `rpc.generate_client_file(<output>)`. This command
-/// runs that binary (`cargo run --bin generate-client -- <output>`) so the
-/// client is produced by the real registry — no source parsing, no guessing.
+/// By default the app is run with `ULTIMO_EMIT_RPC_SCHEMA=<file>`: an app that
+/// mounts its registry with `app.mount_rpc(..)` writes an [`RpcSchema`] (a
+/// versioned JSON description of its procedures and types) from `listen()`
+/// instead of serving, and the client is generated from that file.
+///
+/// Projects with a `src/bin/generate-client.rs` keep the older convention:
+/// that binary is run (`cargo run --bin generate-client -- <output>`) and
+/// writes the client itself. Either way, no source parsing, no guessing.
 pub async fn run(project: PathBuf, output: PathBuf, watch: bool) -> Result<()> {
     println!("📂 Project: {}", project.display().to_string().cyan());
     println!("📝 Output: {}", output.display().to_string().cyan());
@@ -63,7 +68,7 @@ pub async fn run(project: PathBuf, output: PathBuf, watch: bool) -> Result<()> {
     Ok(())
 }
 
-/// Run the generate-client binary once.
+/// Generate the client once.
 fn generate_once(project: &Path, output: &Path) -> Result<()> {
     let cargo_toml = project.join("Cargo.toml");
     if !cargo_toml.exists() {
@@ -75,6 +80,64 @@ fn generate_once(project: &Path, output: &Path) -> Result<()> {
         std::fs::create_dir_all(parent).context("Failed to create output directory")?;
     }
 
+    if project.join("src/bin/generate-client.rs").exists() {
+        run_generate_client_bin(project, &output_abs)?;
+    } else {
+        generate_from_schema(project, &output_abs)?;
+    }
+
+    println!(
+        "{}",
+        "✨ TypeScript client generated successfully!"
+            .green()
+            .bold()
+    );
+    println!("📄 {}", output_abs.display().to_string().cyan());
+    Ok(())
+}
+
+/// Run the app with `ULTIMO_EMIT_RPC_SCHEMA` set and write the client for the
+/// schema it dumps.
+fn generate_from_schema(project: &Path, output: &Path) -> Result<()> {
+    let schema_path = absolutize(&project.join("target").join("ultimo-rpc-schema.json"))?;
+    if let Some(parent) = schema_path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create target directory")?;
+    }
+    let _ = std::fs::remove_file(&schema_path);
+
+    println!("{}", "🔨 Reading RPC schema from the app...".bold());
+
+    let status = Command::new("cargo")
+        .arg("run")
+        .arg("--quiet")
+        .current_dir(project)
+        .env(ultimo::rpc::EMIT_RPC_SCHEMA_ENV, &schema_path)
+        .status()
+        .context("Failed to invoke `cargo run`")?;
+
+    if !status.success() || !schema_path.exists() {
+        anyhow::bail!(
+            "`cargo run` did not write an RPC schema in {project}.\n\n\
+             `ultimo generate` runs your app with `ULTIMO_EMIT_RPC_SCHEMA=<file>`; \
+             the app must mount its registry with `app.mount_rpc(path, rpc)` \
+             and then call `app.listen(..)`, which writes the schema and \
+             returns instead of serving. Alternatively, add \
+             `src/bin/generate-client.rs`:\n\n\
+             {snippet}",
+            project = project.display(),
+            snippet = EXAMPLE_BIN.trim_end(),
+        );
+    }
+
+    let json = std::fs::read_to_string(&schema_path).context("Failed to read RPC schema")?;
+    let schema = RpcSchema::from_json(&json).context("Invalid RPC schema")?;
+    std::fs::write(output, schema.generate_typescript_client())
+        .context("Failed to write TypeScript client")?;
+    Ok(())
+}
+
+/// Run the project's `generate-client` binary, which writes the client.
+fn run_generate_client_bin(project: &Path, output_abs: &Path) -> Result<()> {
     println!("{}", "🔨 Running generate-client binary...".bold());
 
     let status = Command::new("cargo")
@@ -84,17 +147,16 @@ fn generate_once(project: &Path, output: &Path) -> Result<()> {
         .arg("generate-client")
         .current_dir(project)
         .arg("--")
-        .arg(&output_abs)
+        .arg(output_abs)
         .status()
         .context("Failed to invoke `cargo run --bin generate-client`")?;
 
     if !status.success() {
         anyhow::bail!(
             "`cargo run --bin generate-client` failed in {project}.\n\n\
-             `ultimo generate` runs a `generate-client` binary in your project. \
-             Add `src/bin/generate-client.rs`:\n\n\
-             {snippet}\n\n\
-             It must accept the output path as its first argument.",
+             `src/bin/generate-client.rs` must build your registry and write \
+             the client to the path given as its first argument:\n\n\
+             {snippet}",
             project = project.display(),
             snippet = EXAMPLE_BIN.trim_end(),
         );
@@ -108,14 +170,6 @@ fn generate_once(project: &Path, output: &Path) -> Result<()> {
             output_abs.display()
         );
     }
-
-    println!(
-        "{}",
-        "✨ TypeScript client generated successfully!"
-            .green()
-            .bold()
-    );
-    println!("📄 {}", output_abs.display().to_string().cyan());
     Ok(())
 }
 
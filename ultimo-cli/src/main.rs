@@ -2,6 +2,7 @@ use clap::{Parser, Subcommand};
 use colored::Colorize;
 use std::path::PathBuf;
 
+mod build;
 mod dev;
 mod generate;
 mod new;
@@ -37,7 +38,7 @@ enum Commands {
         /// Project name
         name: String,
 
-        /// Template to use (basic, fullstack, api-only, rpc, production)
+        /// Template to use (basic, fullstack, api-only, rpc, production, database)
         #[arg(short, long, default_value = "basic")]
         template: String,
     },
@@ -51,11 +52,15 @@ enum Commands {
         /// Host to bind to
         #[arg(long, default_value = "127.0.0.1")]
         host: String,
+
+        /// Binary to run, for packages with several
+        #[arg(long)]
+        bin: Option<String>,
     },
 
-    /// [not implemented yet] Production build — use `cargo build --release` for now
+    /// Production build: the backend and, if present, the frontend
     Build {
-        /// Build profile (debug or release)
+        /// Cargo build profile (debug, release, or a custom profile)
         #[arg(short, long, default_value = "release")]
         profile: String,
     },
@@ -79,15 +84,11 @@ async fn main() -> anyhow::Result<()> {
         Commands::New { name, template } => {
             new::run(name, template).await?;
         }
-        Commands::Dev { port, host } => {
-            dev::run(port, host).await?;
+        Commands::Dev { port, host, bin } => {
+            dev::run(port, host, bin).await?;
         }
-        Commands::Build { profile: _ } => {
-            println!("{}", "`ultimo build` is not implemented yet.".yellow());
-            println!(
-                "Build with cargo for now:  {}",
-                "cargo build --release".cyan()
-            );
+        Commands::Build { profile } => {
+            build::run(profile).await?;
         }
     }
 
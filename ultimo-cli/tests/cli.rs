@@ -1,5 +1,5 @@
 //! CLI integration tests — guard the command surface (flags, help, scaffolding,
-//! and the `generate` convention) against drift.
+//! and the `generate` conventions) against drift.
 //! Run with: cargo test -p ultimo-cli
 
 use assert_cmd::Command;
@@ -41,12 +41,134 @@ fn dev_shows_help() {
 }
 
 #[test]
-fn build_reports_not_implemented() {
+fn dev_requires_a_cargo_project() {
+    let tmp = tempfile::tempdir().unwrap();
+    ultimo()
+        .current_dir(tmp.path())
+        .arg("dev")
+        .assert()
+        .failure()
+        .stderr(contains("no Cargo.toml"));
+}
+
+#[test]
+fn dev_asks_for_bin_when_the_binary_is_ambiguous() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("src/bin")).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"app\"\nversion = \"0.0.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::write(root.join("src/bin/api.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("src/bin/worker.rs"), "fn main() {}\n").unwrap();
+
     ultimo()
+        .current_dir(root)
+        .arg("dev")
+        .assert()
+        .failure()
+        .stderr(contains("--bin <name>"))
+        .stderr(contains("api, worker"));
+}
+
+#[test]
+fn build_fails_outside_a_project() {
+    let tmp = tempfile::tempdir().unwrap();
+    ultimo()
+        .current_dir(tmp.path())
         .arg("build")
         .assert()
-        .success()
-        .stdout(contains("not implemented"));
+        .failure()
+        .stderr(contains("no Cargo.toml"));
+}
+
+/// A fullstack layout under `root`; the frontend's build script writes dist/
+/// with node, so no packages are installed.
+fn fullstack_project(root: &std::path::Path) {
+    fs::create_dir_all(root.join("backend/src")).unwrap();
+    fs::write(
+        root.join("backend/Cargo.toml"),
+        "[package]\nname = \"app\"\nversion = \"0.0.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::write(root.join("backend/src/main.rs"), "fn main() {}\n").unwrap();
+    fs::create_dir_all(root.join("frontend/node_modules")).unwrap();
+    fs::write(
+        root.join("frontend/package.json"),
+        r#"{"name":"web","private":true,"scripts":{"build":"node -e \"require('fs').mkdirSync('dist/assets',{recursive:true});require('fs').writeFileSync('dist/index.html','<h1>hi</h1>')\""}}"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn build_bundles_the_frontend_into_the_backend() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fullstack_project(root);
+    // Stale output from an earlier build is replaced.
+    fs::create_dir_all(root.join("backend/public")).unwrap();
+    fs::write(root.join("backend/public/.ultimo-build"), "").unwrap();
+    fs::write(root.join("backend/public/old.js"), "").unwrap();
+
+    ultimo()
+        .current_dir(root)
+        .args(["build", "--profile", "debug"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(root.join("backend/public/index.html")).unwrap(),
+        "<h1>hi</h1>"
+    );
+    assert!(root.join("backend/public/assets").is_dir());
+    assert!(!root.join("backend/public/old.js").exists());
+    assert!(root.join("backend/target/debug/app").exists());
+}
+
+#[test]
+fn build_keeps_a_public_dir_it_did_not_create() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fullstack_project(root);
+    fs::create_dir_all(root.join("backend/public")).unwrap();
+    fs::write(root.join("backend/public/robots.txt"), "User-agent: *").unwrap();
+
+    ultimo()
+        .current_dir(root)
+        .args(["build", "--profile", "debug"])
+        .assert()
+        .failure()
+        .stderr(contains("was not created by `ultimo build`"));
+
+    assert!(root.join("backend/public/robots.txt").exists());
+}
+
+#[test]
+fn build_fails_loudly_when_the_frontend_build_fails() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"app\"\nversion = \"0.0.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::create_dir_all(root.join("frontend/node_modules")).unwrap();
+    fs::write(
+        root.join("frontend/package.json"),
+        r#"{"name":"web","private":true,"scripts":{"build":"node -e \"process.exit(1)\""}}"#,
+    )
+    .unwrap();
+
+    ultimo()
+        .current_dir(root)
+        .args(["build", "--profile", "debug"])
+        .assert()
+        .failure()
+        .stderr(contains("run build"));
 }
 
 #[test]
@@ -62,6 +184,26 @@ fn new_scaffolds_a_project() {
     assert!(tmp.path().join("demo-app/src/main.rs").exists());
 }
 
+#[test]
+fn new_database_template_scaffolds_sqlx_and_migrations() {
+    let tmp = tempfile::tempdir().unwrap();
+    ultimo()
+        .current_dir(tmp.path())
+        .args(["new", "db-app", "--template", "database"])
+        .assert()
+        .success();
+
+    let proj = tmp.path().join("db-app");
+    let cargo_toml = fs::read_to_string(proj.join("Cargo.toml")).unwrap();
+    assert!(cargo_toml.contains(r#"features = ["database", "sqlx-sqlite"]"#));
+    let main_rs = fs::read_to_string(proj.join("src/main.rs")).unwrap();
+    assert!(main_rs.contains("sqlx::migrate!(\"./migrations\")"));
+    assert!(main_rs.contains("app.health_check(\"/health\")"));
+    assert!(proj.join("migrations/0001_create_users.sql").exists());
+    let env = fs::read_to_string(proj.join(".env.example")).unwrap();
+    assert!(env.contains("DATABASE_URL="));
+}
+
 #[test]
 fn generate_runs_the_convention_bin_and_writes_output() {
     // A minimal cargo project whose generate-client bin writes its first arg.
@@ -93,8 +235,44 @@ fn generate_runs_the_convention_bin_and_writes_output() {
 }
 
 #[test]
-fn generate_errors_helpfully_without_the_bin() {
-    // A cargo project with no generate-client bin.
+fn generate_builds_the_client_from_the_emitted_schema() {
+    // An app that answers `ULTIMO_EMIT_RPC_SCHEMA=<file>` the way `listen()` does.
+    let tmp = tempfile::tempdir().unwrap();
+    let proj = tmp.path().join("proj");
+    fs::create_dir_all(proj.join("src")).unwrap();
+    fs::write(
+        proj.join("Cargo.toml"),
+        "[package]\nname = \"proj\"\nversion = \"0.0.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    let schema = r#"{"version":1,"mode":"rest","base_path":"/api","procedures":[{"name":"getUser","kind":"query","input":"GetUser","output":"User"}],"types":{"User":"type User = { id: number, };"}}"#;
+    fs::write(
+        proj.join("src/main.rs"),
+        format!(
+            "fn main() {{\n    assert_eq!(std::env::args().count(), 1);\n    let file = std::env::var(\"ULTIMO_EMIT_RPC_SCHEMA\").unwrap();\n    std::fs::write(file, r#\"{}\"#).unwrap();\n}}\n",
+            schema
+        ),
+    )
+    .unwrap();
+
+    let out = tmp.path().join("client.ts");
+    ultimo()
+        .args(["generate", "--project"])
+        .arg(&proj)
+        .arg("--output")
+        .arg(&out)
+        .assert()
+        .success();
+
+    let written = fs::read_to_string(&out).expect("client written");
+    assert!(written.contains("async getUser(params: GetUser): Promise<User>"));
+    assert!(written.contains("this.get('/getUser', params)"));
+    assert!(written.contains("type User = { id: number, };"));
+}
+
+#[test]
+fn generate_errors_helpfully_without_a_schema() {
+    // A cargo project whose app never writes the schema.
     let tmp = tempfile::tempdir().unwrap();
     let proj = tmp.path().join("proj");
     fs::create_dir_all(proj.join("src")).unwrap();
@@ -113,5 +291,6 @@ fn generate_errors_helpfully_without_the_bin() {
         .arg(&out)
         .assert()
         .failure()
+        .stderr(contains("mount_rpc"))
         .stderr(contains("generate-client"));
 }
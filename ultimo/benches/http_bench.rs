@@ -73,6 +73,63 @@ fn bench_dispatch_json(c: &mut Criterion) {
     });
 }
 
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct User {
+    id: u32,
+    name: String,
+    email: String,
+    active: bool,
+}
+
+/// JSON codec cost on the user-list endpoint: serialize 100 users, and parse a
+/// 100-user request body. Compare against `--features simd-json`.
+fn bench_user_list(c: &mut Criterion) {
+    let rt = runtime();
+    let users: Arc<Vec<User>> = Arc::new(
+        (0..100)
+            .map(|id| User {
+                id,
+                name: format!("User {id}"),
+                email: format!("user{id}@example.com"),
+                active: id % 2 == 0,
+            })
+            .collect(),
+    );
+    let body = Bytes::from(serde_json::to_vec(&*users).unwrap());
+
+    let mut app = Ultimo::new_without_defaults();
+    let list = users.clone();
+    app.get("/users", move |ctx: Context| {
+        let users = list.clone();
+        async move { ctx.json(&*users).await }
+    });
+    app.post("/users", |ctx: Context| async move {
+        let users: Vec<User> = ctx.req.json().await?;
+        ctx.text(users.len().to_string()).await
+    });
+
+    let mut group = c.benchmark_group("user_list");
+    group.bench_function("serialize", |b| {
+        b.to_async(&rt).iter(|| async {
+            let res = app.oneshot(black_box(request("/users"))).await;
+            black_box(res.status());
+        });
+    });
+    group.bench_function("parse", |b| {
+        b.to_async(&rt).iter(|| async {
+            let req = HyperRequest::builder()
+                .method("POST")
+                .uri("/users")
+                .header("content-type", "application/json")
+                .body(Full::new(body.clone()))
+                .unwrap();
+            let res = app.oneshot(black_box(req)).await;
+            black_box(res.status());
+        });
+    });
+    group.finish();
+}
+
 /// Radix-tree route lookup as the routing table grows. Dispatches to a route in
 /// the middle of the table plus a parameterized route.
 fn bench_routing(c: &mut Criterion) {
@@ -106,6 +163,21 @@ fn bench_routing(c: &mut Criterion) {
                 black_box(res.status());
             });
         });
+
+        // A table of parameterized routes only, so every lookup goes through
+        // the route tree rather than the static index.
+        let mut dynamic = Ultimo::new_without_defaults();
+        for i in 0..n {
+            let path = format!("/resource{i}/:id");
+            dynamic.get(&path, |ctx: Context| async move { ctx.text("ok").await });
+        }
+        let mid = format!("/resource{}/123", n / 2);
+        group.bench_with_input(BenchmarkId::new("dynamic", n), &mid, |b, mid| {
+            b.to_async(&rt).iter(|| async {
+                let res = dynamic.oneshot(black_box(request(mid))).await;
+                black_box(res.status());
+            });
+        });
     }
 
     group.finish();
@@ -138,6 +210,7 @@ criterion_group!(
     benches,
     bench_dispatch_text,
     bench_dispatch_json,
+    bench_user_list,
     bench_routing,
     bench_middleware_chain
 );
@@ -3,52 +3,179 @@
 //! Ties together routing, middleware, handlers, and HTTP server.
 
 use crate::{
-    context::Context,
+    context::{Context, RequestBody},
     error::{Result, UltimoError},
+    group::{
+        with_middleware, GroupRoute, RouteBuilder, RouteDocs, RouteGroup, RouteLimits, Target,
+    },
     handler::{BoxedHandler, IntoHandler},
+    metrics::Metrics,
     middleware::{BoxedMiddleware, MiddlewareChain},
     response::{self, Response},
-    router::{Method, Params, Router},
+    router::{Method, Params, Router, TrailingSlash},
 };
 use bytes::Bytes;
+use futures_util::FutureExt;
 use http_body_util::BodyExt;
 use hyper::body::Incoming;
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use hyper::Request as HyperRequest;
-use hyper_util::rt::TokioIo;
-#[cfg(feature = "websocket")]
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+#[cfg(any(feature = "websocket", feature = "database"))]
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 use tokio::net::TcpListener;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 #[cfg(feature = "database")]
-use crate::database::Database;
+use crate::database::{Database, DatabaseError, PoolStats};
 
 #[cfg(feature = "websocket")]
-use crate::websocket::{ChannelManager, WebSocketConfig, WebSocketHandler, WebSocketUpgrade};
+use crate::websocket::{
+    BoxedWebSocketHandler, ChannelManager, WebSocketConfig, WebSocketHandler, WebSocketRoute,
+    WebSocketUpgrade,
+};
 
-/// WebSocket handler function type
-#[cfg(feature = "websocket")]
-type BoxedWebSocketHandler = Arc<
-    dyn Fn(WebSocketUpgrade<()>) -> hyper::Response<http_body_util::Full<bytes::Bytes>>
+/// Register a table of routes on an [`Ultimo`] app.
+///
+/// Each line is `METHOD "path" => handler`, expanding to the matching
+/// `app.get(..)`/`app.post(..)`/… call. Supported methods are `GET`, `POST`,
+/// `PUT`, `DELETE`, `PATCH` and `OPTIONS`; anything else is a compile error.
+///
+/// ```rust,no_run
+/// use ultimo::prelude::*;
+/// use ultimo::response::Response;
+///
+/// async fn list_users(ctx: Context) -> Result<Response> {
+///     ctx.json(json!([])).await
+/// }
+///
+/// async fn create_user(ctx: Context) -> Result<Response> {
+///     ctx.created(json!({ "created": true })).await
+/// }
+///
+/// let mut app = Ultimo::new();
+/// ultimo::routes!(app, {
+///     GET "/users" => list_users,
+///     POST "/users" => create_user,
+/// });
+/// ```
+#[macro_export]
+macro_rules! routes {
+    ($app:expr, { $($method:ident $path:literal => $handler:expr),* $(,)? }) => {{
+        $( $crate::routes!(@route $app, $method, $path, $handler); )*
+    }};
+    (@route $app:expr, GET, $path:expr, $handler:expr) => { $app.get($path, $handler) };
+    (@route $app:expr, POST, $path:expr, $handler:expr) => { $app.post($path, $handler) };
+    (@route $app:expr, PUT, $path:expr, $handler:expr) => { $app.put($path, $handler) };
+    (@route $app:expr, DELETE, $path:expr, $handler:expr) => { $app.delete($path, $handler) };
+    (@route $app:expr, PATCH, $path:expr, $handler:expr) => { $app.patch($path, $handler) };
+    (@route $app:expr, OPTIONS, $path:expr, $handler:expr) => { $app.options($path, $handler) };
+    (@route $app:expr, $other:ident, $path:expr, $handler:expr) => {
+        compile_error!(concat!(
+            "routes!: unsupported HTTP method `",
+            stringify!($other),
+            "` (expected GET, POST, PUT, DELETE, PATCH or OPTIONS)"
+        ))
+    };
+}
+
+/// Reads the attached pool's current [`PoolStats`] for [`Ultimo::debug_config_route`].
+#[cfg(feature = "database")]
+type PoolStatsFn = Arc<dyn Fn() -> PoolStats + Send + Sync>;
+
+/// Round-trips to the attached pool for [`Ultimo::health_check`].
+#[cfg(feature = "database")]
+type PoolPingFn = Arc<
+    dyn Fn() -> futures_util::future::BoxFuture<'static, std::result::Result<(), DatabaseError>>
         + Send
         + Sync,
 >;
 
+/// A one-shot async lifecycle hook registered with [`Ultimo::on_startup`] or
+/// [`Ultimo::on_shutdown`].
+type LifecycleHook =
+    Box<dyn FnOnce() -> futures_util::future::BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// An async readiness check for [`Ultimo::readiness_route`]; `Err` marks the
+/// dependency as not ready.
+pub type ReadinessCheck =
+    Box<dyn Fn() -> futures_util::future::BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Request details passed to an [`Ultimo::on_error`] hook.
+#[derive(Debug, Clone)]
+pub struct ErrorInfo {
+    /// The request method.
+    pub method: hyper::Method,
+    /// The request path, without the query string.
+    pub path: String,
+    /// The request headers (e.g. to echo an `X-Request-Id` or pick a language).
+    pub headers: hyper::HeaderMap,
+}
+
+/// Renders a handler or middleware error; see [`Ultimo::on_error`].
+type ErrorHook = Arc<dyn Fn(&UltimoError, &ErrorInfo) -> Response + Send + Sync>;
+
 /// Main Ultimo application
 pub struct Ultimo {
     router: Router,
     handlers: Vec<BoxedHandler>,
-    middleware: Vec<BoxedMiddleware>,
-    max_body_size: Option<usize>,
+    /// Per-route body-size and content-type constraints, indexed like `handlers`.
+    route_limits: Vec<RouteLimits>,
+    /// OpenAPI details per route, indexed like `handlers`.
+    route_docs: Vec<RouteDocs>,
+    /// Named TypeScript types declared with [`Ultimo::openapi_schema`].
+    openapi_schemas: Vec<(String, String)>,
+    middleware: Vec<(String, BoxedMiddleware)>,
+    max_body_size: usize,
     trust_proxy: bool,
+    json_charset: bool,
+    /// Also serve HTTP/2 (prior knowledge) on the listener.
+    http2: bool,
+    /// CA bundle and mode for client certificates, see [`Ultimo::tls_client_auth`].
+    #[cfg(feature = "tls")]
+    tls_client_auth: Option<(std::path::PathBuf, crate::tls::ClientAuth)>,
+
+    startup_hooks: Vec<LifecycleHook>,
+    shutdown_hooks: Vec<LifecycleHook>,
+    shutdown_timeout: Duration,
+    error_hook: Option<ErrorHook>,
+
+    /// Shared values attached with [`Ultimo::with_state`], keyed by type.
+    app_state: Arc<hyper::http::Extensions>,
+    /// Registries exposed with `mount_rpc`, by mount path, for
+    /// `ULTIMO_EMIT_RPC_SCHEMA`.
+    rpc_mounts: Vec<(String, crate::RpcRegistry)>,
+
+    /// Snapshot served by [`Ultimo::debug_config_route`], taken on first dispatch.
+    debug_config: Option<Arc<OnceLock<serde_json::Value>>>,
+
+    /// Listing served by [`Ultimo::enable_route_listing`], taken on first dispatch.
+    route_listing: Option<Arc<OnceLock<serde_json::Value>>>,
 
     #[cfg(feature = "database")]
     database: Option<Database>,
 
+    /// Extra pools attached with [`Ultimo::with_named_pool`].
+    #[cfg(feature = "database")]
+    named_databases: Arc<HashMap<String, Database>>,
+
+    #[cfg(feature = "database")]
+    pool_stats: Option<PoolStatsFn>,
+
+    #[cfg(feature = "database")]
+    pool_ping: Option<PoolPingFn>,
+
+    /// Pinger used by [`Ultimo::health_check`] routes, resolved on first
+    /// dispatch so the pool may be attached after the route.
+    #[cfg(feature = "database")]
+    health_ping: Option<Arc<OnceLock<Option<PoolPingFn>>>>,
+
     #[cfg(feature = "websocket")]
     websocket_routes: HashMap<String, BoxedWebSocketHandler>,
 
@@ -70,11 +197,34 @@ impl Ultimo {
         let mut app = Self {
             router: Router::new(),
             handlers: Vec::new(),
+            route_limits: Vec::new(),
+            route_docs: Vec::new(),
+            openapi_schemas: Vec::new(),
             middleware: Vec::new(),
-            max_body_size: None,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
             trust_proxy: false,
+            json_charset: false,
+            http2: false,
+            #[cfg(feature = "tls")]
+            tls_client_auth: None,
+            startup_hooks: Vec::new(),
+            shutdown_hooks: Vec::new(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            error_hook: None,
+            app_state: Arc::default(),
+            rpc_mounts: Vec::new(),
+            debug_config: None,
+            route_listing: None,
             #[cfg(feature = "database")]
             database: None,
+            #[cfg(feature = "database")]
+            named_databases: Arc::default(),
+            #[cfg(feature = "database")]
+            pool_stats: None,
+            #[cfg(feature = "database")]
+            pool_ping: None,
+            #[cfg(feature = "database")]
+            health_ping: None,
             #[cfg(feature = "websocket")]
             websocket_routes: HashMap::new(),
             #[cfg(feature = "websocket")]
@@ -84,8 +234,7 @@ impl Ultimo {
         };
 
         // Add X-Powered-By header by default (like Express.js)
-        app.middleware
-            .push(crate::middleware::builtin::powered_by());
+        app.use_named_middleware("powered_by", crate::middleware::builtin::powered_by());
 
         app
     }
@@ -98,11 +247,34 @@ impl Ultimo {
         Self {
             router: Router::new(),
             handlers: Vec::new(),
+            route_limits: Vec::new(),
+            route_docs: Vec::new(),
+            openapi_schemas: Vec::new(),
             middleware: Vec::new(),
-            max_body_size: None,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
             trust_proxy: false,
+            json_charset: false,
+            http2: false,
+            #[cfg(feature = "tls")]
+            tls_client_auth: None,
+            startup_hooks: Vec::new(),
+            shutdown_hooks: Vec::new(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            error_hook: None,
+            app_state: Arc::default(),
+            rpc_mounts: Vec::new(),
+            debug_config: None,
+            route_listing: None,
             #[cfg(feature = "database")]
             database: None,
+            #[cfg(feature = "database")]
+            named_databases: Arc::default(),
+            #[cfg(feature = "database")]
+            pool_stats: None,
+            #[cfg(feature = "database")]
+            pool_ping: None,
+            #[cfg(feature = "database")]
+            health_ping: None,
             #[cfg(feature = "websocket")]
             websocket_routes: HashMap::new(),
             #[cfg(feature = "websocket")]
@@ -115,14 +287,18 @@ impl Ultimo {
     /// Set the maximum request body size in bytes.
     ///
     /// Requests whose body exceeds this are rejected with **413 Payload Too
-    /// Large** (and, on the live server, the oversized body is not buffered).
-    /// Defaults to no limit — setting one is recommended for production.
+    /// Large**: up front when `Content-Length` declares more, otherwise when
+    /// the handler reads past the limit (the oversized body is never
+    /// buffered).
+    /// Defaults to 2 MB. Routes that need more (or less), such as upload
+    /// endpoints, can override it with [`RouteGroup::max_body_size`] or
+    /// [`RouteBuilder::max_body_size`].
     pub fn max_body_size(&mut self, bytes: usize) -> &mut Self {
-        self.max_body_size = Some(bytes);
+        self.max_body_size = bytes;
         self
     }
 
-    /// Trust `X-Forwarded-For` / `Forwarded` headers for [`Context::client_ip`].
+    /// Trust `X-Forwarded-For` / `X-Real-IP` / `Forwarded` headers for [`Context::client_ip`].
     ///
     /// **Only enable when the app sits behind a trusted proxy/load balancer** —
     /// these headers are client-spoofable, so trusting them on a directly-exposed
@@ -132,12 +308,190 @@ impl Ultimo {
         self
     }
 
+    /// Send `Content-Type: application/json; charset=utf-8` from
+    /// [`Context::json`] instead of the default `application/json`.
+    ///
+    /// The charset parameter is redundant for JSON (it is always UTF-8) but
+    /// some clients require it. Defaults to `false`.
+    pub fn json_charset(&mut self, enabled: bool) -> &mut Self {
+        self.json_charset = enabled;
+        self
+    }
+
+    /// Serve HTTP/2 alongside HTTP/1.1 on the same listener.
+    ///
+    /// Each connection's protocol is detected from its first bytes: a client
+    /// that opens with the HTTP/2 preface (cleartext `h2c` with prior
+    /// knowledge, as proxies and gRPC-style clients do) gets HTTP/2 with
+    /// multiplexed streams; everyone else gets HTTP/1.1 as before. WebSocket
+    /// upgrades keep working over HTTP/1.1. Over TLS (see
+    /// [`listen_tls`](Self::listen_tls)), ALPN also offers `h2`.
+    /// Defaults to `false`.
+    ///
+    /// ```rust,no_run
+    /// # use ultimo::prelude::*;
+    /// let mut app = Ultimo::new();
+    /// app.http2(true);
+    /// ```
+    pub fn http2(&mut self, enabled: bool) -> &mut Self {
+        self.http2 = enabled;
+        self
+    }
+
+    /// Ask TLS clients for a certificate issued by one of the PEM CA
+    /// certificates in `ca_path` (mutual TLS). Takes effect with
+    /// [`listen_tls`](Self::listen_tls) and its variants; handlers read the
+    /// verified certificate with [`Context::client_cert`].
+    ///
+    /// With [`ClientAuth::Required`](crate::tls::ClientAuth::Required) the
+    /// handshake fails without a valid certificate; with
+    /// [`ClientAuth::Optional`](crate::tls::ClientAuth::Optional) clients may
+    /// connect without one and are left to the handlers to turn away.
+    ///
+    /// ```rust,no_run
+    /// # use ultimo::prelude::*;
+    /// use ultimo::tls::ClientAuth;
+    ///
+    /// # async fn run() -> ultimo::Result<()> {
+    /// let mut app = Ultimo::new();
+    /// app.tls_client_auth("certs/client-ca.pem", ClientAuth::Required);
+    /// app.get("/whoami", |ctx: Context| async move {
+    ///     let cn = ctx.client_cert().and_then(|cert| cert.common_name());
+    ///     ctx.text(cn.unwrap_or("anonymous").to_string()).await
+    /// });
+    /// app.listen_tls("0.0.0.0:443", "certs/cert.pem", "certs/key.pem")
+    ///     .await
+    /// # }
+    /// ```
+    #[cfg(feature = "tls")]
+    pub fn tls_client_auth(
+        &mut self,
+        ca_path: impl Into<std::path::PathBuf>,
+        mode: crate::tls::ClientAuth,
+    ) -> &mut Self {
+        self.tls_client_auth = Some((ca_path.into(), mode));
+        self
+    }
+
+    /// Set how a trailing slash on the request path is matched.
+    ///
+    /// [`TrailingSlash::Ignore`] (the default) lets `/users/` reach `/users`
+    /// and vice versa. `Strict` only matches the form a route was registered
+    /// with, and `Redirect` answers the other form with a `301` to it.
+    pub fn trailing_slash(&mut self, mode: TrailingSlash) -> &mut Self {
+        self.router.set_trailing_slash(mode);
+        self
+    }
+
+    /// Answer `HEAD` requests with the matching `GET` route when no `HEAD`
+    /// route is registered for the path. The handler runs as for `GET`; the
+    /// body is dropped from the response, keeping its headers and
+    /// `Content-Length`. Enabled by default.
+    pub fn auto_head(&mut self, enabled: bool) -> &mut Self {
+        self.router.set_auto_head(enabled);
+        self
+    }
+
+    /// Run `hook` once after the listener is bound, before any connection is
+    /// accepted — e.g. to warm caches or run migrations.
+    ///
+    /// Hooks run in registration order. If one fails, `listen` returns its
+    /// error without serving (and without running shutdown hooks).
+    pub fn on_startup<F, Fut>(&mut self, hook: F) -> &mut Self
+    where
+        F: FnOnce() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<()>> + Send + 'static,
+    {
+        self.startup_hooks.push(Box::new(move || Box::pin(hook())));
+        self
+    }
+
+    /// Run `hook` once when the server stops, e.g. to flush buffers or close
+    /// pools.
+    ///
+    /// Hooks run in registration order; a failing hook is logged and the
+    /// remaining hooks still run.
+    pub fn on_shutdown<F, Fut>(&mut self, hook: F) -> &mut Self
+    where
+        F: FnOnce() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<()>> + Send + 'static,
+    {
+        self.shutdown_hooks.push(Box::new(move || Box::pin(hook())));
+        self
+    }
+
+    /// How long [`listen_with_shutdown`](Self::listen_with_shutdown) waits for
+    /// in-flight requests and WebSocket closes before returning anyway.
+    /// Defaults to 30 seconds.
+    pub fn shutdown_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Render errors returned by handlers and middleware with `hook` instead of
+    /// the default JSON body from [`response::helpers::error_response`].
+    ///
+    /// Use it to add a request id, localize messages, or serve HTML error
+    /// pages. A handler or middleware that panics reaches the hook as an
+    /// [`UltimoError::Internal`]. Registering a new hook replaces the
+    /// previous one.
+    ///
+    /// ```
+    /// use ultimo::{response::ResponseBuilder, Ultimo};
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.on_error(|err, info| {
+    ///     ResponseBuilder::new()
+    ///         .status(err.status_code())
+    ///         .html(format!("<h1>{}</h1><p>{}</p>", err.status_code(), info.path))
+    ///         .build()
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn on_error<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&UltimoError, &ErrorInfo) -> Response + Send + Sync + 'static,
+    {
+        self.error_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Turn a handler/middleware error into a response, through the
+    /// [`on_error`](Self::on_error) hook when one is set.
+    fn render_error(&self, err: &UltimoError, info: Option<&ErrorInfo>) -> Response {
+        if let (Some(hook), Some(info)) = (&self.error_hook, info) {
+            return hook(err, info);
+        }
+        response::helpers::error_response(err)
+            .unwrap_or_else(|_| response::helpers::text("Internal Error").unwrap())
+    }
+
+    /// Capture the request details for the error hook, only if one is set.
+    fn error_info(&self, parts: &hyper::http::request::Parts) -> Option<ErrorInfo> {
+        self.error_hook.as_ref().map(|_| ErrorInfo {
+            method: parts.method.clone(),
+            path: parts.uri.path().to_string(),
+            headers: parts.headers.clone(),
+        })
+    }
+
     /// Attach a SQLx database pool to the application
     #[cfg(feature = "sqlx")]
     pub fn with_sqlx<DB>(&mut self, pool: crate::database::sqlx::SqlxPool<DB>) -> &mut Self
     where
         DB: sqlx::Database + 'static,
     {
+        let stats_pool = pool.clone();
+        self.pool_stats = Some(Arc::new(move || stats_pool.stats()));
+        let ping_pool = pool.pool().clone();
+        self.pool_ping = Some(Arc::new(move || {
+            let pool = ping_pool.clone();
+            Box::pin(async move {
+                use sqlx::Connection;
+                pool.acquire().await?.ping().await?;
+                Ok(())
+            })
+        }));
         self.database = Some(Database::from_sqlx(pool));
         self
     }
@@ -151,40 +505,207 @@ impl Ultimo {
     where
         Conn: diesel::Connection + diesel::r2d2::R2D2Connection + 'static,
     {
+        let stats_pool = pool.clone();
+        self.pool_stats = Some(Arc::new(move || stats_pool.stats()));
+        let ping_pool = pool.clone();
+        self.pool_ping = Some(Arc::new(move || {
+            let pool = ping_pool.clone();
+            // Checking out a connection blocks, so keep it off the runtime.
+            Box::pin(async move {
+                tokio::task::spawn_blocking(move || pool.get().map(drop))
+                    .await
+                    .map_err(|e| DatabaseError::Pool(e.to_string()))?
+            })
+        }));
         self.database = Some(Database::from_diesel(pool));
         self
     }
 
+    /// Attach an additional pool under `name`, alongside the default pool
+    /// from [`with_sqlx`](Self::with_sqlx) / [`with_diesel`](Self::with_diesel).
+    ///
+    /// Handlers reach it with [`Context::sqlx_named`] or
+    /// [`Context::diesel_named`]. Use it for a read replica next to the
+    /// primary, or a second database. Attaching the same name again replaces
+    /// the earlier pool. Named pools are not covered by
+    /// [`health_check`](Self::health_check) or the debug pool stats.
+    ///
+    /// ```rust,ignore
+    /// app.with_sqlx(SqlxPool::<Postgres>::connect(&primary_url).await?);
+    /// app.with_named_pool("replica", SqlxPool::<Postgres>::connect(&replica_url).await?);
+    ///
+    /// app.get("/users", |ctx: Context| async move {
+    ///     let db = ctx.sqlx_named::<Postgres>("replica")?;
+    ///     // ...
+    /// });
+    /// ```
+    #[cfg(feature = "database")]
+    pub fn with_named_pool(
+        &mut self,
+        name: impl Into<String>,
+        pool: impl Into<Database>,
+    ) -> &mut Self {
+        Arc::make_mut(&mut self.named_databases).insert(name.into(), pool.into());
+        self
+    }
+
+    /// Share `state` with every handler and middleware, retrieved with
+    /// [`Context::state`].
+    ///
+    /// The value is stored once in an `Arc` and handed out by type, so
+    /// handlers don't need a captured clone each. Use interior mutability
+    /// (`Mutex`, `RwLock`, atomics) for anything that changes. Calling it
+    /// again with the same type replaces the earlier value; different types
+    /// sit side by side.
+    ///
+    /// ```rust,no_run
+    /// use std::sync::Mutex;
+    /// use ultimo::prelude::*;
+    ///
+    /// struct AppState {
+    ///     visits: Mutex<u64>,
+    /// }
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.with_state(AppState { visits: Mutex::new(0) });
+    /// app.get("/", |ctx: Context| async move {
+    ///     let state = ctx.state::<AppState>()?;
+    ///     let visits = {
+    ///         let mut visits = state.visits.lock().unwrap();
+    ///         *visits += 1;
+    ///         *visits
+    ///     };
+    ///     ctx.json(json!({ "visits": visits })).await
+    /// });
+    /// ```
+    pub fn with_state<S: Send + Sync + 'static>(&mut self, state: S) -> &mut Self {
+        Arc::make_mut(&mut self.app_state).insert(Arc::new(state));
+        self
+    }
+
+    /// Start a route at `path` with its own constraints, checked before any
+    /// middleware or the handler runs: a body-size limit (`413` past it) and
+    /// the content types its requests may send (`415` otherwise).
+    ///
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.route("/imports")
+    ///     .content_type("application/json")
+    ///     .max_body_size(10 * 1024 * 1024)
+    ///     .post(|ctx: Context| async move {
+    ///         let rows: Vec<serde_json::Value> = ctx.req.json().await?;
+    ///         ctx.json(json!({ "imported": rows.len() })).await
+    ///     });
+    /// ```
+    pub fn route(&mut self, path: &str) -> RouteBuilder<'_> {
+        RouteBuilder::new(Target::App(self), path)
+    }
+
     /// Add a GET route
-    pub fn get(&mut self, path: &str, handler: impl IntoHandler + 'static) -> &mut Self {
+    pub fn get<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
         self.add_route(Method::GET, path, handler)
     }
 
     /// Add a POST route
-    pub fn post(&mut self, path: &str, handler: impl IntoHandler + 'static) -> &mut Self {
+    pub fn post<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
         self.add_route(Method::POST, path, handler)
     }
 
     /// Add a PUT route
-    pub fn put(&mut self, path: &str, handler: impl IntoHandler + 'static) -> &mut Self {
+    pub fn put<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
         self.add_route(Method::PUT, path, handler)
     }
 
     /// Add a DELETE route
-    pub fn delete(&mut self, path: &str, handler: impl IntoHandler + 'static) -> &mut Self {
+    pub fn delete<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
         self.add_route(Method::DELETE, path, handler)
     }
 
     /// Add a PATCH route
-    pub fn patch(&mut self, path: &str, handler: impl IntoHandler + 'static) -> &mut Self {
+    pub fn patch<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
         self.add_route(Method::PATCH, path, handler)
     }
 
     /// Add an OPTIONS route
-    pub fn options(&mut self, path: &str, handler: impl IntoHandler + 'static) -> &mut Self {
+    pub fn options<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
         self.add_route(Method::OPTIONS, path, handler)
     }
 
+    /// Add a GET route with middleware that runs only for it, after global
+    /// middleware and in the order given. Use a [`group`](Self::group) to
+    /// share middleware between several routes.
+    ///
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    /// use ultimo::middleware::builtin::logger;
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.get("/health", |ctx: Context| async move { ctx.text("ok").await });
+    /// app.get_with("/admin/stats", [logger()], |ctx: Context| async move {
+    ///     ctx.json(json!({ "users": 42 })).await
+    /// });
+    /// ```
+    pub fn get_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::GET, path, middleware, handler)
+    }
+
+    /// Add a POST route with route-specific middleware; see [`get_with`](Self::get_with).
+    pub fn post_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::POST, path, middleware, handler)
+    }
+
+    /// Add a PUT route with route-specific middleware; see [`get_with`](Self::get_with).
+    pub fn put_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::PUT, path, middleware, handler)
+    }
+
+    /// Add a DELETE route with route-specific middleware; see [`get_with`](Self::get_with).
+    pub fn delete_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::DELETE, path, middleware, handler)
+    }
+
+    /// Add a PATCH route with route-specific middleware; see [`get_with`](Self::get_with).
+    pub fn patch_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::PATCH, path, middleware, handler)
+    }
+
+    /// Add an OPTIONS route with route-specific middleware; see [`get_with`](Self::get_with).
+    pub fn options_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::OPTIONS, path, middleware, handler)
+    }
+
     /// Add a WebSocket route
     ///
     /// # Example
@@ -234,70 +755,231 @@ impl Ultimo {
     where
         H: WebSocketHandler<Data = ()> + 'static,
     {
-        let handler = Arc::new(handler);
-        let channel_manager = self.channel_manager.clone();
-
-        let ws_handler = move |upgrade: WebSocketUpgrade<()>| {
-            let handler = handler.clone();
-            let upgrade = upgrade
-                .with_data(())
-                .with_channel_manager(channel_manager.clone())
-                .with_config(config.clone());
-
-            upgrade.on_upgrade_with_receiver(move |ws, mut incoming_rx, mut drain_rx| {
-                let handler = handler.clone();
-                async move {
-                    // Call on_open
-                    handler.on_open(&ws).await;
-
-                    // Handle incoming messages and drain notifications
-                    loop {
-                        tokio::select! {
-                            Some(msg) = incoming_rx.recv() => {
-                                handler.on_message(&ws, msg).await;
-                            }
-                            Some(_) = drain_rx.recv() => {
-                                handler.on_drain(&ws).await;
-                            }
-                            else => break,
-                        }
-                    }
+        self.websocket_route(path).config(config).handler(handler)
+    }
 
-                    // Call on_close when connection ends
-                    handler.on_close(&ws, 1000, "Connection closed").await;
-                }
-            })
-        };
+    /// Start a WebSocket route at `path`: optionally gate the handshake with
+    /// a [`guard`](WebSocketRoute::guard) and set a
+    /// [`config`](WebSocketRoute::config), then register one
+    /// [`handler`](WebSocketRoute::handler) or a
+    /// [`protocols`](WebSocketRoute::protocols) table.
+    ///
+    /// ```rust,ignore
+    /// use ultimo::response::ResponseBuilder;
+    /// use ultimo::websocket::{WebSocketConfig, WebSocketProtocols};
+    ///
+    /// app.websocket_route("/ws")
+    ///     .guard(|req| match req.headers().get("x-user") {
+    ///         Some(user) => Ok(user.to_str().unwrap_or_default().to_string()),
+    ///         None => Err(ResponseBuilder::new().status(401).body("login required").build().unwrap()),
+    ///     })
+    ///     .config(WebSocketConfig { compression: true, ..Default::default() })
+    ///     .protocols(
+    ///         WebSocketProtocols::new()
+    ///             .protocol("chat", ChatHandler)
+    ///             .protocol("admin", AdminHandler),
+    ///     );
+    /// ```
+    #[cfg(feature = "websocket")]
+    pub fn websocket_route(&mut self, path: &str) -> WebSocketRoute<'_> {
+        WebSocketRoute::new(self, path)
+    }
 
-        self.websocket_routes
-            .insert(path.to_string(), Arc::new(ws_handler));
+    #[cfg(feature = "websocket")]
+    pub(crate) fn add_websocket_route(
+        &mut self,
+        path: &str,
+        handler: BoxedWebSocketHandler,
+    ) -> &mut Self {
+        self.websocket_routes.insert(path.to_string(), handler);
         self
     }
 
+    /// The app's WebSocket [`ChannelManager`], for publishing to channels from
+    /// outside a connection — an HTTP handler, a webhook, a background task.
+    ///
+    /// All WebSocket routes on this app share it, so a message published here
+    /// reaches every socket subscribed to the topic.
+    ///
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    /// use ultimo::websocket::Message;
+    ///
+    /// let mut app = Ultimo::new();
+    /// let channels = app.channels();
+    /// app.post("/broadcast", move |ctx: Context| {
+    ///     let channels = channels.clone();
+    ///     async move {
+    ///         let text: String = ctx.req.text().await?;
+    ///         let sent = channels.publish("lobby", Message::Text(text)).await?;
+    ///         ctx.json(json!({ "delivered": sent })).await
+    ///     }
+    /// });
+    /// ```
+    #[cfg(feature = "websocket")]
+    pub fn channels(&self) -> Arc<ChannelManager> {
+        self.channel_manager.clone()
+    }
+
     /// Add a route with any method
-    fn add_route(
+    fn add_route<M>(
+        &mut self,
+        method: Method,
+        path: &str,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_boxed_route(
+            method,
+            path,
+            handler.into_handler(),
+            RouteLimits::default(),
+            RouteDocs::default(),
+        )
+    }
+
+    fn add_route_with<M>(
+        &mut self,
+        method: Method,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        let handler = with_middleware(handler.into_handler(), middleware);
+        self.add_boxed_route(
+            method,
+            path,
+            handler,
+            RouteLimits::default(),
+            RouteDocs::default(),
+        )
+    }
+
+    /// Add a GET route for one of the framework's own endpoints, which
+    /// [`generate_openapi`](Self::generate_openapi) leaves out.
+    fn add_internal_route<M>(
+        &mut self,
+        path: &str,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_boxed_route(
+            Method::GET,
+            path,
+            handler.into_handler(),
+            RouteLimits::default(),
+            RouteDocs {
+                hidden: true,
+                ..RouteDocs::default()
+            },
+        )
+    }
+
+    fn add_boxed_route(
         &mut self,
         method: Method,
         path: &str,
-        handler: impl IntoHandler + 'static,
+        handler: BoxedHandler,
+        limits: RouteLimits,
+        docs: RouteDocs,
     ) -> &mut Self {
         let handler_id = self.handlers.len();
-        self.handlers.push(handler.into_handler());
+        self.handlers.push(handler);
+        self.route_limits.push(limits);
+        self.route_docs.push(docs);
         self.router.add_route(method, path, handler_id);
         self
     }
 
+    pub(crate) fn add_group_routes(&mut self, routes: Vec<GroupRoute>) -> &mut Self {
+        for route in routes {
+            self.add_boxed_route(
+                route.method,
+                &route.path,
+                route.handler,
+                route.limits,
+                route.docs,
+            );
+        }
+        self
+    }
+
+    /// Register routes under a shared path `prefix`, optionally with
+    /// middleware that runs only for those routes.
+    ///
+    /// Group middleware runs after global middleware and before the handler.
+    /// Groups can be nested with [`RouteGroup::group`].
+    ///
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.group("/api/v1", |api| {
+    ///     api.use_middleware(ultimo::middleware::builtin::logger());
+    ///     api.get("/users/:id", |ctx: Context| async move {
+    ///         let id = ctx.req.param("id")?;
+    ///         ctx.json(json!({ "id": id })).await
+    ///     });
+    /// });
+    /// ```
+    pub fn group(&mut self, prefix: &str, configure: impl FnOnce(&mut RouteGroup)) -> &mut Self {
+        let mut group = RouteGroup::new(prefix);
+        configure(&mut group);
+        self.add_group_routes(group.into_routes())
+    }
+
+    /// Create a standalone router to be attached later with [`mount`](Self::mount).
+    ///
+    /// Unlike [`group`](Self::group), the router is a value: it can be built in
+    /// another module, returned from a function and passed around.
+    ///
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    /// use ultimo::group::RouteGroup;
+    ///
+    /// fn users() -> RouteGroup {
+    ///     let mut users = Ultimo::router();
+    ///     users.get("/", |ctx: Context| async move { ctx.json(json!([])).await });
+    ///     users.get("/:id", |ctx: Context| async move {
+    ///         let id = ctx.req.param("id")?;
+    ///         ctx.json(json!({ "id": id })).await
+    ///     });
+    ///     users
+    /// }
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.mount("/users", users());
+    /// ```
+    pub fn router() -> RouteGroup {
+        RouteGroup::default()
+    }
+
+    /// Attach `router`'s routes under `prefix`. Middleware added to the router
+    /// runs only for its routes, after global middleware.
+    pub fn mount(&mut self, prefix: &str, router: RouteGroup) -> &mut Self {
+        self.add_group_routes(router.into_routes_under(prefix))
+    }
+
     /// Add global middleware
     pub fn use_middleware(&mut self, middleware: BoxedMiddleware) -> &mut Self {
-        self.middleware.push(middleware);
+        self.use_named_middleware("anonymous", middleware)
+    }
+
+    /// Add global middleware under a display name, as listed by
+    /// [`debug_config_route`](Self::debug_config_route).
+    pub fn use_named_middleware(
+        &mut self,
+        name: impl Into<String>,
+        middleware: BoxedMiddleware,
+    ) -> &mut Self {
+        self.middleware.push((name.into(), middleware));
         self
     }
 
     /// Serve static files from `dir` under the URL prefix `prefix`.
     ///
     /// Registers a `GET {prefix}/*path` route. Responds with the correct
-    /// `Content-Type`, sets an `ETag`, and handles `If-None-Match` → 304.
-    /// Path traversal attempts return 404.
+    /// `Content-Type`, sets `ETag` and `Last-Modified`, and answers
+    /// `If-None-Match` / `If-Modified-Since` with 304 when the file is
+    /// unchanged. Path traversal attempts return 404.
     ///
     /// Requires the `static-files` Cargo feature.
     ///
@@ -309,35 +991,76 @@ impl Ultimo {
     /// ```
     #[cfg(feature = "static-files")]
     pub fn serve_static(&mut self, prefix: &str, dir: impl Into<std::path::PathBuf>) -> &mut Self {
-        let root = dir.into();
-        let pattern = format!("{}/*path", prefix.trim_end_matches('/'));
-        self.get(&pattern, move |ctx: Context| {
-            let root = root.clone();
-            async move {
-                let rel = ctx.req.param("path")?.to_string();
-                let inm = ctx.req.header("if-none-match");
-                crate::static_files::serve_file(&root, &rel, inm).await
-            }
-        });
-        self
+        self.add_static_route(prefix, dir.into(), None)
     }
 
-    /// Serve a Single Page Application from `dir`.
-    ///
-    /// Any `GET` request that returns 404 (no matching route) is answered
-    /// with `dir/fallback` instead, enabling client-side routing.
-    ///
-    /// Mount API routes **before** calling `serve_spa` so they take
-    /// precedence.
+    /// [`serve_static`](Self::serve_static), also sending `cache_control` as
+    /// the `Cache-Control` header of every file (and 304).
     ///
     /// Requires the `static-files` Cargo feature.
     ///
     /// ```rust,no_run
     /// use ultimo::prelude::*;
+    /// use ultimo::response::CacheControl;
     ///
     /// let mut app = Ultimo::new();
-    /// app.get("/api/hello", |ctx: Context| async move {
-    ///     ctx.json(serde_json::json!({ "ok": true })).await
+    /// app.serve_static_with_cache(
+    ///     "/assets",
+    ///     "./public/assets",
+    ///     CacheControl::new().public().max_age(31_536_000),
+    /// );
+    /// ```
+    #[cfg(feature = "static-files")]
+    pub fn serve_static_with_cache(
+        &mut self,
+        prefix: &str,
+        dir: impl Into<std::path::PathBuf>,
+        cache_control: crate::response::CacheControl,
+    ) -> &mut Self {
+        self.add_static_route(prefix, dir.into(), Some(cache_control.to_string()))
+    }
+
+    #[cfg(feature = "static-files")]
+    fn add_static_route(
+        &mut self,
+        prefix: &str,
+        root: std::path::PathBuf,
+        cache_control: Option<String>,
+    ) -> &mut Self {
+        let pattern = format!("{}/*path", prefix.trim_end_matches('/'));
+        self.get(&pattern, move |ctx: Context| {
+            let root = root.clone();
+            let cache_control = cache_control.clone();
+            async move {
+                let rel = ctx.req.param("path")?;
+                crate::static_files::serve_file(
+                    &root,
+                    rel,
+                    Some(ctx.req.headers()),
+                    cache_control.as_deref(),
+                )
+                .await
+            }
+        });
+        self
+    }
+
+    /// Serve a Single Page Application from `dir`.
+    ///
+    /// Any `GET` request that returns 404 (no matching route) is answered
+    /// with `dir/fallback` instead, enabling client-side routing.
+    ///
+    /// Mount API routes **before** calling `serve_spa` so they take
+    /// precedence.
+    ///
+    /// Requires the `static-files` Cargo feature.
+    ///
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.get("/api/hello", |ctx: Context| async move {
+    ///     ctx.json(serde_json::json!({ "ok": true })).await
     /// });
     /// app.serve_spa("./dist", "index.html");
     /// ```
@@ -368,19 +1091,200 @@ impl Ultimo {
     /// ```
     pub fn serve_docs(&mut self, path: &str, spec: crate::openapi::OpenApiSpec) -> &mut Self {
         let path = path.trim_end_matches('/');
-        let spec_path = format!("{}/openapi.json", path);
-        let ui_html = spec.swagger_ui_html(&spec_path);
-        let spec_json = std::sync::Arc::new(spec);
+        self.serve_openapi(spec, &format!("{}/openapi.json", path), path)
+    }
+
+    /// Build an OpenAPI spec from the routes registered so far.
+    ///
+    /// Every GET, POST, PUT, DELETE and PATCH route becomes an operation, and
+    /// `:id` / `*rest` segments become `{id}` / `{rest}` path parameters (an
+    /// unnamed `*` becomes `{wildcard}`). The framework's own endpoints
+    /// ([`serve_openapi`](Self::serve_openapi), [`serve_metrics`](Self::serve_metrics),
+    /// [`debug_config_route`](Self::debug_config_route) and
+    /// [`enable_route_listing`](Self::enable_route_listing)) are left out.
+    /// Summaries, tags and request/response types attached with
+    /// [`route`](Self::route) are included; a route without documented
+    /// responses gets a bare `200`. Call it once the API is registered, then
+    /// pass the spec to [`serve_openapi`](Self::serve_openapi).
+    ///
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.openapi_schema("User", "{ id: number; name: string }");
+    /// app.route("/users/:id")
+    ///     .summary("Get a user")
+    ///     .response(200, "User")
+    ///     .empty_response(404)
+    ///     .get(|ctx: Context| async move { ctx.json(json!({ "id": 1, "name": "Ada" })).await });
+    /// app.delete("/users/:id", |ctx: Context| async move { ctx.no_content().await });
+    ///
+    /// let spec = app.generate_openapi("Users API", "1.0.0");
+    /// app.serve_openapi(spec, "/openapi.json", "/docs");
+    /// ```
+    pub fn generate_openapi(&self, title: &str, version: &str) -> crate::openapi::OpenApiSpec {
+        use crate::openapi::{
+            MediaType, OpenApiBuilder, Operation, Parameter, ParameterLocation, PathItem,
+            RequestBody, Response,
+        };
+        use std::collections::HashMap;
+
+        let mut spec = OpenApiBuilder::new().title(title).version(version).build();
+        spec.add_ts_schemas(
+            self.openapi_schemas
+                .iter()
+                .map(|(name, ts_type)| (name.as_str(), ts_type.as_str())),
+        );
+
+        for entry in self.router.routes() {
+            let docs = &self.route_docs[entry.handler_id];
+            if docs.hidden || matches!(entry.method, Method::HEAD | Method::OPTIONS) {
+                continue;
+            }
+            let limits = &self.route_limits[entry.handler_id];
+            let (path, params) = crate::openapi::path_template(entry.route.path());
+
+            let content = |ts_type: &str, media_types: &[String]| {
+                let schema = spec.ts_to_schema_with_refs(ts_type);
+                media_types
+                    .iter()
+                    .map(|media| {
+                        let media_type = MediaType {
+                            schema: schema.clone(),
+                            example: None,
+                        };
+                        (media.clone(), media_type)
+                    })
+                    .collect::<HashMap<_, _>>()
+            };
+            let json = ["application/json".to_string()];
+            let request_types = match limits.content_types.as_slice() {
+                [] => &json[..],
+                types => types,
+            };
+
+            let parameters: Vec<Parameter> = params
+                .into_iter()
+                .map(|name| Parameter {
+                    name,
+                    location: ParameterLocation::Path,
+                    description: None,
+                    required: Some(true),
+                    schema: crate::openapi::OpenApiSpec::ts_to_schema("string"),
+                })
+                .collect();
+            let mut responses: HashMap<String, Response> = docs
+                .responses
+                .iter()
+                .map(|(status, ts_type)| {
+                    let description = hyper::StatusCode::from_u16(*status)
+                        .ok()
+                        .and_then(|status| status.canonical_reason())
+                        .unwrap_or("Response");
+                    let response = Response {
+                        description: description.to_string(),
+                        content: ts_type.as_deref().map(|ts_type| content(ts_type, &json)),
+                    };
+                    (status.to_string(), response)
+                })
+                .collect();
+            if responses.is_empty() {
+                responses.insert(
+                    "200".to_string(),
+                    Response {
+                        description: "Successful response".to_string(),
+                        content: None,
+                    },
+                );
+            }
+            let operation = Operation {
+                summary: docs.summary.clone(),
+                description: None,
+                operation_id: None,
+                tags: (!docs.tags.is_empty()).then(|| docs.tags.clone()),
+                parameters: (!parameters.is_empty()).then_some(parameters),
+                request_body: docs.request_body.as_deref().map(|ts_type| RequestBody {
+                    description: None,
+                    content: content(ts_type, request_types),
+                    required: Some(true),
+                }),
+                responses,
+            };
+
+            let item = spec.paths.entry(path).or_insert(PathItem {
+                get: None,
+                post: None,
+                put: None,
+                delete: None,
+                patch: None,
+            });
+            let slot = match entry.method {
+                Method::GET => &mut item.get,
+                Method::POST => &mut item.post,
+                Method::PUT => &mut item.put,
+                Method::DELETE => &mut item.delete,
+                _ => &mut item.patch,
+            };
+            // The first registration is the one that serves requests.
+            slot.get_or_insert(operation);
+        }
+        spec
+    }
+
+    /// Declare a named TypeScript type for [`generate_openapi`](Self::generate_openapi).
+    /// It becomes a `components.schemas` entry, and route types that mention
+    /// it (`"User"`, `"User[]"`) reference it with `$ref`.
+    pub fn openapi_schema(&mut self, name: &str, ts_type: &str) -> &mut Self {
+        self.openapi_schemas
+            .push((name.to_string(), ts_type.to_string()));
+        self
+    }
+
+    /// Serve `spec` as JSON at `spec_path` and a Swagger UI page pointing at it
+    /// at `docs_path`.
+    ///
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    /// use ultimo::openapi::OpenApiBuilder;
+    ///
+    /// let mut app = Ultimo::new();
+    /// let spec = OpenApiBuilder::new().title("My API").build();
+    /// app.serve_openapi(spec, "/openapi.json", "/docs");
+    /// ```
+    pub fn serve_openapi(
+        &mut self,
+        spec: crate::openapi::OpenApiSpec,
+        spec_path: &str,
+        docs_path: &str,
+    ) -> &mut Self {
+        self.serve_openapi_with(
+            spec,
+            spec_path,
+            docs_path,
+            crate::openapi::DocsRenderer::SwaggerUi,
+        )
+    }
+
+    /// [`serve_openapi`](Self::serve_openapi) with a choice of documentation
+    /// page, e.g. [`DocsRenderer::Redoc`](crate::openapi::DocsRenderer::Redoc).
+    pub fn serve_openapi_with(
+        &mut self,
+        spec: crate::openapi::OpenApiSpec,
+        spec_path: &str,
+        docs_path: &str,
+        renderer: crate::openapi::DocsRenderer,
+    ) -> &mut Self {
+        let ui_html = spec.docs_html(renderer, spec_path);
+        let spec = std::sync::Arc::new(spec);
 
         // Serve the OpenAPI JSON spec
-        let spec_clone = spec_json.clone();
-        self.get(&spec_path, move |ctx: Context| {
-            let spec = spec_clone.clone();
+        self.add_internal_route(spec_path, move |ctx: Context| {
+            let spec = spec.clone();
             async move { ctx.json(spec.as_ref()).await }
         });
 
-        // Serve the Swagger UI page
-        self.get(path, move |ctx: Context| {
+        // Serve the documentation page
+        self.add_internal_route(docs_path, move |ctx: Context| {
             let html = ui_html.clone();
             async move { ctx.html(html).await }
         });
@@ -388,6 +1292,421 @@ impl Ultimo {
         self
     }
 
+    /// Expose every procedure in `registry` under `path`, according to its mode.
+    ///
+    /// - [`RpcMode::JsonRpc`](crate::rpc::RpcMode::JsonRpc): a single `POST path`
+    ///   endpoint dispatching through [`RpcRegistry::handle_request`](crate::RpcRegistry::handle_request)
+    ///   (single, batch, notifications; `204` when there is nothing to return).
+    /// - [`RpcMode::Rest`](crate::rpc::RpcMode::Rest): `GET path/<name>` per query
+    ///   (input from the query string) and `POST path/<name>` per mutation
+    ///   (input from the JSON body). Only procedures registered before this
+    ///   call get a route.
+    ///
+    /// Query-string values are read as JSON where they parse (`id=1` is the
+    /// number `1`) and as strings otherwise.
+    ///
+    /// A mutation whose input is an array (`T[]`) takes the raw JSON array as
+    /// its body, with no wrapper object; an empty body is `[]` rather than `{}`.
+    ///
+    /// With the `ULTIMO_EMIT_RPC_SCHEMA=<file>` environment variable set
+    /// ([`EMIT_RPC_SCHEMA_ENV`](crate::rpc::EMIT_RPC_SCHEMA_ENV)), every
+    /// `listen*` method, including [`listen_on`](Self::listen_on), writes the
+    /// mounted registry's [`RpcSchema`](crate::rpc::RpcSchema) to `file` and
+    /// returns instead of serving; `ultimo generate` uses this to build the
+    /// TypeScript client from the real registrations. Command-line arguments
+    /// are left to the app.
+    ///
+    /// ```rust,ignore
+    /// let rpc = RpcRegistry::new_with_mode(RpcMode::Rest);
+    /// rpc.query("getUser", get_user);
+    /// app.mount_rpc("/api", rpc); // GET /api/getUser?id=1
+    /// ```
+    pub fn mount_rpc(&mut self, path: &str, registry: crate::RpcRegistry) -> &mut Self {
+        let path = path.trim_end_matches('/');
+        self.rpc_mounts.push((path.to_string(), registry.clone()));
+        match registry.mode() {
+            crate::rpc::RpcMode::JsonRpc => {
+                let route = if path.is_empty() { "/" } else { path };
+                self.post(route, move |ctx: Context| {
+                    let rpc = registry.clone();
+                    async move {
+                        let body = ctx.req.bytes().await?;
+                        let output = rpc.handle_request(&body).await;
+                        if output.is_none() {
+                            ctx.status(204).await;
+                            return ctx.text("").await;
+                        }
+                        ctx.json(output).await
+                    }
+                });
+            }
+            crate::rpc::RpcMode::Rest => {
+                let array_inputs: std::collections::HashSet<String> = registry
+                    .schema()
+                    .procedures
+                    .into_iter()
+                    .filter(|p| crate::rpc::is_array_type(&p.input))
+                    .map(|p| p.name)
+                    .collect();
+                for procedure in registry.procedure_metadata() {
+                    let route = format!("{}/{}", path, procedure.name);
+                    let rpc = registry.clone();
+                    let name = procedure.name;
+                    if procedure.is_query {
+                        self.get(&route, move |ctx: Context| {
+                            let rpc = rpc.clone();
+                            let name = name.clone();
+                            async move {
+                                let input = query_input(ctx.req.queries());
+                                let output = rpc.call_query(&name, input).await?;
+                                ctx.json(output).await
+                            }
+                        });
+                    } else {
+                        let is_array = array_inputs.contains(&name);
+                        self.post(&route, move |ctx: Context| {
+                            let rpc = rpc.clone();
+                            let name = name.clone();
+                            async move {
+                                let body = ctx.req.bytes().await?;
+                                let input = if body.is_empty() && is_array {
+                                    serde_json::json!([])
+                                } else if body.is_empty() {
+                                    serde_json::json!({})
+                                } else {
+                                    serde_json::from_slice(&body)?
+                                };
+                                let output = rpc.call(&name, input).await?;
+                                ctx.json(output).await
+                            }
+                        });
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Serve mock responses for every operation documented in `spec`.
+    ///
+    /// Each path/method pair is registered as a route that answers with the
+    /// operation's documented success status and its `example` payload (or a
+    /// sample derived from the response schema). Request bodies are not
+    /// inspected — responses are static per operation. Path templates such as
+    /// `/users/{id}` are mapped to `/users/:id`.
+    ///
+    /// ```rust,ignore
+    /// let spec: OpenApiSpec = serde_json::from_str(&std::fs::read_to_string("openapi.json")?)?;
+    /// app.mock_from_spec(spec);
+    /// ```
+    pub fn mock_from_spec(&mut self, spec: crate::openapi::OpenApiSpec) -> &mut Self {
+        let mut paths: Vec<_> = spec.paths.iter().collect();
+        paths.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (path, item) in paths {
+            let route = openapi_path_to_route(path);
+            let operations = [
+                (Method::GET, &item.get),
+                (Method::POST, &item.post),
+                (Method::PUT, &item.put),
+                (Method::DELETE, &item.delete),
+                (Method::PATCH, &item.patch),
+            ];
+            for (method, operation) in operations {
+                let Some(operation) = operation else {
+                    continue;
+                };
+                let (status, body) = spec.mock_response(operation);
+                let body = Arc::new(body);
+                self.add_route(method, &route, move |ctx: Context| {
+                    let body = body.clone();
+                    async move {
+                        ctx.status(status).await;
+                        match body.as_ref() {
+                            Some(value) => ctx.json(value).await,
+                            None => response::ResponseBuilder::new().status(status).build(),
+                        }
+                    }
+                });
+            }
+        }
+
+        self
+    }
+
+    /// Register a liveness probe at `path` that always answers
+    /// `200 {"status":"ok"}` while the process is serving requests.
+    pub fn liveness_route(&mut self, path: &str) -> &mut Self {
+        self.get(path, |ctx: Context| async move {
+            ctx.json(serde_json::json!({ "status": "ok" })).await
+        })
+    }
+
+    /// Register a readiness probe at `path` backed by named async `checks`.
+    ///
+    /// All checks run concurrently on every probe. If every check returns
+    /// `Ok`, the route answers `200 {"status":"ready"}`; otherwise `503` with
+    /// the names of the failing checks (`{"status":"not_ready","failed":[..]}`).
+    /// Check errors are logged, not returned, so internals don't leak.
+    ///
+    /// ```rust,ignore
+    /// let pool = pool.clone();
+    /// app.readiness_route("/readyz", vec![(
+    ///     "database",
+    ///     Box::new(move || {
+    ///         let pool = pool.clone();
+    ///         Box::pin(async move {
+    ///             pool.health_check().await.map_err(|e| UltimoError::Internal(e.to_string()))
+    ///         })
+    ///     }),
+    /// )]);
+    /// ```
+    pub fn readiness_route(
+        &mut self,
+        path: &str,
+        checks: Vec<(&str, ReadinessCheck)>,
+    ) -> &mut Self {
+        let checks: Arc<Vec<(String, ReadinessCheck)>> = Arc::new(
+            checks
+                .into_iter()
+                .map(|(name, check)| (name.to_string(), check))
+                .collect(),
+        );
+        self.get(path, move |ctx: Context| {
+            let checks = checks.clone();
+            async move {
+                let results =
+                    futures_util::future::join_all(checks.iter().map(|(_, check)| check())).await;
+                let failed: Vec<&str> = checks
+                    .iter()
+                    .zip(results)
+                    .filter_map(|((name, _), result)| {
+                        let err = result.err()?;
+                        tracing::warn!(check = %name, error = %err, "readiness check failed");
+                        Some(name.as_str())
+                    })
+                    .collect();
+
+                if failed.is_empty() {
+                    ctx.json(serde_json::json!({ "status": "ready" })).await
+                } else {
+                    ctx.status(503).await;
+                    ctx.json(serde_json::json!({ "status": "not_ready", "failed": failed }))
+                        .await
+                }
+            }
+        })
+    }
+
+    /// Register a health endpoint at `path` that checks the attached
+    /// database pool.
+    ///
+    /// With a pool attached ([`with_sqlx`](Self::with_sqlx) or
+    /// [`with_diesel`](Self::with_diesel), before or after this call), each
+    /// request pings the database — a SQLx connection ping or a Diesel
+    /// connection checkout — and answers
+    /// `200 {"status":"healthy","database":"connected"}`, or `503` with
+    /// `"status":"unhealthy"` and `"error":"unavailable"`. A ping that takes
+    /// longer than five seconds counts as a failure. The underlying error is
+    /// logged, not returned. Without a pool it always answers
+    /// `200 {"status":"ok"}`.
+    #[cfg(feature = "database")]
+    pub fn health_check(&mut self, path: &str) -> &mut Self {
+        let slot = self
+            .health_ping
+            .get_or_insert_with(|| Arc::new(OnceLock::new()))
+            .clone();
+        self.get(path, move |ctx: Context| {
+            let ping = slot.get().cloned().flatten();
+            async move {
+                let Some(ping) = ping else {
+                    return ctx.json(serde_json::json!({ "status": "ok" })).await;
+                };
+                let result = match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, ping()).await {
+                    Ok(result) => result.map_err(|err| err.to_string()),
+                    Err(_) => Err("timed out".to_string()),
+                };
+                match result {
+                    Ok(()) => {
+                        ctx.json(serde_json::json!({
+                            "status": "healthy",
+                            "database": "connected",
+                        }))
+                        .await
+                    }
+                    Err(err) => {
+                        tracing::warn!(error = %err, "database health check failed");
+                        ctx.status(503).await;
+                        ctx.json(serde_json::json!({
+                            "status": "unhealthy",
+                            "database": "disconnected",
+                            "error": "unavailable",
+                        }))
+                        .await
+                    }
+                }
+            }
+        })
+    }
+
+    /// Serve request metrics in the Prometheus text format at `GET path`.
+    ///
+    /// The numbers are recorded by
+    /// [`middleware::builtin::metrics`](crate::middleware::builtin::metrics),
+    /// which must be installed too; without it the endpoint stays empty.
+    /// Like any route, the endpoint runs through global middleware, so it can
+    /// be put behind auth or an IP filter.
+    ///
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.use_middleware(ultimo::middleware::builtin::metrics());
+    /// app.serve_metrics("/metrics");
+    /// ```
+    pub fn serve_metrics(&mut self, path: &str) -> &mut Self {
+        let metrics = match self.app_state.get::<Arc<Metrics>>() {
+            Some(metrics) => metrics.clone(),
+            None => {
+                let metrics = Arc::new(Metrics::new());
+                Arc::make_mut(&mut self.app_state).insert(metrics.clone());
+                metrics
+            }
+        };
+        self.add_internal_route(path, move |_ctx: Context| {
+            let metrics = metrics.clone();
+            async move {
+                response::ResponseBuilder::new()
+                    .header("Content-Type", "text/plain; version=0.0.4; charset=utf-8")
+                    .body(metrics.render())
+                    .build()
+            }
+        })
+    }
+
+    /// Register a troubleshooting endpoint at `path` that reports the
+    /// effective configuration as JSON: body limit, proxy trust, enabled Cargo
+    /// features, middleware names in order, route counts and (with a database
+    /// attached) pool statistics.
+    ///
+    /// Off unless called, because the output exposes internals. The endpoint
+    /// answers only in-process and loopback requests (other peers get `404`).
+    /// Requests relayed by a proxy on the same host are refused too: loopback
+    /// requests carrying `Forwarded`, `X-Forwarded-For`, `X-Real-IP` or `Via`,
+    /// and every request once [`trust_proxy`](Self::trust_proxy) is on. A proxy
+    /// that adds none of those headers is indistinguishable from a local
+    /// client, so put the endpoint behind auth there; it runs through global
+    /// middleware like any route. The snapshot is taken when the first
+    /// request is served.
+    pub fn debug_config_route(&mut self, path: &str) -> &mut Self {
+        let snapshot = Arc::new(OnceLock::new());
+        self.debug_config = Some(snapshot.clone());
+        self.add_internal_route(path, move |ctx: Context| {
+            let snapshot = snapshot.clone();
+            async move {
+                if !ctx.is_local() {
+                    return response::helpers::not_found();
+                }
+                ctx.json(snapshot.get()).await
+            }
+        })
+    }
+
+    /// Register a development endpoint at `path` listing every registered
+    /// route as JSON: `[{"method":"GET","path":"/users/:id","websocket":false}, …]`.
+    ///
+    /// HTTP routes come in registration order with their patterns as written
+    /// (group prefixes included), followed by WebSocket routes sorted by path.
+    /// Like [`debug_config_route`](Self::debug_config_route) it is off unless
+    /// called and answers only in-process and loopback requests, refusing
+    /// those relayed by a proxy on the same host. The listing
+    /// is taken when the first request is served, so it covers routes
+    /// registered after this call.
+    pub fn enable_route_listing(&mut self, path: &str) -> &mut Self {
+        let listing = Arc::new(OnceLock::new());
+        self.route_listing = Some(listing.clone());
+        self.add_internal_route(path, move |ctx: Context| {
+            let listing = listing.clone();
+            async move {
+                if !ctx.is_local() {
+                    return response::helpers::not_found();
+                }
+                ctx.json(listing.get()).await
+            }
+        })
+    }
+
+    /// The routes reported by [`enable_route_listing`](Self::enable_route_listing).
+    fn route_listing(&self) -> serde_json::Value {
+        let routes = self.router.routes().iter().map(|entry| {
+            serde_json::json!({
+                "method": entry.method.as_str(),
+                "path": entry.route.path(),
+                "websocket": false,
+            })
+        });
+        #[cfg(feature = "websocket")]
+        let routes = {
+            let mut paths: Vec<&String> = self.websocket_routes.keys().collect();
+            paths.sort();
+            routes.chain(paths.into_iter().map(
+                |path| serde_json::json!({ "method": "GET", "path": path, "websocket": true }),
+            ))
+        };
+        routes.collect::<Vec<_>>().into()
+    }
+
+    /// The effective configuration reported by [`debug_config_route`](Self::debug_config_route).
+    fn config_snapshot(&self) -> serde_json::Value {
+        let features: Vec<&str> = [
+            ("websocket", cfg!(feature = "websocket")),
+            ("websocket-deflate", cfg!(feature = "websocket-deflate")),
+            ("test-helpers", cfg!(feature = "test-helpers")),
+            ("testing", cfg!(feature = "testing")),
+            ("session", cfg!(feature = "session")),
+            ("csrf", cfg!(feature = "csrf")),
+            ("jwt", cfg!(feature = "jwt")),
+            ("api-key", cfg!(feature = "api-key")),
+            ("static-files", cfg!(feature = "static-files")),
+            ("compression", cfg!(feature = "compression")),
+            ("tls", cfg!(feature = "tls")),
+            ("simd-json", cfg!(feature = "simd-json")),
+            ("client-gen", cfg!(feature = "client-gen")),
+            ("sqlx", cfg!(feature = "sqlx")),
+            ("diesel", cfg!(feature = "diesel")),
+        ]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect();
+
+        let routes = serde_json::json!({ "http": self.router.routes().len() });
+        #[cfg(feature = "websocket")]
+        let routes = {
+            let mut routes = routes;
+            routes["websocket"] = self.websocket_routes.len().into();
+            routes
+        };
+
+        #[cfg(feature = "database")]
+        let database = self
+            .pool_stats
+            .as_ref()
+            .map(|stats| serde_json::json!({ "pool": stats() }));
+        #[cfg(not(feature = "database"))]
+        let database: Option<serde_json::Value> = None;
+
+        serde_json::json!({
+            "max_body_size": self.max_body_size,
+            "trust_proxy": self.trust_proxy,
+            "json_charset": self.json_charset,
+            "http2": self.http2,
+            "features": features,
+            "middleware": self.middleware.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            "routes": routes,
+            "database": database,
+        })
+    }
+
     /// Handle an incoming HTTP request
     async fn handle_request(&self, req: HyperRequest<Incoming>, peer_addr: SocketAddr) -> Response {
         // Check for WebSocket upgrade request (needs the live `Incoming` body)
@@ -409,49 +1728,57 @@ impl Ultimo {
             }
         }
 
-        // Buffer the body (capped if a max is configured, so an oversized body
-        // is never fully buffered), then dispatch through the body-agnostic core.
+        // Leave the body on the connection: it is read (capped at the route's
+        // body-size limit) only if the handler asks for it.
         let (parts, body) = req.into_parts();
-        let bytes = match self.max_body_size {
-            Some(max) => match http_body_util::Limited::new(body, max).collect().await {
-                Ok(c) => c.to_bytes(),
-                Err(e) => {
-                    if e.downcast_ref::<http_body_util::LengthLimitError>()
-                        .is_some()
-                    {
-                        return body_too_large();
-                    }
-                    error!("Failed to read body: {}", e);
-                    return internal_error();
-                }
-            },
-            None => match body.collect().await {
-                Ok(c) => c.to_bytes(),
-                Err(e) => {
-                    error!("Failed to read body: {}", e);
-                    return internal_error();
-                }
-            },
-        };
-        self.dispatch_parts(parts, bytes, Some(peer_addr)).await
+        let body = RequestBody::pending(body, None);
+        self.dispatch_parts(parts, body, Some(peer_addr)).await
     }
 
-    /// Run routing + middleware + handler against an already-buffered request.
+    /// Run routing + middleware + handler against a request. Buffered
+    /// responses get their `Content-Length` here, and responses to `HEAD`
+    /// requests lose their body.
     async fn dispatch_parts(
         &self,
         parts: hyper::http::request::Parts,
-        body: Bytes,
+        body: RequestBody,
+        client_addr: Option<SocketAddr>,
+    ) -> Response {
+        let head = parts.method == hyper::Method::HEAD;
+        let response =
+            response::set_content_length(self.route_parts(parts, body, client_addr).await);
+        if head {
+            response::strip_head_body(response)
+        } else {
+            response
+        }
+    }
+
+    async fn route_parts(
+        &self,
+        parts: hyper::http::request::Parts,
+        body: RequestBody,
         client_addr: Option<SocketAddr>,
     ) -> Response {
         let method_str = parts.method.clone();
         let path = parts.uri.path().to_string();
 
-        // Enforce the body-size limit (covers in-process dispatch + a backstop
-        // for the live path).
-        if let Some(max) = self.max_body_size {
-            if body.len() > max {
-                return body_too_large();
-            }
+        if let Some(snapshot) = &self.debug_config {
+            snapshot.get_or_init(|| self.config_snapshot());
+        }
+        if let Some(listing) = &self.route_listing {
+            listing.get_or_init(|| self.route_listing());
+        }
+        #[cfg(feature = "database")]
+        if let Some(slot) = &self.health_ping {
+            slot.get_or_init(|| self.pool_ping.clone());
+        }
+
+        // `Expect: 100-continue`: hyper sends the interim `100 Continue` the
+        // first time the body is polled, so any rejection before the handler
+        // reads means the client never uploads a body we would refuse anyway.
+        if let Some(rejection) = check_expectation(&parts.headers) {
+            return rejection;
         }
 
         // Parse method
@@ -466,98 +1793,137 @@ impl Ultimo {
             }
         };
 
-        // Handle OPTIONS requests through middleware before routing
-        // This allows CORS middleware to respond to preflight requests
-        if method_str == hyper::Method::OPTIONS {
+        // Handle OPTIONS requests without an OPTIONS route through middleware
+        // alone. This allows CORS middleware to respond to preflight requests
+        if method_str == hyper::Method::OPTIONS && self.router.match_route(method, &path).is_none()
+        {
+            if exceeds_limit(&parts.headers, &body, self.max_body_size) {
+                return body_too_large();
+            }
+            let body = body.limit(self.max_body_size);
+            let error_info = self.error_info(&parts);
             // Create context for OPTIONS request
             let mut ctx = Context::from_parts(parts, body, Params::new());
+            ctx.attach_app_state(self.app_state.clone());
             ctx.set_client(client_addr, self.trust_proxy);
+            ctx.set_json_charset(self.json_charset);
             let cookie_sink = ctx.set_cookies_handle();
+            let header_sink = ctx.response_headers_handle();
 
             // Build and execute middleware chain
             let mut chain = MiddlewareChain::new();
-            for middleware in &self.middleware {
+            for (_, middleware) in &self.middleware {
                 chain.push(middleware.clone());
             }
 
             // Execute with a dummy handler that returns 404
             // CORS middleware should intercept OPTIONS and return early
-            let result = chain
-                .execute(ctx, |_ctx| async move {
-                    Ok(response::helpers::not_found()
-                        .unwrap_or_else(|_| response::helpers::text("Not Found").unwrap()))
-                })
-                .await;
+            let result = catch_panic(chain.execute(ctx, |_ctx| async move {
+                Ok(response::helpers::not_found()
+                    .unwrap_or_else(|_| response::helpers::text("Not Found").unwrap()))
+            }))
+            .await;
 
             let response = match result {
                 Ok(response) => response,
                 Err(err) => {
                     error!("Middleware error: {}", err);
-                    response::helpers::error_response(&err)
-                        .unwrap_or_else(|_| response::helpers::text("Internal Error").unwrap())
+                    let response = self.render_error(&err, error_info.as_ref());
+                    add_context_headers(response, header_sink).await
                 }
             };
-            return flush_set_cookies(response, cookie_sink).await;
+            return response::strip_bodiless(flush_set_cookies(response, cookie_sink).await);
         }
 
         // Find matching route
-        let (handler_id, params) = match self.router.find_route(method, &path) {
+        let route_match = match self.router.match_route(method, &path) {
             Some(route_match) => route_match,
             None => {
+                // Wrong trailing slash under `TrailingSlash::Redirect`.
+                if let Some(target) = self.router.redirect_path(method, &path) {
+                    let location = match parts.uri.query() {
+                        Some(query) => format!("{}?{}", target, query),
+                        None => target,
+                    };
+                    return response::helpers::redirect(&location, Some(301))
+                        .unwrap_or_else(|_| response::helpers::text("Moved Permanently").unwrap());
+                }
                 // SPA fallback: serve index.html for unmatched GET requests.
                 #[cfg(feature = "static-files")]
                 if parts.method == hyper::Method::GET {
                     if let Some((ref spa_dir, ref spa_file)) = self.spa_fallback {
                         if let Ok(spa_resp) =
-                            crate::static_files::serve_file(spa_dir, spa_file, None).await
+                            crate::static_files::serve_file(spa_dir, spa_file, None, None).await
                         {
                             return spa_resp;
                         }
                     }
                 }
+                // The path exists under other methods: 405, not 404.
+                let allowed = self.router.allowed_methods(&path);
+                if !allowed.is_empty() {
+                    return response::helpers::method_not_allowed(&allowed).unwrap_or_else(|_| {
+                        response::helpers::text("Method Not Allowed").unwrap()
+                    });
+                }
                 return response::helpers::not_found()
                     .unwrap_or_else(|_| response::helpers::text("Not Found").unwrap());
             }
         };
 
-        // Get the handler
-        let _handler = &self.handlers[handler_id];
+        let limits = &self.route_limits[route_match.handler_id];
+        let max_body_size = limits.max_body_size.unwrap_or(self.max_body_size);
+        if exceeds_limit(&parts.headers, &body, max_body_size) {
+            return body_too_large();
+        }
+        if !limits.accepts_content_type(&parts.headers, has_body(&body)) {
+            return unsupported_media_type(&limits.content_types);
+        }
+        let body = body.limit(max_body_size);
 
         // Create context
-        let mut ctx = Context::from_parts(parts, body, params);
+        let error_info = self.error_info(&parts);
+        let mut ctx = Context::from_parts(parts, body, route_match.params);
+        ctx.set_matched_route(route_match.pattern);
+        ctx.attach_app_state(self.app_state.clone());
         ctx.set_client(client_addr, self.trust_proxy);
+        ctx.set_json_charset(self.json_charset);
         let cookie_sink = ctx.set_cookies_handle();
+        let header_sink = ctx.response_headers_handle();
 
         // Attach database if configured
         #[cfg(feature = "database")]
         if let Some(ref db) = self.database {
             ctx.attach_database(db.clone());
         }
+        #[cfg(feature = "database")]
+        if !self.named_databases.is_empty() {
+            ctx.attach_named_databases(self.named_databases.clone());
+        }
 
         // Build middleware chain
         let mut chain = MiddlewareChain::new();
-        for middleware in &self.middleware {
+        for (_, middleware) in &self.middleware {
             chain.push(middleware.clone());
         }
 
         // Get the handler
-        let handler = self.handlers[handler_id].clone();
+        let handler = self.handlers[route_match.handler_id].clone();
 
         // Execute middleware chain with handler
-        let result = chain
-            .execute(ctx, move |ctx| async move { handler(ctx).await })
-            .await;
+        let result =
+            catch_panic(chain.execute(ctx, move |ctx| async move { handler(ctx).await })).await;
 
         // Handle result
         let response = match result {
             Ok(response) => response,
             Err(err) => {
                 error!("Handler error: {}", err);
-                response::helpers::error_response(&err)
-                    .unwrap_or_else(|_| response::helpers::text("Internal Error").unwrap())
+                let response = self.render_error(&err, error_info.as_ref());
+                add_context_headers(response, header_sink).await
             }
         };
-        flush_set_cookies(response, cookie_sink).await
+        response::strip_bodiless(flush_set_cookies(response, cookie_sink).await)
     }
 
     /// Dispatch a fully-buffered request through the app in-process (no socket).
@@ -568,60 +1934,567 @@ impl Ultimo {
             .await
             .map(|c| c.to_bytes())
             .unwrap_or_default();
-        self.dispatch_parts(parts, bytes, None).await
+        self.dispatch_parts(parts, bytes.into(), None).await
     }
 
     /// Start the HTTP server
+    ///
+    /// Runs [`on_startup`](Self::on_startup) hooks once bound, and
+    /// [`on_shutdown`](Self::on_shutdown) hooks when the accept loop stops.
+    /// Serves until the accept loop fails; use
+    /// [`listen_with_shutdown`](Self::listen_with_shutdown) to stop it.
     pub async fn listen(self, addr: &str) -> Result<()> {
+        self.listen_with_shutdown(addr, std::future::pending())
+            .await
+    }
+
+    /// Handle [`EMIT_RPC_SCHEMA_ENV`](crate::rpc::EMIT_RPC_SCHEMA_ENV): when
+    /// set, write the schema of the registry mounted with
+    /// [`mount_rpc`](Self::mount_rpc) to the file it names. Returns whether it
+    /// did, in which case the app should not serve.
+    fn emit_rpc_schema(&self) -> Result<bool> {
+        let Some(file) =
+            std::env::var_os(crate::rpc::EMIT_RPC_SCHEMA_ENV).filter(|f| !f.is_empty())
+        else {
+            return Ok(false);
+        };
+        let file = std::path::PathBuf::from(file);
+        let (path, registry) = match self.rpc_mounts.as_slice() {
+            [mount] => mount,
+            [] => {
+                return Err(UltimoError::Internal(
+                    "ULTIMO_EMIT_RPC_SCHEMA: no RPC registry mounted with app.mount_rpc()".to_string(),
+                ))
+            }
+            _ => {
+                return Err(UltimoError::Internal(
+                    "ULTIMO_EMIT_RPC_SCHEMA: more than one RPC registry mounted; generate each client with RpcRegistry::generate_client_file()".to_string(),
+                ))
+            }
+        };
+        let mut schema = registry.schema();
+        schema.base_path = Some(if path.is_empty() { "/" } else { path }.to_string());
+        std::fs::write(&file, schema.to_json())?;
+        info!("Wrote RPC schema to {}", file.display());
+        Ok(true)
+    }
+
+    /// Bind a TCP listener on `addr` and report the address it actually got,
+    /// e.g. the port chosen for `127.0.0.1:0`. Pass the listener to
+    /// [`listen_on`](Self::listen_on).
+    ///
+    /// ```rust,no_run
+    /// # use ultimo::prelude::*;
+    /// # async fn run() -> ultimo::Result<()> {
+    /// let (listener, addr) = Ultimo::bind("127.0.0.1:0").await?;
+    /// println!("serving on http://{}", addr);
+    /// Ultimo::new().listen_on(listener).await
+    /// # }
+    /// ```
+    pub async fn bind(addr: &str) -> Result<(TcpListener, SocketAddr)> {
         let addr: SocketAddr = addr
             .parse()
             .map_err(|_| UltimoError::Internal(format!("Invalid address: {}", addr)))?;
-
         let listener = TcpListener::bind(addr).await?;
-        info!("🚀 Ultimo server listening on http://{}", addr);
-
-        // Wrap self in Arc for sharing across connections
-        let app = Arc::new(self);
+        let local_addr = listener.local_addr()?;
+        Ok((listener, local_addr))
+    }
 
-        loop {
-            let (stream, peer_addr) = listener.accept().await?;
-            let io = TokioIo::new(stream);
-            let app = app.clone();
+    /// Serve on a listener you created, e.g. with `SO_REUSEPORT` or from
+    /// socket activation. Otherwise behaves like [`listen`](Self::listen).
+    pub async fn listen_on(self, listener: TcpListener) -> Result<()> {
+        self.listen_on_with_shutdown(listener, std::future::pending())
+            .await
+    }
 
-            tokio::task::spawn(async move {
-                let service = service_fn(move |req| {
-                    let app = app.clone();
-                    async move { Ok::<_, hyper::Error>(app.handle_request(req, peer_addr).await) }
-                });
+    /// Serve on a `std::net::TcpListener`, which is switched to non-blocking
+    /// mode for Tokio.
+    pub async fn listen_on_std(self, listener: std::net::TcpListener) -> Result<()> {
+        listener.set_nonblocking(true)?;
+        self.listen_on(TcpListener::from_std(listener)?).await
+    }
 
-                if let Err(err) = http1::Builder::new()
-                    .serve_connection(io, service)
-                    .with_upgrades() // Enable HTTP upgrades for WebSockets
-                    .await
-                {
-                    error!("Connection error: {}", err);
-                }
-            });
+    /// Start the HTTP server and shut it down gracefully once `shutdown`
+    /// resolves.
+    ///
+    /// On shutdown the listener stops accepting, idle keep-alive connections
+    /// are closed, open WebSockets are sent a `1001` (going away) close frame,
+    /// and in-flight requests get up to
+    /// [`shutdown_timeout`](Self::shutdown_timeout) to finish. Then the
+    /// [`on_shutdown`](Self::on_shutdown) hooks run and this returns.
+    ///
+    /// ```rust,no_run
+    /// # use ultimo::prelude::*;
+    /// # async fn run() -> ultimo::Result<()> {
+    /// let app = Ultimo::new();
+    /// app.listen_with_shutdown("127.0.0.1:3000", async {
+    ///     tokio::signal::ctrl_c().await.ok();
+    /// })
+    /// .await
+    /// # }
+    /// ```
+    pub async fn listen_with_shutdown<F>(self, addr: &str, shutdown: F) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send,
+    {
+        if self.emit_rpc_schema()? {
+            return Ok(());
         }
+        let (listener, _) = Self::bind(addr).await?;
+        self.serve(
+            listener,
+            shutdown,
+            #[cfg(feature = "tls")]
+            None,
+        )
+        .await
     }
-}
-
-/// 413 Payload Too Large response (body exceeded `max_body_size`).
-fn body_too_large() -> Response {
-    response::ResponseBuilder::new()
-        .status(413)
-        .text("Payload Too Large")
-        .build()
-        .unwrap_or_else(|_| response::helpers::text("Payload Too Large").unwrap())
-}
 
-/// 500 response for a genuine body-read failure (preserves the JSON error shape).
-fn internal_error() -> Response {
-    response::helpers::error_response(&UltimoError::Internal("Failed to read body".to_string()))
-        .unwrap_or_else(|_| response::helpers::text("Internal Error").unwrap())
-}
+    /// [`listen_with_shutdown`](Self::listen_with_shutdown) on a listener
+    /// you created.
+    pub async fn listen_on_with_shutdown<F>(self, listener: TcpListener, shutdown: F) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send,
+    {
+        if self.emit_rpc_schema()? {
+            return Ok(());
+        }
+        self.serve(
+            listener,
+            shutdown,
+            #[cfg(feature = "tls")]
+            None,
+        )
+        .await
+    }
 
-/// Append queued `Set-Cookie` header values (from `ctx.set_cookie`) onto the
+    /// Serve HTTPS on `addr`, terminating TLS in-process with the PEM
+    /// certificate chain (leaf first) and private key at `cert_path` and
+    /// `key_path`. ALPN offers `http/1.1`, plus `h2` when
+    /// [`http2`](Self::http2) is enabled. Clients get 10 seconds to complete
+    /// the handshake, and handshakes still running when shutdown starts are
+    /// dropped. Otherwise behaves like [`listen`](Self::listen).
+    ///
+    /// ```rust,no_run
+    /// # use ultimo::prelude::*;
+    /// # async fn run() -> ultimo::Result<()> {
+    /// Ultimo::new()
+    ///     .listen_tls("0.0.0.0:443", "certs/cert.pem", "certs/key.pem")
+    ///     .await
+    /// # }
+    /// ```
+    #[cfg(feature = "tls")]
+    pub async fn listen_tls(
+        self,
+        addr: &str,
+        cert_path: impl AsRef<std::path::Path>,
+        key_path: impl AsRef<std::path::Path>,
+    ) -> Result<()> {
+        self.listen_tls_with_shutdown(addr, cert_path, key_path, std::future::pending())
+            .await
+    }
+
+    /// [`listen_tls`](Self::listen_tls) on a listener you created.
+    #[cfg(feature = "tls")]
+    pub async fn listen_on_tls(
+        self,
+        listener: TcpListener,
+        cert_path: impl AsRef<std::path::Path>,
+        key_path: impl AsRef<std::path::Path>,
+    ) -> Result<()> {
+        self.listen_on_tls_with_shutdown(listener, cert_path, key_path, std::future::pending())
+            .await
+    }
+
+    /// [`listen_tls`](Self::listen_tls) with graceful shutdown, as in
+    /// [`listen_with_shutdown`](Self::listen_with_shutdown).
+    #[cfg(feature = "tls")]
+    pub async fn listen_tls_with_shutdown<F>(
+        self,
+        addr: &str,
+        cert_path: impl AsRef<std::path::Path>,
+        key_path: impl AsRef<std::path::Path>,
+        shutdown: F,
+    ) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send,
+    {
+        if self.emit_rpc_schema()? {
+            return Ok(());
+        }
+        let (listener, _) = Self::bind(addr).await?;
+        self.serve_tls(listener, cert_path.as_ref(), key_path.as_ref(), shutdown)
+            .await
+    }
+
+    /// [`listen_tls_with_shutdown`](Self::listen_tls_with_shutdown) on a
+    /// listener you created.
+    #[cfg(feature = "tls")]
+    pub async fn listen_on_tls_with_shutdown<F>(
+        self,
+        listener: TcpListener,
+        cert_path: impl AsRef<std::path::Path>,
+        key_path: impl AsRef<std::path::Path>,
+        shutdown: F,
+    ) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send,
+    {
+        if self.emit_rpc_schema()? {
+            return Ok(());
+        }
+        self.serve_tls(listener, cert_path.as_ref(), key_path.as_ref(), shutdown)
+            .await
+    }
+
+    /// [`serve`](Self::serve) with TLS, once the RPC schema check is done.
+    #[cfg(feature = "tls")]
+    async fn serve_tls<F>(
+        self,
+        listener: TcpListener,
+        cert_path: &std::path::Path,
+        key_path: &std::path::Path,
+        shutdown: F,
+    ) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send,
+    {
+        let client_auth = self
+            .tls_client_auth
+            .as_ref()
+            .map(|(ca_path, mode)| (ca_path.as_path(), *mode));
+        let acceptor = crate::tls::acceptor(cert_path, key_path, self.http2, client_auth)?;
+        self.serve(listener, shutdown, Some(acceptor)).await
+    }
+
+    /// The accept loop behind every `listen*` method, which have each checked
+    /// [`emit_rpc_schema`](Self::emit_rpc_schema) first. With an acceptor,
+    /// each connection completes its TLS handshake in its own task first.
+    async fn serve<F>(
+        mut self,
+        listener: TcpListener,
+        shutdown: F,
+        #[cfg(feature = "tls")] tls: Option<tokio_rustls::TlsAcceptor>,
+    ) -> Result<()>
+    where
+        F: std::future::Future<Output = ()> + Send,
+    {
+        let addr = listener.local_addr()?;
+        #[cfg(feature = "tls")]
+        let scheme = if tls.is_some() { "https" } else { "http" };
+        #[cfg(not(feature = "tls"))]
+        let scheme = "http";
+
+        for hook in std::mem::take(&mut self.startup_hooks) {
+            hook().await?;
+        }
+        let shutdown_hooks = std::mem::take(&mut self.shutdown_hooks);
+
+        info!("🚀 Ultimo server listening on {}://{}", scheme, addr);
+
+        // Every connection task holds a receiver, so `closed()` on the sender
+        // resolves once they have all finished.
+        let (draining, _) = tokio::sync::watch::channel(false);
+
+        // Wrap self in Arc for sharing across connections
+        let app = Arc::new(self);
+
+        let result: Result<()> = async {
+            tokio::pin!(shutdown);
+            loop {
+                let (stream, peer_addr) = tokio::select! {
+                    accepted = listener.accept() => accepted?,
+                    _ = &mut shutdown => return Ok(()),
+                };
+                let app = app.clone();
+                let drain_signal = draining.subscribe();
+                #[cfg(feature = "tls")]
+                let tls = tls.clone();
+
+                tokio::task::spawn(async move {
+                    #[cfg(feature = "tls")]
+                    if let Some(tls) = tls {
+                        // A peer that stalls its handshake must not hold a
+                        // task (and the drain) open indefinitely.
+                        let mut draining = drain_signal.clone();
+                        let handshake = tokio::select! {
+                            handshake = tokio::time::timeout(
+                                TLS_HANDSHAKE_TIMEOUT,
+                                tls.accept(stream),
+                            ) => handshake,
+                            _ = draining.wait_for(|draining| *draining) => return,
+                        };
+                        match handshake {
+                            Err(_) => {
+                                tracing::debug!("TLS handshake with {} timed out", peer_addr)
+                            }
+                            Ok(Ok(stream)) => {
+                                let client_cert = stream
+                                    .get_ref()
+                                    .1
+                                    .peer_certificates()
+                                    .and_then(crate::tls::ClientCertInfo::from_chain)
+                                    .map(Arc::new);
+                                serve_connection(app, stream, peer_addr, drain_signal, client_cert)
+                                    .await
+                            }
+                            Ok(Err(err)) => {
+                                tracing::debug!("TLS handshake with {} failed: {}", peer_addr, err)
+                            }
+                        }
+                        return;
+                    }
+                    serve_connection(
+                        app,
+                        stream,
+                        peer_addr,
+                        drain_signal,
+                        #[cfg(feature = "tls")]
+                        None,
+                    )
+                    .await;
+                });
+            }
+        }
+        .await;
+
+        if result.is_ok() {
+            drop(listener);
+            info!("Shutting down: draining open connections");
+            draining.send_replace(true);
+            #[cfg(feature = "websocket")]
+            app.channel_manager.close_all();
+
+            let drained = async {
+                draining.closed().await;
+                #[cfg(feature = "websocket")]
+                app.channel_manager.all_closed().await;
+            };
+            if tokio::time::timeout(app.shutdown_timeout, drained)
+                .await
+                .is_err()
+            {
+                warn!(
+                    "Connections still open after {:?}; shutting down anyway",
+                    app.shutdown_timeout
+                );
+            }
+        }
+
+        run_shutdown_hooks(shutdown_hooks).await;
+        result
+    }
+}
+
+/// Turn a panic in middleware or a handler into a 500 instead of a dropped
+/// connection. The panic hook has already printed where it happened (and the
+/// backtrace, with `RUST_BACKTRACE=1`); the payload is logged here but never
+/// sent to the client.
+async fn catch_panic(
+    chain: impl std::future::Future<Output = Result<Response>>,
+) -> Result<Response> {
+    match std::panic::AssertUnwindSafe(chain).catch_unwind().await {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("non-string panic payload");
+            error!("Handler panicked: {}", message);
+            Err(UltimoError::Internal("Handler panicked".to_string()))
+        }
+    }
+}
+
+/// Serve HTTP on an accepted connection until it closes. A TLS client
+/// certificate is attached to every request on the connection.
+async fn serve_connection<I>(
+    app: Arc<Ultimo>,
+    stream: I,
+    peer_addr: SocketAddr,
+    drain_signal: tokio::sync::watch::Receiver<bool>,
+    #[cfg(feature = "tls")] client_cert: Option<Arc<crate::tls::ClientCertInfo>>,
+) where
+    I: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    let io = TokioIo::new(stream);
+    let http2 = app.http2;
+    let service = service_fn(move |req: HyperRequest<Incoming>| {
+        let app = app.clone();
+        #[cfg(feature = "tls")]
+        let req = {
+            let mut req = req;
+            if let Some(cert) = &client_cert {
+                req.extensions_mut().insert(cert.clone());
+            }
+            req
+        };
+        async move { Ok::<_, hyper::Error>(app.handle_request(req, peer_addr).await) }
+    });
+
+    let served = if http2 {
+        let builder = auto::Builder::new(TokioExecutor::new());
+        // HTTP/1 connections keep upgrades for WebSockets.
+        let conn = builder.serve_connection_with_upgrades(io, service);
+        drive_connection(conn, |conn| conn.graceful_shutdown(), drain_signal).await
+    } else {
+        let conn = http1::Builder::new()
+            .serve_connection(io, service)
+            .with_upgrades(); // Enable HTTP upgrades for WebSockets
+        drive_connection(conn, |conn| conn.graceful_shutdown(), drain_signal)
+            .await
+            .map_err(response::BoxError::from)
+    };
+    if let Err(err) = served {
+        error!("Connection error: {}", err);
+    }
+}
+
+/// Serve `conn` until it closes. Once draining starts, `graceful_shutdown`
+/// lets it finish its in-flight requests, then close.
+async fn drive_connection<C, E>(
+    conn: C,
+    graceful_shutdown: impl FnOnce(std::pin::Pin<&mut C>),
+    mut drain_signal: tokio::sync::watch::Receiver<bool>,
+) -> std::result::Result<(), E>
+where
+    C: std::future::Future<Output = std::result::Result<(), E>>,
+{
+    tokio::pin!(conn);
+    tokio::select! {
+        served = conn.as_mut() => served,
+        _ = async { drain_signal.wait_for(|draining| *draining).await.ok(); } => {
+            graceful_shutdown(conn.as_mut());
+            conn.await
+        }
+    }
+}
+
+/// Build a REST-mode RPC input object from query parameters. Values stay
+/// strings (the procedure's input type decides what to parse, see
+/// `RpcRegistry::call_query`); repeated keys become arrays.
+fn query_input(queries: std::collections::HashMap<String, Vec<String>>) -> serde_json::Value {
+    let object = queries
+        .into_iter()
+        .map(|(key, mut values)| {
+            let value = if values.len() == 1 {
+                serde_json::Value::String(values.remove(0))
+            } else {
+                serde_json::Value::Array(
+                    values.into_iter().map(serde_json::Value::String).collect(),
+                )
+            };
+            (key, value)
+        })
+        .collect();
+    serde_json::Value::Object(object)
+}
+
+/// Convert an OpenAPI path template (`/users/{id}`) to router syntax (`/users/:id`).
+fn openapi_path_to_route(path: &str) -> String {
+    path.split('/')
+        .map(
+            |segment| match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                Some(name) => format!(":{}", name),
+                None => segment.to_string(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Default for [`Ultimo::shutdown_timeout`].
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a [`Ultimo::health_check`] ping may take before it counts as failed.
+#[cfg(feature = "database")]
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a TLS client gets to complete its handshake.
+#[cfg(feature = "tls")]
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run shutdown hooks in order, logging (not propagating) failures.
+async fn run_shutdown_hooks(hooks: Vec<LifecycleHook>) {
+    for hook in hooks {
+        if let Err(err) = hook().await {
+            error!("Shutdown hook failed: {}", err);
+        }
+    }
+}
+
+/// Default for [`Ultimo::max_body_size`].
+const DEFAULT_MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// 413 Payload Too Large response (body exceeded `max_body_size`).
+fn body_too_large() -> Response {
+    response::ResponseBuilder::new()
+        .status(413)
+        .text("Payload Too Large")
+        .build()
+        .unwrap_or_else(|_| response::helpers::text("Payload Too Large").unwrap())
+}
+
+/// 415 Unsupported Media Type response naming the route's accepted types.
+fn unsupported_media_type(accepted: &[String]) -> Response {
+    response::ResponseBuilder::new()
+        .status(415)
+        .text(format!("Expected Content-Type: {}", accepted.join(", ")))
+        .build()
+        .unwrap_or_else(|_| response::helpers::text("Unsupported Media Type").unwrap())
+}
+
+/// Validate an `Expect` request header before the body is read.
+///
+/// Only `100-continue` is supported; any other expectation gets **417
+/// Expectation Failed**. Returns `None` when the request may proceed.
+fn check_expectation(headers: &hyper::HeaderMap) -> Option<Response> {
+    let expect = headers.get(hyper::header::EXPECT)?;
+    let continue_expected = expect
+        .to_str()
+        .map(|v| v.trim().eq_ignore_ascii_case("100-continue"))
+        .unwrap_or(false);
+    if !continue_expected {
+        return Some(
+            response::ResponseBuilder::new()
+                .status(417)
+                .text("Expectation Failed")
+                .build()
+                .unwrap_or_else(|_| response::helpers::text("Expectation Failed").unwrap()),
+        );
+    }
+    None
+}
+
+/// Whether `body` is known to exceed `max` bytes before it is read: a
+/// buffered body, or a declared `Content-Length`. A body still on the
+/// connection is otherwise capped while being read.
+fn exceeds_limit(headers: &hyper::HeaderMap, body: &RequestBody, max: usize) -> bool {
+    declared_length(headers).is_some_and(|len| len > max as u64)
+        || matches!(body, RequestBody::Buffered(bytes) if bytes.len() > max)
+}
+
+/// Whether the request carries a body. Asks the connection rather than the
+/// headers: HTTP/2 requests usually declare neither `Content-Length` nor
+/// `Transfer-Encoding`, yet still send data frames.
+fn has_body(body: &RequestBody) -> bool {
+    match body {
+        RequestBody::Buffered(bytes) => !bytes.is_empty(),
+        RequestBody::Pending(body, _) => !hyper::body::Body::is_end_stream(body),
+        RequestBody::Taken => false,
+    }
+}
+
+/// The body length declared by `Content-Length`, if present and valid.
+fn declared_length(headers: &hyper::HeaderMap) -> Option<u64> {
+    headers
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+}
+
+/// Append queued `Set-Cookie` header values (from `ctx.set_cookie`) onto the
 /// response. Uses `append` so multiple cookies become multiple headers.
 async fn flush_set_cookies(
     mut response: Response,
@@ -636,6 +2509,30 @@ async fn flush_set_cookies(
     response
 }
 
+/// Add the headers set on the context (`ctx.set_header`) to an error
+/// response, which is rendered without the context. Headers the error
+/// response already has are kept.
+async fn add_context_headers(
+    mut response: Response,
+    headers: Arc<tokio::sync::RwLock<Vec<(String, String)>>>,
+) -> Response {
+    // Checked against the names the error response came with, so repeated
+    // context headers (two `Vary` values) are all appended.
+    let existing: HashSet<_> = response.headers().keys().cloned().collect();
+    for (name, value) in headers.read().await.iter() {
+        let (Ok(name), Ok(value)) = (
+            hyper::header::HeaderName::from_bytes(name.as_bytes()),
+            hyper::header::HeaderValue::from_str(value),
+        ) else {
+            continue;
+        };
+        if !existing.contains(&name) {
+            response.headers_mut().append(name, value);
+        }
+    }
+    response
+}
+
 impl Default for Ultimo {
     fn default() -> Self {
         Self::new()
@@ -794,6 +2691,7 @@ mod tests {
 #[cfg(test)]
 mod oneshot_tests {
     use super::*;
+    use crate::response::ResponseBuilder;
     use http_body_util::{BodyExt, Full};
     use hyper::Request as HyperRequest;
 
@@ -821,6 +2719,934 @@ mod oneshot_tests {
         assert_eq!(body_string(resp).await, "pong");
     }
 
+    #[tokio::test]
+    async fn serve_openapi_serves_spec_and_ui() {
+        let mut app = Ultimo::new_without_defaults();
+        let spec = crate::openapi::OpenApiBuilder::new()
+            .title("Spec API")
+            .build();
+        app.serve_openapi(spec, "/spec.json", "/reference");
+
+        let get = |path: &str| {
+            HyperRequest::builder()
+                .uri(path)
+                .body(Full::new(bytes::Bytes::new()))
+                .unwrap()
+        };
+        let resp = app.oneshot(get("/spec.json")).await;
+        assert_eq!(resp.status(), 200);
+        let spec: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(spec["info"]["title"], "Spec API");
+
+        let resp = app.oneshot(get("/reference")).await;
+        assert_eq!(resp.status(), 200);
+        assert!(body_string(resp).await.contains("url: \"/spec.json\""));
+    }
+
+    #[tokio::test]
+    async fn serve_openapi_with_redoc() {
+        let mut app = Ultimo::new_without_defaults();
+        let spec = crate::openapi::OpenApiBuilder::new().build();
+        app.serve_openapi_with(
+            spec,
+            "/openapi.json",
+            "/docs",
+            crate::openapi::DocsRenderer::Redoc,
+        );
+
+        let req = HyperRequest::builder()
+            .uri("/docs")
+            .body(Full::new(bytes::Bytes::new()))
+            .unwrap();
+        let resp = app.oneshot(req).await;
+        assert_eq!(resp.status(), 200);
+        assert!(body_string(resp)
+            .await
+            .contains(r#"<redoc spec-url="/openapi.json">"#));
+    }
+
+    fn post(path: &str, body: &str) -> HyperRequest<Full<bytes::Bytes>> {
+        HyperRequest::builder()
+            .method("POST")
+            .uri(path)
+            .body(Full::new(bytes::Bytes::from(body.to_string())))
+            .unwrap()
+    }
+
+    fn rpc_registry(mode: crate::rpc::RpcMode) -> crate::RpcRegistry {
+        let rpc = crate::RpcRegistry::new_with_mode(mode);
+        rpc.query_with_types(
+            "getUser",
+            |input: serde_json::Value| async move {
+                let id = input["id"]
+                    .as_u64()
+                    .ok_or_else(|| UltimoError::BadRequest("id must be a number".to_string()))?;
+                Ok(serde_json::json!({ "id": id }))
+            },
+            "{ id: number }".to_string(),
+            "{ id: number }".to_string(),
+        );
+        rpc.mutation_with_types(
+            "createUser",
+            |input: serde_json::Value| async move { Ok(serde_json::json!({ "created": input })) },
+            "{}".to_string(),
+            "{}".to_string(),
+        );
+        rpc
+    }
+
+    #[tokio::test]
+    async fn mount_rpc_rest_routes_by_procedure_kind() {
+        let mut app = Ultimo::new_without_defaults();
+        app.mount_rpc("/api/", rpc_registry(crate::rpc::RpcMode::Rest));
+
+        let resp = app.oneshot(get("/api/getUser?id=7")).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(body_string(resp).await, r#"{"id":7}"#);
+
+        let resp = app
+            .oneshot(post("/api/createUser", r#"{"name":"a"}"#))
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(body_string(resp).await, r#"{"created":{"name":"a"}}"#);
+
+        // Mutations take an empty body as `{}`
+        let resp = app.oneshot(post("/api/createUser", "")).await;
+        assert_eq!(body_string(resp).await, r#"{"created":{}}"#);
+
+        // Queries are GET-only, mutations POST-only
+        assert_eq!(app.oneshot(post("/api/getUser", "{}")).await.status(), 405);
+        assert_eq!(app.oneshot(get("/api/createUser")).await.status(), 405);
+
+        // Procedure errors keep their HTTP status
+        assert_eq!(app.oneshot(get("/api/getUser?id=x")).await.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn mount_rpc_rest_query_strings_fill_string_fields() {
+        #[derive(serde::Deserialize)]
+        struct Lookup {
+            code: String,
+            limit: u32,
+            verbose: Option<bool>,
+            tags: Vec<String>,
+        }
+        let rpc = crate::RpcRegistry::new_with_mode(crate::rpc::RpcMode::Rest);
+        rpc.query_with_types(
+            "lookup",
+            |input: Lookup| async move {
+                Ok(serde_json::json!({
+                    "code": input.code,
+                    "limit": input.limit,
+                    "verbose": input.verbose,
+                    "tags": input.tags,
+                }))
+            },
+            "{ code: string; limit: number; verbose?: boolean; tags: string[] }".to_string(),
+            "{}".to_string(),
+        );
+        let mut app = Ultimo::new_without_defaults();
+        app.mount_rpc("/api", rpc);
+
+        let resp = app
+            .oneshot(get("/api/lookup?code=0123&limit=10&verbose=true&tags=1"))
+            .await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "code": "0123", "limit": 10, "verbose": true, "tags": ["1"] })
+        );
+
+        let resp = app
+            .oneshot(get("/api/lookup?code=true&limit=1&tags=a&tags=2"))
+            .await;
+        let body: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(body["code"], "true");
+        assert_eq!(body["verbose"], serde_json::Value::Null);
+        assert_eq!(body["tags"], serde_json::json!(["a", "2"]));
+
+        assert_eq!(
+            app.oneshot(get("/api/lookup?code=a&limit=ten&tags=a"))
+                .await
+                .status(),
+            400
+        );
+    }
+
+    #[tokio::test]
+    async fn mount_rpc_rest_posts_array_inputs_unwrapped() {
+        let rpc = crate::RpcRegistry::new_with_mode(crate::rpc::RpcMode::Rest);
+        rpc.mutation_with_types(
+            "tagUsers",
+            |tags: Vec<String>| async move { Ok(tags.len()) },
+            "string[]".to_string(),
+            "number".to_string(),
+        );
+        let mut app = Ultimo::new_without_defaults();
+        app.mount_rpc("/api", rpc);
+
+        let resp = app.oneshot(post("/api/tagUsers", r#"["a","b"]"#)).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(body_string(resp).await, "2");
+
+        // An empty body is an empty array, not `{}`
+        let resp = app.oneshot(post("/api/tagUsers", "")).await;
+        assert_eq!(body_string(resp).await, "0");
+    }
+
+    #[tokio::test]
+    async fn mount_rpc_jsonrpc_single_endpoint() {
+        let mut app = Ultimo::new_without_defaults();
+        app.mount_rpc("/rpc", rpc_registry(crate::rpc::RpcMode::JsonRpc));
+
+        let resp = app
+            .oneshot(post(
+                "/rpc",
+                r#"[{"jsonrpc":"2.0","method":"getUser","params":{"id":1},"id":1},
+                    {"jsonrpc":"2.0","method":"nope","id":2}]"#,
+            ))
+            .await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(body[0]["result"], serde_json::json!({ "id": 1 }));
+        assert_eq!(
+            body[1]["error"]["code"],
+            crate::rpc::error_code::METHOD_NOT_FOUND
+        );
+
+        // Notification-only requests get an empty 204
+        let resp = app
+            .oneshot(post(
+                "/rpc",
+                r#"{"jsonrpc":"2.0","method":"createUser","params":{}}"#,
+            ))
+            .await;
+        assert_eq!(resp.status(), 204);
+        assert_eq!(body_string(resp).await, "");
+    }
+
+    fn get(path: &str) -> HyperRequest<Full<bytes::Bytes>> {
+        HyperRequest::builder()
+            .uri(path)
+            .body(Full::new(bytes::Bytes::new()))
+            .unwrap()
+    }
+
+    fn check(ok: bool) -> ReadinessCheck {
+        Box::new(move || {
+            Box::pin(async move {
+                if ok {
+                    Ok(())
+                } else {
+                    Err(UltimoError::Internal("connection refused".into()))
+                }
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn liveness_route_is_always_ok() {
+        let mut app = Ultimo::new_without_defaults();
+        app.liveness_route("/livez");
+        let resp = app.oneshot(get("/livez")).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(body_string(resp).await, r#"{"status":"ok"}"#);
+    }
+
+    #[tokio::test]
+    async fn readiness_route_passes_when_all_checks_pass() {
+        let mut app = Ultimo::new_without_defaults();
+        app.readiness_route("/readyz", vec![("db", check(true)), ("cache", check(true))]);
+        let resp = app.oneshot(get("/readyz")).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(body_string(resp).await, r#"{"status":"ready"}"#);
+    }
+
+    #[tokio::test]
+    async fn readiness_route_reports_failing_checks_with_503() {
+        let mut app = Ultimo::new_without_defaults();
+        app.readiness_route(
+            "/readyz",
+            vec![
+                ("db", check(false)),
+                ("cache", check(true)),
+                ("queue", check(false)),
+            ],
+        );
+        let resp = app.oneshot(get("/readyz")).await;
+        assert_eq!(resp.status(), 503);
+        let body: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "status": "not_ready", "failed": ["db", "queue"] })
+        );
+    }
+
+    #[cfg(feature = "database")]
+    #[tokio::test]
+    async fn health_check_without_pool_is_ok() {
+        let mut app = Ultimo::new_without_defaults();
+        app.health_check("/health");
+        let resp = app.oneshot(get("/health")).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(body_string(resp).await, r#"{"status":"ok"}"#);
+    }
+
+    #[cfg(feature = "sqlx-sqlite")]
+    #[tokio::test]
+    async fn health_check_pings_sqlx_pool() {
+        use crate::database::sqlx::SqlxPool;
+
+        let pool = SqlxPool::<sqlx::Sqlite>::connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let mut app = Ultimo::new_without_defaults();
+        // Registered before the pool is attached.
+        app.health_check("/health").with_sqlx(pool.clone());
+
+        let resp = app.oneshot(get("/health")).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "status": "healthy", "database": "connected" })
+        );
+
+        pool.pool().close().await;
+        let resp = app.oneshot(get("/health")).await;
+        assert_eq!(resp.status(), 503);
+        let body: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(body["status"], "unhealthy");
+        assert_eq!(body["database"], "disconnected");
+        // The driver's error is logged, not exposed.
+        assert_eq!(body["error"], "unavailable");
+    }
+
+    #[cfg(feature = "diesel-sqlite")]
+    #[tokio::test]
+    async fn health_check_checks_out_diesel_connection() {
+        use crate::database::diesel::DieselPool;
+
+        let pool = DieselPool::<diesel::SqliteConnection>::new(":memory:").unwrap();
+        let mut app = Ultimo::new_without_defaults();
+        app.with_diesel(pool).health_check("/health");
+
+        let resp = app.oneshot(get("/health")).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(body["status"], "healthy");
+    }
+
+    #[cfg(all(feature = "sqlx-sqlite", feature = "diesel-sqlite"))]
+    #[tokio::test]
+    async fn named_pools_sit_alongside_default_pool() {
+        use crate::database::diesel::DieselPool;
+        use crate::database::sqlx::SqlxPool;
+
+        async fn db_with(name: &str) -> SqlxPool<sqlx::Sqlite> {
+            let pool = SqlxPool::<sqlx::Sqlite>::connect_with_options(
+                sqlx::sqlite::SqlitePoolOptions::new().max_connections(1),
+                "sqlite::memory:",
+            )
+            .await
+            .unwrap();
+            sqlx::query(&format!("CREATE TABLE t AS SELECT '{}' AS name", name))
+                .execute(pool.pool())
+                .await
+                .unwrap();
+            pool
+        }
+
+        async fn name_in(pool: &sqlx::SqlitePool) -> Result<String> {
+            Ok(sqlx::query_scalar("SELECT name FROM t")
+                .fetch_one(pool)
+                .await?)
+        }
+
+        let mut app = Ultimo::new_without_defaults();
+        app.with_sqlx(db_with("primary").await)
+            .with_named_pool("replica", db_with("replica").await)
+            .with_named_pool(
+                "jobs",
+                DieselPool::<diesel::SqliteConnection>::new(":memory:").unwrap(),
+            );
+        app.get("/", |ctx: Context| async move {
+            let primary = name_in(ctx.sqlx::<sqlx::Sqlite>()?).await?;
+            let replica = name_in(ctx.sqlx_named::<sqlx::Sqlite>("replica")?).await?;
+            ctx.diesel_named::<diesel::SqliteConnection>("jobs")?;
+            ctx.text(format!("{} {}", primary, replica)).await
+        });
+        app.get("/missing", |ctx: Context| async move {
+            ctx.sqlx_named::<sqlx::Sqlite>("analytics")?;
+            ctx.text("unreachable").await
+        });
+        app.get("/wrong-kind", |ctx: Context| async move {
+            ctx.sqlx_named::<sqlx::Sqlite>("jobs")?;
+            ctx.text("unreachable").await
+        });
+
+        let resp = app.oneshot(get("/")).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(body_string(resp).await, "primary replica");
+
+        let resp = app.oneshot(get("/missing")).await;
+        assert_eq!(resp.status(), 500);
+        assert!(body_string(resp)
+            .await
+            .contains("'analytics' not configured"));
+
+        let resp = app.oneshot(get("/wrong-kind")).await;
+        assert_eq!(resp.status(), 500);
+    }
+
+    #[tokio::test]
+    async fn matched_route_is_the_registered_pattern() {
+        let mut app = Ultimo::new_without_defaults();
+        app.use_middleware(Arc::new(|ctx: Context, next| {
+            Box::pin(async move {
+                let route = ctx.matched_route().unwrap_or("-").to_string();
+                let mut res = next(ctx).await?;
+                res.headers_mut().insert("x-route", route.parse().unwrap());
+                Ok(res)
+            })
+        }));
+        app.group("/api", |api| {
+            api.get("/users/:id", |ctx: Context| async move {
+                let route = ctx.matched_route().unwrap_or_default().to_string();
+                ctx.text(route).await
+            });
+        });
+
+        let resp = app.oneshot(get("/api/users/42")).await;
+        assert_eq!(resp.headers()["x-route"], "/api/users/:id");
+        assert_eq!(body_string(resp).await, "/api/users/:id");
+    }
+
+    #[tokio::test]
+    async fn app_state_is_shared_across_requests_and_middleware() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        struct Hits(AtomicU64);
+
+        let mut app = Ultimo::new_without_defaults();
+        app.with_state(Hits(AtomicU64::new(0)))
+            .with_state(String::from("ultimo"))
+            .with_state(String::from("replaced"));
+        app.use_middleware(Arc::new(|ctx: Context, next| {
+            Box::pin(async move {
+                ctx.state::<Hits>()?.0.fetch_add(1, Ordering::SeqCst);
+                next(ctx).await
+            })
+        }));
+        app.get("/", |ctx: Context| async move {
+            let hits = ctx.state::<Hits>()?.0.load(Ordering::SeqCst);
+            let name = ctx.state::<String>()?;
+            ctx.text(format!("{} {}", name, hits)).await
+        });
+        app.get("/missing", |ctx: Context| async move {
+            ctx.state::<u8>()?;
+            ctx.text("unreachable").await
+        });
+
+        assert_eq!(body_string(app.oneshot(get("/")).await).await, "replaced 1");
+        assert_eq!(body_string(app.oneshot(get("/")).await).await, "replaced 2");
+
+        let resp = app.oneshot(get("/missing")).await;
+        assert_eq!(resp.status(), 500);
+        assert!(body_string(resp).await.contains("u8 not configured"));
+    }
+
+    async fn list_users(ctx: Context) -> Result<Response> {
+        ctx.text("list").await
+    }
+
+    #[tokio::test]
+    async fn routes_macro_registers_each_line() {
+        let mut app = Ultimo::new_without_defaults();
+        crate::routes!(app, {
+            GET "/users" => list_users,
+            POST "/users" => |ctx: Context| async move { ctx.text("create").await },
+            DELETE "/users/:id" => |ctx: Context| async move {
+                let id = ctx.req.param("id")?.to_string();
+                ctx.text(format!("delete {}", id)).await
+            },
+        });
+
+        let send = |method: &str, uri: &str| {
+            HyperRequest::builder()
+                .method(method)
+                .uri(uri)
+                .body(Full::new(bytes::Bytes::new()))
+                .unwrap()
+        };
+        assert_eq!(
+            body_string(app.oneshot(send("GET", "/users")).await).await,
+            "list"
+        );
+        assert_eq!(
+            body_string(app.oneshot(send("POST", "/users")).await).await,
+            "create"
+        );
+        assert_eq!(
+            body_string(app.oneshot(send("DELETE", "/users/7")).await).await,
+            "delete 7"
+        );
+        assert_eq!(app.oneshot(send("PUT", "/users")).await.status(), 405);
+    }
+
+    #[tokio::test]
+    async fn startup_hooks_run_in_order_and_failure_aborts_listen() {
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut app = Ultimo::new_without_defaults();
+        for (step, fail) in [(1, false), (2, true), (3, false)] {
+            let calls = calls.clone();
+            app.on_startup(move || async move {
+                calls.lock().unwrap().push(step);
+                if fail {
+                    return Err(UltimoError::Internal("migration failed".into()));
+                }
+                Ok(())
+            });
+        }
+        let shutdown_ran = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flag = shutdown_ran.clone();
+        app.on_shutdown(move || async move {
+            flag.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        });
+
+        let err = app.listen("127.0.0.1:0").await.unwrap_err();
+        assert!(err.to_string().contains("migration failed"));
+        assert_eq!(*calls.lock().unwrap(), [1, 2]);
+        assert!(!shutdown_ran.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn shutdown_hooks_keep_running_after_a_failure() {
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut hooks: Vec<LifecycleHook> = Vec::new();
+        for (step, fail) in [(1, true), (2, false)] {
+            let calls = calls.clone();
+            hooks.push(Box::new(move || {
+                Box::pin(async move {
+                    calls.lock().unwrap().push(step);
+                    if fail {
+                        return Err(UltimoError::Internal("flush failed".into()));
+                    }
+                    Ok(())
+                })
+            }));
+        }
+        run_shutdown_hooks(hooks).await;
+        assert_eq!(*calls.lock().unwrap(), [1, 2]);
+    }
+
+    /// Middleware that appends `tag` to the `x-trail` response header.
+    fn trail(tag: &'static str) -> BoxedMiddleware {
+        Arc::new(move |ctx, next| {
+            Box::pin(async move {
+                let mut resp = next(ctx).await?;
+                let trail = match resp.headers().get("x-trail") {
+                    Some(v) => format!("{},{}", tag, v.to_str().unwrap()),
+                    None => tag.to_string(),
+                };
+                resp.headers_mut().insert("x-trail", trail.parse().unwrap());
+                Ok(resp)
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn group_prefixes_routes_and_scopes_middleware() {
+        let mut app = Ultimo::new_without_defaults();
+        app.use_middleware(trail("global"));
+        app.group("/api/v1", |api| {
+            api.use_middleware(trail("api"));
+            api.get("/users/:id", |ctx: Context| async move {
+                let id = ctx.req.param("id")?.to_string();
+                ctx.text(id).await
+            });
+            api.group("/admin", |admin| {
+                admin.use_middleware(trail("admin"));
+                admin.get("/", |ctx: Context| async move { ctx.text("admin").await });
+            });
+        });
+        app.get(
+            "/health",
+            |ctx: Context| async move { ctx.text("ok").await },
+        );
+
+        let resp = app.oneshot(get("/api/v1/users/42")).await;
+        assert_eq!(resp.headers()["x-trail"], "global,api");
+        assert_eq!(body_string(resp).await, "42");
+
+        let resp = app.oneshot(get("/api/v1/admin")).await;
+        assert_eq!(resp.headers()["x-trail"], "global,api,admin");
+        assert_eq!(body_string(resp).await, "admin");
+
+        let resp = app.oneshot(get("/health")).await;
+        assert_eq!(resp.headers()["x-trail"], "global");
+
+        assert_eq!(app.oneshot(get("/users/42")).await.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn error_responses_keep_repeated_context_headers() {
+        let mut app = Ultimo::new_without_defaults();
+        app.get("/", |ctx: Context| async move {
+            ctx.header("Vary", "Origin").await;
+            ctx.header("Vary", "Accept-Encoding").await;
+            ctx.header("Content-Type", "text/plain").await;
+            Err::<Response, _>(UltimoError::BadRequest("nope".to_string()))
+        });
+
+        let resp = app.oneshot(get("/")).await;
+        assert_eq!(resp.status(), 400);
+        let vary: Vec<_> = resp.headers().get_all("vary").iter().collect();
+        assert_eq!(vary, ["Origin", "Accept-Encoding"]);
+        // The error response's own headers win.
+        let content_types: Vec<_> = resp.headers().get_all("content-type").iter().collect();
+        assert_eq!(content_types, ["application/json"]);
+    }
+
+    #[tokio::test]
+    async fn route_middleware_runs_only_for_its_route() {
+        let mut app = Ultimo::new_without_defaults();
+        app.use_middleware(trail("global"));
+        app.get_with(
+            "/admin",
+            [trail("auth"), trail("audit")],
+            |ctx: Context| async move { ctx.text("admin").await },
+        );
+        app.get("/admin/public", |ctx: Context| async move {
+            ctx.text("public").await
+        });
+        app.group("/api", |api| {
+            api.use_middleware(trail("api"));
+            api.post_with("/users", [trail("limit")], |ctx: Context| async move {
+                ctx.text("created").await
+            });
+            api.options_with("/users", [trail("preflight")], |ctx: Context| async move {
+                ctx.no_content().await
+            });
+        });
+        app.options_with("/admin", [trail("preflight")], |ctx: Context| async move {
+            ctx.no_content().await
+        });
+
+        let resp = app.oneshot(get("/admin")).await;
+        assert_eq!(resp.headers()["x-trail"], "global,auth,audit");
+        assert_eq!(body_string(resp).await, "admin");
+
+        let resp = app.oneshot(get("/admin/public")).await;
+        assert_eq!(resp.headers()["x-trail"], "global");
+
+        let req = HyperRequest::builder()
+            .method("POST")
+            .uri("/api/users")
+            .body(Full::new(Bytes::new()))
+            .unwrap();
+        let resp = app.oneshot(req).await;
+        assert_eq!(resp.headers()["x-trail"], "global,api,limit");
+
+        for (path, trail) in [
+            ("/admin", "global,preflight"),
+            ("/api/users", "global,api,preflight"),
+        ] {
+            let req = HyperRequest::builder()
+                .method("OPTIONS")
+                .uri(path)
+                .body(Full::new(Bytes::new()))
+                .unwrap();
+            let resp = app.oneshot(req).await;
+            assert_eq!(resp.headers()["x-trail"], trail);
+        }
+    }
+
+    #[tokio::test]
+    async fn mount_prefixes_router_and_scopes_middleware() {
+        fn users() -> RouteGroup {
+            let mut users = Ultimo::router();
+            users.use_middleware(trail("users"));
+            users.get("/", |ctx: Context| async move { ctx.text("list").await });
+            users.get("/:id", |ctx: Context| async move {
+                let id = ctx.req.param("id")?.to_string();
+                ctx.text(id).await
+            });
+            users
+        }
+
+        let mut app = Ultimo::new_without_defaults();
+        app.use_middleware(trail("global"));
+        app.mount("/users", users());
+        app.group("/api", |api| {
+            api.use_middleware(trail("api"));
+            api.mount("/v2/users", users());
+        });
+        app.get(
+            "/health",
+            |ctx: Context| async move { ctx.text("ok").await },
+        );
+
+        let resp = app.oneshot(get("/users")).await;
+        assert_eq!(resp.headers()["x-trail"], "global,users");
+        assert_eq!(body_string(resp).await, "list");
+
+        let resp = app.oneshot(get("/users/7")).await;
+        assert_eq!(body_string(resp).await, "7");
+
+        let resp = app.oneshot(get("/api/v2/users/9")).await;
+        assert_eq!(resp.headers()["x-trail"], "global,api,users");
+        assert_eq!(body_string(resp).await, "9");
+
+        let resp = app.oneshot(get("/health")).await;
+        assert_eq!(resp.headers()["x-trail"], "global");
+        assert_eq!(app.oneshot(get("/7")).await.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn debug_config_route_reports_effective_settings() {
+        let mut app = Ultimo::new();
+        app.max_body_size(1024)
+            .use_middleware(crate::middleware::builtin::logger())
+            .get("/a", |ctx: Context| async move { ctx.text("a").await })
+            .debug_config_route("/__config");
+
+        let resp = app.oneshot(get("/__config")).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(body["max_body_size"], 1024);
+        assert_eq!(body["trust_proxy"], false);
+        assert_eq!(
+            body["middleware"],
+            serde_json::json!(["powered_by", "anonymous"])
+        );
+        assert_eq!(body["routes"]["http"], 2);
+        assert!(body["features"].is_array());
+    }
+
+    #[tokio::test]
+    async fn debug_config_route_hides_from_remote_peers() {
+        let mut app = Ultimo::new_without_defaults();
+        app.debug_config_route("/__config");
+
+        let (parts, _) = get("/__config").into_parts();
+        let remote: SocketAddr = "203.0.113.7:4000".parse().unwrap();
+        let resp = app
+            .dispatch_parts(parts, Bytes::new().into(), Some(remote))
+            .await;
+        assert_eq!(resp.status(), 404);
+
+        let (parts, _) = get("/__config").into_parts();
+        let local: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+        let resp = app
+            .dispatch_parts(parts, Bytes::new().into(), Some(local))
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn debug_config_route_hides_from_same_host_proxies() {
+        let mut app = Ultimo::new_without_defaults();
+        app.debug_config_route("/__config");
+        let local: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+
+        for header in ["X-Forwarded-For", "X-Real-IP", "Forwarded", "Via"] {
+            let req = HyperRequest::builder()
+                .uri("/__config")
+                .header(header, "203.0.113.7")
+                .body(())
+                .unwrap();
+            let (parts, _) = req.into_parts();
+            let resp = app
+                .dispatch_parts(parts, Bytes::new().into(), Some(local))
+                .await;
+            assert_eq!(resp.status(), 404, "{header}");
+        }
+
+        app.trust_proxy(true);
+        let (parts, _) = get("/__config").into_parts();
+        let resp = app
+            .dispatch_parts(parts, Bytes::new().into(), Some(local))
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn route_listing_reports_patterns_in_registration_order() {
+        let mut app = Ultimo::new_without_defaults();
+        app.enable_route_listing("/__routes");
+        app.get(
+            "/users/:id",
+            |ctx: Context| async move { ctx.text("u").await },
+        );
+        app.group("/api", |api| {
+            api.post("/items", |ctx: Context| async move { ctx.text("i").await });
+        });
+
+        let resp = app.oneshot(get("/__routes")).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!([
+                { "method": "GET", "path": "/__routes", "websocket": false },
+                { "method": "GET", "path": "/users/:id", "websocket": false },
+                { "method": "POST", "path": "/api/items", "websocket": false },
+            ])
+        );
+
+        let (parts, _) = get("/__routes").into_parts();
+        let remote: SocketAddr = "203.0.113.7:4000".parse().unwrap();
+        let resp = app
+            .dispatch_parts(parts, Bytes::new().into(), Some(remote))
+            .await;
+        assert_eq!(resp.status(), 404);
+
+        let req = HyperRequest::builder()
+            .uri("/__routes")
+            .header("X-Forwarded-For", "203.0.113.7")
+            .body(())
+            .unwrap();
+        let (parts, _) = req.into_parts();
+        let local: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+        let resp = app
+            .dispatch_parts(parts, Bytes::new().into(), Some(local))
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[test]
+    fn generate_openapi_skips_framework_endpoints() {
+        let mut app = Ultimo::new_without_defaults();
+        app.get("/files/*", |ctx: Context| async move { ctx.json(()).await });
+        app.serve_metrics("/metrics")
+            .debug_config_route("/__config")
+            .enable_route_listing("/__routes");
+        let earlier = app.generate_openapi("API", "1.0.0");
+        app.serve_openapi(earlier, "/openapi.json", "/docs");
+
+        let spec = app.generate_openapi("API", "1.0.0");
+        let paths: Vec<_> = spec.paths.keys().cloned().collect();
+        assert_eq!(paths, ["/files/{wildcard}"]);
+    }
+
+    #[test]
+    fn generate_openapi_documents_plain_routes() {
+        let mut app = Ultimo::new_without_defaults();
+        app.openapi_schema("User", "{ id: number; name: string }");
+        app.get("/users", |ctx: Context| async move { ctx.json(()).await });
+        app.route("/users")
+            .summary("Create a user")
+            .tag("users")
+            .request_body("{ name: string }")
+            .response(201, "User")
+            .post(|ctx: Context| async move { ctx.json(()).await });
+        app.group("/users", |users| {
+            users
+                .route("/:id")
+                .response(200, "User")
+                .empty_response(404)
+                .get(|ctx: Context| async move { ctx.json(()).await });
+            users.delete("/:id", |ctx: Context| async move { ctx.no_content().await });
+        });
+        app.options(
+            "/users",
+            |ctx: Context| async move { ctx.no_content().await },
+        );
+
+        let spec = app.generate_openapi("Users API", "1.0.0");
+        assert_eq!(spec.info.title, "Users API");
+        let mut paths: Vec<_> = spec.paths.keys().cloned().collect();
+        paths.sort();
+        assert_eq!(paths, ["/users", "/users/{id}"]);
+
+        // Undocumented routes still get their method and a bare 200
+        let list = spec.paths["/users"].get.as_ref().unwrap();
+        assert!(list.parameters.is_none() && list.request_body.is_none());
+        assert_eq!(list.responses["200"].description, "Successful response");
+
+        let create = spec.paths["/users"].post.as_ref().unwrap();
+        assert_eq!(create.summary.as_deref(), Some("Create a user"));
+        assert_eq!(create.tags, Some(vec!["users".to_string()]));
+        let body = &create.request_body.as_ref().unwrap().content["application/json"];
+        assert!(body
+            .schema
+            .properties
+            .as_ref()
+            .unwrap()
+            .contains_key("name"));
+        let created = create.responses["201"].content.as_ref().unwrap();
+        assert_eq!(
+            created["application/json"].schema.reference.as_deref(),
+            Some("#/components/schemas/User")
+        );
+
+        let item = &spec.paths["/users/{id}"];
+        let get_user = item.get.as_ref().unwrap();
+        let param = &get_user.parameters.as_ref().unwrap()[0];
+        assert_eq!(param.name, "id");
+        assert!(matches!(
+            param.location,
+            crate::openapi::ParameterLocation::Path
+        ));
+        assert_eq!(get_user.responses["404"].description, "Not Found");
+        assert!(get_user.responses["404"].content.is_none());
+        assert!(item.delete.is_some() && item.post.is_none());
+
+        let schemas = spec.components.unwrap().schemas.unwrap();
+        assert!(schemas.contains_key("User"));
+    }
+
+    #[test]
+    fn generate_openapi_lists_accepted_content_types() {
+        let mut app = Ultimo::new_without_defaults();
+        app.route("/upload")
+            .content_type("application/json")
+            .content_type("application/x-ndjson")
+            .request_body("{ name: string }[]")
+            .post(|ctx: Context| async move { ctx.no_content().await });
+
+        let spec = app.generate_openapi("API", "1.0.0");
+        let op = spec.paths["/upload"].post.as_ref().unwrap();
+        let content = &op.request_body.as_ref().unwrap().content;
+        let mut types: Vec<_> = content.keys().cloned().collect();
+        types.sort();
+        assert_eq!(types, ["application/json", "application/x-ndjson"]);
+        assert_eq!(
+            content["application/json"].schema.schema_type.as_deref(),
+            Some("array")
+        );
+    }
+
+    #[tokio::test]
+    async fn oneshot_strips_body_from_middleware_built_204() {
+        let mut app = Ultimo::new_without_defaults();
+        app.use_middleware(Arc::new(|_ctx, _next| {
+            Box::pin(async move {
+                Ok(hyper::Response::builder()
+                    .status(204)
+                    .header("content-type", "application/json")
+                    .body(response::Body::from("{}"))
+                    .unwrap())
+            })
+        }));
+        app.get(
+            "/",
+            |ctx: Context| async move { ctx.text("unreachable").await },
+        );
+
+        let req = HyperRequest::builder()
+            .uri("/")
+            .body(Full::new(bytes::Bytes::new()))
+            .unwrap();
+        let resp = app.oneshot(req).await;
+        assert_eq!(resp.status(), 204);
+        assert!(resp.headers().get("content-type").is_none());
+        assert_eq!(body_string(resp).await, "");
+    }
+
     #[tokio::test]
     async fn oneshot_unknown_route_is_404() {
         let app = Ultimo::new_without_defaults();
@@ -830,4 +3656,152 @@ mod oneshot_tests {
             .unwrap();
         assert_eq!(app.oneshot(req).await.status(), 404);
     }
+
+    #[tokio::test]
+    async fn oneshot_wrong_method_is_405_with_allow() {
+        let mut app = Ultimo::new_without_defaults();
+        app.get(
+            "/items",
+            |ctx: Context| async move { ctx.text("list").await },
+        );
+        app.delete("/items/:id", |ctx: Context| async move {
+            ctx.text("deleted").await
+        });
+        app.get(
+            "/items/:id",
+            |ctx: Context| async move { ctx.text("one").await },
+        );
+
+        let resp = app.oneshot(post("/items", "{}")).await;
+        assert_eq!(resp.status(), 405);
+        assert_eq!(resp.headers()["allow"], "GET, HEAD");
+        let body: serde_json::Value = serde_json::from_str(&body_string(resp).await).unwrap();
+        assert_eq!(body["error"], "MethodNotAllowed");
+
+        let resp = app.oneshot(post("/items/3", "{}")).await;
+        assert_eq!(resp.headers()["allow"], "DELETE, GET, HEAD");
+        assert_eq!(app.oneshot(post("/missing", "{}")).await.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn oneshot_trailing_slash_modes() {
+        let build = |mode| {
+            let mut app = Ultimo::new_without_defaults();
+            app.trailing_slash(mode);
+            app.get(
+                "/users",
+                |ctx: Context| async move { ctx.text("users").await },
+            );
+            app.get("/users/:id", |ctx: Context| async move {
+                let id = ctx.req.param("id")?.to_string();
+                ctx.text(id).await
+            });
+            app
+        };
+
+        let app = build(TrailingSlash::Ignore);
+        assert_eq!(
+            body_string(app.oneshot(get("/users/")).await).await,
+            "users"
+        );
+        assert_eq!(body_string(app.oneshot(get("/users/4/")).await).await, "4");
+
+        let app = build(TrailingSlash::Strict);
+        assert_eq!(app.oneshot(get("/users")).await.status(), 200);
+        assert_eq!(app.oneshot(get("/users/")).await.status(), 404);
+        assert_eq!(app.oneshot(get("/users/4/")).await.status(), 404);
+
+        // Group routes keep their trailing slash, so both forms can coexist.
+        let mut app = build(TrailingSlash::Strict);
+        app.group("/docs", |docs| {
+            docs.get(
+                "/guide",
+                |ctx: Context| async move { ctx.text("page").await },
+            );
+            docs.get(
+                "/guide/",
+                |ctx: Context| async move { ctx.text("index").await },
+            );
+        });
+        assert_eq!(
+            body_string(app.oneshot(get("/docs/guide")).await).await,
+            "page"
+        );
+        assert_eq!(
+            body_string(app.oneshot(get("/docs/guide/")).await).await,
+            "index"
+        );
+
+        let app = build(TrailingSlash::Redirect);
+        let resp = app.oneshot(get("/users/?page=2")).await;
+        assert_eq!(resp.status(), 301);
+        assert_eq!(resp.headers()["location"], "/users?page=2");
+        let resp = app.oneshot(get("/users/4/")).await;
+        assert_eq!(resp.status(), 301);
+        assert_eq!(resp.headers()["location"], "/users/4");
+        assert_eq!(app.oneshot(get("/users/4")).await.status(), 200);
+
+        // A protocol-relative target would send the browser to another host.
+        let mut app = build(TrailingSlash::Redirect);
+        app.get(
+            "/:slug",
+            |ctx: Context| async move { ctx.text("slug").await },
+        );
+        let resp = app.oneshot(get("//evil.com/")).await;
+        assert_eq!(resp.status(), 301);
+        assert_eq!(resp.headers()["location"], "/evil.com");
+        // Browsers read `/\evil.com` as `//evil.com` too.
+        let resp = app.oneshot(get("/\\evil.com/")).await;
+        assert_ne!(resp.status(), 301);
+        assert!(resp.headers().get("location").is_none());
+    }
+
+    #[tokio::test]
+    async fn oneshot_on_error_hook_renders_handler_and_middleware_errors() {
+        let mut app = Ultimo::new_without_defaults();
+        app.on_error(|err, info| {
+            let id = info
+                .headers
+                .get("x-request-id")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("-");
+            ResponseBuilder::new()
+                .status(err.status_code())
+                .header("X-Request-Id", id)
+                .text(format!("{} {} {}", info.method, info.path, err))
+                .build()
+                .unwrap()
+        });
+        app.get("/missing", |_ctx: Context| async move {
+            Err::<Response, _>(UltimoError::NotFound("no such thing".into()))
+        });
+        app.group("/admin", |admin| {
+            admin.use_middleware(Arc::new(|_ctx, _next| {
+                Box::pin(async move { Err(UltimoError::Forbidden("admins only".into())) })
+            }));
+            admin.get("/", |ctx: Context| async move { ctx.text("admin").await });
+        });
+
+        let req = HyperRequest::builder()
+            .uri("/missing?x=1")
+            .header("X-Request-Id", "abc")
+            .body(Full::new(bytes::Bytes::new()))
+            .unwrap();
+        let resp = app.oneshot(req).await;
+        assert_eq!(resp.status(), 404);
+        assert_eq!(resp.headers()["x-request-id"], "abc");
+        assert_eq!(
+            body_string(resp).await,
+            "GET /missing Not found: no such thing"
+        );
+
+        let resp = app.oneshot(get("/admin")).await;
+        assert_eq!(resp.status(), 403);
+        assert!(body_string(resp).await.starts_with("GET /admin "));
+
+        // Unmatched routes are not errors: the default 404 is untouched.
+        let resp = app.oneshot(get("/nope")).await;
+        assert_eq!(resp.status(), 404);
+        assert!(body_string(resp).await.contains("NotFound"));
+    }
 }
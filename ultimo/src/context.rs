@@ -4,7 +4,7 @@
 
 use crate::{
     error::{Result, UltimoError},
-    response::{Response, ResponseBuilder},
+    response::{BoxError, Response, ResponseBuilder},
     router::Params,
 };
 use bytes::Bytes;
@@ -13,26 +13,144 @@ use hyper::{body::Incoming, Request as HyperRequest};
 use serde::{de::DeserializeOwned, Serialize};
 use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::task::Poll;
+use tokio::sync::{Mutex, RwLock};
+
+/// Longest incoming `X-Request-Id` that is reused or logged.
+const MAX_REQUEST_ID_LEN: usize = 128;
 
 #[cfg(feature = "database")]
 use crate::database::Database;
 
+/// A request body, read from the connection only when a handler asks for it.
+pub(crate) enum RequestBody {
+    /// Still on the connection; reading fails once the limit (in bytes) is
+    /// exceeded.
+    Pending(Incoming, usize),
+    /// Read (or supplied) in full.
+    Buffered(Bytes),
+    /// Handed out by [`Request::body_stream`], or lost to a failed read.
+    Taken,
+}
+
+impl RequestBody {
+    /// A live connection body, capped at `limit` bytes if set.
+    pub(crate) fn pending(body: Incoming, limit: Option<usize>) -> Self {
+        Self::Pending(body, limit.unwrap_or(usize::MAX))
+    }
+
+    /// Replace the limit on a body still on the connection.
+    pub(crate) fn limit(self, limit: usize) -> Self {
+        match self {
+            Self::Pending(body, _) => Self::Pending(body, limit),
+            other => other,
+        }
+    }
+}
+
+impl From<Bytes> for RequestBody {
+    fn from(bytes: Bytes) -> Self {
+        Self::Buffered(bytes)
+    }
+}
+
+/// Map a failed body read to the error handed to the handler.
+fn body_read_error(err: Box<dyn std::error::Error + Send + Sync>) -> UltimoError {
+    if err.is::<http_body_util::LengthLimitError>() {
+        UltimoError::Http {
+            status: 413,
+            message: "Payload Too Large".to_string(),
+        }
+    } else {
+        UltimoError::Internal(format!("Failed to read body: {}", err))
+    }
+}
+
+/// Parse one `Accept` entry into `(type/subtype, q)`, lowercased. Entries
+/// without a `/` are skipped; a missing or unparsable `q` counts as 1.
+fn parse_media_range(entry: &str) -> Option<(String, f32)> {
+    let mut parts = entry.split(';').map(str::trim);
+    let range = parts.next()?.to_ascii_lowercase();
+    if !range.contains('/') {
+        return None;
+    }
+    let q = parts
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+        .and_then(|(_, value)| value.trim().parse::<f32>().ok())
+        .map_or(1.0, |q| q.clamp(0.0, 1.0));
+    Some((range, q))
+}
+
+/// The quality `ranges` give `media_type`: that of the most specific
+/// matching range (`type/subtype` over `type/*` over `*/*`), 0 if none
+/// match, and 1 when there are no ranges at all.
+fn accept_quality(ranges: &[(String, f32)], media_type: &str) -> f32 {
+    if ranges.is_empty() {
+        return 1.0;
+    }
+    let media_type = media_type.to_ascii_lowercase();
+    let main_type = media_type.split('/').next().unwrap_or_default();
+    let specificity = |range: &str| {
+        if range == media_type {
+            Some(2)
+        } else if range.strip_suffix("/*") == Some(main_type) {
+            Some(1)
+        } else if range == "*/*" {
+            Some(0)
+        } else {
+            None
+        }
+    };
+    ranges
+        .iter()
+        .filter_map(|(range, q)| specificity(range).map(|rank| (rank, *q)))
+        .max_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)))
+        .map_or(0.0, |(_, q)| q)
+}
+
+fn body_consumed() -> UltimoError {
+    UltimoError::BadRequest("Body already consumed".to_string())
+}
+
+/// The request body as a stream of chunks, from [`Request::body_stream`].
+///
+/// Yields each chunk as it arrives, so large uploads can be processed without
+/// holding the whole body in memory. The route's `max_body_size` still applies:
+/// exceeding it yields a `413 Payload Too Large` error.
+pub struct BodyStream {
+    inner: Pin<Box<dyn futures_util::Stream<Item = Result<Bytes>> + Send>>,
+}
+
+impl futures_util::Stream for BodyStream {
+    type Item = Result<Bytes>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
 /// Request wraps the incoming HTTP request and provides easy access to request data
 pub struct Request {
     method: hyper::Method,
     uri: hyper::Uri,
     headers: hyper::HeaderMap,
     params: Params,
-    body: Arc<RwLock<Option<Bytes>>>,
+    body: Arc<Mutex<RequestBody>>,
 }
 
 impl Request {
-    /// Build a Request from already-parsed parts and a buffered body.
+    /// Build a Request from already-parsed parts and a body, which is read
+    /// on first use if still on the connection.
     pub(crate) fn from_parts(
         parts: hyper::http::request::Parts,
-        body: Bytes,
+        body: impl Into<RequestBody>,
         params: Params,
     ) -> Self {
         Self {
@@ -40,18 +158,20 @@ impl Request {
             uri: parts.uri,
             headers: parts.headers,
             params,
-            body: Arc::new(RwLock::new(Some(body))),
+            body: Arc::new(Mutex::new(body.into())),
         }
     }
 
-    /// Create a new Request from a Hyper request and path parameters
+    /// Create a new Request from a Hyper request and path parameters.
+    ///
+    /// The body is not read here; see [`bytes`](Self::bytes).
     pub async fn new(req: HyperRequest<Incoming>, params: Params) -> Result<Self> {
         let (parts, body) = req.into_parts();
-        let collected = body
-            .collect()
-            .await
-            .map_err(|e| UltimoError::Internal(format!("Failed to read body: {}", e)))?;
-        Ok(Self::from_parts(parts, collected.to_bytes(), params))
+        Ok(Self::from_parts(
+            parts,
+            RequestBody::pending(body, None),
+            params,
+        ))
     }
 
     /// Get a path parameter by name
@@ -62,6 +182,21 @@ impl Request {
             .ok_or_else(|| UltimoError::BadRequest(format!("Missing path parameter: {}", name)))
     }
 
+    /// Get a path parameter parsed as `T`
+    ///
+    /// Returns [`UltimoError::BadRequest`] naming the parameter and target
+    /// type (e.g. `Invalid path parameter 'id': expected i32`) if it is
+    /// missing or fails to parse.
+    pub fn param_as<T: FromStr>(&self, name: &str) -> Result<T> {
+        self.param(name)?.parse().map_err(|_| {
+            UltimoError::BadRequest(format!(
+                "Invalid path parameter '{}': expected {}",
+                name,
+                std::any::type_name::<T>()
+            ))
+        })
+    }
+
     /// Get all path parameters at once
     pub fn params(&self) -> &Params {
         &self.params
@@ -108,57 +243,341 @@ impl Request {
             .map(|s| s.to_string())
     }
 
+    /// The media ranges listed in the `Accept` header(s) with their quality
+    /// values, highest quality first (ties keep header order). Parameters
+    /// other than `q` are dropped; an absent header yields an empty list.
+    pub fn accepted_types(&self) -> Vec<(String, f32)> {
+        let mut ranges: Vec<(String, f32)> = self
+            .headers
+            .get_all(hyper::header::ACCEPT)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(parse_media_range)
+            .collect();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges
+    }
+
+    /// Whether the client accepts `media_type` (e.g. `"application/json"`),
+    /// directly or through a wildcard like `text/*` or `*/*`, with a
+    /// non-zero quality. A request without an `Accept` header accepts
+    /// anything.
+    pub fn accepts(&self, media_type: &str) -> bool {
+        accept_quality(&self.accepted_types(), media_type) > 0.0
+    }
+
+    /// Pick the media type from `offered` the client prefers, by the
+    /// quality of the most specific matching `Accept` range. Ties go to the
+    /// earlier entry in `offered`, so list the server's preference first.
+    /// Returns `None` when the client accepts none of them (answer with
+    /// `406 Not Acceptable`).
+    ///
+    /// ```rust,ignore
+    /// app.get("/report", |ctx: Context| async move {
+    ///     match ctx.req.negotiate(&["application/json", "text/html"]) {
+    ///         Some("text/html") => ctx.html("<h1>Report</h1>").await,
+    ///         Some(_) => ctx.json(json!({ "title": "Report" })).await,
+    ///         None => Err(UltimoError::Http {
+    ///             status: 406,
+    ///             message: "Not Acceptable".into(),
+    ///         }),
+    ///     }
+    /// });
+    /// ```
+    pub fn negotiate<'a>(&self, offered: &[&'a str]) -> Option<&'a str> {
+        let ranges = self.accepted_types();
+        let mut best: Option<(&'a str, f32)> = None;
+        for &media_type in offered {
+            let q = accept_quality(&ranges, media_type);
+            if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) {
+                best = Some((media_type, q));
+            }
+        }
+        best.map(|(media_type, _)| media_type)
+    }
+
+    /// The token from an `Authorization: Bearer <token>` header. The scheme
+    /// is matched case-insensitively; a missing header, another scheme, or an
+    /// empty token gives `None`.
+    ///
+    /// ```rust,ignore
+    /// let token = ctx.req.bearer_token().ok_or_else(|| {
+    ///     UltimoError::Unauthorized("missing bearer token".into())
+    /// })?;
+    /// ```
+    pub fn bearer_token(&self) -> Option<String> {
+        let value = self
+            .headers
+            .get(hyper::header::AUTHORIZATION)?
+            .to_str()
+            .ok()?;
+        let (scheme, token) = value.trim().split_once(' ')?;
+        let token = token.trim();
+        (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then(|| token.to_string())
+    }
+
+    /// Read a cookie sent in the `Cookie` header(s) by name.
+    pub fn cookie(&self, name: &str) -> Option<String> {
+        self.cookies().remove(name)
+    }
+
+    /// All cookies sent in the request. Multiple `Cookie` headers (as sent
+    /// over HTTP/2) are combined.
+    pub fn cookies(&self) -> HashMap<String, String> {
+        let mut cookies = HashMap::new();
+        for header in self.headers.get_all(hyper::header::COOKIE) {
+            let Ok(header) = header.to_str() else {
+                continue;
+            };
+            for (name, value) in crate::cookie::parse_cookie_header(header) {
+                cookies.entry(name).or_insert(value);
+            }
+        }
+        cookies
+    }
+
     /// Get the request path
     pub fn path(&self) -> &str {
         self.uri.path()
     }
 
+    /// Get the request URI
+    pub fn uri(&self) -> &hyper::Uri {
+        &self.uri
+    }
+
+    /// Get all request headers
+    pub fn headers(&self) -> &hyper::HeaderMap {
+        &self.headers
+    }
+
     /// Get the full URL as a string
     pub fn url(&self) -> String {
         self.uri.to_string()
     }
 
+    /// Replace the request path, keeping the query string (used by
+    /// path-normalizing middleware).
+    pub(crate) fn set_path(&mut self, path: &str) {
+        let path_and_query = match self.uri.query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path.to_string(),
+        };
+        let mut parts = self.uri.clone().into_parts();
+        if let Ok(pq) = path_and_query.parse() {
+            parts.path_and_query = Some(pq);
+            if let Ok(uri) = hyper::Uri::from_parts(parts) {
+                self.uri = uri;
+            }
+        }
+    }
+
     /// Get the request method
     pub fn method(&self) -> &hyper::Method {
         &self.method
     }
 
-    /// Parse request body as JSON
+    /// Parse the request body as JSON into `T`.
+    ///
+    /// Returns a `415 Unsupported Media Type` error if a `Content-Type` is
+    /// sent and is neither `application/json` nor a `+json` type (e.g.
+    /// `application/merge-patch+json`); requests without one are parsed
+    /// anyway. An empty or malformed body is an [`UltimoError::BadRequest`]
+    /// whose message names the line and column serde stopped at.
+    ///
+    /// ```rust,ignore
+    /// let input: CreateUser = ctx.req.json().await?;
+    /// ```
     pub async fn json<T: DeserializeOwned>(&self) -> Result<T> {
-        let body = self.body.read().await;
-        let bytes = body
-            .as_ref()
-            .ok_or_else(|| UltimoError::BadRequest("Body already consumed".to_string()))?;
+        if let Some(content_type) = self.header("content-type") {
+            let mime = content_type.split(';').next().unwrap_or_default().trim();
+            let mime = mime.to_ascii_lowercase();
+            if mime != "application/json" && !mime.ends_with("+json") {
+                return Err(UltimoError::Http {
+                    status: 415,
+                    message: "Expected Content-Type: application/json".to_string(),
+                });
+            }
+        }
+        let bytes = self.bytes().await?;
+        if bytes.iter().all(u8::is_ascii_whitespace) {
+            return Err(UltimoError::BadRequest("request body is empty".to_string()));
+        }
+        crate::json::from_slice(&bytes).map_err(|e| {
+            UltimoError::BadRequest(format!(
+                "Invalid JSON body at line {}, column {}: {}",
+                e.line(),
+                e.column(),
+                e
+            ))
+        })
+    }
 
-        serde_json::from_slice(bytes).map_err(UltimoError::Json)
+    /// Parse the request body as JSON into `T`, then validate it.
+    ///
+    /// Parse errors are the same as [`json`](Self::json)'s; a body that
+    /// parses but fails validation is an [`UltimoError::Validation`] (400)
+    /// listing each failing field.
+    ///
+    /// ```rust,ignore
+    /// let input: CreateUser = ctx.req.json_validated().await?;
+    /// ```
+    pub async fn json_validated<T: DeserializeOwned + validator::Validate>(&self) -> Result<T> {
+        let value = self.json::<T>().await?;
+        crate::validation::validate(&value)?;
+        Ok(value)
+    }
+
+    /// Apply the request body to `existing` as a JSON Merge Patch
+    /// ([RFC 7396]) and return the patched copy.
+    ///
+    /// Fields the body omits keep their current value, fields set to `null`
+    /// are cleared (an `Option` field becomes `None`), and nested objects are
+    /// merged the same way. The body is read as with [`json`](Self::json); a
+    /// patch that leaves a field with the wrong type, or clears a required
+    /// one, is an [`UltimoError::BadRequest`].
+    ///
+    /// ```rust,ignore
+    /// // PATCH /users/1 with {"bio": null} clears the bio, keeps the name.
+    /// let user: User = ctx.req.json_patch(&current).await?;
+    /// ```
+    ///
+    /// [RFC 7396]: https://www.rfc-editor.org/rfc/rfc7396
+    pub async fn json_patch<T: Serialize + DeserializeOwned>(&self, existing: &T) -> Result<T> {
+        let patch: serde_json::Value = self.json().await?;
+        let mut value = serde_json::to_value(existing)?;
+        crate::json::merge_patch(&mut value, patch);
+        serde_json::from_value(value)
+            .map_err(|e| UltimoError::BadRequest(format!("Invalid patch: {}", e)))
     }
 
     /// Parse request body as text
     pub async fn text(&self) -> Result<String> {
-        let body = self.body.read().await;
-        let bytes = body
-            .as_ref()
-            .ok_or_else(|| UltimoError::BadRequest("Body already consumed".to_string()))?;
-
+        let bytes = self.bytes().await?;
         String::from_utf8(bytes.to_vec())
             .map_err(|e| UltimoError::BadRequest(format!("Invalid UTF-8: {}", e)))
     }
 
+    /// Parse an `application/x-www-form-urlencoded` body into `T`.
+    ///
+    /// Returns a `415 Unsupported Media Type` error if the `Content-Type` is
+    /// not form-encoded, and [`UltimoError::BadRequest`] if decoding fails.
+    ///
+    /// ```rust,ignore
+    /// #[derive(Deserialize)]
+    /// struct Login { username: String, password: String }
+    ///
+    /// let login: Login = ctx.req.form().await?;
+    /// ```
+    pub async fn form<T: DeserializeOwned>(&self) -> Result<T> {
+        let is_form = self.header("content-type").is_some_and(|ct| {
+            ct.split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+        });
+        if !is_form {
+            return Err(UltimoError::Http {
+                status: 415,
+                message: "Expected Content-Type: application/x-www-form-urlencoded".to_string(),
+            });
+        }
+        let bytes = self.bytes().await?;
+        serde_urlencoded::from_bytes(&bytes)
+            .map_err(|e| UltimoError::BadRequest(format!("Invalid form body: {}", e)))
+    }
+
     /// Get request body as bytes.
     ///
-    /// The body is buffered and cached, so this (and [`json`](Self::json) /
-    /// [`text`](Self::text)) may be called any number of times.
+    /// The body is read from the connection on the first call (or the first
+    /// [`json`](Self::json) / [`text`](Self::text) / [`form`](Self::form)),
+    /// then cached, so these may be called any number of times. Routes that
+    /// never ask for the body never read it. Fails with `413 Payload Too
+    /// Large` past the route's `max_body_size`, and with `400` after
+    /// [`body_stream`](Self::body_stream) has taken the body.
     pub async fn bytes(&self) -> Result<Bytes> {
-        let body = self.body.read().await;
-        body.as_ref()
-            .cloned()
-            .ok_or_else(|| UltimoError::BadRequest("Body already consumed".to_string()))
+        let mut body = self.body.lock().await;
+        match std::mem::replace(&mut *body, RequestBody::Taken) {
+            RequestBody::Buffered(bytes) => {
+                *body = RequestBody::Buffered(bytes.clone());
+                Ok(bytes)
+            }
+            RequestBody::Pending(incoming, limit) => {
+                let bytes = http_body_util::Limited::new(incoming, limit)
+                    .collect()
+                    .await
+                    .map_err(body_read_error)?
+                    .to_bytes();
+                *body = RequestBody::Buffered(bytes.clone());
+                Ok(bytes)
+            }
+            RequestBody::Taken => Err(body_consumed()),
+        }
+    }
+
+    /// Take the request body as a stream of chunks, without buffering it.
+    ///
+    /// The body can be streamed once; afterwards [`bytes`](Self::bytes) and
+    /// the other readers fail with `400 Body already consumed`. If the body
+    /// was already buffered, the stream yields it as a single chunk and the
+    /// buffered copy stays readable.
+    ///
+    /// ```rust,ignore
+    /// use futures_util::StreamExt;
+    ///
+    /// app.post("/upload", |ctx: Context| async move {
+    ///     let mut stream = ctx.req.body_stream().await?;
+    ///     let mut total = 0;
+    ///     while let Some(chunk) = stream.next().await {
+    ///         total += chunk?.len();
+    ///     }
+    ///     ctx.json(json!({ "received": total })).await
+    /// });
+    /// ```
+    pub async fn body_stream(&self) -> Result<BodyStream> {
+        use futures_util::{stream, StreamExt, TryStreamExt};
+
+        let mut body = self.body.lock().await;
+        let inner = match std::mem::replace(&mut *body, RequestBody::Taken) {
+            RequestBody::Pending(incoming, limit) => http_body_util::Limited::new(incoming, limit)
+                .into_data_stream()
+                .map_err(body_read_error)
+                .boxed(),
+            RequestBody::Buffered(bytes) => {
+                *body = RequestBody::Buffered(bytes.clone());
+                stream::once(async move { Ok(bytes) }).boxed()
+            }
+            RequestBody::Taken => return Err(body_consumed()),
+        };
+        Ok(BodyStream { inner })
     }
 
     /// Raw request body bytes (alias for [`bytes`](Self::bytes)). Repeatable.
     pub async fn raw_body(&self) -> Result<Bytes> {
         self.bytes().await
     }
+
+    /// Parse the body as `multipart/form-data`.
+    ///
+    /// The body is streamed (see [`body_stream`](Self::body_stream)), so
+    /// [`save_all`](crate::multipart::Multipart::save_all) writes uploads to
+    /// disk chunk by chunk instead of holding them in memory. The route's
+    /// `max_body_size` still caps the whole body; raise it for upload routes.
+    /// Returns [`UltimoError::BadRequest`] if the `Content-Type` is missing or
+    /// has no boundary.
+    ///
+    /// ```rust,ignore
+    /// let saved = ctx.req.multipart().await?
+    ///     .max_file_size(10 * 1024 * 1024)
+    ///     .save_all("./uploads")
+    ///     .await?;
+    /// ```
+    pub async fn multipart(&self) -> Result<crate::multipart::Multipart> {
+        let body = self.body_stream().await?;
+        crate::multipart::Multipart::new(self.header("content-type").as_deref(), body)
+    }
 }
 
 #[cfg(test)]
@@ -217,17 +636,32 @@ fn parse_forwarded_for(header: &str) -> Option<IpAddr> {
     None
 }
 
+/// Extension key for the identity stored by the basic / bearer auth middlewares,
+/// so it can't collide with a value of the same type set via `set_ext`.
+struct AuthIdentity<T>(T);
+
 /// Context holds request data and provides response building methods
 pub struct Context {
     pub req: Request,
     state: Arc<RwLock<HashMap<String, String>>>,
+    /// Typed request-scoped values, keyed by type (see [`Context::set_ext`]).
+    extensions: Arc<RwLock<hyper::http::Extensions>>,
+    /// App-wide values shared with [`Ultimo::with_state`](crate::Ultimo::with_state).
+    app_state: Arc<hyper::http::Extensions>,
+    /// Pattern of the route that matched, e.g. `/users/:id`.
+    matched_route: Option<Arc<str>>,
     response_status: Arc<RwLock<Option<u16>>>,
-    response_headers: Arc<RwLock<HashMap<String, String>>>,
+    response_headers: Arc<RwLock<Vec<(String, String)>>>,
     set_cookies: Arc<RwLock<Vec<String>>>,
     /// Peer address of the connection (set by the server; None for in-process dispatch).
     client_addr: Option<SocketAddr>,
-    /// Whether to trust `X-Forwarded-For` / `Forwarded` headers for `client_ip()`.
+    /// Whether to trust `X-Forwarded-For` / `X-Real-IP` / `Forwarded` headers for `client_ip()`.
     trust_proxy: bool,
+    /// Whether JSON responses declare `charset=utf-8` in their `Content-Type`.
+    json_charset: bool,
+    /// Verified certificate of a mutual-TLS client (set by the server).
+    #[cfg(feature = "tls")]
+    client_cert: Option<Arc<crate::tls::ClientCertInfo>>,
     #[cfg(feature = "session")]
     session: Arc<RwLock<Option<crate::session::Session>>>,
     #[cfg(feature = "jwt")]
@@ -239,23 +673,37 @@ pub struct Context {
 
     #[cfg(feature = "database")]
     database: Option<Database>,
+    #[cfg(feature = "database")]
+    named_databases: Option<Arc<HashMap<String, Database>>>,
 }
 
 impl Context {
-    /// Build a Context from already-parsed parts and a buffered body.
+    /// Build a Context from already-parsed parts and a body, which is read on
+    /// first use if still on the connection.
     pub(crate) fn from_parts(
         parts: hyper::http::request::Parts,
-        body: Bytes,
+        body: impl Into<RequestBody>,
         params: Params,
     ) -> Self {
+        #[cfg(feature = "tls")]
+        let client_cert = parts
+            .extensions
+            .get::<Arc<crate::tls::ClientCertInfo>>()
+            .cloned();
         Self {
             req: Request::from_parts(parts, body, params),
             state: Arc::new(RwLock::new(HashMap::new())),
+            extensions: Arc::new(RwLock::new(hyper::http::Extensions::new())),
+            app_state: Arc::default(),
+            matched_route: None,
             response_status: Arc::new(RwLock::new(None)),
-            response_headers: Arc::new(RwLock::new(HashMap::new())),
+            response_headers: Arc::new(RwLock::new(Vec::new())),
             set_cookies: Arc::new(RwLock::new(Vec::new())),
             client_addr: None,
             trust_proxy: false,
+            json_charset: false,
+            #[cfg(feature = "tls")]
+            client_cert,
             #[cfg(feature = "session")]
             session: Arc::new(RwLock::new(None)),
             #[cfg(feature = "jwt")]
@@ -266,17 +714,50 @@ impl Context {
             principal: Arc::new(RwLock::new(None)),
             #[cfg(feature = "database")]
             database: None,
+            #[cfg(feature = "database")]
+            named_databases: None,
         }
     }
 
-    /// Create a new context from a request and params
+    /// Create a new context from a request and params.
+    ///
+    /// The body is not read here; see [`Request::bytes`].
     pub async fn new(req: HyperRequest<Incoming>, params: Params) -> Result<Self> {
         let (parts, body) = req.into_parts();
-        let collected = body
-            .collect()
-            .await
-            .map_err(|e| UltimoError::Internal(format!("Failed to read body: {}", e)))?;
-        Ok(Self::from_parts(parts, collected.to_bytes(), params))
+        Ok(Self::from_parts(
+            parts,
+            RequestBody::pending(body, None),
+            params,
+        ))
+    }
+
+    /// Record the pattern of the route that matched (internal use)
+    pub(crate) fn set_matched_route(&mut self, pattern: Arc<str>) {
+        self.matched_route = Some(pattern);
+    }
+
+    /// The pattern of the route that matched, e.g. `/users/:id` for a request
+    /// to `/users/42`.
+    ///
+    /// Use it instead of the concrete path to group logs, traces or metrics
+    /// per route. `None` when no route matched, i.e. in global middleware
+    /// answering a CORS preflight.
+    ///
+    /// ```rust,no_run
+    /// # use ultimo::prelude::*;
+    /// # let mut app = Ultimo::new();
+    /// app.get("/users/:id", |ctx: Context| async move {
+    ///     assert_eq!(ctx.matched_route(), Some("/users/:id"));
+    ///     ctx.text("ok").await
+    /// });
+    /// ```
+    pub fn matched_route(&self) -> Option<&str> {
+        self.matched_route.as_deref()
+    }
+
+    /// Attach the app's shared state to this context (internal use)
+    pub(crate) fn attach_app_state(&mut self, state: Arc<hyper::http::Extensions>) {
+        self.app_state = state;
     }
 
     /// Attach a database to this context (internal use)
@@ -285,6 +766,12 @@ impl Context {
         self.database = Some(db);
     }
 
+    /// Attach the app's named pools to this context (internal use)
+    #[cfg(feature = "database")]
+    pub(crate) fn attach_named_databases(&mut self, dbs: Arc<HashMap<String, Database>>) {
+        self.named_databases = Some(dbs);
+    }
+
     /// Get the database pool (SQLx)
     #[cfg(feature = "sqlx")]
     pub fn sqlx<DB: sqlx::Database>(&self) -> Result<&sqlx::Pool<DB>> {
@@ -314,6 +801,120 @@ impl Context {
         diesel_pool.get().map_err(Into::into)
     }
 
+    /// Get a named SQLx pool attached with [`Ultimo::with_named_pool`](crate::Ultimo::with_named_pool)
+    #[cfg(feature = "sqlx")]
+    pub fn sqlx_named<DB: sqlx::Database>(&self, name: &str) -> Result<&sqlx::Pool<DB>> {
+        let sqlx_pool = self.database_named(name)?.as_sqlx::<DB>()?;
+        Ok(sqlx_pool.pool())
+    }
+
+    /// Get a Diesel connection from a named pool attached with
+    /// [`Ultimo::with_named_pool`](crate::Ultimo::with_named_pool)
+    #[cfg(feature = "diesel")]
+    pub fn diesel_named<Conn>(
+        &self,
+        name: &str,
+    ) -> Result<diesel::r2d2::PooledConnection<diesel::r2d2::ConnectionManager<Conn>>>
+    where
+        Conn: diesel::Connection + diesel::r2d2::R2D2Connection + 'static,
+    {
+        let diesel_pool = self.database_named(name)?.as_diesel::<Conn>()?;
+        diesel_pool.get().map_err(Into::into)
+    }
+
+    /// Run `f` in a transaction on the SQLx pool: commit if it returns `Ok`,
+    /// roll back if it returns `Err` or panics. Returns `f`'s result.
+    ///
+    /// The closure gets `&mut Transaction` and returns a boxed future, the
+    /// same shape as sqlx's own `Connection::transaction`. `sqlx::Error`
+    /// converts into [`UltimoError`], so `?` works inside it.
+    ///
+    /// ```rust,ignore
+    /// let moved = ctx
+    ///     .transaction::<sqlx::Postgres, _, _>(|tx| {
+    ///         Box::pin(async move {
+    ///             sqlx::query("UPDATE accounts SET balance = balance - $1 WHERE id = $2")
+    ///                 .bind(amount).bind(from).execute(&mut **tx).await?;
+    ///             sqlx::query("UPDATE accounts SET balance = balance + $1 WHERE id = $2")
+    ///                 .bind(amount).bind(to).execute(&mut **tx).await?;
+    ///             Ok(amount)
+    ///         })
+    ///     })
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "sqlx")]
+    pub async fn transaction<DB, T, F>(&self, f: F) -> Result<T>
+    where
+        DB: sqlx::Database,
+        for<'c> F: FnOnce(
+            &'c mut sqlx::Transaction<'static, DB>,
+        ) -> std::pin::Pin<
+            Box<dyn std::future::Future<Output = Result<T>> + Send + 'c>,
+        >,
+    {
+        use crate::database::DatabaseError;
+
+        let mut tx = self
+            .sqlx::<DB>()?
+            .begin()
+            .await
+            .map_err(|e| DatabaseError::Transaction(e.to_string()))?;
+        // On panic `tx` is dropped mid-flight, and sqlx rolls it back when the
+        // connection returns to the pool.
+        match f(&mut tx).await {
+            Ok(value) => {
+                tx.commit()
+                    .await
+                    .map_err(|e| DatabaseError::Transaction(e.to_string()))?;
+                Ok(value)
+            }
+            Err(err) => {
+                if let Err(e) = tx.rollback().await {
+                    tracing::error!("Transaction rollback failed: {}", e);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Run `f` in a transaction on a pooled Diesel connection: commit if it
+    /// returns `Ok`, roll back if it returns `Err` or panics (the panic is
+    /// resumed after the rollback). Returns `f`'s result.
+    ///
+    /// ```rust,ignore
+    /// let user = ctx.diesel_transaction(|conn: &mut PgConnection| {
+    ///     let user = diesel::insert_into(users::table).values(&new_user).get_result(conn)?;
+    ///     diesel::insert_into(audit::table).values(&entry_for(&user)).execute(conn)?;
+    ///     Ok(user)
+    /// })?;
+    /// ```
+    #[cfg(feature = "diesel")]
+    pub fn diesel_transaction<Conn, T, F>(&self, f: F) -> Result<T>
+    where
+        Conn: diesel::Connection + diesel::r2d2::R2D2Connection + 'static,
+        F: FnOnce(&mut Conn) -> Result<T>,
+    {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut pooled = self.diesel::<Conn>()?;
+        let conn: &mut Conn = &mut pooled;
+        let mut panicked = None;
+        let result = conn.transaction(|conn| {
+            panic::catch_unwind(AssertUnwindSafe(|| f(conn))).unwrap_or_else(|payload| {
+                // Turn the panic into an error so Diesel rolls back first.
+                panicked = Some(payload);
+                Err(UltimoError::Internal("transaction closure panicked".into()))
+            })
+        });
+        if let Some(payload) = panicked {
+            // Return the (rolled back) connection first: r2d2 discards
+            // connections dropped while unwinding.
+            drop(pooled);
+            panic::resume_unwind(payload);
+        }
+        result
+    }
+
     /// Get the database (generic access)
     #[cfg(feature = "database")]
     pub fn database(&self) -> Result<&Database> {
@@ -322,6 +923,15 @@ impl Context {
             .ok_or(crate::database::DatabaseError::NotConfigured.into())
     }
 
+    /// Get a named database attached with [`Ultimo::with_named_pool`](crate::Ultimo::with_named_pool)
+    #[cfg(feature = "database")]
+    pub fn database_named(&self, name: &str) -> Result<&Database> {
+        self.named_databases
+            .as_ref()
+            .and_then(|dbs| dbs.get(name))
+            .ok_or_else(|| crate::database::DatabaseError::PoolNotFound(name.to_string()).into())
+    }
+
     /// Set a value in the context state (shared between middleware)
     pub async fn set(&self, key: impl Into<String>, value: impl Into<String>) {
         let mut state = self.state.write().await;
@@ -334,19 +944,82 @@ impl Context {
         state.get(key).cloned()
     }
 
-    /// Read a request cookie by name.
+    /// Attach a typed value to this request, replacing any previous value of
+    /// the same type. Unlike [`set`](Self::set), values aren't limited to
+    /// strings, so middleware can hand handlers a resolved `User` directly.
+    pub async fn set_ext<T: Send + Sync + 'static>(&self, value: T) {
+        self.extensions.write().await.insert(Arc::new(value));
+    }
+
+    /// The value of type `T` attached with [`set_ext`](Self::set_ext), if any.
+    pub async fn ext<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.extensions.read().await.get::<Arc<T>>().cloned()
+    }
+
+    /// The app-wide value of type `S` shared with
+    /// [`Ultimo::with_state`](crate::Ultimo::with_state).
+    ///
+    /// Unlike [`ext`](Self::ext), which is per request, this is the same
+    /// instance for every request. Fails with an internal error if no state
+    /// of that type was registered.
+    pub fn state<S: Send + Sync + 'static>(&self) -> Result<Arc<S>> {
+        self.app_state.get::<Arc<S>>().cloned().ok_or_else(|| {
+            UltimoError::Internal(format!(
+                "App state of type {} not configured. Use app.with_state()",
+                std::any::type_name::<S>()
+            ))
+        })
+    }
+
+    /// The `X-Request-Id` request header, if it is usable as a request id:
+    /// non-empty, at most 128 bytes and printable ASCII. Anything else is
+    /// client-controlled noise that must not reach logs or responses.
+    pub(crate) fn request_id_header(&self) -> Option<String> {
+        self.req.header("x-request-id").filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.bytes().all(|b| b.is_ascii_graphic())
+        })
+    }
+
+    /// The `request_id` context state value, falling back to a usable
+    /// `X-Request-Id` header.
+    pub(crate) async fn current_request_id(&self) -> Option<String> {
+        match self.get("request_id").await {
+            Some(id) => Some(id),
+            None => self.request_id_header(),
+        }
+    }
+
+    /// A `tracing` logger with this request's id, method, and path bound.
+    ///
+    /// The request id is the `request_id` context state value (set by
+    /// middleware) or, failing that, the `X-Request-Id` request header when
+    /// it is a usable id (non-empty, at most 128 bytes, printable ASCII).
+    ///
+    /// ```rust,ignore
+    /// app.get("/users/:id", |ctx: Context| async move {
+    ///     let log = ctx.logger().await;
+    ///     log.info("loading user");
+    ///     ctx.text("ok").await
+    /// });
+    /// ```
+    pub async fn logger(&self) -> RequestLogger {
+        RequestLogger {
+            request_id: self.current_request_id().await,
+            method: self.req.method().to_string(),
+            path: self.req.path().to_string(),
+        }
+    }
+
+    /// Read a request cookie by name (shorthand for [`Request::cookie`]).
     pub fn cookie(&self, name: &str) -> Option<String> {
-        self.req
-            .header("cookie")
-            .and_then(|h| crate::cookie::parse_cookie_header(&h).remove(name))
+        self.req.cookie(name)
     }
 
-    /// All request cookies.
+    /// All request cookies (shorthand for [`Request::cookies`]).
     pub fn cookies(&self) -> HashMap<String, String> {
-        self.req
-            .header("cookie")
-            .map(|h| crate::cookie::parse_cookie_header(&h))
-            .unwrap_or_default()
+        self.req.cookies()
     }
 
     /// Queue a `Set-Cookie` for the response. Errors if the cookie is invalid.
@@ -367,12 +1040,32 @@ impl Context {
         self.set_cookies.clone()
     }
 
+    /// Shared handle to the headers set with [`header`](Self::header) /
+    /// [`set_header`](Self::set_header), which the dispatcher also applies to
+    /// error responses.
+    pub(crate) fn response_headers_handle(&self) -> Arc<RwLock<Vec<(String, String)>>> {
+        self.response_headers.clone()
+    }
+
     /// Set the connection peer address + proxy-trust (used by the server).
     pub(crate) fn set_client(&mut self, addr: Option<SocketAddr>, trust_proxy: bool) {
         self.client_addr = addr;
         self.trust_proxy = trust_proxy;
     }
 
+    /// Set whether JSON responses include `charset=utf-8` (used by the server).
+    pub(crate) fn set_json_charset(&mut self, json_charset: bool) {
+        self.json_charset = json_charset;
+    }
+
+    fn json_content_type(&self) -> &'static str {
+        if self.json_charset {
+            crate::response::JSON_UTF8_CONTENT_TYPE
+        } else {
+            crate::response::JSON_CONTENT_TYPE
+        }
+    }
+
     /// The peer address of the underlying connection, if known. This is the
     /// direct socket peer — for the originating client behind a proxy, use
     /// [`client_ip`](Self::client_ip).
@@ -380,10 +1073,35 @@ impl Context {
         self.client_addr
     }
 
+    /// Whether the request comes from this host itself: an in-process call,
+    /// or a loopback peer that is not relaying for someone else. A loopback
+    /// peer does not count when proxy trust is on or the request carries
+    /// forwarding headers, since a reverse proxy on the same host connects
+    /// from loopback on behalf of remote clients.
+    pub(crate) fn is_local(&self) -> bool {
+        let Some(addr) = self.client_addr else {
+            return true;
+        };
+        addr.ip().is_loopback()
+            && !self.trust_proxy
+            && ["forwarded", "x-forwarded-for", "x-real-ip", "via"]
+                .iter()
+                .all(|name| self.req.header(name).is_none())
+    }
+
+    /// The certificate the client authenticated with, when serving mutual TLS
+    /// (see [`Ultimo::tls_client_auth`](crate::Ultimo::tls_client_auth)).
+    /// `None` over plain HTTP and for clients that presented no certificate.
+    #[cfg(feature = "tls")]
+    pub fn client_cert(&self) -> Option<&crate::tls::ClientCertInfo> {
+        self.client_cert.as_deref()
+    }
+
     /// Best-effort originating client IP.
     ///
     /// When proxy trust is enabled (`app.trust_proxy(true)`) this honors the
-    /// left-most `X-Forwarded-For` entry, then `Forwarded: for=…`; otherwise (or
+    /// left-most `X-Forwarded-For` entry, then `X-Real-IP`, then
+    /// `Forwarded: for=…`; otherwise (or
     /// if no such header) it falls back to the connection peer. **Only enable
     /// proxy trust when the app is actually behind a trusted proxy** — these
     /// headers are client-spoofable.
@@ -398,6 +1116,13 @@ impl Context {
                     return Some(ip);
                 }
             }
+            if let Some(ip) = self
+                .req
+                .header("x-real-ip")
+                .and_then(|v| v.trim().parse::<IpAddr>().ok())
+            {
+                return Some(ip);
+            }
             if let Some(fwd) = self.req.header("forwarded") {
                 if let Some(ip) = parse_forwarded_for(&fwd) {
                     return Some(ip);
@@ -460,6 +1185,20 @@ impl Context {
         *self.api_key.write().await = Some(identity);
     }
 
+    /// The identity resolved by the `basic_auth` / `bearer_auth` middleware's
+    /// verifier. `None` if no such middleware accepted the request or `T` is not
+    /// the verifier's identity type.
+    pub async fn identity<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        self.ext::<AuthIdentity<T>>()
+            .await
+            .map(|identity| identity.0.clone())
+    }
+
+    /// Store the resolved identity (used by the basic / bearer auth middlewares).
+    pub(crate) async fn set_identity<T: Send + Sync + 'static>(&self, identity: T) {
+        self.set_ext(AuthIdentity(identity)).await;
+    }
+
     /// The normalized authenticated caller, if an auth middleware accepted the
     /// request. Populated by the JWT / API-key middlewares; read by the guards.
     #[cfg(any(feature = "jwt", feature = "api-key"))]
@@ -537,10 +1276,33 @@ impl Context {
         *status = Some(code);
     }
 
-    /// Add a response header
+    /// Add a response header. Repeated calls with the same name append, so
+    /// `Vary`, `Link` and similar headers can carry several values.
     pub async fn header(&self, name: impl Into<String>, value: impl Into<String>) {
         let mut headers = self.response_headers.write().await;
-        headers.insert(name.into(), value.into());
+        headers.push((name.into(), value.into()));
+    }
+
+    /// Set a response header, replacing any values already set for the same
+    /// (case-insensitive) name.
+    pub async fn set_header(&self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        let mut headers = self.response_headers.write().await;
+        headers.retain(|(k, _)| !k.eq_ignore_ascii_case(&name));
+        headers.push((name, value.into()));
+    }
+
+    /// Set the `Cache-Control` response header, replacing any set before.
+    ///
+    /// ```rust,ignore
+    /// use ultimo::response::CacheControl;
+    ///
+    /// ctx.cache_control(CacheControl::new().private().max_age(60)).await;
+    /// ctx.json(profile).await
+    /// ```
+    pub async fn cache_control(&self, directive: crate::response::CacheControl) {
+        self.set_header("Cache-Control", directive.to_string())
+            .await;
     }
 
     /// Build response with collected status and headers
@@ -553,7 +1315,7 @@ impl Context {
         // Apply headers
         let headers = self.response_headers.read().await;
         for (name, value) in headers.iter() {
-            builder = builder.header(name.clone(), value.clone());
+            builder = builder.append_header(name.clone(), value.clone());
         }
 
         builder
@@ -562,7 +1324,103 @@ impl Context {
     /// Return a JSON response
     pub async fn json<T: Serialize>(&self, value: T) -> Result<Response> {
         let builder = self.build_response(ResponseBuilder::new()).await;
-        builder.json(&value)?.build()
+        builder
+            .json(&value)?
+            .json_headers(self.json_content_type())
+            .build()
+    }
+
+    /// Return a JSON response with the given status code, in one call
+    /// instead of [`status`](Self::status) followed by [`json`](Self::json).
+    pub async fn json_with_status<T: Serialize>(&self, status: u16, value: T) -> Result<Response> {
+        self.status(status).await;
+        self.json(value).await
+    }
+
+    /// Return `value` as JSON with `201 Created`.
+    ///
+    /// ```rust,ignore
+    /// app.post("/users", |ctx: Context| async move {
+    ///     let user = create_user(ctx.req.json().await?).await?;
+    ///     ctx.created(user).await
+    /// });
+    /// ```
+    pub async fn created<T: Serialize>(&self, value: T) -> Result<Response> {
+        self.json_with_status(201, value).await
+    }
+
+    /// Return an empty `204 No Content` response.
+    pub async fn no_content(&self) -> Result<Response> {
+        self.status(204).await;
+        self.build_response(ResponseBuilder::new()).await.build()
+    }
+
+    /// Stream a JSON array response, serializing each element as `stream`
+    /// yields it.
+    ///
+    /// The client receives one standard JSON array (`[a,b,...]`) sent with
+    /// chunked transfer encoding, so large result sets never need to be held
+    /// in memory. A serialization error aborts the response mid-stream.
+    ///
+    /// ```rust,ignore
+    /// let rows = futures_util::stream::iter(0..100_000).map(|id| json!({ "id": id }));
+    /// ctx.json_array_stream(rows).await
+    /// ```
+    pub async fn json_array_stream<S, T>(&self, stream: S) -> Result<Response>
+    where
+        S: futures_util::Stream<Item = T> + Send + 'static,
+        T: Serialize,
+    {
+        use futures_util::StreamExt;
+
+        self.json_stream(stream.map(|item| Ok::<_, std::convert::Infallible>(item)))
+            .await
+    }
+
+    /// Stream a JSON array response from a fallible stream, such as rows from
+    /// SQLx's `fetch`.
+    ///
+    /// Like [`json_array_stream`](Self::json_array_stream): `[` is sent
+    /// first, then each item, comma-separated, as soon as `stream` yields it,
+    /// then `]`. The first `Err` (or serialization error) aborts the response
+    /// mid-stream, so the client sees a truncated body rather than a valid
+    /// array that is silently missing rows.
+    ///
+    /// ```rust,ignore
+    /// app.get("/export/users", |ctx: Context| async move {
+    ///     let pool = ctx.sqlx::<Sqlite>()?.clone();
+    ///     // `fetch` borrows the pool, so wrap it in a stream that owns it.
+    ///     let rows = async_stream::stream! {
+    ///         let mut users = sqlx::query_as::<_, User>("SELECT * FROM users").fetch(&pool);
+    ///         while let Some(user) = users.next().await {
+    ///             yield user;
+    ///         }
+    ///     };
+    ///     ctx.json_stream(rows).await
+    /// });
+    /// ```
+    pub async fn json_stream<S, T, E>(&self, stream: S) -> Result<Response>
+    where
+        S: futures_util::Stream<Item = std::result::Result<T, E>> + Send + 'static,
+        T: Serialize,
+        E: Into<BoxError> + 'static,
+    {
+        use futures_util::stream::{self, StreamExt};
+
+        let elements = stream.enumerate().map(|(i, item)| {
+            let mut chunk = if i == 0 { Vec::new() } else { vec![b','] };
+            chunk.extend(crate::json::to_vec(&item.map_err(Into::into)?)?);
+            Ok::<_, BoxError>(Bytes::from(chunk))
+        });
+        let body = stream::once(async { Ok(Bytes::from_static(b"[")) })
+            .chain(elements)
+            .chain(stream::once(async { Ok(Bytes::from_static(b"]")) }));
+
+        let builder = self.build_response(ResponseBuilder::new()).await;
+        builder
+            .json_headers(self.json_content_type())
+            .stream(body)
+            .build()
     }
 
     /// Return a text response
@@ -577,6 +1435,65 @@ impl Context {
         builder.html(html).build()
     }
 
+    /// Stream the file at `path` as the response body.
+    ///
+    /// The file is read in chunks rather than loaded into memory. Sets
+    /// `Content-Type` from the file extension (falling back to
+    /// `application/octet-stream`), `Content-Length` from the file size and
+    /// `Last-Modified` from its mtime, answering a matching
+    /// `If-Modified-Since` with `304 Not Modified`. Returns
+    /// [`UltimoError::NotFound`] if `path` is missing or not a file.
+    ///
+    /// Requires the `static-files` Cargo feature.
+    ///
+    /// ```rust,ignore
+    /// app.get("/download", |ctx: Context| async move {
+    ///     ctx.cache_control(CacheControl::new().private().no_cache()).await;
+    ///     ctx.file("./exports/report.csv").await
+    /// });
+    /// ```
+    #[cfg(feature = "static-files")]
+    pub async fn file(&self, path: impl AsRef<std::path::Path>) -> Result<Response> {
+        use tokio::io::AsyncReadExt;
+
+        const CHUNK_SIZE: usize = 64 * 1024;
+
+        let path = path.as_ref();
+        let not_found = || UltimoError::NotFound("file not found".into());
+        let file = tokio::fs::File::open(path).await.map_err(|_| not_found())?;
+        let metadata = file.metadata().await.map_err(|_| not_found())?;
+        if !metadata.is_file() {
+            return Err(not_found());
+        }
+
+        let modified = crate::static_files::last_modified(&metadata);
+        let mut builder = self.build_response(ResponseBuilder::new()).await;
+        if let Some(modified) = modified {
+            builder = builder.header("Last-Modified", httpdate::fmt_http_date(modified));
+            if crate::static_files::not_modified_since(
+                self.req.header("if-modified-since").as_deref(),
+                modified,
+            ) {
+                return builder.status(304).build();
+            }
+        }
+
+        let chunks = futures_util::stream::try_unfold(file, |mut file| async move {
+            let mut buf = bytes::BytesMut::with_capacity(CHUNK_SIZE);
+            match file.read_buf(&mut buf).await? {
+                0 => Ok::<_, std::io::Error>(None),
+                _ => Ok(Some((buf.freeze(), file))),
+            }
+        });
+        let mime = mime_guess::from_path(path).first_or_octet_stream();
+
+        builder
+            .header("Content-Type", mime.to_string())
+            .header("Content-Length", metadata.len().to_string())
+            .stream(chunks)
+            .build()
+    }
+
     /// Return a redirect response
     pub async fn redirect(&self, location: &str) -> Result<Response> {
         let status = self.response_status.read().await.unwrap_or(302);
@@ -597,6 +1514,61 @@ impl Context {
     }
 }
 
+/// Request-scoped logger returned by [`Context::logger`].
+///
+/// Every event carries `request_id`, `method`, and `path` fields, so handler
+/// logs can be correlated without repeating them.
+#[derive(Debug, Clone)]
+pub struct RequestLogger {
+    request_id: Option<String>,
+    method: String,
+    path: String,
+}
+
+macro_rules! request_event {
+    ($level:ident, $logger:expr, $message:expr) => {
+        tracing::$level!(
+            request_id = $logger.request_id.as_deref().unwrap_or("-"),
+            method = %$logger.method,
+            path = %$logger.path,
+            "{}",
+            $message
+        )
+    };
+}
+
+impl RequestLogger {
+    /// The bound request id, if one was available.
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Log at `TRACE` level.
+    pub fn trace(&self, message: impl std::fmt::Display) {
+        request_event!(trace, self, message);
+    }
+
+    /// Log at `DEBUG` level.
+    pub fn debug(&self, message: impl std::fmt::Display) {
+        request_event!(debug, self, message);
+    }
+
+    /// Log at `INFO` level.
+    pub fn info(&self, message: impl std::fmt::Display) {
+        request_event!(info, self, message);
+    }
+
+    /// Log at `WARN` level.
+    pub fn warn(&self, message: impl std::fmt::Display) {
+        request_event!(warn, self, message);
+    }
+
+    /// Log at `ERROR` level.
+    pub fn error(&self, message: impl std::fmt::Display) {
+        request_event!(error, self, message);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -613,6 +1585,280 @@ mod tests {
         assert_eq!(params.get("missing"), None);
     }
 
+    #[test]
+    fn test_param_as_parses_or_names_param_and_type() {
+        let mut params = Params::new();
+        params.insert("id".to_string(), "42".to_string());
+        params.insert("slug".to_string(), "abc".to_string());
+        let (parts, ()) = HyperRequest::builder().body(()).unwrap().into_parts();
+        let req = Request::from_parts(parts, Bytes::new(), params);
+
+        assert_eq!(req.param_as::<i32>("id").unwrap(), 42);
+        assert_eq!(req.param_as::<String>("slug").unwrap(), "abc");
+        match req.param_as::<i32>("slug") {
+            Err(UltimoError::BadRequest(msg)) => {
+                assert_eq!(msg, "Invalid path parameter 'slug': expected i32")
+            }
+            other => panic!("expected BadRequest, got {:?}", other),
+        }
+        assert!(matches!(
+            req.param_as::<u64>("missing"),
+            Err(UltimoError::BadRequest(_))
+        ));
+    }
+
+    fn form_request(content_type: Option<&str>, body: &'static str) -> Request {
+        let mut builder = HyperRequest::builder().method("POST");
+        if let Some(ct) = content_type {
+            builder = builder.header("content-type", ct);
+        }
+        let (parts, ()) = builder.body(()).unwrap().into_parts();
+        Request::from_parts(parts, Bytes::from_static(body.as_bytes()), Params::new())
+    }
+
+    #[derive(Debug, serde::Deserialize, validator::Validate)]
+    struct Signup {
+        #[validate(length(min = 3))]
+        username: String,
+        #[validate(email)]
+        email: String,
+    }
+
+    #[tokio::test]
+    async fn test_json_validated_parses_then_validates() {
+        let req = form_request(None, r#"{"username":"ada","email":"ada@example.com"}"#);
+        assert_eq!(
+            req.json_validated::<Signup>().await.unwrap().username,
+            "ada"
+        );
+
+        let req = form_request(None, r#"{"username":"al","email":"nope"}"#);
+        match req.json_validated::<Signup>().await {
+            Err(UltimoError::Validation { details, .. }) => {
+                let mut fields: Vec<_> = details.iter().map(|d| d.field.as_str()).collect();
+                fields.sort_unstable();
+                assert_eq!(fields, ["email", "username"]);
+            }
+            other => panic!("expected Validation, got {:?}", other),
+        }
+
+        let req = form_request(None, r#"{"username":"ada"}"#);
+        assert!(matches!(
+            req.json_validated::<Signup>().await,
+            Err(UltimoError::BadRequest(_))
+        ));
+    }
+
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Profile {
+        name: String,
+        bio: Option<String>,
+        age: u32,
+    }
+
+    #[tokio::test]
+    async fn test_json_patch_distinguishes_omitted_and_null() {
+        let current = Profile {
+            name: "Ada".to_string(),
+            bio: Some("Mathematician".to_string()),
+            age: 36,
+        };
+
+        let req = form_request(Some("application/merge-patch+json"), r#"{"age":37}"#);
+        let patched = req.json_patch(&current).await.unwrap();
+        assert_eq!(
+            patched,
+            Profile {
+                age: 37,
+                ..current.clone()
+            }
+        );
+
+        let req = form_request(None, r#"{"bio":null}"#);
+        assert_eq!(req.json_patch(&current).await.unwrap().bio, None);
+
+        for body in [r#"{"name":null}"#, r#"{"age":"old"}"#] {
+            let req = form_request(None, body);
+            match req.json_patch(&current).await {
+                Err(UltimoError::BadRequest(msg)) => {
+                    assert!(msg.starts_with("Invalid patch: "), "{}", msg)
+                }
+                other => panic!("expected BadRequest for {}, got {:?}", body, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_bearer_token_strips_scheme() {
+        let bearer = |value: Option<&str>| {
+            let mut builder = HyperRequest::builder();
+            if let Some(value) = value {
+                builder = builder.header("authorization", value);
+            }
+            let (parts, ()) = builder.body(()).unwrap().into_parts();
+            Request::from_parts(parts, Bytes::new(), Params::new()).bearer_token()
+        };
+        assert_eq!(
+            bearer(Some("Bearer abc.def.ghi")),
+            Some("abc.def.ghi".into())
+        );
+        assert_eq!(bearer(Some("bearer  xyz ")), Some("xyz".into()));
+        // Other schemes, missing tokens, and no header at all.
+        assert_eq!(bearer(Some("Basic abc")), None);
+        assert_eq!(bearer(Some("Bearer")), None);
+        assert_eq!(bearer(Some("Bearer ")), None);
+        assert_eq!(bearer(None), None);
+    }
+
+    #[test]
+    fn test_cookies_combine_split_cookie_headers() {
+        let (parts, ()) = HyperRequest::builder()
+            .header("cookie", "theme=dark; lang=en")
+            .header("cookie", "token=abc")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let req = Request::from_parts(parts, Bytes::new(), Params::new());
+        assert_eq!(req.cookie("theme").as_deref(), Some("dark"));
+        assert_eq!(req.cookie("token").as_deref(), Some("abc"));
+        assert_eq!(req.cookie("missing"), None);
+        assert_eq!(req.cookies().len(), 3);
+    }
+
+    fn accept_request(accept: Option<&str>) -> Request {
+        let mut builder = HyperRequest::builder();
+        if let Some(accept) = accept {
+            builder = builder.header("accept", accept);
+        }
+        let (parts, ()) = builder.body(()).unwrap().into_parts();
+        Request::from_parts(parts, Bytes::new(), Params::new())
+    }
+
+    #[test]
+    fn test_accepted_types_are_ordered_by_quality() {
+        let req = accept_request(Some(
+            "text/html;level=1, application/json;q=0.9, */*;q=0.1, Text/Plain;q=0.9, bogus",
+        ));
+        assert_eq!(
+            req.accepted_types(),
+            vec![
+                ("text/html".to_string(), 1.0),
+                ("application/json".to_string(), 0.9),
+                ("text/plain".to_string(), 0.9),
+                ("*/*".to_string(), 0.1),
+            ]
+        );
+        assert!(accept_request(None).accepted_types().is_empty());
+    }
+
+    #[test]
+    fn test_accepts_uses_most_specific_range() {
+        let req = accept_request(Some("text/*;q=0.5, text/csv;q=0, application/json"));
+        assert!(req.accepts("application/json"));
+        assert!(req.accepts("TEXT/HTML"));
+        assert!(!req.accepts("text/csv"));
+        assert!(!req.accepts("image/png"));
+        assert!(accept_request(None).accepts("image/png"));
+    }
+
+    #[test]
+    fn test_negotiate_picks_client_preference_then_server_order() {
+        let offered = ["application/json", "text/html"];
+        let browser = accept_request(Some("text/html,application/xhtml+xml,*/*;q=0.8"));
+        assert_eq!(browser.negotiate(&offered), Some("text/html"));
+
+        let api = accept_request(Some("application/json"));
+        assert_eq!(api.negotiate(&offered), Some("application/json"));
+
+        // Equal quality (or no header): the first offered type wins.
+        let any = accept_request(Some("*/*"));
+        assert_eq!(any.negotiate(&offered), Some("application/json"));
+        assert_eq!(
+            accept_request(None).negotiate(&offered),
+            Some("application/json")
+        );
+
+        let picky = accept_request(Some("image/png, text/html;q=0"));
+        assert_eq!(picky.negotiate(&offered), None);
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Login {
+        username: String,
+        remember: bool,
+    }
+
+    #[tokio::test]
+    async fn test_form_decodes_percent_encoded_body() {
+        let req = form_request(
+            Some("application/x-www-form-urlencoded; charset=UTF-8"),
+            "username=ada+lovelace%21&remember=true",
+        );
+        assert_eq!(
+            req.form::<Login>().await.unwrap(),
+            Login {
+                username: "ada lovelace!".to_string(),
+                remember: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_form_rejects_bad_body_and_wrong_content_type() {
+        let req = form_request(Some("application/x-www-form-urlencoded"), "username=ada");
+        assert!(matches!(
+            req.form::<Login>().await,
+            Err(UltimoError::BadRequest(_))
+        ));
+
+        for content_type in [None, Some("application/json")] {
+            let req = form_request(content_type, "username=ada&remember=true");
+            match req.form::<Login>().await {
+                Err(err) => assert_eq!(err.status_code(), 415),
+                Ok(login) => panic!("expected 415, got {:?}", login),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_accepts_json_content_types() {
+        for content_type in [
+            None,
+            Some("application/json; charset=utf-8"),
+            Some("application/merge-patch+json"),
+        ] {
+            let req = form_request(content_type, r#"{"username":"ada","remember":true}"#);
+            assert_eq!(req.json::<Login>().await.unwrap().username, "ada");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_reports_content_type_empty_body_and_position() {
+        let req = form_request(Some("text/plain"), r#"{"username":"ada"}"#);
+        match req.json::<serde_json::Value>().await {
+            Err(err) => assert_eq!(err.status_code(), 415),
+            Ok(value) => panic!("expected 415, got {:?}", value),
+        }
+
+        let req = form_request(Some("application/json"), "  ");
+        match req.json::<serde_json::Value>().await {
+            Err(UltimoError::BadRequest(msg)) => assert_eq!(msg, "request body is empty"),
+            other => panic!("expected empty-body error, got {:?}", other),
+        }
+
+        let req = form_request(Some("application/json"), "{\n  \"username\": ada\n}");
+        match req.json::<serde_json::Value>().await {
+            Err(UltimoError::BadRequest(msg)) => {
+                assert!(
+                    msg.starts_with("Invalid JSON body at line 2, column"),
+                    "{}",
+                    msg
+                )
+            }
+            other => panic!("expected BadRequest, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_query_parsing() {
         // Test query string parsing logic
@@ -800,6 +2046,7 @@ mod from_parts_tests {
 #[cfg(test)]
 mod context_response_tests {
     use super::*;
+    use futures_util::StreamExt;
     use http_body_util::BodyExt;
 
     fn ctx() -> Context {
@@ -818,6 +2065,170 @@ mod context_response_tests {
         String::from_utf8(b.to_vec()).unwrap()
     }
 
+    #[tokio::test]
+    async fn header_appends_and_set_header_replaces() {
+        let c = ctx();
+        c.header("Vary", "Origin").await;
+        c.header("Vary", "Accept-Encoding").await;
+        c.header("X-Mode", "a").await;
+        c.set_header("x-mode", "b").await;
+        let resp = c.text("ok").await.unwrap();
+        let vary: Vec<_> = resp.headers().get_all("vary").iter().collect();
+        assert_eq!(vary, ["Origin", "Accept-Encoding"]);
+        let mode: Vec<_> = resp.headers().get_all("x-mode").iter().collect();
+        assert_eq!(mode, ["b"]);
+    }
+
+    #[tokio::test]
+    async fn status_shortcuts_set_code_and_body() {
+        let resp = ctx()
+            .json_with_status(202, serde_json::json!({ "queued": true }))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 202);
+        assert_eq!(body(resp).await, r#"{"queued":true}"#);
+
+        let c = ctx();
+        c.header("Location", "/users/7").await;
+        let resp = c.created(serde_json::json!({ "id": 7 })).await.unwrap();
+        assert_eq!(resp.status(), 201);
+        assert_eq!(resp.headers()["location"], "/users/7");
+        assert_eq!(resp.headers()["content-type"], "application/json");
+        assert_eq!(body(resp).await, r#"{"id":7}"#);
+
+        let resp = ctx().no_content().await.unwrap();
+        assert_eq!(resp.status(), 204);
+        assert!(resp.headers().get("content-type").is_none());
+        assert_eq!(body(resp).await, "");
+    }
+
+    #[cfg(feature = "static-files")]
+    #[tokio::test]
+    async fn file_streams_contents_with_type_and_length() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.csv");
+        let contents = "id,name\n".repeat(20_000);
+        std::fs::write(&path, &contents).unwrap();
+
+        let resp = ctx().file(&path).await.unwrap();
+        assert_eq!(resp.headers()["content-type"], "text/csv");
+        assert_eq!(
+            resp.headers()["content-length"],
+            contents.len().to_string().as_str()
+        );
+        assert!(resp.body().is_stream());
+        assert_eq!(body(resp).await, contents);
+    }
+
+    #[cfg(feature = "static-files")]
+    #[tokio::test]
+    async fn file_sets_last_modified_and_cache_control() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.csv");
+        std::fs::write(&path, "id\n").unwrap();
+
+        let c = ctx();
+        c.cache_control(crate::response::CacheControl::new().private().no_cache())
+            .await;
+        let resp = c.file(&path).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["cache-control"], "no-cache, private");
+        let last_modified = resp.headers()["last-modified"]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let (parts, ()) = HyperRequest::builder()
+            .header("if-modified-since", &last_modified)
+            .body(())
+            .unwrap()
+            .into_parts();
+        let c = Context::from_parts(parts, Bytes::new(), Params::new());
+        let resp = c.file(&path).await.unwrap();
+        assert_eq!(resp.status(), 304);
+        assert!(resp.headers().get("content-length").is_none());
+        assert_eq!(body(resp).await, "");
+    }
+
+    #[cfg(feature = "static-files")]
+    #[tokio::test]
+    async fn file_missing_or_directory_is_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        for path in [dir.path().join("nope.txt"), dir.path().to_path_buf()] {
+            assert!(matches!(
+                ctx().file(&path).await,
+                Err(UltimoError::NotFound(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn json_content_type_honours_charset_setting() {
+        let resp = ctx().json(serde_json::json!({})).await.unwrap();
+        assert_eq!(resp.headers()["content-type"], "application/json");
+        assert_eq!(resp.headers()["x-content-type-options"], "nosniff");
+
+        let mut c = ctx();
+        c.set_json_charset(true);
+        c.header("content-type", "text/plain").await;
+        let resp = c.json(Vec::<u8>::new()).await.unwrap();
+        let content_types: Vec<_> = resp.headers().get_all("content-type").iter().collect();
+        assert_eq!(content_types, ["application/json; charset=utf-8"]);
+        assert_eq!(body(resp).await, "[]");
+    }
+
+    #[tokio::test]
+    async fn json_array_stream_produces_one_json_array() {
+        let c = ctx();
+        c.status(201).await;
+        let items = futures_util::stream::iter(1..=3).map(|id| serde_json::json!({ "id": id }));
+        let resp = c.json_array_stream(items).await.unwrap();
+        assert_eq!(resp.status(), 201);
+        assert_eq!(resp.headers()["content-type"], "application/json");
+        assert!(resp.body().is_stream());
+        assert_eq!(body(resp).await, r#"[{"id":1},{"id":2},{"id":3}]"#);
+    }
+
+    #[tokio::test]
+    async fn json_array_stream_of_nothing_is_empty_array() {
+        let resp = ctx()
+            .json_array_stream(futures_util::stream::empty::<u32>())
+            .await
+            .unwrap();
+        assert_eq!(body(resp).await, "[]");
+    }
+
+    #[tokio::test]
+    async fn json_stream_sends_items_until_the_first_error() {
+        let rows = futures_util::stream::iter(1..=2);
+        let resp = ctx()
+            .json_stream(rows.map(Ok::<_, std::io::Error>))
+            .await
+            .unwrap();
+        assert_eq!(body(resp).await, "[1,2]");
+
+        let rows = futures_util::stream::iter(vec![
+            Ok(1),
+            Err(std::io::Error::other("connection reset")),
+            Ok(3),
+        ]);
+        let resp = ctx().json_stream(rows).await.unwrap();
+        let mut chunks = resp.into_body().into_data_stream();
+        assert_eq!(chunks.next().await.unwrap().unwrap(), "[");
+        assert_eq!(chunks.next().await.unwrap().unwrap(), "1");
+        assert!(chunks.next().await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn not_modified_has_no_body_or_content_type() {
+        let c = ctx();
+        c.status(304).await;
+        let resp = c.json(serde_json::json!({"ignored": true})).await.unwrap();
+        assert_eq!(resp.status(), 304);
+        assert!(resp.headers().get("content-type").is_none());
+        assert_eq!(body(resp).await, "");
+    }
+
     #[tokio::test]
     async fn state_set_get() {
         let c = ctx();
@@ -826,6 +2237,88 @@ mod context_response_tests {
         assert_eq!(c.get("missing").await, None);
     }
 
+    #[tokio::test]
+    async fn ext_set_get_by_type() {
+        #[derive(Debug, PartialEq)]
+        struct User {
+            id: u64,
+        }
+
+        let c = ctx();
+        assert!(c.ext::<User>().await.is_none());
+        c.set_ext(User { id: 1 }).await;
+        c.set_ext(7u32).await;
+        assert_eq!(*c.ext::<User>().await.unwrap(), User { id: 1 });
+        assert_eq!(*c.ext::<u32>().await.unwrap(), 7);
+
+        // Same type replaces; the auth identity slot doesn't collide.
+        c.set_ext(User { id: 2 }).await;
+        c.set_identity(User { id: 3 }).await;
+        assert_eq!(c.ext::<User>().await.unwrap().id, 2);
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn logger_binds_request_fields() {
+        let c = ctx();
+        c.set("request_id", "req-42").await;
+        let log = c.logger().await;
+        assert_eq!(log.request_id(), Some("req-42"));
+
+        let captured = CapturedLogs::default();
+        let sink = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || sink.clone())
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || log.info("loading user"));
+
+        let out = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert!(out.contains("loading user"), "{}", out);
+        assert!(out.contains("request_id=\"req-42\""), "{}", out);
+        assert!(out.contains("method=GET"), "{}", out);
+        assert!(out.contains("path=/x"), "{}", out);
+    }
+
+    #[tokio::test]
+    async fn logger_falls_back_to_request_id_header() {
+        let req = HyperRequest::builder()
+            .uri("/")
+            .header("x-request-id", "from-header")
+            .body(())
+            .unwrap();
+        let (parts, ()) = req.into_parts();
+        let c = Context::from_parts(parts, Bytes::new(), Params::new());
+        assert_eq!(c.logger().await.request_id(), Some("from-header"));
+        assert_eq!(ctx().logger().await.request_id(), None);
+    }
+
+    #[tokio::test]
+    async fn logger_ignores_unusable_request_id_headers() {
+        for bad in ["".to_string(), "a b".to_string(), "x".repeat(129)] {
+            let req = HyperRequest::builder()
+                .uri("/")
+                .header("x-request-id", &bad)
+                .body(())
+                .unwrap();
+            let (parts, ()) = req.into_parts();
+            let c = Context::from_parts(parts, Bytes::new(), Params::new());
+            assert_eq!(c.logger().await.request_id(), None, "{bad:?}");
+        }
+    }
+
     #[tokio::test]
     async fn json_text_html_responses() {
         let c = ctx();
@@ -119,7 +119,6 @@ use super::DatabaseError;
 use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
 
 /// Diesel connection pool
-#[derive(Clone)]
 pub struct DieselPool<Conn>
 where
     Conn: diesel::Connection + diesel::r2d2::R2D2Connection + 'static,
@@ -127,6 +126,18 @@ where
     pool: Pool<ConnectionManager<Conn>>,
 }
 
+// Manual impl: the derive would needlessly require `Conn: Clone`.
+impl<Conn> Clone for DieselPool<Conn>
+where
+    Conn: diesel::Connection + diesel::r2d2::R2D2Connection + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+        }
+    }
+}
+
 impl<Conn> DieselPool<Conn>
 where
     Conn: diesel::Connection + diesel::r2d2::R2D2Connection + 'static,
@@ -162,6 +173,15 @@ where
         &self.pool
     }
 
+    /// Current connection counts.
+    pub fn stats(&self) -> super::PoolStats {
+        let state = self.pool.state();
+        super::PoolStats {
+            size: state.connections,
+            idle: state.idle_connections,
+        }
+    }
+
     /// Check if the database connection is healthy
     pub async fn health_check(&self) -> Result<(), DatabaseError> {
         // Get connection to verify pool is healthy
@@ -170,6 +190,14 @@ where
     }
 }
 
+/// Lets `?` on Diesel calls work in handlers and
+/// [`Context::diesel_transaction`](crate::Context::diesel_transaction).
+impl From<diesel::result::Error> for crate::UltimoError {
+    fn from(err: diesel::result::Error) -> Self {
+        DatabaseError::Query(err.to_string()).into()
+    }
+}
+
 /// Type aliases for common Diesel connection types
 #[cfg(feature = "diesel-postgres")]
 pub type PgPool = DieselPool<diesel::PgConnection>;
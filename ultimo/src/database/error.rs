@@ -3,7 +3,11 @@
 use std::fmt;
 
 /// Database-specific errors
+///
+/// Non-exhaustive, so new failure kinds can be added without breaking
+/// matches on it.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum DatabaseError {
     /// Connection error
     Connection(String),
@@ -22,6 +26,9 @@ pub enum DatabaseError {
 
     /// Database not configured
     NotConfigured,
+
+    /// No pool attached under this name
+    PoolNotFound(String),
 }
 
 impl fmt::Display for DatabaseError {
@@ -36,6 +43,11 @@ impl fmt::Display for DatabaseError {
                 f,
                 "Database not configured. Use app.with_sqlx() or app.with_diesel()"
             ),
+            Self::PoolNotFound(name) => write!(
+                f,
+                "Database pool '{}' not configured. Use app.with_named_pool()",
+                name
+            ),
         }
     }
 }
@@ -79,6 +79,15 @@ pub use error::DatabaseError;
 use std::any::Any;
 use std::sync::Arc;
 
+/// A point-in-time view of a connection pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct PoolStats {
+    /// Open connections, idle or in use.
+    pub size: u32,
+    /// Open connections not currently checked out.
+    pub idle: u32,
+}
+
 /// Database connection stored in context
 #[derive(Clone)]
 pub enum Database {
@@ -135,3 +144,20 @@ impl Database {
         }
     }
 }
+
+#[cfg(feature = "sqlx")]
+impl<DB: ::sqlx::Database> From<crate::database::sqlx::SqlxPool<DB>> for Database {
+    fn from(pool: crate::database::sqlx::SqlxPool<DB>) -> Self {
+        Self::from_sqlx(pool)
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl<Conn> From<crate::database::diesel::DieselPool<Conn>> for Database
+where
+    Conn: ::diesel::Connection + ::diesel::r2d2::R2D2Connection + 'static,
+{
+    fn from(pool: crate::database::diesel::DieselPool<Conn>) -> Self {
+        Self::from_diesel(pool)
+    }
+}
@@ -127,11 +127,17 @@ pub type MySqlPool = sqlx::MySqlPool;
 pub type SqlitePool = sqlx::SqlitePool;
 
 /// SQLx database pool wrapper
-#[derive(Clone)]
 pub struct SqlxPool<DB: sqlx::Database> {
     pool: sqlx::Pool<DB>,
 }
 
+// Manual impl: the derive would needlessly require `DB: Clone`.
+impl<DB: sqlx::Database> Clone for SqlxPool<DB> {
+    fn clone(&self) -> Self {
+        Self::new(self.pool.clone())
+    }
+}
+
 impl<DB: sqlx::Database> SqlxPool<DB> {
     /// Create a new pool from an existing sqlx pool
     pub fn new(pool: sqlx::Pool<DB>) -> Self {
@@ -147,6 +153,14 @@ impl<DB: sqlx::Database> SqlxPool<DB> {
     pub fn into_inner(self) -> sqlx::Pool<DB> {
         self.pool
     }
+
+    /// Current connection counts.
+    pub fn stats(&self) -> super::PoolStats {
+        super::PoolStats {
+            size: self.pool.size(),
+            idle: self.pool.num_idle() as u32,
+        }
+    }
 }
 
 #[cfg(feature = "sqlx-postgres")]
@@ -227,6 +241,100 @@ impl SqlxPool<sqlx::Sqlite> {
     }
 }
 
+/// Bind-parameter budget per `bulk_insert` statement.
+///
+/// SQLite's default cap (32 766) is the lowest of the supported backends;
+/// PostgreSQL allows 65 535.
+pub const BULK_INSERT_MAX_PARAMS: usize = 32_766;
+
+/// Reject anything but plain SQL identifiers, since `bulk_insert` interpolates
+/// table and column names into the statement.
+#[cfg(any(feature = "sqlx-postgres", feature = "sqlx-sqlite"))]
+fn validate_bulk_insert_idents(table: &str, columns: &[&str]) -> Result<(), DatabaseError> {
+    if columns.is_empty() {
+        return Err(DatabaseError::Query(
+            "bulk_insert requires at least one column".to_string(),
+        ));
+    }
+    for ident in std::iter::once(&table).chain(columns) {
+        let valid = !ident.is_empty()
+            && ident
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        if !valid {
+            return Err(DatabaseError::Query(format!(
+                "Invalid identifier in bulk_insert: {:?}",
+                ident
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `bulk_insert` for backends that support `INSERT ... RETURNING`. Written
+/// per concrete backend: sqlx's argument types only line up with a
+/// short-lived `QueryBuilder` borrow when the database type is known.
+macro_rules! impl_bulk_insert {
+    ($db:ty, $feature:literal) => {
+        #[cfg(feature = $feature)]
+        impl SqlxPool<$db> {
+            /// Insert many rows with multi-row `INSERT ... VALUES` statements
+            /// and return the inserted rows (`RETURNING *`).
+            ///
+            /// `bind` pushes one value per column for a row, in `columns`
+            /// order. Rows are chunked so no statement exceeds
+            /// [`BULK_INSERT_MAX_PARAMS`], and all chunks run in one
+            /// transaction — either every row is inserted or none are.
+            ///
+            /// `table` and `columns` must be plain identifiers
+            /// (`[A-Za-z0-9_.]`); anything else is rejected.
+            ///
+            /// ```rust,ignore
+            /// let users: Vec<User> = pool
+            ///     .bulk_insert("users", &["name", "email"], &new_users, |mut b, u| {
+            ///         b.push_bind(&u.name).push_bind(&u.email);
+            ///     })
+            ///     .await?;
+            /// ```
+            pub async fn bulk_insert<'r, R, O, F>(
+                &self,
+                table: &str,
+                columns: &[&str],
+                rows: &'r [R],
+                mut bind: F,
+            ) -> Result<Vec<O>, DatabaseError>
+            where
+                O: for<'row> sqlx::FromRow<'row, <$db as sqlx::Database>::Row> + Send + Unpin,
+                F: FnMut(sqlx::query_builder::Separated<'_, 'r, $db, &'static str>, &'r R),
+            {
+                validate_bulk_insert_idents(table, columns)?;
+                if rows.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                let rows_per_chunk = (BULK_INSERT_MAX_PARAMS / columns.len()).max(1);
+                let prefix = format!("INSERT INTO {} ({}) ", table, columns.join(", "));
+
+                let mut tx = self.pool.begin().await?;
+                let mut inserted = Vec::with_capacity(rows.len());
+                for chunk in rows.chunks(rows_per_chunk) {
+                    let mut builder = sqlx::QueryBuilder::<$db>::new(&prefix);
+                    builder.push_values(chunk, |separated, row| bind(separated, row));
+                    builder.push(" RETURNING *");
+                    let mut chunk_rows = builder.build_query_as::<O>().fetch_all(&mut *tx).await?;
+                    inserted.append(&mut chunk_rows);
+                }
+                tx.commit().await?;
+
+                Ok(inserted)
+            }
+        }
+    };
+}
+
+impl_bulk_insert!(sqlx::Postgres, "sqlx-postgres");
+impl_bulk_insert!(sqlx::Sqlite, "sqlx-sqlite");
+
 /// Convert SQLx errors to DatabaseError
 impl From<sqlx::Error> for DatabaseError {
     fn from(err: sqlx::Error) -> Self {
@@ -240,3 +348,10 @@ impl From<sqlx::Error> for DatabaseError {
         }
     }
 }
+
+/// Lets `?` on SQLx calls work in handlers and [`Context::transaction`](crate::Context::transaction).
+impl From<sqlx::Error> for crate::UltimoError {
+    fn from(err: sqlx::Error) -> Self {
+        DatabaseError::from(err).into()
+    }
+}
@@ -18,6 +18,9 @@ mod error_tests {
 
         let err = DatabaseError::NotConfigured;
         assert!(err.to_string().contains("not configured"));
+
+        let err = DatabaseError::PoolNotFound("replica".to_string());
+        assert!(err.to_string().contains("'replica' not configured"));
     }
 
     #[test]
@@ -43,6 +46,7 @@ mod error_tests {
             DatabaseError::Pool("pool".to_string()),
             DatabaseError::Transaction("tx".to_string()),
             DatabaseError::NotConfigured,
+            DatabaseError::PoolNotFound("pool".to_string()),
         ];
 
         for err in errors {
@@ -194,6 +198,306 @@ mod sqlx_tests {
     }
 }
 
+#[cfg(feature = "sqlx-sqlite")]
+#[cfg(test)]
+mod sqlx_bulk_insert_tests {
+    use crate::database::sqlx::{SqlxPool, BULK_INSERT_MAX_PARAMS};
+    use crate::database::DatabaseError;
+
+    #[derive(sqlx::FromRow, Debug)]
+    struct User {
+        id: i64,
+        name: String,
+        email: String,
+    }
+
+    struct NewUser {
+        name: String,
+        email: String,
+    }
+
+    async fn pool() -> SqlxPool<sqlx::Sqlite> {
+        // One connection: every `sqlite::memory:` connection is its own database.
+        let pool = SqlxPool::<sqlx::Sqlite>::connect_with_options(
+            sqlx::sqlite::SqlitePoolOptions::new().max_connections(1),
+            "sqlite::memory:",
+        )
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, email TEXT NOT NULL UNIQUE)",
+        )
+        .execute(pool.pool())
+        .await
+        .unwrap();
+        pool
+    }
+
+    fn new_users(n: usize) -> Vec<NewUser> {
+        (0..n)
+            .map(|i| NewUser {
+                name: format!("user{}", i),
+                email: format!("user{}@example.com", i),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert_returns_inserted_rows() {
+        let pool = pool().await;
+        let users: Vec<User> = pool
+            .bulk_insert("users", &["name", "email"], &new_users(3), |mut b, u| {
+                b.push_bind(&u.name).push_bind(&u.email);
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(users.len(), 3);
+        assert_eq!(users[0].name, "user0");
+        assert_eq!(users[2].email, "user2@example.com");
+        assert!(users.iter().all(|u| u.id > 0));
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert_chunks_past_parameter_limit() {
+        let pool = pool().await;
+        let count = BULK_INSERT_MAX_PARAMS / 2 + 10;
+        let users: Vec<User> = pool
+            .bulk_insert(
+                "users",
+                &["name", "email"],
+                &new_users(count),
+                |mut b, u| {
+                    b.push_bind(&u.name).push_bind(&u.email);
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(users.len(), count);
+
+        let (stored,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users")
+            .fetch_one(pool.pool())
+            .await
+            .unwrap();
+        assert_eq!(stored as usize, count);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert_is_atomic() {
+        let pool = pool().await;
+        let mut rows = new_users(2);
+        rows.push(NewUser {
+            name: "dup".to_string(),
+            email: "user0@example.com".to_string(),
+        });
+        let result: Result<Vec<User>, DatabaseError> = pool
+            .bulk_insert("users", &["name", "email"], &rows, |mut b, u| {
+                b.push_bind(&u.name).push_bind(&u.email);
+            })
+            .await;
+        assert!(matches!(result, Err(DatabaseError::Query(_))));
+
+        let (stored,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users")
+            .fetch_one(pool.pool())
+            .await
+            .unwrap();
+        assert_eq!(stored, 0);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert_rejects_bad_identifiers() {
+        let pool = pool().await;
+        let result: Result<Vec<User>, DatabaseError> = pool
+            .bulk_insert(
+                "users; DROP TABLE users",
+                &["name"],
+                &new_users(1),
+                |mut b, u| {
+                    b.push_bind(&u.name);
+                },
+            )
+            .await;
+        assert!(matches!(result, Err(DatabaseError::Query(_))));
+
+        let empty: Vec<User> = pool
+            .bulk_insert("users", &["name", "email"], &new_users(0), |mut b, u| {
+                b.push_bind(&u.name).push_bind(&u.email);
+            })
+            .await
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+}
+
+/// A `Context` with `db` attached, as dispatch would build it.
+#[cfg(any(feature = "sqlx-sqlite", feature = "diesel-sqlite"))]
+#[cfg(test)]
+fn context_with(db: crate::database::Database) -> crate::Context {
+    let (parts, ()) = hyper::Request::new(()).into_parts();
+    let mut ctx =
+        crate::Context::from_parts(parts, bytes::Bytes::new(), crate::router::Params::new());
+    ctx.attach_database(db);
+    ctx
+}
+
+#[cfg(feature = "sqlx-sqlite")]
+#[cfg(test)]
+mod sqlx_transaction_tests {
+    use super::context_with;
+    use crate::database::sqlx::SqlxPool;
+    use crate::database::Database;
+    use crate::{Context, UltimoError};
+
+    async fn setup() -> (SqlxPool<sqlx::Sqlite>, Context) {
+        let pool = SqlxPool::<sqlx::Sqlite>::connect_with_options(
+            sqlx::sqlite::SqlitePoolOptions::new().max_connections(1),
+            "sqlite::memory:",
+        )
+        .await
+        .unwrap();
+        sqlx::query("CREATE TABLE t (id INTEGER PRIMARY KEY)")
+            .execute(pool.pool())
+            .await
+            .unwrap();
+        let ctx = context_with(Database::from_sqlx(pool.clone()));
+        (pool, ctx)
+    }
+
+    async fn count(pool: &SqlxPool<sqlx::Sqlite>) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM t")
+            .fetch_one(pool.pool())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transaction_commits_on_ok() {
+        let (pool, ctx) = setup().await;
+        let id = ctx
+            .transaction::<sqlx::Sqlite, _, _>(|tx| {
+                Box::pin(async move {
+                    sqlx::query("INSERT INTO t (id) VALUES (7)")
+                        .execute(&mut **tx)
+                        .await?;
+                    Ok(7)
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(id, 7);
+        assert_eq!(count(&pool).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_rolls_back_on_err() {
+        let (pool, ctx) = setup().await;
+        let result = ctx
+            .transaction::<sqlx::Sqlite, (), _>(|tx| {
+                Box::pin(async move {
+                    sqlx::query("INSERT INTO t (id) VALUES (1)")
+                        .execute(&mut **tx)
+                        .await?;
+                    Err(UltimoError::Conflict("nope".into()))
+                })
+            })
+            .await;
+        assert!(matches!(result, Err(UltimoError::Conflict(_))));
+        assert_eq!(count(&pool).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_rolls_back_on_panic() {
+        let (pool, ctx) = setup().await;
+        let task = tokio::spawn(async move {
+            ctx.transaction::<sqlx::Sqlite, (), _>(|tx| {
+                Box::pin(async move {
+                    sqlx::query("INSERT INTO t (id) VALUES (1)")
+                        .execute(&mut **tx)
+                        .await?;
+                    panic!("boom");
+                })
+            })
+            .await
+        });
+        assert!(task.await.unwrap_err().is_panic());
+        assert_eq!(count(&pool).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_without_pool_is_an_error() {
+        let (parts, ()) = hyper::Request::new(()).into_parts();
+        let ctx = Context::from_parts(parts, bytes::Bytes::new(), crate::router::Params::new());
+        let result = ctx
+            .transaction::<sqlx::Sqlite, (), _>(|_| Box::pin(async { Ok(()) }))
+            .await;
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(feature = "diesel-sqlite")]
+#[cfg(test)]
+mod diesel_transaction_tests {
+    use super::context_with;
+    use crate::database::diesel::DieselPool;
+    use crate::database::Database;
+    use crate::UltimoError;
+    use diesel::{RunQueryDsl, SqliteConnection};
+
+    fn setup() -> (DieselPool<SqliteConnection>, crate::Context) {
+        // One connection: every `:memory:` connection is its own database.
+        let pool = DieselPool::<SqliteConnection>::with_config(":memory:", 1).unwrap();
+        diesel::sql_query("CREATE TABLE t (id INTEGER PRIMARY KEY)")
+            .execute(&mut *pool.get().unwrap())
+            .unwrap();
+        let ctx = context_with(Database::from_diesel(pool.clone()));
+        (pool, ctx)
+    }
+
+    fn count(pool: &DieselPool<SqliteConnection>) -> i64 {
+        diesel::select(diesel::dsl::sql::<diesel::sql_types::BigInt>(
+            "(SELECT COUNT(*) FROM t)",
+        ))
+        .get_result(&mut *pool.get().unwrap())
+        .unwrap()
+    }
+
+    fn insert(conn: &mut SqliteConnection) -> crate::Result<usize> {
+        Ok(diesel::sql_query("INSERT INTO t (id) VALUES (1)").execute(conn)?)
+    }
+
+    #[test]
+    fn test_diesel_transaction_commits_on_ok() {
+        let (pool, ctx) = setup();
+        let inserted = ctx.diesel_transaction(insert).unwrap();
+        assert_eq!(inserted, 1);
+        assert_eq!(count(&pool), 1);
+    }
+
+    #[test]
+    fn test_diesel_transaction_rolls_back_on_err() {
+        let (pool, ctx) = setup();
+        let result = ctx.diesel_transaction(|conn: &mut SqliteConnection| -> crate::Result<()> {
+            insert(conn)?;
+            Err(UltimoError::Conflict("nope".into()))
+        });
+        assert!(matches!(result, Err(UltimoError::Conflict(_))));
+        assert_eq!(count(&pool), 0);
+    }
+
+    #[test]
+    fn test_diesel_transaction_rolls_back_and_resumes_panic() {
+        let (pool, ctx) = setup();
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            ctx.diesel_transaction(|conn: &mut SqliteConnection| -> crate::Result<()> {
+                insert(conn)?;
+                panic!("boom");
+            })
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(count(&pool), 0);
+    }
+}
+
 #[cfg(feature = "diesel-postgres")]
 #[cfg(test)]
 mod diesel_tests {
@@ -361,3 +665,25 @@ mod integration_hints {
     #[allow(dead_code)]
     fn example_diesel_test() {}
 }
+
+#[cfg(feature = "sqlx-sqlite")]
+#[cfg(test)]
+mod sqlx_pool_stats_tests {
+    use crate::database::sqlx::SqlxPool;
+    use crate::database::PoolStats;
+
+    #[tokio::test]
+    async fn test_stats_count_open_and_idle_connections() {
+        let pool = SqlxPool::<sqlx::Sqlite>::connect_with_options(
+            sqlx::sqlite::SqlitePoolOptions::new().max_connections(1),
+            "sqlite::memory:",
+        )
+        .await
+        .unwrap();
+        assert_eq!(pool.stats(), PoolStats { size: 1, idle: 1 });
+
+        let conn = pool.pool().acquire().await.unwrap();
+        assert_eq!(pool.stats(), PoolStats { size: 1, idle: 0 });
+        drop(conn);
+    }
+}
@@ -7,7 +7,11 @@ use serde::Serialize;
 use std::fmt;
 
 /// Main error type for Ultimo framework
+///
+/// Non-exhaustive: new variants may be added in minor releases, so matches
+/// need a `_` arm (typically mapping to [`status_code`](Self::status_code)).
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum UltimoError {
     /// HTTP error with status code and message
     Http { status: u16, message: String },
@@ -22,6 +26,10 @@ pub enum UltimoError {
     Forbidden(String),
     /// Not found error (404)
     NotFound(String),
+    /// Conflict with the current state of the resource (409)
+    Conflict(String),
+    /// Well-formed request that cannot be processed (422)
+    UnprocessableEntity(String),
     /// Internal server error (500)
     Internal(String),
     /// Bad request error (400)
@@ -61,6 +69,8 @@ impl UltimoError {
             UltimoError::Unauthorized(_) => 401,
             UltimoError::Forbidden(_) => 403,
             UltimoError::NotFound(_) => 404,
+            UltimoError::Conflict(_) => 409,
+            UltimoError::UnprocessableEntity(_) => 422,
             UltimoError::BadRequest(_) => 400,
             UltimoError::Internal(_) => 500,
             UltimoError::Hyper(_) => 500,
@@ -98,6 +108,16 @@ impl UltimoError {
                 message: msg.clone(),
                 details: None,
             },
+            UltimoError::Conflict(msg) => ErrorResponse {
+                error: "Conflict".to_string(),
+                message: msg.clone(),
+                details: None,
+            },
+            UltimoError::UnprocessableEntity(msg) => ErrorResponse {
+                error: "UnprocessableEntity".to_string(),
+                message: msg.clone(),
+                details: None,
+            },
             UltimoError::BadRequest(msg) => ErrorResponse {
                 error: "BadRequest".to_string(),
                 message: msg.clone(),
@@ -142,6 +162,8 @@ impl fmt::Display for UltimoError {
             UltimoError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
             UltimoError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             UltimoError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            UltimoError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            UltimoError::UnprocessableEntity(msg) => write!(f, "Unprocessable entity: {}", msg),
             UltimoError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
             UltimoError::Internal(msg) => write!(f, "Internal error: {}", msg),
             UltimoError::Hyper(err) => write!(f, "Hyper error: {}", err),
@@ -191,6 +213,11 @@ mod tests {
         assert_eq!(UltimoError::Unauthorized("test".into()).status_code(), 401);
         assert_eq!(UltimoError::Forbidden("test".into()).status_code(), 403);
         assert_eq!(UltimoError::NotFound("test".into()).status_code(), 404);
+        assert_eq!(UltimoError::Conflict("test".into()).status_code(), 409);
+        assert_eq!(
+            UltimoError::UnprocessableEntity("test".into()).status_code(),
+            422
+        );
         assert_eq!(UltimoError::BadRequest("test".into()).status_code(), 400);
         assert_eq!(UltimoError::Internal("test".into()).status_code(), 500);
     }
@@ -252,6 +279,9 @@ mod tests {
         let err = UltimoError::Unauthorized("Invalid token".to_string());
         assert_eq!(format!("{}", err), "Unauthorized: Invalid token");
 
+        let err = UltimoError::Conflict("Email taken".to_string());
+        assert_eq!(format!("{}", err), "Conflict: Email taken");
+
         let err = UltimoError::Http {
             status: 503,
             message: "Service unavailable".to_string(),
@@ -288,6 +318,18 @@ mod tests {
         assert_eq!(response.error, "NotFound");
         assert_eq!(response.message, "Resource not found");
 
+        // Test Conflict error response
+        let err = UltimoError::Conflict("Email taken".to_string());
+        let response = err.to_error_response();
+        assert_eq!(response.error, "Conflict");
+        assert_eq!(response.message, "Email taken");
+
+        // Test UnprocessableEntity error response
+        let err = UltimoError::UnprocessableEntity("Order already shipped".to_string());
+        let response = err.to_error_response();
+        assert_eq!(response.error, "UnprocessableEntity");
+        assert_eq!(response.message, "Order already shipped");
+
         // Test BadRequest error response
         let err = UltimoError::BadRequest("Invalid data".to_string());
         let response = err.to_error_response();
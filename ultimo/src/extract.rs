@@ -0,0 +1,438 @@
+//! Typed handler arguments.
+//!
+//! Besides `|ctx: Context|`, a handler may declare what it needs as typed
+//! arguments, each pulled from the request before the handler runs:
+//!
+//! ```rust,ignore
+//! use ultimo::extract::{Json, Path, Query, State};
+//!
+//! app.put("/users/:id", |Path(id): Path<u32>, Json(input): Json<UpdateUser>| async move {
+//!     Ok(Json(update_user(id, input).await?))
+//! });
+//!
+//! app.get("/search", |Query(q): Query<Search>, State(db): State<Db>| async move {
+//!     Ok(Json(db.search(&q.term).await?))
+//! });
+//! ```
+//!
+//! Any number of [`FromRequestParts`] extractors may be combined (up to 8
+//! arguments). A [`Context`] is also accepted, as the last argument. An
+//! extractor that fails answers the request with its error (`400 Bad Request`
+//! for a malformed path, query or body) and the handler is not called.
+
+use crate::{
+    context::Context,
+    error::{Result, UltimoError},
+};
+use async_trait::async_trait;
+use serde::de::{
+    self,
+    value::{MapDeserializer, SeqDeserializer},
+    DeserializeOwned, IntoDeserializer, Visitor,
+};
+use std::sync::Arc;
+
+pub use crate::response::Json;
+
+/// A value extracted from a borrowed [`Context`], usable in any argument
+/// position.
+///
+/// Request bodies are cached once read, so [`Json`] is one of these too.
+#[async_trait]
+pub trait FromRequestParts: Sized {
+    /// Extract `Self` from the request, or fail it with the returned error.
+    async fn from_request_parts(ctx: &Context) -> Result<Self>;
+}
+
+/// A value extracted from the [`Context`] itself, usable as the last
+/// argument.
+///
+/// Implemented for [`Context`] and for every [`FromRequestParts`] type; `M`
+/// only keeps the two impls apart.
+#[async_trait]
+pub trait FromRequest<M = ViaRequest>: Sized {
+    /// Extract `Self`, consuming the context.
+    async fn from_request(ctx: Context) -> Result<Self>;
+}
+
+/// Marker for [`FromRequest`] impls that consume the context.
+#[doc(hidden)]
+pub enum ViaRequest {}
+
+/// Marker for the [`FromRequest`] impl of [`FromRequestParts`] types.
+#[doc(hidden)]
+pub enum ViaParts {}
+
+#[async_trait]
+impl<T: FromRequestParts> FromRequest<ViaParts> for T {
+    async fn from_request(ctx: Context) -> Result<Self> {
+        T::from_request_parts(&ctx).await
+    }
+}
+
+#[async_trait]
+impl FromRequest for Context {
+    async fn from_request(ctx: Context) -> Result<Self> {
+        Ok(ctx)
+    }
+}
+
+/// Path parameters deserialized into `T`.
+///
+/// A single parameter can be read as a scalar (`Path<u32>`), several as a
+/// tuple in route order (`Path<(u32, String)>`) or as a struct with fields
+/// named after the parameters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned> FromRequestParts for Path<T> {
+    async fn from_request_parts(ctx: &Context) -> Result<Self> {
+        let params = ctx.req.params();
+        let mut ordered: Vec<(&str, &str)> = ctx
+            .matched_route()
+            .unwrap_or_default()
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix(':').or(segment.strip_prefix('*')))
+            .filter_map(|name| params.get_key_value(name))
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        if ordered.len() != params.len() {
+            ordered = params
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect();
+            ordered.sort_unstable();
+        }
+        T::deserialize(Fields(ordered))
+            .map(Path)
+            .map_err(|e| UltimoError::BadRequest(format!("Invalid path parameters: {}", e)))
+    }
+}
+
+/// The query string deserialized into `T`, typically a struct; use
+/// `Option` fields for parameters that may be absent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned> FromRequestParts for Query<T> {
+    async fn from_request_parts(ctx: &Context) -> Result<Self> {
+        let query = ctx.req.uri().query().unwrap_or_default();
+        serde_urlencoded::from_str(query)
+            .map(Query)
+            .map_err(|e| UltimoError::BadRequest(format!("Invalid query string: {}", e)))
+    }
+}
+
+/// The request body parsed as JSON, with the checks of
+/// [`Request::json`](crate::context::Request::json).
+#[async_trait]
+impl<T: DeserializeOwned> FromRequestParts for Json<T> {
+    async fn from_request_parts(ctx: &Context) -> Result<Self> {
+        ctx.req.json().await.map(Json)
+    }
+}
+
+/// App state registered with [`Ultimo::with_state`](crate::Ultimo::with_state).
+///
+/// Fails with an internal error if no state of type `S` was registered.
+#[derive(Debug, Default)]
+pub struct State<S>(pub Arc<S>);
+
+impl<S> Clone for State<S> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<S> std::ops::Deref for State<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync + 'static> FromRequestParts for State<S> {
+    async fn from_request_parts(ctx: &Context) -> Result<Self> {
+        ctx.state::<S>().map(State)
+    }
+}
+
+/// Request headers deserialized into `T`, a struct whose fields are named
+/// (or `#[serde(rename)]`d) after lowercase header names.
+///
+/// ```rust,ignore
+/// #[derive(Deserialize)]
+/// struct Client {
+///     #[serde(rename = "user-agent")]
+///     user_agent: String,
+///     #[serde(rename = "x-api-version")]
+///     api_version: Option<u32>,
+/// }
+///
+/// app.get("/", |Header(client): Header<Client>| async move { Ok(client.user_agent) });
+/// ```
+///
+/// Repeated headers yield their first value; values that are not valid
+/// UTF-8 are skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Header<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned> FromRequestParts for Header<T> {
+    async fn from_request_parts(ctx: &Context) -> Result<Self> {
+        let headers = ctx.req.headers();
+        let fields = headers
+            .keys()
+            .filter_map(|name| {
+                let value = headers.get(name)?.to_str().ok()?;
+                Some((name.as_str(), value))
+            })
+            .collect();
+        T::deserialize(Fields(fields))
+            .map(Header)
+            .map_err(|e| UltimoError::BadRequest(format!("Invalid headers: {}", e)))
+    }
+}
+
+type DeError = de::value::Error;
+
+/// Named string values (path parameters or headers) deserialized as a map or
+/// struct by name, as a tuple or sequence in order, or, when there is
+/// exactly one, as a scalar.
+struct Fields<'a>(Vec<(&'a str, &'a str)>);
+
+impl<'a> Fields<'a> {
+    fn single(self) -> std::result::Result<FieldValue<'a>, DeError> {
+        match self.0.as_slice() {
+            [(_, value)] => Ok(FieldValue(value)),
+            fields => Err(de::Error::custom(format!(
+                "expected 1 value, found {}",
+                fields.len()
+            ))),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Fields<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        let entries = self
+            .0
+            .into_iter()
+            .map(|(name, value)| (name, FieldValue(value)));
+        visitor.visit_map(MapDeserializer::new(entries))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        let values = self.0.into_iter().map(|(_, value)| FieldValue(value));
+        visitor.visit_seq(SeqDeserializer::new(values))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_option deserialize_unit
+        deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.single()?.deserialize_unit(visitor)
+    }
+}
+
+/// One string value, parsed into whatever scalar type is asked for.
+struct FieldValue<'a>(&'a str);
+
+impl<'de> IntoDeserializer<'de, DeError> for FieldValue<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for FieldValue<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    parse_value! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    fn fields<T: DeserializeOwned>(pairs: &[(&str, &str)]) -> std::result::Result<T, DeError> {
+        T::deserialize(Fields(pairs.to_vec()))
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Ids {
+        user: u32,
+        slug: String,
+        draft: Option<bool>,
+    }
+
+    #[test]
+    fn fields_deserialize_as_scalar_tuple_or_struct() {
+        assert_eq!(fields::<u32>(&[("id", "42")]).unwrap(), 42);
+        assert_eq!(
+            fields::<(u32, String)>(&[("id", "7"), ("slug", "hello")]).unwrap(),
+            (7, "hello".to_string())
+        );
+        assert_eq!(
+            fields::<Ids>(&[("slug", "hi"), ("user", "3")]).unwrap(),
+            Ids {
+                user: 3,
+                slug: "hi".to_string(),
+                draft: None,
+            }
+        );
+    }
+
+    #[test]
+    fn fields_report_bad_values_and_counts() {
+        let err = fields::<u32>(&[("id", "abc")]).unwrap_err().to_string();
+        assert!(err.contains("abc"), "{}", err);
+        let err = fields::<u32>(&[("a", "1"), ("b", "2")]).unwrap_err();
+        assert_eq!(err.to_string(), "expected 1 value, found 2");
+    }
+}
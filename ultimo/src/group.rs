@@ -0,0 +1,516 @@
+//! Route groups: shared path prefixes and group-scoped middleware
+//!
+//! A [`RouteGroup`] collects routes registered under a common prefix via
+//! [`Ultimo::group`](crate::Ultimo::group). Middleware added to a group runs
+//! only for that group's routes, after global middleware and before the
+//! handler. A group may also raise or lower the app's body-size limit for its
+//! routes with [`RouteGroup::max_body_size`], e.g. for upload endpoints.
+//!
+//! A group can also be built on its own with [`Ultimo::router`](crate::Ultimo::router),
+//! e.g. in a separate module, and attached later with
+//! [`Ultimo::mount`](crate::Ultimo::mount) or [`RouteGroup::mount`].
+//!
+//! A single route can carry its own body-size limit and accepted content
+//! types through a [`RouteBuilder`], from [`Ultimo::route`](crate::Ultimo::route)
+//! or [`RouteGroup::route`]. The builder also attaches the summary, tags and
+//! request/response types that [`Ultimo::generate_openapi`](crate::Ultimo::generate_openapi)
+//! documents.
+
+use crate::{
+    context::Context,
+    handler::{BoxedHandler, IntoHandler},
+    middleware::{BoxedMiddleware, MiddlewareChain},
+    router::Method,
+    Ultimo,
+};
+use std::sync::Arc;
+
+/// Routes sharing a path prefix and middleware, built by [`Ultimo::group`](crate::Ultimo::group).
+pub struct RouteGroup {
+    prefix: String,
+    middleware: Vec<BoxedMiddleware>,
+    max_body_size: Option<usize>,
+    routes: Vec<GroupRoute>,
+}
+
+/// A route collected by a [`RouteGroup`], with its full path.
+pub(crate) struct GroupRoute {
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) handler: BoxedHandler,
+    pub(crate) limits: RouteLimits,
+    pub(crate) docs: RouteDocs,
+}
+
+/// Request constraints for one route, checked before any middleware runs.
+#[derive(Clone, Default)]
+pub(crate) struct RouteLimits {
+    /// Body-size limit overriding the app's, if the route or a group set one.
+    pub(crate) max_body_size: Option<usize>,
+    /// Media types a request body may have; empty accepts any.
+    pub(crate) content_types: Vec<String>,
+}
+
+/// OpenAPI details for one route, read by
+/// [`Ultimo::generate_openapi`](crate::Ultimo::generate_openapi).
+#[derive(Clone, Default)]
+pub(crate) struct RouteDocs {
+    pub(crate) summary: Option<String>,
+    pub(crate) tags: Vec<String>,
+    /// TypeScript type of the request body.
+    pub(crate) request_body: Option<String>,
+    /// Documented responses by status, with the TypeScript type of the body
+    /// (`None` for no body).
+    pub(crate) responses: Vec<(u16, Option<String>)>,
+    /// Left out of the spec: the framework's own endpoints (docs, metrics,
+    /// debug routes) are not part of the documented API.
+    pub(crate) hidden: bool,
+}
+
+impl RouteLimits {
+    /// Whether a request with `headers` passes the content-type constraint.
+    /// Requests without a body always do.
+    pub(crate) fn accepts_content_type(&self, headers: &hyper::HeaderMap, has_body: bool) -> bool {
+        if self.content_types.is_empty() || !has_body {
+            return true;
+        }
+        let Some(content_type) = headers
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+        else {
+            return false;
+        };
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        self.content_types
+            .iter()
+            .any(|accepted| accepted.eq_ignore_ascii_case(mime))
+    }
+}
+
+impl RouteGroup {
+    pub(crate) fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/').to_string(),
+            middleware: Vec::new(),
+            max_body_size: None,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Add middleware that runs for every route in this group, in the order added.
+    pub fn use_middleware(&mut self, middleware: BoxedMiddleware) -> &mut Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Override the app's [`max_body_size`](crate::Ultimo::max_body_size) for
+    /// every route in this group, e.g. to accept large uploads on one endpoint
+    /// while keeping a tight limit elsewhere. Nested groups inherit it unless
+    /// they set their own.
+    ///
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.group("/uploads", |uploads| {
+    ///     uploads.max_body_size(50 * 1024 * 1024);
+    ///     uploads.post("/", |ctx: Context| async move {
+    ///         let body = ctx.req.bytes().await?;
+    ///         ctx.json(json!({ "received": body.len() })).await
+    ///     });
+    /// });
+    /// ```
+    pub fn max_body_size(&mut self, bytes: usize) -> &mut Self {
+        self.max_body_size = Some(bytes);
+        self
+    }
+
+    /// Start a route at `path`, relative to the group prefix, with its own
+    /// constraints; see [`Ultimo::route`](crate::Ultimo::route).
+    pub fn route(&mut self, path: &str) -> RouteBuilder<'_> {
+        RouteBuilder::new(Target::Group(self), path)
+    }
+
+    /// Add a GET route relative to the group prefix
+    pub fn get<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.add_route(Method::GET, path, handler)
+    }
+
+    /// Add a POST route relative to the group prefix
+    pub fn post<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.add_route(Method::POST, path, handler)
+    }
+
+    /// Add a PUT route relative to the group prefix
+    pub fn put<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.add_route(Method::PUT, path, handler)
+    }
+
+    /// Add a DELETE route relative to the group prefix
+    pub fn delete<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.add_route(Method::DELETE, path, handler)
+    }
+
+    /// Add a PATCH route relative to the group prefix
+    pub fn patch<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.add_route(Method::PATCH, path, handler)
+    }
+
+    /// Add an OPTIONS route relative to the group prefix
+    pub fn options<M>(&mut self, path: &str, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.add_route(Method::OPTIONS, path, handler)
+    }
+
+    /// Add a GET route relative to the group prefix, with middleware that
+    /// runs only for it, after the group's own.
+    pub fn get_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::GET, path, middleware, handler)
+    }
+
+    /// Add a POST route relative to the group prefix, with middleware that
+    /// runs only for it, after the group's own.
+    pub fn post_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::POST, path, middleware, handler)
+    }
+
+    /// Add a PUT route relative to the group prefix, with middleware that
+    /// runs only for it, after the group's own.
+    pub fn put_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::PUT, path, middleware, handler)
+    }
+
+    /// Add a DELETE route relative to the group prefix, with middleware that
+    /// runs only for it, after the group's own.
+    pub fn delete_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::DELETE, path, middleware, handler)
+    }
+
+    /// Add a PATCH route relative to the group prefix, with middleware that
+    /// runs only for it, after the group's own.
+    pub fn patch_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::PATCH, path, middleware, handler)
+    }
+
+    /// Add an OPTIONS route relative to the group prefix, with middleware that
+    /// runs only for it, after the group's own.
+    pub fn options_with<M>(
+        &mut self,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M> + 'static,
+    ) -> &mut Self {
+        self.add_route_with(Method::OPTIONS, path, middleware, handler)
+    }
+
+    /// Nest a group under `prefix`, relative to this group. The nested group's
+    /// routes run this group's middleware first, then its own.
+    pub fn group(&mut self, prefix: &str, configure: impl FnOnce(&mut RouteGroup)) -> &mut Self {
+        let mut nested = RouteGroup::new(&join_path(&self.prefix, prefix));
+        configure(&mut nested);
+        self.routes.extend(nested.into_routes());
+        self
+    }
+
+    /// Attach a standalone router under `prefix`, relative to this group. Its
+    /// routes run this group's middleware first, then the router's own.
+    pub fn mount(&mut self, prefix: &str, router: RouteGroup) -> &mut Self {
+        let prefix = join_path(&self.prefix, prefix);
+        self.routes.extend(router.into_routes_under(&prefix));
+        self
+    }
+
+    fn add_route<M>(
+        &mut self,
+        method: Method,
+        path: &str,
+        handler: impl IntoHandler<M>,
+    ) -> &mut Self {
+        self.add_route_with(method, path, [], handler)
+    }
+
+    fn add_route_with<M>(
+        &mut self,
+        method: Method,
+        path: &str,
+        middleware: impl IntoIterator<Item = BoxedMiddleware>,
+        handler: impl IntoHandler<M>,
+    ) -> &mut Self {
+        self.routes.push(GroupRoute {
+            method,
+            path: join_path(&self.prefix, path),
+            handler: with_middleware(handler.into_handler(), middleware),
+            limits: RouteLimits::default(),
+            docs: RouteDocs::default(),
+        });
+        self
+    }
+
+    /// The group's routes with full paths, each wrapped in the group
+    /// middleware and carrying the group's body-size limit unless a nested
+    /// group set its own.
+    pub(crate) fn into_routes(self) -> Vec<GroupRoute> {
+        let max_body_size = self.max_body_size;
+        let middleware = (!self.middleware.is_empty()).then(|| Arc::new(self.middleware));
+        self.routes
+            .into_iter()
+            .map(|route| GroupRoute {
+                handler: match &middleware {
+                    Some(middleware) => wrap(route.handler, middleware.clone()),
+                    None => route.handler,
+                },
+                limits: RouteLimits {
+                    max_body_size: route.limits.max_body_size.or(max_body_size),
+                    ..route.limits
+                },
+                ..route
+            })
+            .collect()
+    }
+
+    /// [`into_routes`](Self::into_routes) with every path moved under `prefix`.
+    pub(crate) fn into_routes_under(self, prefix: &str) -> Vec<GroupRoute> {
+        let prefix = prefix.trim_end_matches('/');
+        self.into_routes()
+            .into_iter()
+            .map(|route| GroupRoute {
+                path: join_path(prefix, &route.path),
+                ..route
+            })
+            .collect()
+    }
+}
+
+impl Default for RouteGroup {
+    /// A router with no prefix, to be mounted later.
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+/// A single route with its own constraints, started by
+/// [`Ultimo::route`](crate::Ultimo::route) or [`RouteGroup::route`].
+///
+/// Set the constraints first, then register one or more methods; each
+/// method gets the constraints, middleware and OpenAPI details set before it.
+pub struct RouteBuilder<'a> {
+    target: Target<'a>,
+    path: String,
+    middleware: Vec<BoxedMiddleware>,
+    limits: RouteLimits,
+    docs: RouteDocs,
+}
+
+pub(crate) enum Target<'a> {
+    App(&'a mut Ultimo),
+    Group(&'a mut RouteGroup),
+}
+
+impl<'a> RouteBuilder<'a> {
+    pub(crate) fn new(target: Target<'a>, path: &str) -> Self {
+        Self {
+            target,
+            path: path.to_string(),
+            middleware: Vec::new(),
+            limits: RouteLimits::default(),
+            docs: RouteDocs::default(),
+        }
+    }
+
+    /// Reject bodies larger than `bytes` with `413 Payload Too Large`,
+    /// overriding the app's (or group's) limit for this route.
+    pub fn max_body_size(&mut self, bytes: usize) -> &mut Self {
+        self.limits.max_body_size = Some(bytes);
+        self
+    }
+
+    /// Accept request bodies of media type `mime`, e.g. `application/json`.
+    /// Call it again to accept several. A request with a body of any other
+    /// type, or none, gets `415 Unsupported Media Type`; parameters such as
+    /// `charset` are ignored, and requests without a body are let through.
+    pub fn content_type(&mut self, mime: &str) -> &mut Self {
+        self.limits.content_types.push(mime.trim().to_string());
+        self
+    }
+
+    /// Add middleware that runs only for this route, as with
+    /// [`Ultimo::get_with`](crate::Ultimo::get_with).
+    pub fn middleware(&mut self, middleware: BoxedMiddleware) -> &mut Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Short description of the operation, for
+    /// [`Ultimo::generate_openapi`](crate::Ultimo::generate_openapi).
+    pub fn summary(&mut self, summary: &str) -> &mut Self {
+        self.docs.summary = Some(summary.to_string());
+        self
+    }
+
+    /// Group the operation under an OpenAPI tag. Call it again to add more.
+    pub fn tag(&mut self, tag: &str) -> &mut Self {
+        self.docs.tags.push(tag.to_string());
+        self
+    }
+
+    /// Document the request body as a TypeScript type, e.g.
+    /// `"{ name: string; email: string }"` or a type declared with
+    /// [`Ultimo::openapi_schema`](crate::Ultimo::openapi_schema). It is
+    /// listed under each [`content_type`](Self::content_type), or
+    /// `application/json` if none is set.
+    pub fn request_body(&mut self, ts_type: &str) -> &mut Self {
+        self.docs.request_body = Some(ts_type.to_string());
+        self
+    }
+
+    /// Document a `status` response with a JSON body of TypeScript type
+    /// `ts_type`, e.g. `(200, "User[]")`. Call it once per status.
+    pub fn response(&mut self, status: u16, ts_type: &str) -> &mut Self {
+        self.docs
+            .responses
+            .push((status, Some(ts_type.to_string())));
+        self
+    }
+
+    /// Document a `status` response without a body, e.g. `204` or `404`.
+    pub fn empty_response(&mut self, status: u16) -> &mut Self {
+        self.docs.responses.push((status, None));
+        self
+    }
+
+    /// Register a GET handler.
+    pub fn get<M>(&mut self, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.register(Method::GET, handler)
+    }
+
+    /// Register a POST handler.
+    pub fn post<M>(&mut self, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.register(Method::POST, handler)
+    }
+
+    /// Register a PUT handler.
+    pub fn put<M>(&mut self, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.register(Method::PUT, handler)
+    }
+
+    /// Register a DELETE handler.
+    pub fn delete<M>(&mut self, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.register(Method::DELETE, handler)
+    }
+
+    /// Register a PATCH handler.
+    pub fn patch<M>(&mut self, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        self.register(Method::PATCH, handler)
+    }
+
+    fn register<M>(&mut self, method: Method, handler: impl IntoHandler<M> + 'static) -> &mut Self {
+        let handler = with_middleware(handler.into_handler(), self.middleware.clone());
+        let limits = self.limits.clone();
+        let docs = self.docs.clone();
+        match &mut self.target {
+            Target::App(app) => {
+                app.add_group_routes(vec![GroupRoute {
+                    method,
+                    path: self.path.clone(),
+                    handler,
+                    limits,
+                    docs,
+                }]);
+            }
+            Target::Group(group) => {
+                let path = join_path(&group.prefix, &self.path);
+                group.routes.push(GroupRoute {
+                    method,
+                    path,
+                    handler,
+                    limits,
+                    docs,
+                });
+            }
+        }
+        self
+    }
+}
+
+/// Wrap `handler` in route-specific `middleware`, if there is any.
+pub(crate) fn with_middleware(
+    handler: BoxedHandler,
+    middleware: impl IntoIterator<Item = BoxedMiddleware>,
+) -> BoxedHandler {
+    let middleware: Vec<_> = middleware.into_iter().collect();
+    if middleware.is_empty() {
+        handler
+    } else {
+        wrap(handler, Arc::new(middleware))
+    }
+}
+
+/// Run `middleware` around `handler` on every call.
+fn wrap(handler: BoxedHandler, middleware: Arc<Vec<BoxedMiddleware>>) -> BoxedHandler {
+    Arc::new(move |ctx: Context| {
+        let handler = handler.clone();
+        let middleware = middleware.clone();
+        Box::pin(async move {
+            let mut chain = MiddlewareChain::new();
+            for m in middleware.iter() {
+                chain.push(m.clone());
+            }
+            chain.execute(ctx, move |ctx| handler(ctx)).await
+        })
+    })
+}
+
+/// Join a group prefix and a relative route path; `"/"` or `""` maps to the
+/// prefix itself. A trailing slash on the route path is kept, so `/x/` stays
+/// distinct from `/x` under [`TrailingSlash::Strict`](crate::router::TrailingSlash::Strict).
+fn join_path(prefix: &str, path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    if !path.is_empty() {
+        format!("{}/{}", prefix, path)
+    } else if prefix.is_empty() {
+        "/".to_string()
+    } else {
+        prefix.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_path() {
+        assert_eq!(join_path("/api/v1", "/users"), "/api/v1/users");
+        assert_eq!(join_path("/api/v1", "users/:id"), "/api/v1/users/:id");
+        assert_eq!(join_path("/api/v1", "/users/"), "/api/v1/users/");
+        assert_eq!(join_path("", "/users/"), "/users/");
+        assert_eq!(join_path("/api/v1", "/"), "/api/v1");
+        assert_eq!(join_path("/api/v1", ""), "/api/v1");
+        assert_eq!(join_path("", "/users"), "/users");
+        assert_eq!(join_path("", "/"), "/");
+    }
+}
@@ -1,6 +1,11 @@
 //! Handler traits and types for async request handling
 
-use crate::{context::Context, error::Result, response::Response};
+use crate::{
+    context::Context,
+    error::Result,
+    extract::{FromRequest, FromRequestParts, ViaRequest},
+    response::{IntoResponse, Response},
+};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -10,21 +15,68 @@ pub type BoxedHandler =
     Arc<dyn Fn(Context) -> Pin<Box<dyn Future<Output = Result<Response>> + Send>> + Send + Sync>;
 
 /// Trait for types that can be converted into handlers
-pub trait IntoHandler {
+///
+/// Implemented for async functions and closures taking a [`Context`], or
+/// typed extractors (see [`crate::extract`]), and returning
+/// `Result<impl IntoResponse>`. `M` only tells the impls apart and is always
+/// inferred at call sites; it defaults to the `Fn(Context)` handler shape, so
+/// a bare `impl IntoHandler` bound still accepts `|ctx: Context| ...`
+/// handlers.
+pub trait IntoHandler<M = (ViaRequest, Context)> {
     fn into_handler(self) -> BoxedHandler;
 }
 
-/// Implement IntoHandler for async functions with Context parameter
-impl<F, Fut> IntoHandler for F
+/// Implement IntoHandler for async functions without parameters
+impl<F, Fut, R> IntoHandler<()> for F
 where
-    F: Fn(Context) -> Fut + Send + Sync + 'static,
-    Fut: Future<Output = Result<Response>> + Send + 'static,
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<R>> + Send + 'static,
+    R: IntoResponse,
 {
     fn into_handler(self) -> BoxedHandler {
-        Arc::new(move |ctx| Box::pin(self(ctx)))
+        Arc::new(move |_ctx| {
+            let fut = self();
+            Box::pin(async move { fut.await?.into_response() })
+        })
     }
 }
 
+/// Implement IntoHandler for async functions whose parameters are
+/// extractors, the last of which may be the [`Context`] itself
+macro_rules! impl_into_handler {
+    ($($part:ident $part_var:ident,)* ; $last:ident $last_var:ident) => {
+        impl<F, Fut, R, M, $($part,)* $last> IntoHandler<(M, $($part,)* $last)> for F
+        where
+            F: Fn($($part,)* $last) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<R>> + Send + 'static,
+            R: IntoResponse,
+            $($part: FromRequestParts + Send,)*
+            $last: FromRequest<M> + Send,
+        {
+            fn into_handler(self) -> BoxedHandler {
+                let handler = Arc::new(self);
+                Arc::new(move |ctx| {
+                    let handler = handler.clone();
+                    Box::pin(async move {
+                        $(let $part_var = $part::from_request_parts(&ctx).await?;)*
+                        let $last_var = $last::from_request(ctx).await?;
+                        handler($($part_var,)* $last_var).await?.into_response()
+                    })
+                })
+            }
+        }
+    };
+}
+
+impl_into_handler!(; T1 t1);
+impl_into_handler!(T1 t1, ; T2 t2);
+impl_into_handler!(T1 t1, T2 t2, ; T3 t3);
+impl_into_handler!(T1 t1, T2 t2, T3 t3, ; T4 t4);
+impl_into_handler!(T1 t1, T2 t2, T3 t3, T4 t4, ; T5 t5);
+impl_into_handler!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, ; T6 t6);
+impl_into_handler!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6, ; T7 t7);
+impl_into_handler!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6, T7 t7, ; T8 t8);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -38,4 +90,12 @@ mod tests {
         // Handler can be called (we'd need a real context to fully test)
         // This just verifies the trait implementation compiles
     }
+
+    #[test]
+    fn bare_into_handler_bound_takes_context_handlers() {
+        fn register(handler: impl IntoHandler) -> BoxedHandler {
+            handler.into_handler()
+        }
+        let _handler = register(|_ctx: Context| async move { response::helpers::text("Hello") });
+    }
 }
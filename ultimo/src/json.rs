@@ -0,0 +1,268 @@
+//! JSON encoding and decoding for request and response bodies
+//!
+//! [`Request::json`](crate::context::Request::json) and JSON responses
+//! ([`Context::json`](crate::Context::json),
+//! [`Context::json_stream`](crate::Context::json_stream), [`Json`](crate::Json),
+//! [`ResponseBuilder::json`](crate::response::ResponseBuilder::json)) go
+//! through a [`JsonCodec`]. [`SerdeJson`] is the default; the `simd-json`
+//! feature switches [`DefaultCodec`] to [`SimdJson`] for higher throughput on
+//! large payloads. Both produce the same JSON and the same parse errors, so
+//! clients can't tell which one is in use.
+
+use crate::error::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+/// A JSON implementation.
+pub trait JsonCodec {
+    /// Deserialize a value from `bytes`.
+    fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> std::result::Result<T, JsonError>;
+
+    /// Serialize `value` to bytes.
+    fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>>;
+}
+
+/// A JSON parse error, with the (1-based) position it was detected at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl JsonError {
+    /// An error detected at (1-based) `line` and `column`, for implementing
+    /// [`JsonCodec`]. `message` is shown to clients in the `400` response.
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Line of the input the error was detected on.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Column of the input the error was detected at.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// [`serde_json`], the default codec.
+pub struct SerdeJson;
+
+impl JsonCodec for SerdeJson {
+    fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> std::result::Result<T, JsonError> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| JsonError::new(e.line(), e.column(), e.to_string()))
+    }
+
+    fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(value)?)
+    }
+}
+
+/// [`simd_json`], enabled by the `simd-json` feature.
+#[cfg(feature = "simd-json")]
+pub struct SimdJson;
+
+#[cfg(feature = "simd-json")]
+impl JsonCodec for SimdJson {
+    fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> std::result::Result<T, JsonError> {
+        // simd-json parses in place, so it needs its own copy of the input.
+        let mut buf = bytes.to_vec();
+        simd_json::serde::from_slice(&mut buf).map_err(|e| {
+            // simd-json's messages name its internals (`InternalError(TapeError)
+            // at character 23`). Errors are the slow path, so re-parse with
+            // serde_json for the message clients would get by default.
+            match SerdeJson::from_slice::<T>(bytes) {
+                Err(err) => err,
+                Ok(_) => {
+                    let (line, column) = position(bytes, e.index());
+                    let message = format!("invalid JSON at line {} column {}", line, column);
+                    JsonError::new(line, column, message)
+                }
+            }
+        })
+    }
+
+    fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+        simd_json::serde::to_vec(value).map_err(|e| {
+            crate::error::UltimoError::Internal(format!("JSON serialization failed: {}", e))
+        })
+    }
+}
+
+/// The codec used for request and response bodies.
+#[cfg(not(feature = "simd-json"))]
+pub type DefaultCodec = SerdeJson;
+
+/// The codec used for request and response bodies.
+#[cfg(feature = "simd-json")]
+pub type DefaultCodec = SimdJson;
+
+/// Deserialize with the [`DefaultCodec`].
+pub(crate) fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> std::result::Result<T, JsonError> {
+    DefaultCodec::from_slice(bytes)
+}
+
+/// Serialize with the [`DefaultCodec`].
+pub(crate) fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    DefaultCodec::to_vec(value)
+}
+
+/// Apply a JSON Merge Patch ([RFC 7396]) to `target`.
+///
+/// Object members in `patch` replace those in `target`, recursively; a `null`
+/// member removes the key, and members the patch omits are left alone. Any
+/// other patch value (an array, a string, ...) replaces `target` outright.
+///
+/// [RFC 7396]: https://www.rfc-editor.org/rfc/rfc7396
+pub fn merge_patch(target: &mut serde_json::Value, patch: serde_json::Value) {
+    let serde_json::Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(serde_json::Map::new());
+    }
+    let serde_json::Value::Object(target) = target else {
+        unreachable!("target was just made an object");
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(&key);
+        } else {
+            merge_patch(target.entry(key).or_insert(serde_json::Value::Null), value);
+        }
+    }
+}
+
+/// 1-based line and column of byte `index` in `bytes`.
+#[cfg(any(feature = "simd-json", test))]
+fn position(bytes: &[u8], index: usize) -> (usize, usize) {
+    let before = &bytes[..index.min(bytes.len())];
+    let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+    let column = before.len()
+        - before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1)
+        + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize, Serialize)]
+    struct User {
+        id: u32,
+        name: String,
+    }
+
+    #[test]
+    fn default_codec_round_trips() {
+        let user = User {
+            id: 7,
+            name: "Ada".to_string(),
+        };
+        let bytes = to_vec(&user).unwrap();
+        assert_eq!(bytes, br#"{"id":7,"name":"Ada"}"#);
+        assert_eq!(from_slice::<User>(&bytes).unwrap(), user);
+    }
+
+    #[test]
+    fn default_codec_reports_error_position() {
+        let err = from_slice::<User>(b"{\n  \"id\": 1,\n  \"name\": }").unwrap_err();
+        assert_eq!(err.line(), 3);
+        assert!(err.column() > 1, "column: {}", err.column());
+    }
+
+    #[test]
+    fn json_error_new_sets_position_and_message() {
+        let err = JsonError::new(2, 5, "trailing comma");
+        assert_eq!((err.line(), err.column()), (2, 5));
+        assert_eq!(err.to_string(), "trailing comma");
+    }
+
+    #[cfg(feature = "simd-json")]
+    #[test]
+    fn simd_json_errors_match_serde_json() {
+        let inputs: [&[u8]; 4] = [
+            b"{\n  \"id\": 1,\n  \"name\": }",
+            br#"{"id": 1}"#,
+            br#"{"id": "one", "name": "Ada"}"#,
+            b"[1, 2",
+        ];
+        for input in inputs {
+            assert_eq!(
+                SimdJson::from_slice::<User>(input).unwrap_err(),
+                SerdeJson::from_slice::<User>(input).unwrap_err(),
+                "{}",
+                String::from_utf8_lossy(input)
+            );
+        }
+    }
+
+    #[test]
+    fn merge_patch_follows_rfc_7396() {
+        // The example from RFC 7396, section 3.
+        let mut target = serde_json::json!({
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        });
+        merge_patch(
+            &mut target,
+            serde_json::json!({
+                "title": "Hello!",
+                "phoneNumber": "+01-123-456-7890",
+                "author": {"familyName": null},
+                "tags": ["example"]
+            }),
+        );
+        assert_eq!(
+            target,
+            serde_json::json!({
+                "title": "Hello!",
+                "author": {"givenName": "John"},
+                "tags": ["example"],
+                "content": "This will be unchanged",
+                "phoneNumber": "+01-123-456-7890"
+            })
+        );
+
+        let mut target = serde_json::json!({"a": "b"});
+        merge_patch(&mut target, serde_json::json!(["c"]));
+        assert_eq!(target, serde_json::json!(["c"]));
+
+        let mut target = serde_json::json!(["c"]);
+        merge_patch(&mut target, serde_json::json!({"a": {"b": null}}));
+        assert_eq!(target, serde_json::json!({"a": {}}));
+    }
+
+    #[test]
+    fn position_counts_lines_and_columns_from_one() {
+        let input = b"ab\ncd\nef";
+        assert_eq!(position(input, 0), (1, 1));
+        assert_eq!(position(input, 1), (1, 2));
+        assert_eq!(position(input, 4), (2, 2));
+        assert_eq!(position(input, 6), (3, 1));
+        assert_eq!(position(input, 100), (3, 3));
+    }
+}
@@ -33,8 +33,13 @@ pub mod app;
 pub mod context;
 pub mod cookie;
 pub mod error;
+pub mod extract;
+pub mod group;
 pub mod handler;
+pub mod json;
+pub mod metrics;
 pub mod middleware;
+pub mod multipart;
 pub mod openapi;
 pub mod response;
 pub mod router;
@@ -62,10 +67,14 @@ pub mod auth;
 #[cfg(feature = "static-files")]
 pub(crate) mod static_files;
 
+#[cfg(feature = "tls")]
+pub mod tls;
+
 // Re-exports for convenience
 pub use app::Ultimo;
 pub use context::Context;
 pub use error::{Result, UltimoError};
+pub use response::{IntoResponse, Json};
 pub use rpc::{
     error_code, JsonRpcError, JsonRpcErrorResponse, JsonRpcOutput, JsonRpcRequest, JsonRpcResponse,
 };
@@ -78,6 +87,7 @@ pub mod prelude {
     pub use crate::context::Context;
     pub use crate::error::{Result, UltimoError};
     pub use crate::middleware;
+    pub use crate::response::{IntoResponse, Json, StatusCode};
     pub use crate::rpc::{
         JsonRpcError, JsonRpcErrorResponse, JsonRpcOutput, JsonRpcRequest, JsonRpcResponse,
     };
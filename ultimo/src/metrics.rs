@@ -0,0 +1,309 @@
+//! Request metrics in the Prometheus text exposition format.
+//!
+//! [`Metrics`] is the registry filled by
+//! [`middleware::builtin::metrics`](crate::middleware::builtin::metrics) and
+//! served by [`Ultimo::serve_metrics`](crate::Ultimo::serve_metrics). Series
+//! are labeled with the request method, the matched route pattern
+//! (`/users/:id`, not `/users/42`, so label cardinality stays bounded) and,
+//! for finished requests, the response status. Request and response body
+//! sizes are labeled by method and route only.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the latency histogram buckets.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Upper bounds, in bytes, of the body-size histogram buckets.
+const SIZE_BUCKETS: [f64; 7] = [
+    100.0,
+    1_000.0,
+    10_000.0,
+    100_000.0,
+    1_000_000.0,
+    10_000_000.0,
+    100_000_000.0,
+];
+
+/// Path label for requests that matched no route (e.g. CORS preflights).
+pub(crate) const UNMATCHED: &str = "<unmatched>";
+
+/// `(method, route)` labels.
+type RouteKey = (String, String);
+
+/// `(method, route, status)` labels.
+type StatusKey = (String, String, u16);
+
+/// Counters for one histogram series.
+struct Histogram {
+    /// Bucket upper bounds, excluding `+Inf`.
+    bounds: &'static [f64],
+    /// Observations per bucket (not cumulative), plus one for `+Inf`.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: vec![0; bounds.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|&le| value <= le)
+            .unwrap_or(self.bounds.len());
+        self.buckets[bucket] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Append the `_bucket`, `_sum` and `_count` lines of series `name`.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            cumulative += count;
+            let le = self
+                .bounds
+                .get(i)
+                .map_or("+Inf".to_string(), f64::to_string);
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"{}\"}} {}",
+                name, labels, le, cumulative
+            );
+        }
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+/// Registry of HTTP request counters, in-flight gauges, and latency and
+/// body-size histograms.
+#[derive(Default)]
+pub struct Metrics {
+    in_flight: Mutex<BTreeMap<RouteKey, i64>>,
+    finished: Mutex<BTreeMap<StatusKey, Histogram>>,
+    request_sizes: Mutex<BTreeMap<RouteKey, Histogram>>,
+    response_sizes: Mutex<BTreeMap<RouteKey, Histogram>>,
+}
+
+impl Metrics {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that a request to `route` started.
+    pub(crate) fn start(&self, method: &str, route: &str) {
+        *self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry((method.to_string(), route.to_string()))
+            .or_default() += 1;
+    }
+
+    /// Record that a request to `route` finished with `status` after
+    /// `elapsed`. Pairs with [`start`](Self::start).
+    pub(crate) fn finish(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        if let Some(n) = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .get_mut(&(method.to_string(), route.to_string()))
+        {
+            *n -= 1;
+        }
+        self.finished
+            .lock()
+            .unwrap()
+            .entry((method.to_string(), route.to_string(), status))
+            .or_insert_with(|| Histogram::new(&BUCKETS))
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record the body size, in bytes, of a request to `route`.
+    pub(crate) fn request_size(&self, method: &str, route: &str, bytes: u64) {
+        observe_size(&self.request_sizes, method, route, bytes);
+    }
+
+    /// Record the body size, in bytes, of a response from `route`.
+    pub(crate) fn response_size(&self, method: &str, route: &str, bytes: u64) {
+        observe_size(&self.response_sizes, method, route, bytes);
+    }
+
+    /// Render every series in the Prometheus text format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let finished = self.finished.lock().unwrap();
+
+        out.push_str("# HELP http_requests_total Total HTTP requests handled.\n");
+        out.push_str("# TYPE http_requests_total counter\n");
+        for ((method, route, status), series) in finished.iter() {
+            let _ = writeln!(
+                out,
+                "http_requests_total{{{}}} {}",
+                labels(method, route, Some(*status)),
+                series.count
+            );
+        }
+
+        out.push_str("# HELP http_requests_in_flight HTTP requests currently being handled.\n");
+        out.push_str("# TYPE http_requests_in_flight gauge\n");
+        for ((method, route), n) in self.in_flight.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "http_requests_in_flight{{{}}} {}",
+                labels(method, route, None),
+                n
+            );
+        }
+
+        out.push_str("# HELP http_request_duration_seconds HTTP request latency in seconds.\n");
+        out.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for ((method, route, status), series) in finished.iter() {
+            let labels = labels(method, route, Some(*status));
+            series.render(&mut out, "http_request_duration_seconds", &labels);
+        }
+
+        for (name, help, sizes) in [
+            (
+                "http_request_size_bytes",
+                "HTTP request body size in bytes.",
+                &self.request_sizes,
+            ),
+            (
+                "http_response_size_bytes",
+                "HTTP response body size in bytes.",
+                &self.response_sizes,
+            ),
+        ] {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} histogram", name);
+            for ((method, route), series) in sizes.lock().unwrap().iter() {
+                series.render(&mut out, name, &labels(method, route, None));
+            }
+        }
+        out
+    }
+}
+
+/// Add one body-size observation to the `(method, route)` series of `sizes`.
+fn observe_size(
+    sizes: &Mutex<BTreeMap<RouteKey, Histogram>>,
+    method: &str,
+    route: &str,
+    bytes: u64,
+) {
+    sizes
+        .lock()
+        .unwrap()
+        .entry((method.to_string(), route.to_string()))
+        .or_insert_with(|| Histogram::new(&SIZE_BUCKETS))
+        .observe(bytes as f64);
+}
+
+/// Format the label set of a series.
+fn labels(method: &str, route: &str, status: Option<u16>) -> String {
+    let mut out = format!("method=\"{}\",path=\"{}\"", escape(method), escape(route));
+    if let Some(status) = status {
+        let _ = write!(out, ",status=\"{}\"", status);
+    }
+    out
+}
+
+/// Escape a label value (backslash, double quote, newline).
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_counters_gauges_and_cumulative_buckets() {
+        let metrics = Metrics::new();
+        metrics.start("GET", "/users/:id");
+        metrics.start("GET", "/users/:id");
+        metrics.finish("GET", "/users/:id", 200, Duration::from_millis(3));
+        metrics.finish("GET", "/users/:id", 200, Duration::from_millis(300));
+
+        let text = metrics.render();
+        let series = "method=\"GET\",path=\"/users/:id\",status=\"200\"";
+        assert!(text.contains(&format!("http_requests_total{{{}}} 2\n", series)));
+        assert!(text.contains("http_requests_in_flight{method=\"GET\",path=\"/users/:id\"} 0\n"));
+        for (le, count) in [("0.005", 1), ("0.25", 1), ("0.5", 2), ("+Inf", 2)] {
+            assert!(
+                text.contains(&format!(
+                    "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}\n",
+                    series, le, count
+                )),
+                "missing bucket le={}:\n{}",
+                le,
+                text
+            );
+        }
+        assert!(text.contains(&format!(
+            "http_request_duration_seconds_count{{{}}} 2\n",
+            series
+        )));
+    }
+
+    #[test]
+    fn renders_body_size_histograms_per_route() {
+        let metrics = Metrics::new();
+        metrics.request_size("POST", "/users", 40);
+        metrics.request_size("POST", "/users", 4_000);
+        metrics.response_size("POST", "/users", 250);
+
+        let text = metrics.render();
+        let series = "method=\"POST\",path=\"/users\"";
+        for line in [
+            format!(
+                "http_request_size_bytes_bucket{{{},le=\"100\"}} 1\n",
+                series
+            ),
+            format!(
+                "http_request_size_bytes_bucket{{{},le=\"10000\"}} 2\n",
+                series
+            ),
+            format!("http_request_size_bytes_sum{{{}}} 4040\n", series),
+            format!("http_request_size_bytes_count{{{}}} 2\n", series),
+            format!(
+                "http_response_size_bytes_bucket{{{},le=\"100\"}} 0\n",
+                series
+            ),
+            format!(
+                "http_response_size_bytes_bucket{{{},le=\"1000\"}} 1\n",
+                series
+            ),
+            format!("http_response_size_bytes_count{{{}}} 1\n", series),
+        ] {
+            assert!(text.contains(&line), "missing {:?}:\n{}", line, text);
+        }
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(
+            labels("GET", "/a\"b\\c", Some(404)),
+            "method=\"GET\",path=\"/a\\\"b\\\\c\",status=\"404\""
+        );
+    }
+}
@@ -2,15 +2,32 @@
 //!
 //! Middleware can execute before and after handlers, modify context,
 //! and short-circuit request handling.
-
-use crate::{context::Context, error::Result, response::Response};
-use http_body_util::Full;
-use hyper::{body::Bytes, Response as HyperResponse};
+//!
+//! Calling [`Next`] runs the rest of the chain and the handler, and resolves
+//! to their `Result<Response>`. Code after `next(ctx).await` therefore sees
+//! the response the inner layers produced (status, headers, body) and may
+//! replace or mutate it, which is how logging, metrics, compression and ETag
+//! middleware work. A handler or inner middleware error arrives as `Err`,
+//! with its status available from
+//! [`UltimoError::status_code`](crate::error::UltimoError::status_code); it
+//! is rendered into a response only after the whole chain returns.
+
+use crate::{
+    context::Context,
+    error::Result,
+    response::{Body, Response},
+};
+use hyper::Response as HyperResponse;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
 /// Type alias for the next() function in middleware
+///
+/// `next(ctx)` consumes the context, so read anything needed afterwards
+/// (method, path, start time) before calling it. The future resolves once the
+/// handler and every later middleware have finished, with the response they
+/// produced.
 pub type Next<'a> = Box<
     dyn FnOnce(Context) -> Pin<Box<dyn Future<Output = Result<Response>> + Send + 'a>> + Send + 'a,
 >;
@@ -105,59 +122,262 @@ impl Default for MiddlewareChain {
 pub mod builtin {
     use super::*;
     use std::time::Instant;
-    use tracing::{error, info};
+    use tracing::error;
+
+    /// `tracing` fixes an event's level at compile time; dispatch on a runtime one.
+    macro_rules! event_at {
+        ($level:expr, $($args:tt)+) => {
+            match $level {
+                tracing::Level::ERROR => tracing::error!($($args)+),
+                tracing::Level::WARN => tracing::warn!($($args)+),
+                tracing::Level::INFO => tracing::info!($($args)+),
+                tracing::Level::DEBUG => tracing::debug!($($args)+),
+                _ => tracing::trace!($($args)+),
+            }
+        };
+    }
+
+    /// Request headers whose values the logger never writes out.
+    const REDACTED_HEADERS: [&str; 4] = [
+        "authorization",
+        "proxy-authorization",
+        "cookie",
+        "x-api-key",
+    ];
+
+    /// Logger middleware configuration.
+    #[derive(Debug, Clone)]
+    pub struct LoggerConfig {
+        /// Level of the per-request span and its log lines (default `INFO`).
+        /// Handler errors are always logged at `ERROR`.
+        pub level: tracing::Level,
+        /// Log request headers on the `-->` line (default false). Credential
+        /// headers (`Authorization`, `Cookie`, …) are redacted.
+        pub include_headers: bool,
+    }
+
+    impl Default for LoggerConfig {
+        fn default() -> Self {
+            Self {
+                level: tracing::Level::INFO,
+                include_headers: false,
+            }
+        }
+    }
+
+    impl LoggerConfig {
+        /// Set the level requests are logged at.
+        pub fn level(mut self, level: tracing::Level) -> Self {
+            self.level = level;
+            self
+        }
+        /// Log request headers (credentials redacted).
+        pub fn include_headers(mut self, include: bool) -> Self {
+            self.include_headers = include;
+            self
+        }
+    }
 
     /// Logger middleware that logs request/response details
+    ///
+    /// Each request runs inside a `request` span carrying `method`, `path`,
+    /// the matched `route` pattern and, when known, `request_id` (see
+    /// [`request_id()`]; a client's `X-Request-Id` counts only if that
+    /// middleware would reuse it), so handler logs are tagged with them too. The response line adds
+    /// `status` and `latency_ms` fields. Uses [`LoggerConfig::default`]; see
+    /// [`logger_with`] to change the level or log headers.
     pub fn logger() -> BoxedMiddleware {
-        Arc::new(|ctx, next| {
+        logger_with(LoggerConfig::default())
+    }
+
+    /// Logger middleware with custom configuration
+    ///
+    /// ```
+    /// # use ultimo::Ultimo;
+    /// use ultimo::middleware::builtin::{logger_with, LoggerConfig};
+    ///
+    /// let mut app = Ultimo::new_without_defaults();
+    /// app.use_middleware(logger_with(
+    ///     LoggerConfig::default()
+    ///         .level(tracing::Level::DEBUG)
+    ///         .include_headers(true),
+    /// ));
+    /// ```
+    pub fn logger_with(config: LoggerConfig) -> BoxedMiddleware {
+        use tracing::Instrument;
+
+        let LoggerConfig {
+            level,
+            include_headers,
+        } = config;
+        Arc::new(move |ctx, next| {
             Box::pin(async move {
                 let method = ctx.req.method().clone();
                 let path = ctx.req.path().to_string();
-                let start = Instant::now();
-
-                info!("--> {} {}", method, path);
+                let request_id = ctx.current_request_id().await;
+                let request_id = request_id.as_deref();
+                let route = ctx.matched_route();
+                let span = match level {
+                    tracing::Level::ERROR => {
+                        tracing::error_span!("request", %method, %path, route, request_id)
+                    }
+                    tracing::Level::WARN => {
+                        tracing::warn_span!("request", %method, %path, route, request_id)
+                    }
+                    tracing::Level::INFO => {
+                        tracing::info_span!("request", %method, %path, route, request_id)
+                    }
+                    tracing::Level::DEBUG => {
+                        tracing::debug_span!("request", %method, %path, route, request_id)
+                    }
+                    _ => tracing::trace_span!("request", %method, %path, route, request_id),
+                };
 
-                let result = next(ctx).await;
+                async move {
+                    let start = Instant::now();
 
-                let duration = start.elapsed();
-                match &result {
-                    Ok(response) => {
-                        info!(
-                            "<-- {} {} {} ({:?})",
-                            method,
-                            path,
-                            response.status().as_u16(),
-                            duration
-                        );
+                    if include_headers {
+                        let headers = loggable_headers(ctx.req.headers());
+                        event_at!(level, headers = %headers, "--> {} {}", method, path);
+                    } else {
+                        event_at!(level, "--> {} {}", method, path);
                     }
-                    Err(err) => {
-                        error!("<-- {} {} ERROR: {} ({:?})", method, path, err, duration);
+
+                    let result = next(ctx).await;
+
+                    let duration = start.elapsed();
+                    let latency_ms = duration.as_secs_f64() * 1000.0;
+                    match &result {
+                        Ok(response) => {
+                            let status = response.status().as_u16();
+                            event_at!(
+                                level,
+                                status,
+                                latency_ms,
+                                "<-- {} {} {} ({:?})",
+                                method,
+                                path,
+                                status,
+                                duration
+                            );
+                        }
+                        Err(err) => {
+                            error!(
+                                status = err.status_code(),
+                                latency_ms,
+                                "<-- {} {} ERROR: {} ({:?})",
+                                method,
+                                path,
+                                err,
+                                duration
+                            );
+                        }
                     }
+
+                    result
                 }
+                .instrument(span)
+                .await
+            })
+        })
+    }
 
-                result
+    /// Request-ID middleware
+    ///
+    /// Reuses the incoming `X-Request-Id` header, or generates a UUID v4 when
+    /// it is missing or unusable (empty, longer than 128 bytes, or not
+    /// printable ASCII). The id is stored in context state as `request_id`
+    /// and echoed in the `X-Request-Id` response header, including on error
+    /// responses rendered by the app.
+    ///
+    /// Install it before [`logger()`] so the logger's span and
+    /// [`Context::logger`] pick up generated ids:
+    ///
+    /// ```
+    /// # use ultimo::prelude::*;
+    /// use ultimo::middleware::builtin::{logger, request_id};
+    ///
+    /// let mut app = Ultimo::new_without_defaults();
+    /// app.use_middleware(request_id());
+    /// app.use_middleware(logger());
+    /// app.get("/", |ctx: Context| async move {
+    ///     let id = ctx.get("request_id").await.unwrap_or_default();
+    ///     ctx.text(id).await
+    /// });
+    /// ```
+    pub fn request_id() -> BoxedMiddleware {
+        Arc::new(|ctx, next| {
+            Box::pin(async move {
+                let id = ctx
+                    .request_id_header()
+                    .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+                ctx.set("request_id", id.clone()).await;
+                // Set on the context too, so error responses carry it.
+                ctx.set_header("X-Request-Id", id.clone()).await;
+
+                let mut response = next(ctx).await?;
+                if let Ok(value) = hyper::header::HeaderValue::from_str(&id) {
+                    response.headers_mut().insert("x-request-id", value);
+                }
+                Ok(response)
             })
         })
     }
 
+    /// Render request headers as `name: value, …` with credentials masked.
+    fn loggable_headers(headers: &hyper::HeaderMap) -> String {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+                    "[redacted]"
+                } else {
+                    value.to_str().unwrap_or("[non-utf8]")
+                };
+                format!("{}: {}", name, value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     /// CORS middleware with configurable options
+    ///
+    /// Origins are matched against an allowlist (`"*"` allows any). A matching
+    /// request `Origin` is echoed back in `Access-Control-Allow-Origin` with
+    /// `Vary: Origin`. With [`allow_credentials`](Self::allow_credentials)
+    /// only explicitly listed origins are echoed; `"*"` is rejected, since
+    /// reflecting any origin with credentials lets every site read
+    /// authenticated responses.
     pub struct Cors {
-        allow_origin: String,
+        allow_origins: Vec<String>,
         allow_methods: Vec<String>,
         allow_headers: Vec<String>,
+        expose_headers: Vec<String>,
+        allow_credentials: bool,
+        max_age: Option<u64>,
     }
 
     impl Cors {
         pub fn new() -> Self {
             Self {
-                allow_origin: "*".to_string(),
+                allow_origins: vec!["*".to_string()],
                 allow_methods: vec!["GET".to_string(), "POST".to_string()],
                 allow_headers: vec!["Content-Type".to_string()],
+                expose_headers: Vec::new(),
+                allow_credentials: false,
+                max_age: None,
             }
         }
 
+        /// Allow a single origin (or `"*"` for any), replacing the allowlist.
         pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
-            self.allow_origin = origin.into();
+            self.allow_origins = vec![origin.into()];
+            self
+        }
+
+        /// Allow any of `origins`, e.g. `https://app.example.com`.
+        pub fn allow_origins(mut self, origins: Vec<impl Into<String>>) -> Self {
+            self.allow_origins = origins.into_iter().map(|o| o.into()).collect();
             self
         }
 
@@ -166,44 +386,149 @@ pub mod builtin {
             self
         }
 
+        /// Request headers a preflight may ask for. `"*"` allows whatever the
+        /// preflight lists in `Access-Control-Request-Headers`.
         pub fn allow_headers(mut self, headers: Vec<impl Into<String>>) -> Self {
             self.allow_headers = headers.into_iter().map(|h| h.into()).collect();
             self
         }
 
+        /// Response headers the browser may expose to scripts
+        /// (`Access-Control-Expose-Headers`).
+        pub fn expose_headers(mut self, headers: Vec<impl Into<String>>) -> Self {
+            self.expose_headers = headers.into_iter().map(|h| h.into()).collect();
+            self
+        }
+
+        /// Send `Access-Control-Allow-Credentials: true` so browsers include
+        /// cookies and `Authorization`. The origins must then be listed
+        /// explicitly; [`build`](Self::build) panics if the allowlist
+        /// contains `"*"`.
+        pub fn allow_credentials(mut self, allow: bool) -> Self {
+            self.allow_credentials = allow;
+            self
+        }
+
+        /// How long, in seconds, browsers may cache a preflight result
+        /// (`Access-Control-Max-Age`).
+        pub fn max_age(mut self, secs: u64) -> Self {
+            self.max_age = Some(secs);
+            self
+        }
+
+        /// # Panics
+        ///
+        /// If credentials are allowed and the origin allowlist contains `"*"`.
         pub fn build(self) -> BoxedMiddleware {
-            let origin = self.allow_origin;
-            let methods = self.allow_methods.join(", ");
-            let headers = self.allow_headers.join(", ");
+            assert!(
+                !(self.allow_credentials && self.allow_origins.iter().any(|o| o == "*")),
+                "Cors: allow_credentials(true) cannot be combined with the \"*\" origin; \
+                 list the allowed origins with allow_origin/allow_origins"
+            );
+            let config = Arc::new(self);
 
             Arc::new(move |ctx, next| {
-                let origin = origin.clone();
-                let methods = methods.clone();
-                let headers = headers.clone();
+                let config = config.clone();
 
                 Box::pin(async move {
+                    let origin = ctx.req.header("origin");
+                    let allowed_origin = config.allowed_origin(origin.as_deref());
+                    // Echoed origins make the response depend on the request.
+                    let varies = allowed_origin.is_some() && allowed_origin.as_deref() != Some("*");
+
                     // Handle preflight OPTIONS requests
                     if ctx.req.method() == "OPTIONS" {
-                        let response = HyperResponse::builder()
-                            .status(204)
-                            .header("Access-Control-Allow-Origin", origin)
-                            .header("Access-Control-Allow-Methods", methods)
-                            .header("Access-Control-Allow-Headers", headers)
-                            .body(Full::new(Bytes::new()))
-                            .unwrap();
-                        return Ok(response);
+                        let mut response = HyperResponse::builder().status(204);
+                        if let Some(origin) = &allowed_origin {
+                            response = response
+                                .header("Access-Control-Allow-Origin", origin)
+                                .header(
+                                    "Access-Control-Allow-Methods",
+                                    config.allow_methods.join(", "),
+                                )
+                                .header(
+                                    "Access-Control-Allow-Headers",
+                                    config.preflight_headers(
+                                        ctx.req.header("access-control-request-headers").as_deref(),
+                                    ),
+                                );
+                            if config.allow_credentials {
+                                response =
+                                    response.header("Access-Control-Allow-Credentials", "true");
+                            }
+                            if let Some(max_age) = config.max_age {
+                                response =
+                                    response.header("Access-Control-Max-Age", max_age.to_string());
+                            }
+                        }
+                        if varies {
+                            response = response.header("Vary", "Origin");
+                        }
+                        if config.allow_headers.iter().any(|h| h == "*") {
+                            response = response.header("Vary", "Access-Control-Request-Headers");
+                        }
+                        return Ok(response.body(Body::empty()).unwrap());
                     }
 
                     // Set CORS headers on context before calling next
-                    ctx.header("Access-Control-Allow-Origin", origin).await;
-                    ctx.header("Access-Control-Allow-Methods", methods).await;
-                    ctx.header("Access-Control-Allow-Headers", headers).await;
+                    if let Some(origin) = allowed_origin {
+                        ctx.set_header("Access-Control-Allow-Origin", origin).await;
+                        ctx.set_header(
+                            "Access-Control-Allow-Methods",
+                            config.allow_methods.join(", "),
+                        )
+                        .await;
+                        ctx.set_header(
+                            "Access-Control-Allow-Headers",
+                            config.allow_headers.join(", "),
+                        )
+                        .await;
+                        if config.allow_credentials {
+                            ctx.set_header("Access-Control-Allow-Credentials", "true")
+                                .await;
+                        }
+                        if !config.expose_headers.is_empty() {
+                            ctx.set_header(
+                                "Access-Control-Expose-Headers",
+                                config.expose_headers.join(", "),
+                            )
+                            .await;
+                        }
+                    }
+                    if varies {
+                        ctx.header("Vary", "Origin").await;
+                    }
 
                     // Call next with the modified context
                     next(ctx).await
                 })
             })
         }
+
+        /// The `Access-Control-Allow-Origin` value for a request from
+        /// `origin`, or `None` when the origin is not allowed.
+        fn allowed_origin(&self, origin: Option<&str>) -> Option<String> {
+            // `build` rules out `*` together with credentials.
+            let any = self.allow_origins.iter().any(|o| o == "*");
+            match origin {
+                _ if any => Some("*".to_string()),
+                Some(origin) if self.allow_origins.iter().any(|o| o == origin) => {
+                    Some(origin.to_string())
+                }
+                _ => None,
+            }
+        }
+
+        /// The `Access-Control-Allow-Headers` value for a preflight: the
+        /// configured list, or the requested headers when any are allowed.
+        fn preflight_headers(&self, requested: Option<&str>) -> String {
+            match requested.filter(|r| !r.trim().is_empty()) {
+                Some(requested) if self.allow_headers.iter().any(|h| h == "*") => {
+                    requested.to_string()
+                }
+                _ => self.allow_headers.join(", "),
+            }
+        }
     }
 
     impl Default for Cors {
@@ -217,6 +542,400 @@ pub mod builtin {
         Cors::new().build()
     }
 
+    /// Conditional-GET middleware: weak `ETag`s and `If-None-Match`.
+    ///
+    /// For `GET` and `HEAD` requests answered with `200 OK`, hashes the
+    /// buffered response body into a weak `ETag` (`W/"…"`) unless the handler
+    /// set one. When the request's `If-None-Match` lists that tag (or `*`),
+    /// the response becomes `304 Not Modified` with an empty body, keeping
+    /// headers such as `ETag`, `Cache-Control` and `Vary`. Other methods,
+    /// other statuses and streamed bodies pass through untouched.
+    ///
+    /// ```
+    /// # use ultimo::prelude::*;
+    /// let mut app = Ultimo::new_without_defaults();
+    /// app.use_middleware(ultimo::middleware::builtin::etag());
+    /// app.get("/users", |ctx: Context| async move {
+    ///     ctx.json(json!([{ "id": 1 }])).await
+    /// });
+    /// ```
+    pub fn etag() -> BoxedMiddleware {
+        use hyper::header::{ETAG, IF_NONE_MATCH};
+
+        Arc::new(|ctx, next| {
+            Box::pin(async move {
+                let method = ctx.req.method().clone();
+                if method != hyper::Method::GET && method != hyper::Method::HEAD {
+                    return next(ctx).await;
+                }
+                let if_none_match = ctx.req.header(IF_NONE_MATCH.as_str());
+
+                let response = next(ctx).await?;
+                if response.status() != hyper::StatusCode::OK {
+                    return Ok(response);
+                }
+
+                let (mut parts, body) = response.into_parts();
+                let body = match parts.headers.get(ETAG) {
+                    Some(_) => body,
+                    None => {
+                        let bytes = match body.try_into_bytes() {
+                            Ok(bytes) => bytes,
+                            Err(stream) => return Ok(HyperResponse::from_parts(parts, stream)),
+                        };
+                        if let Ok(tag) = hyper::header::HeaderValue::from_str(&weak_etag(&bytes)) {
+                            parts.headers.insert(ETAG, tag);
+                        }
+                        Body::from(bytes)
+                    }
+                };
+
+                let tag = parts.headers.get(ETAG).and_then(|v| v.to_str().ok());
+                if let (Some(tag), Some(if_none_match)) = (tag, &if_none_match) {
+                    if etag_matches(if_none_match, tag) {
+                        parts.status = hyper::StatusCode::NOT_MODIFIED;
+                        return Ok(HyperResponse::from_parts(parts, Body::empty()));
+                    }
+                }
+                Ok(HyperResponse::from_parts(parts, body))
+            })
+        })
+    }
+
+    /// A weak entity tag derived from the body's length and FNV-1a hash.
+    ///
+    /// The hash is fixed rather than `DefaultHasher`, whose output may change
+    /// between Rust releases, so tags stay valid across rebuilds and between
+    /// instances behind a load balancer.
+    fn weak_etag(body: &[u8]) -> String {
+        let hash = body.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+        format!("W/\"{:x}-{:016x}\"", body.len(), hash)
+    }
+
+    /// Whether an `If-None-Match` header value matches `tag`, using the weak
+    /// comparison (RFC 9110 §13.1.2): `W/` prefixes are ignored.
+    fn etag_matches(if_none_match: &str, tag: &str) -> bool {
+        let opaque = |t: &str| t.trim().trim_start_matches("W/").to_string();
+        let tag = opaque(tag);
+        if_none_match
+            .split(',')
+            .any(|candidate| candidate.trim() == "*" || opaque(candidate) == tag)
+    }
+
+    /// Request metrics middleware
+    ///
+    /// Counts requests, tracks how many are in flight and records a latency
+    /// histogram, labeled by method, route pattern (`/users/:id`) and status.
+    /// Request and response body sizes go into histograms labeled by method
+    /// and route pattern: requests by their `Content-Length` (none counts as
+    /// empty), responses by their buffered length or `Content-Length`. Bodies
+    /// of unknown length (chunked uploads and streams) and error responses
+    /// rendered by the app are not sized.
+    /// Pair it with [`Ultimo::serve_metrics`](crate::Ultimo::serve_metrics),
+    /// which creates the registry and exposes it in the Prometheus text
+    /// format; without that call the middleware does nothing.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    /// use ultimo::middleware::builtin::metrics;
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.use_middleware(metrics());
+    /// app.serve_metrics("/metrics");
+    /// ```
+    pub fn metrics() -> BoxedMiddleware {
+        use crate::metrics::{Metrics, UNMATCHED};
+
+        /// Decrements the in-flight gauge even if the request is cancelled.
+        struct InFlight {
+            metrics: Arc<Metrics>,
+            method: String,
+            route: String,
+            start: Instant,
+            status: u16,
+        }
+
+        impl Drop for InFlight {
+            fn drop(&mut self) {
+                self.metrics
+                    .finish(&self.method, &self.route, self.status, self.start.elapsed());
+            }
+        }
+
+        Arc::new(|ctx, next| {
+            Box::pin(async move {
+                let Ok(metrics) = ctx.state::<Metrics>() else {
+                    return next(ctx).await;
+                };
+                let method = ctx.req.method().to_string();
+                let route = ctx.matched_route().unwrap_or(UNMATCHED).to_string();
+                metrics.start(&method, &route);
+                let request_size = match ctx.req.header("content-length") {
+                    Some(length) => length.trim().parse().ok(),
+                    None if ctx.req.header("transfer-encoding").is_none() => Some(0),
+                    None => None,
+                };
+                if let Some(bytes) = request_size {
+                    metrics.request_size(&method, &route, bytes);
+                }
+                let mut guard = InFlight {
+                    metrics,
+                    method,
+                    route,
+                    start: Instant::now(),
+                    // Reported if the request is dropped before it completes.
+                    status: 499,
+                };
+
+                let result = next(ctx).await;
+                guard.status = match &result {
+                    Ok(response) => response.status().as_u16(),
+                    Err(err) => err.status_code(),
+                };
+                if let Ok(response) = &result {
+                    let length = hyper::body::Body::size_hint(response.body())
+                        .exact()
+                        .or_else(|| {
+                            response
+                                .headers()
+                                .get(hyper::header::CONTENT_LENGTH)?
+                                .to_str()
+                                .ok()?
+                                .parse()
+                                .ok()
+                        });
+                    if let Some(bytes) = length {
+                        guard
+                            .metrics
+                            .response_size(&guard.method, &guard.route, bytes);
+                    }
+                }
+                result
+            })
+        })
+    }
+
+    type AuthFuture<I> = Pin<Box<dyn Future<Output = Result<I>> + Send>>;
+    type BasicVerifier<I> = Arc<dyn Fn(String, String) -> AuthFuture<I> + Send + Sync>;
+    type BearerVerifier<I> = Arc<dyn Fn(String) -> AuthFuture<I> + Send + Sync>;
+
+    /// HTTP Basic authentication middleware
+    ///
+    /// Decodes `Authorization: Basic <base64(user:pass)>` and passes the
+    /// credentials to the verifier. On `Ok(identity)` the identity is stored on
+    /// the context and read in handlers with [`Context::identity`]. A missing or
+    /// malformed header, or a `401` verifier error such as
+    /// [`UltimoError::Unauthorized`](crate::UltimoError::Unauthorized), yields
+    /// `401 Unauthorized` with a `WWW-Authenticate: Basic` challenge. Other
+    /// verifier errors (a failed user lookup, say) are returned as they are.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    /// use ultimo::middleware::builtin::BasicAuth;
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.use_middleware(
+    ///     BasicAuth::new(|user, pass| async move {
+    ///         if user == "admin" && pass == "secret" {
+    ///             Ok(user)
+    ///         } else {
+    ///             Err(UltimoError::Unauthorized("bad credentials".into()))
+    ///         }
+    ///     })
+    ///     .realm("admin")
+    ///     .build(),
+    /// );
+    /// app.get("/", |ctx: Context| async move {
+    ///     let user: String = ctx.identity().await.unwrap_or_default();
+    ///     ctx.text(format!("hello {user}")).await
+    /// });
+    /// ```
+    pub struct BasicAuth<I> {
+        verify: BasicVerifier<I>,
+        realm: String,
+    }
+
+    impl<I: Send + Sync + 'static> BasicAuth<I> {
+        /// Verify credentials with `verify(username, password)`.
+        pub fn new<F, Fut>(verify: F) -> Self
+        where
+            F: Fn(String, String) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<I>> + Send + 'static,
+        {
+            Self {
+                verify: Arc::new(move |user, pass| Box::pin(verify(user, pass))),
+                realm: "Restricted".to_string(),
+            }
+        }
+
+        /// Set the realm advertised in the `WWW-Authenticate` challenge.
+        pub fn realm(mut self, realm: impl Into<String>) -> Self {
+            self.realm = realm.into();
+            self
+        }
+
+        /// Build the middleware.
+        pub fn build(self) -> BoxedMiddleware {
+            let verify = self.verify;
+            let challenge = format!("Basic realm=\"{}\", charset=\"UTF-8\"", self.realm);
+
+            Arc::new(move |ctx, next| {
+                let verify = verify.clone();
+                let challenge = challenge.clone();
+
+                Box::pin(async move {
+                    let credentials = ctx
+                        .req
+                        .header("authorization")
+                        .and_then(|value| parse_basic(&value));
+                    let Some((user, pass)) = credentials else {
+                        return Ok(auth_challenge(&challenge));
+                    };
+                    match verify(user, pass).await {
+                        Ok(identity) => {
+                            ctx.set_identity(identity).await;
+                            next(ctx).await
+                        }
+                        Err(err) if err.status_code() == 401 => Ok(auth_challenge(&challenge)),
+                        Err(err) => Err(err),
+                    }
+                })
+            })
+        }
+    }
+
+    /// Convenience function to create Basic auth middleware with the default realm
+    pub fn basic_auth<F, Fut, I>(verify: F) -> BoxedMiddleware
+    where
+        F: Fn(String, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<I>> + Send + 'static,
+        I: Send + Sync + 'static,
+    {
+        BasicAuth::new(verify).build()
+    }
+
+    /// Bearer token authentication middleware
+    ///
+    /// Extracts the token from `Authorization: Bearer <token>` and passes it to
+    /// the verifier. On `Ok(identity)` the identity is stored on the context and
+    /// read in handlers with [`Context::identity`]. A missing token, or a `401`
+    /// verifier error such as
+    /// [`UltimoError::Unauthorized`](crate::UltimoError::Unauthorized), gets
+    /// `401 Unauthorized` with a `WWW-Authenticate: Bearer` challenge
+    /// (`error="invalid_token"` when a token was presented but rejected).
+    /// Other verifier errors are returned as they are.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use ultimo::prelude::*;
+    /// use ultimo::middleware::builtin::bearer_auth;
+    ///
+    /// let mut app = Ultimo::new();
+    /// app.use_middleware(bearer_auth(|token| async move {
+    ///     match token.as_str() {
+    ///         "token-123" => Ok(42u64),
+    ///         _ => Err(UltimoError::Unauthorized("unknown token".into())),
+    ///     }
+    /// }));
+    /// ```
+    pub struct BearerAuth<I> {
+        verify: BearerVerifier<I>,
+        realm: String,
+    }
+
+    impl<I: Send + Sync + 'static> BearerAuth<I> {
+        /// Verify tokens with `verify(token)`.
+        pub fn new<F, Fut>(verify: F) -> Self
+        where
+            F: Fn(String) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<I>> + Send + 'static,
+        {
+            Self {
+                verify: Arc::new(move |token| Box::pin(verify(token))),
+                realm: "api".to_string(),
+            }
+        }
+
+        /// Set the realm advertised in the `WWW-Authenticate` challenge.
+        pub fn realm(mut self, realm: impl Into<String>) -> Self {
+            self.realm = realm.into();
+            self
+        }
+
+        /// Build the middleware.
+        pub fn build(self) -> BoxedMiddleware {
+            let verify = self.verify;
+            let challenge = format!("Bearer realm=\"{}\"", self.realm);
+            let invalid = format!("{}, error=\"invalid_token\"", challenge);
+
+            Arc::new(move |ctx, next| {
+                let verify = verify.clone();
+                let challenge = challenge.clone();
+                let invalid = invalid.clone();
+
+                Box::pin(async move {
+                    let Some(token) = ctx.req.bearer_token() else {
+                        return Ok(auth_challenge(&challenge));
+                    };
+                    match verify(token).await {
+                        Ok(identity) => {
+                            ctx.set_identity(identity).await;
+                            next(ctx).await
+                        }
+                        Err(err) if err.status_code() == 401 => Ok(auth_challenge(&invalid)),
+                        Err(err) => Err(err),
+                    }
+                })
+            })
+        }
+    }
+
+    /// Convenience function to create bearer auth middleware with the default realm
+    pub fn bearer_auth<F, Fut, I>(verify: F) -> BoxedMiddleware
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<I>> + Send + 'static,
+        I: Send + Sync + 'static,
+    {
+        BearerAuth::new(verify).build()
+    }
+
+    /// Split `Authorization: <scheme> <credentials>`, matching the scheme
+    /// case-insensitively.
+    fn auth_credentials<'a>(value: &'a str, scheme: &str) -> Option<&'a str> {
+        let (given, rest) = value.trim().split_once(' ')?;
+        given
+            .eq_ignore_ascii_case(scheme)
+            .then(|| rest.trim())
+            .filter(|rest| !rest.is_empty())
+    }
+
+    /// Decode Basic credentials into `(username, password)`.
+    fn parse_basic(value: &str) -> Option<(String, String)> {
+        use base64::Engine;
+        let encoded = auth_credentials(value, "Basic")?;
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (user, pass) = decoded.split_once(':')?;
+        Some((user.to_string(), pass.to_string()))
+    }
+
+    /// 401 response carrying a `WWW-Authenticate` challenge.
+    fn auth_challenge(challenge: &str) -> Response {
+        HyperResponse::builder()
+            .status(401)
+            .header("WWW-Authenticate", challenge)
+            .header("Content-Type", "text/plain")
+            .body(Body::from("Unauthorized"))
+            .unwrap()
+    }
+
     /// Powered-by header middleware that adds framework identification
     ///
     /// Adds `X-Powered-By: Ultimo` header to all responses.
@@ -236,7 +955,7 @@ pub mod builtin {
     pub fn powered_by() -> BoxedMiddleware {
         Arc::new(|ctx, next| {
             Box::pin(async move {
-                ctx.header("X-Powered-By", "Ultimo").await;
+                ctx.set_header("X-Powered-By", "Ultimo").await;
                 next(ctx).await
             })
         })
@@ -272,7 +991,7 @@ pub mod builtin {
         Arc::new(move |ctx, next| {
             let powered_by = version.clone();
             Box::pin(async move {
-                ctx.header("X-Powered-By", powered_by).await;
+                ctx.set_header("X-Powered-By", powered_by).await;
                 next(ctx).await
             })
         })
@@ -569,7 +1288,7 @@ pub mod builtin {
                     } else {
                         Ok(HyperResponse::builder()
                             .status(403)
-                            .body(Full::new(Bytes::from("Forbidden")))
+                            .body(Body::from("Forbidden"))
                             .unwrap())
                     }
                 })
@@ -577,6 +1296,138 @@ pub mod builtin {
         }
     }
 
+    // -------------------------------------------------------------------------
+    // Path normalization
+    // -------------------------------------------------------------------------
+
+    /// How [`NormalizePath`] handles a non-canonical request path.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum NormalizePathMode {
+        /// Rewrite the path in place so handlers see the canonical form (default).
+        Rewrite,
+        /// Answer with `308 Permanent Redirect` to the canonical path.
+        Redirect,
+    }
+
+    /// Path normalization middleware builder.
+    ///
+    /// Collapses repeated slashes (`/users//123`, `//api/users`) and optionally
+    /// strips a trailing slash. The router already tolerates such paths when
+    /// matching, so this controls what handlers, logs and clients see: either
+    /// the path is rewritten before the handler runs, or the client is
+    /// redirected to the canonical URL (query string preserved).
+    ///
+    /// Like all middleware it runs after routing, so it only sees requests
+    /// that matched a route and cannot change which route that is. Repeated
+    /// slashes never affect matching; trailing slashes follow
+    /// [`Ultimo::trailing_slash`](crate::Ultimo::trailing_slash), so under
+    /// [`TrailingSlash::Strict`](crate::router::TrailingSlash::Strict) a
+    /// request for the other form is a `404` before this middleware runs.
+    ///
+    /// ```
+    /// # use ultimo::Ultimo;
+    /// use ultimo::middleware::builtin::{normalize_path, NormalizePath, NormalizePathMode};
+    ///
+    /// let mut app = Ultimo::new_without_defaults();
+    /// app.use_middleware(normalize_path());
+    /// // or redirect, also removing trailing slashes:
+    /// app.use_middleware(
+    ///     NormalizePath::new()
+    ///         .mode(NormalizePathMode::Redirect)
+    ///         .trim_trailing_slash(true)
+    ///         .build(),
+    /// );
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct NormalizePath {
+        mode: NormalizePathMode,
+        trim_trailing_slash: bool,
+    }
+
+    impl Default for NormalizePath {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl NormalizePath {
+        /// Rewrite mode, trailing slashes kept.
+        pub fn new() -> Self {
+            Self {
+                mode: NormalizePathMode::Rewrite,
+                trim_trailing_slash: false,
+            }
+        }
+
+        /// Choose between rewriting the path and redirecting to it.
+        pub fn mode(mut self, mode: NormalizePathMode) -> Self {
+            self.mode = mode;
+            self
+        }
+
+        /// Also remove a trailing slash (`/users/` → `/users`). The root path
+        /// `/` is left alone.
+        pub fn trim_trailing_slash(mut self, trim: bool) -> Self {
+            self.trim_trailing_slash = trim;
+            self
+        }
+
+        /// Build the middleware.
+        pub fn build(self) -> BoxedMiddleware {
+            let mode = self.mode;
+            let trim = self.trim_trailing_slash;
+            Arc::new(move |mut ctx, next| {
+                Box::pin(async move {
+                    let canonical = canonical_path(ctx.req.path(), trim);
+                    if canonical == ctx.req.path() {
+                        return next(ctx).await;
+                    }
+                    match mode {
+                        NormalizePathMode::Rewrite => {
+                            ctx.req.set_path(&canonical);
+                            next(ctx).await
+                        }
+                        NormalizePathMode::Redirect => {
+                            let location = match ctx.req.uri().query() {
+                                Some(query) => format!("{}?{}", canonical, query),
+                                None => canonical,
+                            };
+                            Ok(HyperResponse::builder()
+                                .status(308)
+                                .header("Location", location)
+                                .body(Body::empty())
+                                .unwrap())
+                        }
+                    }
+                })
+            })
+        }
+    }
+
+    /// Collapse repeated slashes and optionally drop a trailing one.
+    pub(crate) fn canonical_path(path: &str, trim_trailing_slash: bool) -> String {
+        let mut canonical = String::with_capacity(path.len() + 1);
+        for c in path.chars() {
+            if c == '/' && canonical.ends_with('/') {
+                continue;
+            }
+            canonical.push(c);
+        }
+        if !canonical.starts_with('/') {
+            canonical.insert(0, '/');
+        }
+        if trim_trailing_slash && canonical.len() > 1 && canonical.ends_with('/') {
+            canonical.pop();
+        }
+        canonical
+    }
+
+    /// Path normalization middleware with default settings (rewrite, collapse
+    /// repeated slashes only).
+    pub fn normalize_path() -> BoxedMiddleware {
+        NormalizePath::new().build()
+    }
+
     // -------------------------------------------------------------------------
     // Rate limiting
     // -------------------------------------------------------------------------
@@ -590,8 +1441,15 @@ pub mod builtin {
         last_refill: std::time::Instant,
     }
 
+    /// Computes a rate-limit key from the request; see [`RateLimiter::key_by`].
+    pub type RateLimitKeyFn = Arc<dyn Fn(&Context) -> Option<String> + Send + Sync>;
+
     /// Key extraction strategy for rate limiting.
+    ///
+    /// Non-exhaustive, so further strategies can be added without breaking
+    /// matches on it.
     #[derive(Clone)]
+    #[non_exhaustive]
     pub enum RateLimitKey {
         /// Rate limit by client IP (default).
         Ip,
@@ -599,12 +1457,21 @@ pub mod builtin {
         Header(String),
         /// Rate limit globally (all requests share one bucket).
         Global,
+        /// Rate limit by a custom key; `None` falls back to the client IP.
+        Custom(RateLimitKeyFn),
     }
 
     /// Per-route or global rate limiting middleware (token bucket algorithm).
     ///
     /// Limits the number of requests per time window. Returns `429 Too Many
-    /// Requests` with a `Retry-After` header when the limit is exceeded.
+    /// Requests` with a `Retry-After` header (seconds until a request is
+    /// allowed again) when the limit is exceeded. Every response carries
+    /// `X-RateLimit-Limit` and `X-RateLimit-Remaining`, including the `429`
+    /// and error responses rendered from a handler's `Err`.
+    ///
+    /// Bucket state lives behind an `Arc<Mutex<..>>` created by
+    /// [`build`](Self::build), so the built middleware is cheap to clone and
+    /// shared across connections.
     ///
     /// ```
     /// # use ultimo::Ultimo;
@@ -619,36 +1486,87 @@ pub mod builtin {
     ///         .key(ultimo::middleware::builtin::RateLimitKey::Header("X-API-Key".into()))
     ///         .build()
     /// );
+    /// // Builder style with a custom key:
+    /// app.use_middleware(
+    ///     ultimo::middleware::builtin::RateLimiter::default()
+    ///         .max_requests(100)
+    ///         .per(std::time::Duration::from_secs(60))
+    ///         .key_by(|ctx| ctx.req.header("x-api-key"))
+    ///         .build()
+    /// );
     /// ```
     #[derive(Clone)]
     pub struct RateLimiter {
         max_requests: u64,
-        window_secs: u64,
+        window: std::time::Duration,
         key: RateLimitKey,
     }
 
+    impl Default for RateLimiter {
+        /// 100 requests per 60 seconds, keyed by client IP.
+        fn default() -> Self {
+            Self::new(100, 60)
+        }
+    }
+
     impl RateLimiter {
         /// Create a rate limiter: `max_requests` per `window_secs` seconds.
         /// Default key is client IP.
         pub fn new(max_requests: u64, window_secs: u64) -> Self {
             Self {
                 max_requests,
-                window_secs,
+                window: std::time::Duration::from_secs(window_secs),
                 key: RateLimitKey::Ip,
             }
         }
 
+        /// Set the number of requests allowed per window. Must be at least 1;
+        /// [`build`](Self::build) panics otherwise.
+        pub fn max_requests(mut self, max_requests: u64) -> Self {
+            self.max_requests = max_requests;
+            self
+        }
+
+        /// Set the window length. Must be non-zero; [`build`](Self::build)
+        /// panics otherwise.
+        pub fn per(mut self, window: std::time::Duration) -> Self {
+            self.window = window;
+            self
+        }
+
         /// Set the key extraction strategy.
         pub fn key(mut self, key: RateLimitKey) -> Self {
             self.key = key;
             self
         }
 
+        /// Key buckets by a value computed from the request, e.g. an API key
+        /// header. Requests for which `f` returns `None` are keyed by client IP.
+        pub fn key_by<F>(self, f: F) -> Self
+        where
+            F: Fn(&Context) -> Option<String> + Send + Sync + 'static,
+        {
+            self.key(RateLimitKey::Custom(Arc::new(f)))
+        }
+
         /// Build the middleware.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `max_requests` is 0 or the window is zero: the first
+        /// would reject every request and the second has no refill rate.
         pub fn build(self) -> BoxedMiddleware {
-            let rate = self.max_requests as f64 / self.window_secs as f64;
+            assert!(
+                self.max_requests > 0,
+                "RateLimiter: max_requests must be at least 1"
+            );
+            assert!(
+                !self.window.is_zero(),
+                "RateLimiter: the window set with per()/new() must be non-zero"
+            );
+            let rate = self.max_requests as f64 / self.window.as_secs_f64();
             let max_tokens = self.max_requests as f64;
-            let window_secs = self.window_secs;
+            let limit = self.max_requests;
             let key_strategy = self.key;
 
             let buckets: Arc<Mutex<HashMap<String, RateBucket>>> =
@@ -657,22 +1575,22 @@ pub mod builtin {
             Arc::new(move |ctx, next| {
                 let buckets = buckets.clone();
                 let key_strategy = key_strategy.clone();
-                let rate = rate;
-                let max_tokens = max_tokens;
-                let window_secs = window_secs;
 
                 Box::pin(async move {
+                    let client_ip = || {
+                        ctx.client_ip()
+                            .map(|ip| ip.to_string())
+                            .unwrap_or_else(|| "unknown".to_string())
+                    };
                     // Extract key
                     let key = match &key_strategy {
-                        RateLimitKey::Ip => ctx
-                            .client_ip()
-                            .map(|ip| ip.to_string())
-                            .unwrap_or_else(|| "unknown".to_string()),
+                        RateLimitKey::Ip => client_ip(),
                         RateLimitKey::Header(name) => ctx
                             .req
                             .header(name)
                             .unwrap_or_else(|| "anonymous".to_string()),
                         RateLimitKey::Global => "__global__".to_string(),
+                        RateLimitKey::Custom(f) => f(&ctx).unwrap_or_else(client_ip),
                     };
 
                     // Check/update bucket
@@ -689,25 +1607,39 @@ pub mod builtin {
                         bucket.tokens = (bucket.tokens + elapsed * rate).min(max_tokens);
                         bucket.last_refill = now;
 
-                        // Try to consume a token
+                        // Try to consume a token; on refusal, report how long
+                        // until the next one refills.
                         if bucket.tokens >= 1.0 {
                             bucket.tokens -= 1.0;
-                            true
+                            Ok(bucket.tokens.floor() as u64)
                         } else {
-                            false
+                            Err(((1.0 - bucket.tokens) / rate).ceil().max(1.0) as u64)
                         }
                     };
 
-                    if allowed {
-                        next(ctx).await
-                    } else {
-                        Ok(HyperResponse::builder()
-                            .status(429)
-                            .header("Retry-After", window_secs.to_string())
-                            .header("Content-Type", "text/plain")
-                            .body(Full::new(Bytes::from("Too Many Requests")))
-                            .unwrap())
-                    }
+                    let (mut response, remaining) = match allowed {
+                        Ok(remaining) => {
+                            // Also recorded on the context, so an `Err` from
+                            // further down is rendered with them.
+                            ctx.set_header("X-RateLimit-Limit", limit.to_string()).await;
+                            ctx.set_header("X-RateLimit-Remaining", remaining.to_string())
+                                .await;
+                            (next(ctx).await?, remaining)
+                        }
+                        Err(retry_after) => (
+                            HyperResponse::builder()
+                                .status(429)
+                                .header("Retry-After", retry_after.to_string())
+                                .header("Content-Type", "text/plain")
+                                .body(Body::from("Too Many Requests"))
+                                .unwrap(),
+                            0,
+                        ),
+                    };
+                    let headers = response.headers_mut();
+                    headers.insert("x-ratelimit-limit", limit.into());
+                    headers.insert("x-ratelimit-remaining", remaining.into());
+                    Ok(response)
                 })
             })
         }
@@ -797,7 +1729,6 @@ pub mod builtin {
         pub fn build(self) -> BoxedMiddleware {
             use brotli::CompressorWriter;
             use flate2::{write::GzEncoder, Compression as GzLevel};
-            use http_body_util::BodyExt;
             use hyper::header::{CONTENT_ENCODING, CONTENT_LENGTH, VARY};
             use std::io::Write;
 
@@ -816,11 +1747,20 @@ pub mod builtin {
 
                     let mut res = next(ctx).await?;
 
-                    // Always set Vary (RFC 7231 §7.1.4).
-                    res.headers_mut().insert(
-                        VARY,
-                        hyper::header::HeaderValue::from_static("Accept-Encoding"),
-                    );
+                    // Always set Vary (RFC 7231 §7.1.4), keeping any values the
+                    // handler already added (e.g. `Vary: Origin` from CORS).
+                    let already_varies = res.headers().get_all(VARY).iter().any(|v| {
+                        v.to_str().is_ok_and(|v| {
+                            v.split(',')
+                                .any(|t| t.trim().eq_ignore_ascii_case("accept-encoding"))
+                        })
+                    });
+                    if !already_varies {
+                        res.headers_mut().append(
+                            VARY,
+                            hyper::header::HeaderValue::from_static("Accept-Encoding"),
+                        );
+                    }
 
                     // Skip if already encoded.
                     if res.headers().contains_key(CONTENT_ENCODING) {
@@ -828,13 +1768,17 @@ pub mod builtin {
                     }
 
                     // Decompose response so we can inspect and replace the body.
+                    // Streamed bodies pass through untouched rather than being
+                    // buffered into memory.
                     let (parts, body) = res.into_parts();
-                    // Full<Bytes> is infallible — unwrap is safe.
-                    let body_bytes = body.collect().await.unwrap().to_bytes();
+                    let body_bytes = match body.try_into_bytes() {
+                        Ok(bytes) => bytes,
+                        Err(stream) => return Ok(hyper::Response::from_parts(parts, stream)),
+                    };
 
                     // Skip below min_size.
                     if body_bytes.len() < min_size {
-                        return Ok(hyper::Response::from_parts(parts, Full::new(body_bytes)));
+                        return Ok(hyper::Response::from_parts(parts, Body::from(body_bytes)));
                     }
 
                     // Skip binary content types.
@@ -856,7 +1800,7 @@ pub mod builtin {
                         || SKIP_EXACT.iter().any(|e| ct.starts_with(e));
 
                     if skip {
-                        return Ok(hyper::Response::from_parts(parts, Full::new(body_bytes)));
+                        return Ok(hyper::Response::from_parts(parts, Body::from(body_bytes)));
                     }
 
                     // Choose algorithm: prefer brotli > gzip > identity.
@@ -873,8 +1817,7 @@ pub mod builtin {
                             writer.write_all(&body_bytes).unwrap();
                         }
                         let len = compressed.len();
-                        let mut res =
-                            hyper::Response::from_parts(parts, Full::new(Bytes::from(compressed)));
+                        let mut res = hyper::Response::from_parts(parts, Body::from(compressed));
                         res.headers_mut().insert(
                             CONTENT_ENCODING,
                             hyper::header::HeaderValue::from_static("br"),
@@ -892,8 +1835,7 @@ pub mod builtin {
                             encoder.finish().unwrap();
                         }
                         let len = compressed.len();
-                        let mut res =
-                            hyper::Response::from_parts(parts, Full::new(Bytes::from(compressed)));
+                        let mut res = hyper::Response::from_parts(parts, Body::from(compressed));
                         res.headers_mut().insert(
                             CONTENT_ENCODING,
                             hyper::header::HeaderValue::from_static("gzip"),
@@ -905,7 +1847,7 @@ pub mod builtin {
                         Ok(res)
                     } else {
                         // No matching encoding — pass through unmodified.
-                        Ok(hyper::Response::from_parts(parts, Full::new(body_bytes)))
+                        Ok(hyper::Response::from_parts(parts, Body::from(body_bytes)))
                     }
                 })
             })
@@ -1008,6 +1950,62 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_middleware_observes_and_mutates_inner_response() {
+        use crate::error::UltimoError;
+        use hyper::header::HeaderValue;
+        use std::sync::Mutex;
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        let observe: BoxedMiddleware = Arc::new(move |ctx, next| {
+            let seen = recorded.clone();
+            Box::pin(async move {
+                let result = next(ctx).await;
+                let status = match &result {
+                    Ok(res) => res.status().as_u16(),
+                    Err(err) => err.status_code(),
+                };
+                seen.lock().unwrap().push(status);
+                let mut res = result?;
+                res.headers_mut()
+                    .insert("x-observed", HeaderValue::from_static("yes"));
+                Ok(res)
+            })
+        });
+        let ctx = || {
+            let (parts, ()) = hyper::Request::builder()
+                .uri("/")
+                .body(())
+                .unwrap()
+                .into_parts();
+            Context::from_parts(parts, bytes::Bytes::new(), Default::default())
+        };
+
+        let mut chain = MiddlewareChain::new();
+        chain.push(observe.clone());
+        let res = chain
+            .execute(ctx(), |ctx| async move {
+                ctx.status(201).await;
+                ctx.text("made").await
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+        assert_eq!(res.headers()["x-observed"], "yes");
+
+        let mut chain = MiddlewareChain::new();
+        chain.push(observe);
+        let err = chain
+            .execute(ctx(), |_ctx| async move {
+                Err::<Response, _>(UltimoError::NotFound("gone".into()))
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), 404);
+        assert_eq!(*seen.lock().unwrap(), [201, 404]);
+    }
+
     #[test]
     fn test_middleware_passthrough() {
         // Test creating a simple passthrough middleware
@@ -1188,6 +2186,21 @@ mod tests {
         assert_sync::<builtin::IpFilter>();
     }
 
+    // Path normalization tests -----------------------------------------------
+
+    #[test]
+    fn canonical_path_collapses_slashes() {
+        assert_eq!(builtin::canonical_path("/users//123", false), "/users/123");
+        assert_eq!(
+            builtin::canonical_path("//api///users", false),
+            "/api/users"
+        );
+        assert_eq!(builtin::canonical_path("/users/", false), "/users/");
+        assert_eq!(builtin::canonical_path("/users//", true), "/users");
+        assert_eq!(builtin::canonical_path("/", true), "/");
+        assert_eq!(builtin::canonical_path("//", true), "/");
+    }
+
     // Rate limiter tests ------------------------------------------------------
 
     #[test]
@@ -1209,6 +2222,20 @@ mod tests {
             .build();
     }
 
+    #[test]
+    #[should_panic(expected = "max_requests must be at least 1")]
+    fn test_rate_limiter_rejects_zero_max_requests() {
+        let _m = builtin::RateLimiter::default().max_requests(0).build();
+    }
+
+    #[test]
+    #[should_panic(expected = "must be non-zero")]
+    fn test_rate_limiter_rejects_zero_window() {
+        let _m = builtin::RateLimiter::default()
+            .per(std::time::Duration::ZERO)
+            .build();
+    }
+
     #[test]
     fn test_rate_limiter_convenience_function() {
         let _m = builtin::rate_limiter();
@@ -0,0 +1,313 @@
+//! `multipart/form-data` request bodies and safe file uploads to disk.
+//!
+//! Obtain a [`Multipart`] with [`Request::multipart`](crate::context::Request::multipart),
+//! then either walk the fields yourself with [`Multipart::next_field`] or
+//! persist every uploaded file with [`Multipart::save_all`].
+
+use crate::error::{Result, UltimoError};
+use bytes::Bytes;
+use futures_util::Stream;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// A `multipart/form-data` body, parsed as it streams in.
+pub struct Multipart {
+    inner: multer::Multipart<'static>,
+    max_field_size: Option<u64>,
+    max_file_size: Option<u64>,
+    max_total_size: Option<u64>,
+    written: u64,
+}
+
+impl Multipart {
+    /// Parse `body` using the boundary from a `Content-Type` header value.
+    /// Chunks are pulled from `body` only as fields are read.
+    ///
+    /// Returns [`UltimoError::BadRequest`] if the content type is not
+    /// `multipart/form-data` or has no boundary.
+    pub(crate) fn new<S>(content_type: Option<&str>, body: S) -> Result<Self>
+    where
+        S: Stream<Item = Result<Bytes>> + Send + 'static,
+    {
+        let content_type = content_type.ok_or_else(|| {
+            UltimoError::BadRequest("Missing multipart/form-data Content-Type".to_string())
+        })?;
+        let boundary = multer::parse_boundary(content_type)
+            .map_err(|e| UltimoError::BadRequest(format!("Invalid multipart body: {}", e)))?;
+        Ok(Self {
+            inner: multer::Multipart::new(body, boundary),
+            max_field_size: None,
+            max_file_size: None,
+            max_total_size: None,
+            written: 0,
+        })
+    }
+
+    /// Cap the size of each field read into memory with [`Field::bytes`] /
+    /// [`Field::text`].
+    pub fn max_field_size(mut self, bytes: u64) -> Self {
+        self.max_field_size = Some(bytes);
+        self
+    }
+
+    /// Cap the size of each file written by [`Field::save_to`] / [`save_all`](Self::save_all).
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Cap the combined size of all files written through this body.
+    pub fn max_total_size(mut self, bytes: u64) -> Self {
+        self.max_total_size = Some(bytes);
+        self
+    }
+
+    /// Next field in the body, or `None` once all fields have been read.
+    pub async fn next_field(&mut self) -> Result<Option<Field<'_>>> {
+        let field = self.inner.next_field().await.map_err(bad_multipart)?;
+        Ok(field.map(|inner| Field {
+            inner,
+            max_field_size: self.max_field_size,
+            max_file_size: self.max_file_size,
+            max_total_size: self.max_total_size,
+            written: &mut self.written,
+        }))
+    }
+
+    /// Save every uploaded file into `dir`, skipping plain (non-file) fields.
+    ///
+    /// Each file is stored under a unique name derived from its original
+    /// filename; filenames containing path separators or `..` are rejected
+    /// with [`UltimoError::BadRequest`]. Files already written are removed if
+    /// a later field fails.
+    pub async fn save_all(&mut self, dir: impl AsRef<Path>) -> Result<Vec<SavedFile>> {
+        let dir = dir.as_ref();
+        let mut saved: Vec<SavedFile> = Vec::new();
+        let result = async {
+            while let Some(field) = self.next_field().await? {
+                let Some(file_name) = field.file_name() else {
+                    continue;
+                };
+                let stored = format!(
+                    "{}_{}",
+                    uuid::Uuid::new_v4().simple(),
+                    safe_file_name(file_name)?
+                );
+                saved.push(field.save_to(dir.join(stored)).await?);
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            for file in &saved {
+                let _ = tokio::fs::remove_file(&file.path).await;
+            }
+            return Err(e);
+        }
+        Ok(saved)
+    }
+}
+
+/// A single part of a [`Multipart`] body.
+pub struct Field<'m> {
+    inner: multer::Field<'static>,
+    max_field_size: Option<u64>,
+    max_file_size: Option<u64>,
+    max_total_size: Option<u64>,
+    written: &'m mut u64,
+}
+
+impl Field<'_> {
+    /// The form field name.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+
+    /// The client-supplied filename, present for file inputs.
+    pub fn file_name(&self) -> Option<&str> {
+        self.inner.file_name()
+    }
+
+    /// The part's `Content-Type`, if sent.
+    pub fn content_type(&self) -> Option<String> {
+        self.inner.content_type().map(|m| m.to_string())
+    }
+
+    /// Read the whole field into memory.
+    ///
+    /// Exceeding the [`Multipart::max_field_size`] cap returns a
+    /// `413 Payload Too Large` error.
+    pub async fn bytes(mut self) -> Result<Bytes> {
+        let mut buf = bytes::BytesMut::new();
+        while let Some(chunk) = self.inner.chunk().await.map_err(bad_multipart)? {
+            if self
+                .max_field_size
+                .is_some_and(|max| (buf.len() + chunk.len()) as u64 > max)
+            {
+                return Err(too_large("Field exceeds the maximum size"));
+            }
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(buf.freeze())
+    }
+
+    /// Read the whole field as UTF-8 text, subject to the same cap as [`bytes`](Self::bytes).
+    pub async fn text(self) -> Result<String> {
+        let bytes = self.bytes().await?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| UltimoError::BadRequest("Multipart field is not valid UTF-8".to_string()))
+    }
+
+    /// Stream the field's contents to `path`, creating or truncating it.
+    ///
+    /// Enforces the per-file and total limits configured on the
+    /// [`Multipart`]; exceeding one removes the partial file and returns a
+    /// `413 Payload Too Large` error.
+    pub async fn save_to(mut self, path: impl AsRef<Path>) -> Result<SavedFile> {
+        let path = path.as_ref().to_path_buf();
+        let field_name = self.name().map(str::to_string);
+        let original_name = self.file_name().map(str::to_string);
+        let content_type = self.content_type();
+
+        let mut file = tokio::fs::File::create(&path).await?;
+        let mut size = 0u64;
+        let result: Result<()> = async {
+            while let Some(chunk) = self.inner.chunk().await.map_err(bad_multipart)? {
+                size += chunk.len() as u64;
+                *self.written += chunk.len() as u64;
+                if self.max_file_size.is_some_and(|max| size > max) {
+                    return Err(too_large("File exceeds the maximum upload size"));
+                }
+                if self.max_total_size.is_some_and(|max| *self.written > max) {
+                    return Err(too_large("Upload exceeds the maximum total size"));
+                }
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            drop(file);
+            let _ = tokio::fs::remove_file(&path).await;
+            return Err(e);
+        }
+
+        Ok(SavedFile {
+            field_name,
+            original_name,
+            path,
+            size,
+            content_type,
+        })
+    }
+}
+
+/// Metadata for a file written to disk by [`Field::save_to`] or [`Multipart::save_all`].
+#[derive(Debug, Clone)]
+pub struct SavedFile {
+    /// The form field the file was uploaded under.
+    pub field_name: Option<String>,
+    /// The filename the client sent.
+    pub original_name: Option<String>,
+    /// Where the file was written.
+    pub path: PathBuf,
+    /// Size in bytes.
+    pub size: u64,
+    /// The part's `Content-Type`, if sent.
+    pub content_type: Option<String>,
+}
+
+/// Validate a client-supplied filename for use as a single path component.
+fn safe_file_name(name: &str) -> Result<&str> {
+    let unsafe_name = name.is_empty()
+        || name == "."
+        || name.contains("..")
+        || name.contains(['/', '\\', ':', '\0']);
+    if unsafe_name {
+        return Err(UltimoError::BadRequest(format!(
+            "Invalid upload filename: {:?}",
+            name
+        )));
+    }
+    Ok(name)
+}
+
+/// Map a parse error to `400`, passing body read errors (such as the route's
+/// `413 Payload Too Large`) through unchanged.
+fn bad_multipart(e: multer::Error) -> UltimoError {
+    match e {
+        multer::Error::StreamReadFailed(err) => match err.downcast::<UltimoError>() {
+            Ok(err) => *err,
+            Err(err) => UltimoError::BadRequest(format!("Invalid multipart body: {}", err)),
+        },
+        e => UltimoError::BadRequest(format!("Invalid multipart body: {}", e)),
+    }
+}
+
+fn too_large(message: &str) -> UltimoError {
+    UltimoError::Http {
+        status: 413,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_traversal_and_separators() {
+        for name in [
+            "",
+            ".",
+            "..",
+            "../etc/passwd",
+            "a/b.txt",
+            "a\\b.txt",
+            "C:evil",
+            "x\0y",
+        ] {
+            assert!(safe_file_name(name).is_err(), "{:?}", name);
+        }
+        assert_eq!(
+            safe_file_name("report.final.pdf").unwrap(),
+            "report.final.pdf"
+        );
+    }
+
+    #[test]
+    fn missing_boundary_is_bad_request() {
+        assert!(matches!(
+            Multipart::new(Some("multipart/form-data"), futures_util::stream::empty()),
+            Err(UltimoError::BadRequest(_))
+        ));
+        assert!(matches!(
+            Multipart::new(None, futures_util::stream::empty()),
+            Err(UltimoError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn body_read_errors_keep_their_status() {
+        let body = futures_util::stream::iter([
+            Ok(Bytes::from_static(
+                b"--x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n",
+            )),
+            Err(too_large("Payload Too Large")),
+        ]);
+        let mut form = Multipart::new(Some("multipart/form-data; boundary=x"), body).unwrap();
+        let walk = async {
+            while let Some(field) = form.next_field().await? {
+                field.bytes().await?;
+            }
+            Ok(())
+        };
+        assert!(matches!(
+            walk.await,
+            Err(UltimoError::Http { status: 413, .. })
+        ));
+    }
+}
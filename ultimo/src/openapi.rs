@@ -13,39 +13,32 @@
 //!
 //! ```rust,ignore
 //! use ultimo::prelude::*;
-//! use ultimo::openapi::OpenApiBuilder;
 //!
 //! #[tokio::main]
 //! async fn main() -> ultimo::Result<()> {
 //!     let mut app = Ultimo::new();
-//!     
-//!     // Create OpenAPI configuration
-//!     let mut openapi = OpenApiBuilder::new()
-//!         .title("My API")
-//!         .version("1.0.0")
-//!         .description("User management API")
-//!         .build();
-//!     
-//!     // Add routes (automatically tracked for OpenAPI)
+//!
+//!     // Plain routes: paths, methods and path parameters are documented
+//!     // automatically; `route()` adds types and summaries.
+//!     app.openapi_schema("User", "{ id: number; name: string }");
 //!     app.get("/users", get_users);
-//!     app.post("/users", create_user);
-//!     
-//!     // Or use RPC registry (auto-generates OpenAPI)
+//!     app.route("/users").request_body("{ name: string }").response(200, "User").post(create_user);
+//!     let spec = app.generate_openapi("My API", "1.0.0");
+//!
+//!     // Or use an RPC registry (auto-generates OpenAPI)
 //!     let rpc = RpcRegistry::new_with_mode(RpcMode::Rest);
-//!     rpc.query("getUser", handler, "{ id: number }", "User");
-//!     
-//!     // Generate and serve OpenAPI spec
-//!     openapi.register_routes(&app);
-//!     openapi.register_rpc(&rpc);
-//!     
-//!     app.get("/openapi.json", openapi.spec_handler());
-//!     app.get("/docs", openapi.swagger_ui_handler());
-//!     
+//!     rpc.query("getUser", get_user);
+//!     let rpc_spec = rpc.generate_openapi("My API", "1.0.0", "/api");
+//!
+//!     app.serve_openapi(spec, "/openapi.json", "/docs");
 //!     app.listen("127.0.0.1:3000").await
 //! }
 //! ```
 
 pub mod docs;
+mod ts_schema;
+
+pub use docs::DocsRenderer;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -60,6 +53,9 @@ pub struct OpenApiSpec {
     pub paths: HashMap<String, PathItem>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub components: Option<Components>,
+    /// Requirements applied to every operation; any one of them must be met
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security: Option<Vec<SecurityRequirement>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<Tag>>,
 }
@@ -177,7 +173,7 @@ pub struct MediaType {
     pub example: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Schema {
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub schema_type: Option<String>,
@@ -189,14 +185,185 @@ pub struct Schema {
     pub required: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub items: Option<Box<Schema>>,
+    /// `true` for values that may also be `null` (`string | null`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
     #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
     pub reference: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Components {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub schemas: Option<HashMap<String, Schema>>,
+    #[serde(rename = "securitySchemes", skip_serializing_if = "Option::is_none")]
+    pub security_schemes: Option<HashMap<String, SecurityScheme>>,
+}
+
+/// Authentication scheme declared under `components.securitySchemes`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SecurityScheme {
+    /// HTTP authentication (`Authorization: <scheme> ...`)
+    #[serde(rename = "http")]
+    Http {
+        /// `bearer`, `basic`, ...
+        scheme: String,
+        #[serde(rename = "bearerFormat", skip_serializing_if = "Option::is_none")]
+        bearer_format: Option<String>,
+    },
+    /// API key sent in a header, query parameter or cookie
+    #[serde(rename = "apiKey")]
+    ApiKey {
+        name: String,
+        #[serde(rename = "in")]
+        location: ApiKeyLocation,
+    },
+    #[serde(rename = "oauth2")]
+    OAuth2 { flows: Box<OAuthFlows> },
+}
+
+impl SecurityScheme {
+    /// `Authorization: Bearer <token>`
+    pub fn bearer() -> Self {
+        Self::Http {
+            scheme: "bearer".to_string(),
+            bearer_format: None,
+        }
+    }
+
+    /// Bearer tokens in JWT format
+    pub fn bearer_jwt() -> Self {
+        Self::Http {
+            scheme: "bearer".to_string(),
+            bearer_format: Some("JWT".to_string()),
+        }
+    }
+
+    /// `Authorization: Basic <credentials>`
+    pub fn basic() -> Self {
+        Self::Http {
+            scheme: "basic".to_string(),
+            bearer_format: None,
+        }
+    }
+
+    /// API key named `name` at `location`
+    pub fn api_key(location: ApiKeyLocation, name: impl Into<String>) -> Self {
+        Self::ApiKey {
+            name: name.into(),
+            location,
+        }
+    }
+
+    /// OAuth2 with the given flows
+    pub fn oauth2(flows: OAuthFlows) -> Self {
+        Self::OAuth2 {
+            flows: Box::new(flows),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyLocation {
+    Header,
+    Query,
+    Cookie,
+}
+
+/// The OAuth2 flows a scheme supports
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OAuthFlows {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub implicit: Option<OAuthFlow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<OAuthFlow>,
+    #[serde(rename = "clientCredentials", skip_serializing_if = "Option::is_none")]
+    pub client_credentials: Option<OAuthFlow>,
+    #[serde(rename = "authorizationCode", skip_serializing_if = "Option::is_none")]
+    pub authorization_code: Option<OAuthFlow>,
+}
+
+/// One OAuth2 flow: its endpoints and available scopes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OAuthFlow {
+    #[serde(rename = "authorizationUrl", skip_serializing_if = "Option::is_none")]
+    pub authorization_url: Option<String>,
+    #[serde(rename = "tokenUrl", skip_serializing_if = "Option::is_none")]
+    pub token_url: Option<String>,
+    #[serde(rename = "refreshUrl", skip_serializing_if = "Option::is_none")]
+    pub refresh_url: Option<String>,
+    /// Scope name → description
+    pub scopes: HashMap<String, String>,
+}
+
+impl OAuthFlow {
+    /// Authorization-code flow
+    pub fn authorization_code(
+        authorization_url: impl Into<String>,
+        token_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            authorization_url: Some(authorization_url.into()),
+            token_url: Some(token_url.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Client-credentials (or password) flow
+    pub fn client_credentials(token_url: impl Into<String>) -> Self {
+        Self {
+            token_url: Some(token_url.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Implicit flow
+    pub fn implicit(authorization_url: impl Into<String>) -> Self {
+        Self {
+            authorization_url: Some(authorization_url.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Add a scope
+    pub fn scope(mut self, name: impl Into<String>, description: impl Into<String>) -> Self {
+        self.scopes.insert(name.into(), description.into());
+        self
+    }
+}
+
+/// Security requirement: scheme name → required scopes. Every scheme listed in
+/// one requirement must be satisfied.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SecurityRequirement(pub HashMap<String, Vec<String>>);
+
+impl SecurityRequirement {
+    /// Require `scheme` with no scopes
+    pub fn new(scheme: impl Into<String>) -> Self {
+        Self::default().and(scheme, Vec::<String>::new())
+    }
+
+    /// Require `scheme` with `scopes` (OAuth2)
+    pub fn with_scopes(
+        scheme: impl Into<String>,
+        scopes: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self::default().and(scheme, scopes)
+    }
+
+    /// Additionally require `scheme`
+    pub fn and(
+        mut self,
+        scheme: impl Into<String>,
+        scopes: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.0
+            .insert(scheme.into(), scopes.into_iter().map(Into::into).collect());
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -215,6 +382,8 @@ pub struct OpenApiBuilder {
     tags: Vec<Tag>,
     contact: Option<Contact>,
     license: Option<License>,
+    security_schemes: HashMap<String, SecurityScheme>,
+    security: Vec<SecurityRequirement>,
 }
 
 impl OpenApiBuilder {
@@ -228,6 +397,8 @@ impl OpenApiBuilder {
             tags: vec![],
             contact: None,
             license: None,
+            security_schemes: HashMap::new(),
+            security: vec![],
         }
     }
 
@@ -287,6 +458,25 @@ impl OpenApiBuilder {
         self
     }
 
+    /// Declare a security scheme under `components.securitySchemes`
+    ///
+    /// ```rust,ignore
+    /// OpenApiBuilder::new()
+    ///     .security_scheme("bearerAuth", SecurityScheme::bearer_jwt())
+    ///     .security(SecurityRequirement::new("bearerAuth"))
+    /// ```
+    pub fn security_scheme(mut self, name: impl Into<String>, scheme: SecurityScheme) -> Self {
+        self.security_schemes.insert(name.into(), scheme);
+        self
+    }
+
+    /// Add a default security requirement for all operations. Clients must
+    /// satisfy any one of the added requirements.
+    pub fn security(mut self, requirement: SecurityRequirement) -> Self {
+        self.security.push(requirement);
+        self
+    }
+
     /// Build the OpenAPI spec (without paths yet)
     pub fn build(self) -> OpenApiSpec {
         OpenApiSpec {
@@ -304,7 +494,19 @@ impl OpenApiBuilder {
                 Some(self.servers)
             },
             paths: HashMap::new(),
-            components: None,
+            components: if self.security_schemes.is_empty() {
+                None
+            } else {
+                Some(Components {
+                    schemas: None,
+                    security_schemes: Some(self.security_schemes),
+                })
+            },
+            security: if self.security.is_empty() {
+                None
+            } else {
+                Some(self.security)
+            },
             tags: if self.tags.is_empty() {
                 None
             } else {
@@ -320,6 +522,31 @@ impl Default for OpenApiBuilder {
     }
 }
 
+/// A route pattern as an OpenAPI path template, with the names of its path
+/// parameters: `/users/:id` is `/users/{id}` and `/files/*path` is
+/// `/files/{path}`. An unnamed wildcard (`/files/*`) is `{wildcard}`.
+pub(crate) fn path_template(pattern: &str) -> (String, Vec<String>) {
+    let mut params = Vec::new();
+    let path = pattern
+        .split('/')
+        .map(|segment| {
+            match segment
+                .strip_prefix(':')
+                .or_else(|| segment.strip_prefix('*'))
+            {
+                Some(name) => {
+                    let name = if name.is_empty() { "wildcard" } else { name };
+                    params.push(name.to_string());
+                    format!("{{{}}}", name)
+                }
+                None => segment.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    (path, params)
+}
+
 impl OpenApiSpec {
     /// Add a path to the spec
     pub fn add_path(&mut self, path: String, item: PathItem) {
@@ -328,69 +555,195 @@ impl OpenApiSpec {
 
     /// Add a schema to components
     pub fn add_schema(&mut self, name: String, schema: Schema) {
-        if self.components.is_none() {
-            self.components = Some(Components {
-                schemas: Some(HashMap::new()),
-            });
+        self.components
+            .get_or_insert_with(Components::default)
+            .schemas
+            .get_or_insert_with(HashMap::new)
+            .insert(name, schema);
+    }
+
+    /// Add a security scheme to components
+    pub fn add_security_scheme(&mut self, name: impl Into<String>, scheme: SecurityScheme) {
+        self.components
+            .get_or_insert_with(Components::default)
+            .security_schemes
+            .get_or_insert_with(HashMap::new)
+            .insert(name.into(), scheme);
+    }
+
+    /// A `$ref` schema pointing at `#/components/schemas/{name}`.
+    pub fn schema_ref(name: &str) -> Schema {
+        ts_schema::reference(name)
+    }
+
+    /// Register `schema` under `components.schemas` and return a `$ref` to it.
+    ///
+    /// Identical schemas are stored once: if an equal schema is already
+    /// registered, the reference points at that component instead. If `name`
+    /// is taken by a different schema, a numeric suffix is appended
+    /// (`User2`, `User3`, …).
+    pub fn register_schema(&mut self, name: impl Into<String>, schema: Schema) -> Schema {
+        let name = name.into();
+        let schemas = self
+            .components
+            .get_or_insert_with(Components::default)
+            .schemas
+            .get_or_insert_with(HashMap::new);
+
+        if let Some(existing) = schemas
+            .iter()
+            .filter(|(_, s)| **s == schema)
+            .map(|(n, _)| n)
+            .min()
+        {
+            return Self::schema_ref(existing);
         }
 
-        if let Some(components) = &mut self.components {
-            if components.schemas.is_none() {
-                components.schemas = Some(HashMap::new());
-            }
-            if let Some(schemas) = &mut components.schemas {
-                schemas.insert(name, schema);
-            }
+        let mut unique = name.clone();
+        let mut n = 2;
+        while schemas.contains_key(&unique) {
+            unique = format!("{}{}", name, n);
+            n += 1;
         }
+        schemas.insert(unique.clone(), schema);
+        Self::schema_ref(&unique)
+    }
+
+    /// Register named TypeScript types (`(name, type expression)` pairs) as
+    /// component schemas. Names may refer to each other and to components
+    /// already registered; those references become `$ref`s.
+    pub fn add_ts_schemas<'a>(&mut self, types: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        let types: Vec<_> = types.into_iter().collect();
+        let mut known = self.component_names();
+        known.extend(types.iter().map(|(name, _)| name.to_string()));
+
+        for (name, ts_type) in types {
+            let schema = ts_schema::parse_or_object(ts_type, &known);
+            self.add_schema(name.to_string(), schema);
+        }
+    }
+
+    /// Like [`ts_to_schema`](Self::ts_to_schema), but named types registered
+    /// under `components.schemas` become `$ref`s instead of plain objects.
+    pub fn ts_to_schema_with_refs(&self, ts_type: &str) -> Schema {
+        ts_schema::parse_or_object(ts_type, &self.component_names())
+    }
+
+    fn component_names(&self) -> std::collections::HashSet<String> {
+        self.components
+            .as_ref()
+            .and_then(|c| c.schemas.as_ref())
+            .map(|schemas| schemas.keys().cloned().collect())
+            .unwrap_or_default()
     }
 
     /// Convert TypeScript type string to OpenAPI schema
+    ///
+    /// Handles primitives, arrays (`T[]`, `Array<T>`) and inline object
+    /// literals such as `{ id: number; tags?: string[] }`, nested to any depth.
+    /// Optional (`?`) fields are left out of `required`. Named types and
+    /// anything unparseable fall back to a plain `object` schema.
     pub fn ts_to_schema(ts_type: &str) -> Schema {
-        // Simple type mapping - can be expanded
-        match ts_type {
-            "string" => Schema {
-                schema_type: Some("string".to_string()),
-                format: None,
-                properties: None,
-                required: None,
-                items: None,
-                reference: None,
-            },
-            "number" => Schema {
-                schema_type: Some("number".to_string()),
-                format: None,
-                properties: None,
-                required: None,
-                items: None,
-                reference: None,
-            },
-            "boolean" => Schema {
-                schema_type: Some("boolean".to_string()),
-                format: None,
-                properties: None,
-                required: None,
-                items: None,
-                reference: None,
+        ts_schema::parse_or_object(ts_type, &Default::default())
+    }
+
+    /// Pick the status code and JSON body a mock server should answer
+    /// `operation` with.
+    ///
+    /// Uses the lowest documented 2xx response (or `default`), preferring its
+    /// `application/json` example and otherwise deriving a sample from the
+    /// schema. The body is `None` when the response documents no content.
+    pub fn mock_response(&self, operation: &Operation) -> (u16, Option<serde_json::Value>) {
+        let mut success: Vec<(u16, &Response)> = operation
+            .responses
+            .iter()
+            .filter_map(|(code, resp)| {
+                let code = code.parse::<u16>().ok()?;
+                (200..300).contains(&code).then_some((code, resp))
+            })
+            .collect();
+        success.sort_by_key(|(code, _)| *code);
+
+        let (status, response) = match success.first() {
+            Some(&(code, resp)) => (code, resp),
+            None => match operation.responses.get("default") {
+                Some(resp) => (200, resp),
+                None => return (200, None),
             },
-            _ if ts_type.ends_with("[]") => {
-                let item_type = &ts_type[..ts_type.len() - 2];
-                Schema {
-                    schema_type: Some("array".to_string()),
-                    format: None,
-                    properties: None,
-                    required: None,
-                    items: Some(Box::new(Self::ts_to_schema(item_type))),
-                    reference: None,
+        };
+
+        let media = response.content.as_ref().and_then(|content| {
+            content
+                .get("application/json")
+                .or_else(|| content.values().next())
+        });
+        let body = media.map(|media| {
+            media
+                .example
+                .clone()
+                .unwrap_or_else(|| self.sample_for_schema(&media.schema))
+        });
+        (status, body)
+    }
+
+    /// Generate a placeholder JSON value matching `schema`, resolving
+    /// `#/components/schemas/*` references against this spec.
+    pub fn sample_for_schema(&self, schema: &Schema) -> serde_json::Value {
+        self.sample_at_depth(schema, 0)
+    }
+
+    fn sample_at_depth(&self, schema: &Schema, depth: usize) -> serde_json::Value {
+        use serde_json::Value;
+
+        // Guard against self-referential component schemas.
+        if depth > 8 {
+            return Value::Null;
+        }
+
+        if let Some(reference) = &schema.reference {
+            let target = reference
+                .strip_prefix("#/components/schemas/")
+                .and_then(|name| self.components.as_ref()?.schemas.as_ref()?.get(name));
+            return match target {
+                Some(target) => self.sample_at_depth(target, depth + 1),
+                None => Value::Null,
+            };
+        }
+
+        match schema.schema_type.as_deref() {
+            Some("string") => Value::String(
+                match schema.format.as_deref() {
+                    Some("date-time") => "1970-01-01T00:00:00Z",
+                    Some("date") => "1970-01-01",
+                    Some("uuid") => "00000000-0000-0000-0000-000000000000",
+                    Some("email") => "user@example.com",
+                    _ => "string",
                 }
+                .to_string(),
+            ),
+            Some("number") | Some("integer") => Value::from(0),
+            Some("boolean") => Value::Bool(false),
+            Some("array") => Value::Array(
+                schema
+                    .items
+                    .as_ref()
+                    .map(|items| vec![self.sample_at_depth(items, depth + 1)])
+                    .unwrap_or_default(),
+            ),
+            _ => {
+                let mut object = serde_json::Map::new();
+                if let Some(properties) = &schema.properties {
+                    let mut names: Vec<&String> = properties.keys().collect();
+                    names.sort();
+                    for name in names {
+                        object.insert(
+                            name.clone(),
+                            self.sample_at_depth(&properties[name], depth + 1),
+                        );
+                    }
+                }
+                Value::Object(object)
             }
-            _ => Schema {
-                schema_type: Some("object".to_string()),
-                format: None,
-                properties: None,
-                required: None,
-                items: None,
-                reference: None,
-            },
         }
     }
 
@@ -409,12 +762,48 @@ impl OpenApiSpec {
     pub fn swagger_ui_html(&self, spec_url: &str) -> String {
         docs::SWAGGER_UI_HTML.replace("{OPENAPI_URL}", spec_url)
     }
+
+    /// Get Redoc HTML with this spec's URL
+    pub fn redoc_ui_html(&self, spec_url: &str) -> String {
+        docs::REDOC_HTML.replace("{OPENAPI_URL}", spec_url)
+    }
+
+    /// Documentation page for `renderer`, loading the spec from `spec_url`
+    pub fn docs_html(&self, renderer: DocsRenderer, spec_url: &str) -> String {
+        match renderer {
+            DocsRenderer::SwaggerUi => self.swagger_ui_html(spec_url),
+            DocsRenderer::Redoc => self.redoc_ui_html(spec_url),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_path_template() {
+        assert_eq!(path_template("/users"), ("/users".to_string(), vec![]));
+        assert_eq!(
+            path_template("/users/:id/posts/:post_id"),
+            (
+                "/users/{id}/posts/{post_id}".to_string(),
+                vec!["id".to_string(), "post_id".to_string()]
+            )
+        );
+        assert_eq!(
+            path_template("/files/*path"),
+            ("/files/{path}".to_string(), vec!["path".to_string()])
+        );
+        assert_eq!(
+            path_template("/files/*"),
+            (
+                "/files/{wildcard}".to_string(),
+                vec!["wildcard".to_string()]
+            )
+        );
+    }
+
     #[test]
     fn test_builder() {
         let spec = OpenApiBuilder::new()
@@ -564,6 +953,156 @@ mod tests {
         assert_eq!(schema.schema_type, Some("object".to_string()));
     }
 
+    #[test]
+    fn test_ts_to_schema_inline_object() {
+        let schema = OpenApiSpec::ts_to_schema("{ id: number; name: string }");
+        assert_eq!(schema.schema_type, Some("object".to_string()));
+
+        let props = schema.properties.unwrap();
+        assert_eq!(props["id"].schema_type, Some("number".to_string()));
+        assert_eq!(props["name"].schema_type, Some("string".to_string()));
+        assert_eq!(
+            schema.required,
+            Some(vec!["id".to_string(), "name".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_ts_to_schema_optional_fields_not_required() {
+        let schema = OpenApiSpec::ts_to_schema("{ id: number, nickname?: string }");
+        assert_eq!(schema.required, Some(vec!["id".to_string()]));
+        assert!(schema.properties.unwrap().contains_key("nickname"));
+    }
+
+    #[test]
+    fn test_ts_to_schema_nested_object() {
+        let schema =
+            OpenApiSpec::ts_to_schema("{ user: { id: number; address: { city: string } } }");
+        let user = &schema.properties.unwrap()["user"];
+        let address = &user.properties.as_ref().unwrap()["address"];
+        let city = &address.properties.as_ref().unwrap()["city"];
+        assert_eq!(city.schema_type, Some("string".to_string()));
+        assert_eq!(address.required, Some(vec!["city".to_string()]));
+    }
+
+    #[test]
+    fn test_ts_to_schema_array_of_objects() {
+        let schema = OpenApiSpec::ts_to_schema("{ id: number; tags: string[] }[]");
+        assert_eq!(schema.schema_type, Some("array".to_string()));
+
+        let item = schema.items.unwrap();
+        assert_eq!(item.schema_type, Some("object".to_string()));
+        let tags = &item.properties.unwrap()["tags"];
+        assert_eq!(tags.schema_type, Some("array".to_string()));
+        assert_eq!(
+            tags.items.as_ref().unwrap().schema_type,
+            Some("string".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ts_to_schema_generic_array_and_nested_arrays() {
+        let schema = OpenApiSpec::ts_to_schema("Array<{ ok: boolean }>");
+        let item = schema.items.unwrap();
+        assert_eq!(
+            item.properties.unwrap()["ok"].schema_type,
+            Some("boolean".to_string())
+        );
+
+        let matrix = OpenApiSpec::ts_to_schema("number[][]");
+        let row = matrix.items.unwrap();
+        assert_eq!(row.schema_type, Some("array".to_string()));
+        assert_eq!(row.items.unwrap().schema_type, Some("number".to_string()));
+    }
+
+    #[test]
+    fn test_ts_to_schema_unions_and_literals() {
+        let nullable = OpenApiSpec::ts_to_schema("{ name: string | null; age?: number }");
+        let properties = nullable.properties.unwrap();
+        assert_eq!(properties["name"].schema_type, Some("string".to_string()));
+        assert_eq!(properties["name"].nullable, Some(true));
+        assert_eq!(properties["age"].nullable, None);
+        assert_eq!(nullable.required, Some(vec!["name".to_string()]));
+
+        let items = OpenApiSpec::ts_to_schema("({ id: number } | undefined)[]");
+        let item = items.items.unwrap();
+        assert_eq!(item.nullable, Some(true));
+        assert!(item.properties.unwrap().contains_key("id"));
+
+        let literal = OpenApiSpec::ts_to_schema("\"admin\" | \"user\"");
+        assert_eq!(literal.schema_type, Some("string".to_string()));
+    }
+
+    #[test]
+    fn test_ts_to_schema_malformed_falls_back_to_object() {
+        let schema = OpenApiSpec::ts_to_schema("{ id: number");
+        assert_eq!(schema.schema_type, Some("object".to_string()));
+        assert!(schema.properties.is_none());
+    }
+
+    #[test]
+    fn test_ts_to_schema_deep_nesting_falls_back_to_object() {
+        let deep = format!("{}number{}", "{ a: ".repeat(5000), " }".repeat(5000));
+        let schema = OpenApiSpec::ts_to_schema(&deep);
+        assert_eq!(schema.schema_type, Some("object".to_string()));
+        assert!(schema.properties.is_none());
+
+        let parens = format!("{}string{}", "(".repeat(5000), ")".repeat(5000));
+        assert!(OpenApiSpec::ts_to_schema(&parens).properties.is_none());
+
+        let nested = format!("{}string{}", "{ a: ".repeat(10), " }".repeat(10));
+        assert!(OpenApiSpec::ts_to_schema(&nested).properties.is_some());
+    }
+
+    #[test]
+    fn test_register_schema_dedups_identical_schemas() {
+        let mut spec = OpenApiBuilder::new().build();
+        let user = OpenApiSpec::ts_to_schema("{ id: number; name: string }");
+
+        let first = spec.register_schema("User", user.clone());
+        let again = spec.register_schema("Member", user);
+        assert_eq!(
+            first.reference.as_deref(),
+            Some("#/components/schemas/User")
+        );
+        assert_eq!(again, first);
+
+        // Same name, different shape: kept apart with a suffix
+        let other = spec.register_schema("User", OpenApiSpec::ts_to_schema("{ sku: string }"));
+        assert_eq!(
+            other.reference.as_deref(),
+            Some("#/components/schemas/User2")
+        );
+        assert_eq!(spec.components.unwrap().schemas.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_ts_schemas_reference_known_components() {
+        let mut spec = OpenApiBuilder::new().build();
+        spec.add_ts_schemas([
+            ("User", "{ id: number, tags: Array<Tag>, }"),
+            ("Tag", "{ label: string }"),
+        ]);
+
+        let schemas = spec.components.as_ref().unwrap().schemas.as_ref().unwrap();
+        let tags = &schemas["User"].properties.as_ref().unwrap()["tags"];
+        assert_eq!(
+            tags.items.as_ref().unwrap().reference.as_deref(),
+            Some("#/components/schemas/Tag")
+        );
+
+        let list = spec.ts_to_schema_with_refs("User[]");
+        assert_eq!(list.schema_type.as_deref(), Some("array"));
+        assert_eq!(
+            list.items.unwrap().reference.as_deref(),
+            Some("#/components/schemas/User")
+        );
+        // Unknown names still fall back to a plain object
+        let unknown = spec.ts_to_schema_with_refs("Product | null");
+        assert_eq!(unknown.schema_type.as_deref(), Some("object"));
+        assert!(unknown.reference.is_none());
+    }
+
     #[test]
     fn test_openapi_spec_version() {
         let spec = OpenApiBuilder::new()
@@ -615,6 +1154,7 @@ mod tests {
                 items: None,
                 properties: None,
                 required: None,
+                nullable: None,
                 reference: None,
             }),
         );
@@ -625,6 +1165,7 @@ mod tests {
             items: None,
             properties: Some(properties),
             required: Some(vec!["id".to_string()]),
+            nullable: None,
             reference: None,
         };
 
@@ -646,6 +1187,7 @@ mod tests {
                 items: None,
                 properties: None,
                 required: None,
+                nullable: None,
                 reference: None,
             },
         };
@@ -706,6 +1248,163 @@ mod tests {
         assert!(json_value.contains("3.0.0"));
     }
 
+    #[test]
+    fn test_security_schemes_serialize() {
+        let spec = OpenApiBuilder::new()
+            .security_scheme("bearerAuth", SecurityScheme::bearer_jwt())
+            .security_scheme(
+                "apiKey",
+                SecurityScheme::api_key(ApiKeyLocation::Header, "X-API-Key"),
+            )
+            .security_scheme(
+                "oauth",
+                SecurityScheme::oauth2(OAuthFlows {
+                    client_credentials: Some(
+                        OAuthFlow::client_credentials("https://auth.example.com/token")
+                            .scope("read", "Read access"),
+                    ),
+                    ..OAuthFlows::default()
+                }),
+            )
+            .security(SecurityRequirement::new("bearerAuth"))
+            .security(
+                SecurityRequirement::with_scopes("oauth", ["read"])
+                    .and("apiKey", Vec::<String>::new()),
+            )
+            .build();
+
+        let json = serde_json::to_value(&spec).unwrap();
+        let schemes = &json["components"]["securitySchemes"];
+        assert_eq!(
+            schemes["bearerAuth"],
+            serde_json::json!({"type": "http", "scheme": "bearer", "bearerFormat": "JWT"})
+        );
+        assert_eq!(
+            schemes["apiKey"],
+            serde_json::json!({"type": "apiKey", "name": "X-API-Key", "in": "header"})
+        );
+        assert_eq!(
+            schemes["oauth"],
+            serde_json::json!({"type": "oauth2", "flows": {"clientCredentials": {
+                "tokenUrl": "https://auth.example.com/token",
+                "scopes": {"read": "Read access"}
+            }}})
+        );
+        assert_eq!(
+            json["security"],
+            serde_json::json!([{"bearerAuth": []}, {"oauth": ["read"], "apiKey": []}])
+        );
+        assert!(json["components"].get("schemas").is_none());
+
+        let parsed: OpenApiSpec = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            parsed.components.unwrap().security_schemes.unwrap()["bearerAuth"],
+            SecurityScheme::bearer_jwt()
+        );
+    }
+
+    fn json_response(example: Option<serde_json::Value>, schema: Schema) -> Response {
+        let mut content = HashMap::new();
+        content.insert(
+            "application/json".to_string(),
+            MediaType { schema, example },
+        );
+        Response {
+            description: "ok".to_string(),
+            content: Some(content),
+        }
+    }
+
+    fn operation(responses: Vec<(&str, Response)>) -> Operation {
+        Operation {
+            summary: None,
+            description: None,
+            operation_id: None,
+            tags: None,
+            parameters: None,
+            request_body: None,
+            responses: responses
+                .into_iter()
+                .map(|(code, resp)| (code.to_string(), resp))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_mock_response_prefers_example() {
+        let spec = OpenApiBuilder::new().build();
+        let op = operation(vec![
+            (
+                "404",
+                json_response(None, OpenApiSpec::ts_to_schema("string")),
+            ),
+            (
+                "201",
+                json_response(
+                    Some(serde_json::json!({"id": 7})),
+                    OpenApiSpec::ts_to_schema("{ id: number }"),
+                ),
+            ),
+        ]);
+        let (status, body) = spec.mock_response(&op);
+        assert_eq!(status, 201);
+        assert_eq!(body, Some(serde_json::json!({"id": 7})));
+    }
+
+    #[test]
+    fn test_mock_response_derives_sample_from_schema() {
+        let spec = OpenApiBuilder::new().build();
+        let op = operation(vec![(
+            "200",
+            json_response(
+                None,
+                OpenApiSpec::ts_to_schema(
+                    "{ id: number; name: string; tags: string[]; ok: boolean }",
+                ),
+            ),
+        )]);
+        let (_, body) = spec.mock_response(&op);
+        assert_eq!(
+            body,
+            Some(serde_json::json!({"id": 0, "name": "string", "tags": ["string"], "ok": false}))
+        );
+    }
+
+    #[test]
+    fn test_sample_for_schema_resolves_refs() {
+        let mut spec = OpenApiBuilder::new().build();
+        spec.add_schema(
+            "User".to_string(),
+            OpenApiSpec::ts_to_schema("{ id: number }"),
+        );
+        let reference = Schema {
+            schema_type: None,
+            format: None,
+            properties: None,
+            required: None,
+            items: None,
+            nullable: None,
+            reference: Some("#/components/schemas/User".to_string()),
+        };
+        assert_eq!(
+            spec.sample_for_schema(&reference),
+            serde_json::json!({"id": 0})
+        );
+    }
+
+    #[test]
+    fn test_mock_response_without_content() {
+        let spec = OpenApiBuilder::new().build();
+        let op = operation(vec![(
+            "204",
+            Response {
+                description: "deleted".to_string(),
+                content: None,
+            },
+        )]);
+        assert_eq!(spec.mock_response(&op), (204, None));
+    }
+
     #[test]
     fn test_swagger_ui_html_generation() {
         let spec = OpenApiBuilder::new()
@@ -717,4 +1416,16 @@ mod tests {
         assert!(html.contains("Swagger UI"));
         assert!(html.contains("/openapi.json"));
     }
+
+    #[test]
+    fn test_redoc_html_generation() {
+        let spec = OpenApiBuilder::new().build();
+        let html = spec.docs_html(DocsRenderer::Redoc, "/spec.json");
+        assert!(html.contains("redoc.standalone.js"));
+        assert!(html.contains(r#"<redoc spec-url="/spec.json">"#));
+        assert_eq!(
+            spec.docs_html(DocsRenderer::default(), "/spec.json"),
+            spec.swagger_ui_html("/spec.json")
+        );
+    }
 }
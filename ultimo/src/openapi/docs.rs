@@ -1,4 +1,4 @@
-//! Swagger UI embedded HTML for serving /docs endpoint
+//! Embedded HTML pages (Swagger UI, Redoc) for serving the /docs endpoint
 
 pub const SWAGGER_UI_HTML: &str = r#"<!DOCTYPE html>
 <html lang="en">
@@ -43,3 +43,29 @@ pub const SWAGGER_UI_HTML: &str = r#"<!DOCTYPE html>
     </script>
 </body>
 </html>"#;
+
+pub const REDOC_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>API Documentation - Redoc</title>
+    <style>
+        body { margin: 0; padding: 0; }
+    </style>
+</head>
+<body>
+    <redoc spec-url="{OPENAPI_URL}"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/v2.1.3/bundles/redoc.standalone.js"></script>
+</body>
+</html>"#;
+
+/// Which documentation page [`serve_openapi_with`](crate::Ultimo::serve_openapi_with) serves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DocsRenderer {
+    /// Interactive Swagger UI with "Try it out"
+    #[default]
+    SwaggerUi,
+    /// Redoc's three-panel reference layout
+    Redoc,
+}
@@ -0,0 +1,299 @@
+//! Recursive-descent parser turning TypeScript type strings into OpenAPI schemas
+//!
+//! Understands the subset of TypeScript used in RPC type registrations:
+//! primitives, inline object literals (`{ id: number; name?: string }`),
+//! arrays (`T[]` / `Array<T>`), parenthesized types, literal types and simple
+//! unions. Named types (`User`) become `$ref`s when they are known component
+//! schemas and fall back to a plain `object` schema otherwise.
+
+use super::Schema;
+use std::collections::{HashMap, HashSet};
+
+/// Parse `ts_type` into a [`Schema`], or `None` if the string is not a type
+/// expression this parser understands. Named types listed in `known` become
+/// `#/components/schemas/<name>` references.
+fn parse_with_refs(ts_type: &str, known: &HashSet<String>) -> Option<Schema> {
+    let mut parser = Parser {
+        src: ts_type.as_bytes(),
+        pos: 0,
+        depth: 0,
+        known,
+    };
+    let schema = parser.parse_type()?;
+    parser.skip_ws();
+    if parser.pos != parser.src.len() {
+        return None;
+    }
+    Some(schema)
+}
+
+/// [`parse_with_refs`], falling back to a plain `object` schema for anything
+/// unparseable or bare `null`.
+pub(super) fn parse_or_object(ts_type: &str, known: &HashSet<String>) -> Schema {
+    match parse_with_refs(ts_type, known) {
+        Some(schema) if schema.schema_type.as_deref() != Some("null") => schema,
+        _ => typed("object"),
+    }
+}
+
+/// Schema with only `type` set.
+pub(super) fn typed(schema_type: &str) -> Schema {
+    Schema {
+        schema_type: Some(schema_type.to_string()),
+        format: None,
+        properties: None,
+        required: None,
+        items: None,
+        nullable: None,
+        reference: None,
+    }
+}
+
+/// Internal marker for `null` / `undefined` union members.
+const NULL_TYPE: &str = "null";
+
+/// Deepest nesting of objects, parentheses and generics the parser follows.
+/// Deeper types are rejected (and so documented as a plain `object`) rather
+/// than recursing until the stack overflows.
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    /// Current `parse_type` nesting, bounded by [`MAX_DEPTH`].
+    depth: usize,
+    known: &'a HashSet<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `c` (after whitespace) if it is next.
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// `union := postfix ('|' postfix)*`
+    fn parse_type(&mut self) -> Option<Schema> {
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let schema = self.parse_union();
+        self.depth -= 1;
+        schema
+    }
+
+    fn parse_union(&mut self) -> Option<Schema> {
+        // A leading `|` is legal TypeScript (`| "a" | "b"`).
+        self.eat(b'|');
+        let mut members = vec![self.parse_postfix()?];
+        while self.eat(b'|') {
+            members.push(self.parse_postfix()?);
+        }
+        Some(merge_union(members))
+    }
+
+    /// `postfix := primary ('[' ']')*`
+    fn parse_postfix(&mut self) -> Option<Schema> {
+        let mut schema = self.parse_primary()?;
+        loop {
+            self.skip_ws();
+            if self.src[self.pos..].starts_with(b"[]") {
+                self.pos += 2;
+                schema = array_of(schema);
+            } else {
+                return Some(schema);
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Option<Schema> {
+        self.skip_ws();
+        match self.peek()? {
+            b'{' => self.parse_object(),
+            b'(' => {
+                self.pos += 1;
+                let inner = self.parse_type()?;
+                self.eat(b')').then_some(inner)
+            }
+            b'"' | b'\'' => {
+                self.parse_string()?;
+                Some(typed("string"))
+            }
+            c if c.is_ascii_digit() || c == b'-' => {
+                self.pos += 1;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == b'.') {
+                    self.pos += 1;
+                }
+                Some(typed("number"))
+            }
+            _ => {
+                let name = self.parse_ident()?;
+                if self.eat(b'<') {
+                    let mut args = vec![self.parse_type()?];
+                    while self.eat(b',') {
+                        args.push(self.parse_type()?);
+                    }
+                    if !self.eat(b'>') {
+                        return None;
+                    }
+                    return Some(match (name, args.len()) {
+                        ("Array", 1) => array_of(args.remove(0)),
+                        _ => typed("object"),
+                    });
+                }
+                Some(match name {
+                    "string" => typed("string"),
+                    "number" | "bigint" => typed("number"),
+                    "boolean" | "true" | "false" => typed("boolean"),
+                    "null" | "undefined" => typed(NULL_TYPE),
+                    _ if self.known.contains(name) => reference(name),
+                    _ => typed("object"),
+                })
+            }
+        }
+    }
+
+    /// `object := '{' (key '?'? ':' union (';' | ',')?)* '}'`
+    fn parse_object(&mut self) -> Option<Schema> {
+        if !self.eat(b'{') {
+            return None;
+        }
+        let mut properties = HashMap::new();
+        let mut required = Vec::new();
+        loop {
+            if self.eat(b'}') {
+                break;
+            }
+            self.skip_ws();
+            let key = match self.peek()? {
+                b'"' | b'\'' => self.parse_string()?,
+                _ => self.parse_ident()?.to_string(),
+            };
+            let optional = self.eat(b'?');
+            if !self.eat(b':') {
+                return None;
+            }
+            let value = self.parse_type()?;
+            if !optional {
+                required.push(key.clone());
+            }
+            properties.insert(key, Box::new(value));
+            if !self.eat(b';') {
+                self.eat(b',');
+            }
+        }
+
+        let mut schema = typed("object");
+        schema.properties = Some(properties);
+        schema.required = (!required.is_empty()).then_some(required);
+        Some(schema)
+    }
+
+    fn parse_ident(&mut self) -> Option<&'a str> {
+        self.skip_ws();
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c == b'.')
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return None;
+        }
+        let src: &'a [u8] = self.src;
+        std::str::from_utf8(&src[start..self.pos]).ok()
+    }
+
+    /// Parse a quoted string literal, returning its contents.
+    fn parse_string(&mut self) -> Option<String> {
+        let quote = self.peek()?;
+        self.pos += 1;
+        let start = self.pos;
+        while self.peek()? != quote {
+            if self.peek()? == b'\\' {
+                self.pos += 1;
+            }
+            self.pos += 1;
+        }
+        let contents = std::str::from_utf8(&self.src[start..self.pos]).ok()?;
+        self.pos += 1;
+        Some(contents.to_string())
+    }
+}
+
+/// `$ref` to the component schema `name`.
+pub(super) fn reference(name: &str) -> Schema {
+    Schema {
+        schema_type: None,
+        format: None,
+        properties: None,
+        required: None,
+        items: None,
+        nullable: None,
+        reference: Some(format!("#/components/schemas/{}", name)),
+    }
+}
+
+fn array_of(items: Schema) -> Schema {
+    let mut schema = typed("array");
+    schema.items = Some(Box::new(items));
+    schema
+}
+
+/// Collapse union members: `null`/`undefined` are dropped, a single remaining
+/// member (or members sharing one primitive type) becomes that schema, and
+/// anything else falls back to `object`. Dropping a `null` marks the result
+/// `nullable`, except for `$ref`s, whose sibling keywords OpenAPI 3.0 ignores.
+fn merge_union(members: Vec<Schema>) -> Schema {
+    if members.len() == 1 {
+        return members.into_iter().next().unwrap();
+    }
+    let count = members.len();
+    let non_null: Vec<Schema> = members
+        .into_iter()
+        .filter(|m| m.schema_type.as_deref() != Some(NULL_TYPE))
+        .collect();
+    let has_null = non_null.len() < count;
+    let mut schema = merge_non_null(non_null);
+    if has_null && schema.reference.is_none() && schema.schema_type.as_deref() != Some(NULL_TYPE) {
+        schema.nullable = Some(true);
+    }
+    schema
+}
+
+fn merge_non_null(mut non_null: Vec<Schema>) -> Schema {
+    match non_null.len() {
+        0 => typed(NULL_TYPE),
+        1 => non_null.remove(0),
+        _ => {
+            let first = &non_null[0];
+            let is_primitive =
+                |s: &Schema| s.properties.is_none() && s.items.is_none() && s.reference.is_none();
+            if non_null
+                .iter()
+                .all(|m| is_primitive(m) && m.schema_type == first.schema_type)
+            {
+                non_null.remove(0)
+            } else {
+                typed("object")
+            }
+        }
+    }
+}
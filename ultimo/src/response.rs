@@ -3,20 +3,174 @@
 //! Internal response building that gets wrapped by Context methods.
 
 use crate::error::{Result, UltimoError};
-use http_body_util::Full;
-use hyper::{body::Bytes, header::HeaderValue, Response as HyperResponse, StatusCode};
+use futures_util::Stream;
+use hyper::body::{Bytes, Frame, SizeHint};
+use hyper::{header::HeaderValue, Response as HyperResponse};
+
+pub use hyper::StatusCode;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 /// HTTP Response type used throughout Ultimo
-pub type Response = HyperResponse<Full<Bytes>>;
+pub type Response = HyperResponse<Body>;
+
+/// Error type carried by streaming response bodies.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Response body: either fully buffered bytes or a stream of chunks.
+///
+/// Every response to a buffered body carries an accurate `Content-Length`,
+/// set by the app after middleware runs (so it also shows up in
+/// [`Ultimo::oneshot`](crate::Ultimo::oneshot) and tests). Streamed bodies are
+/// sent with chunked transfer encoding, unless the handler set a
+/// `Content-Length` itself, as `Context::file` does. A
+/// stream that yields an error aborts the response mid-flight.
+pub struct Body {
+    inner: BodyInner,
+}
+
+enum BodyInner {
+    Full(Option<Bytes>),
+    Stream(Pin<Box<dyn Stream<Item = std::result::Result<Bytes, BoxError>> + Send>>),
+}
+
+impl Body {
+    /// An empty body.
+    pub fn empty() -> Self {
+        Self {
+            inner: BodyInner::Full(None),
+        }
+    }
+
+    /// A body streamed from `stream`, one frame per yielded chunk.
+    pub fn from_stream<S, E>(stream: S) -> Self
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>> + Send + 'static,
+        E: Into<BoxError> + 'static,
+    {
+        use futures_util::TryStreamExt;
+        Self {
+            inner: BodyInner::Stream(Box::pin(stream.map_err(Into::into))),
+        }
+    }
+
+    /// Whether this body is streamed rather than buffered.
+    pub fn is_stream(&self) -> bool {
+        matches!(self.inner, BodyInner::Stream(_))
+    }
+
+    /// The buffered bytes, or the body itself back if it is a stream.
+    ///
+    /// Lets middleware rewrite buffered bodies without draining a stream
+    /// into memory.
+    pub fn try_into_bytes(self) -> std::result::Result<Bytes, Self> {
+        match self.inner {
+            BodyInner::Full(bytes) => Ok(bytes.unwrap_or_default()),
+            inner @ BodyInner::Stream(_) => Err(Self { inner }),
+        }
+    }
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl std::fmt::Debug for Body {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.inner {
+            BodyInner::Full(bytes) => f.debug_tuple("Body::Full").field(bytes).finish(),
+            BodyInner::Stream(_) => f.write_str("Body::Stream"),
+        }
+    }
+}
+
+impl From<Bytes> for Body {
+    fn from(bytes: Bytes) -> Self {
+        Self {
+            inner: BodyInner::Full(Some(bytes)),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Bytes::from(bytes).into()
+    }
+}
+
+impl From<String> for Body {
+    fn from(text: String) -> Self {
+        Bytes::from(text).into()
+    }
+}
+
+impl From<&'static str> for Body {
+    fn from(text: &'static str) -> Self {
+        Bytes::from_static(text.as_bytes()).into()
+    }
+}
+
+impl From<&'static [u8]> for Body {
+    fn from(bytes: &'static [u8]) -> Self {
+        Bytes::from_static(bytes).into()
+    }
+}
+
+impl hyper::body::Body for Body {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::result::Result<Frame<Bytes>, BoxError>>> {
+        match &mut self.get_mut().inner {
+            BodyInner::Full(bytes) => Poll::Ready(
+                bytes
+                    .take()
+                    .filter(|b| !b.is_empty())
+                    .map(|b| Ok(Frame::data(b))),
+            ),
+            BodyInner::Stream(stream) => stream
+                .as_mut()
+                .poll_next(cx)
+                .map(|chunk| chunk.map(|r| r.map(Frame::data))),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match &self.inner {
+            BodyInner::Full(bytes) => bytes.as_ref().is_none_or(|b| b.is_empty()),
+            BodyInner::Stream(_) => false,
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match &self.inner {
+            BodyInner::Full(bytes) => {
+                SizeHint::with_exact(bytes.as_ref().map_or(0, |b| b.len() as u64))
+            }
+            BodyInner::Stream(_) => SizeHint::default(),
+        }
+    }
+}
+
+/// `Content-Type` sent with JSON responses.
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// `Content-Type` sent with JSON responses when the app opts into an
+/// explicit charset via [`Ultimo::json_charset`](crate::Ultimo::json_charset).
+pub const JSON_UTF8_CONTENT_TYPE: &str = "application/json; charset=utf-8";
 
 /// Response builder for constructing HTTP responses
 #[derive(Debug)]
 pub struct ResponseBuilder {
     status: StatusCode,
-    headers: HashMap<String, String>,
-    body: Option<Vec<u8>>,
+    headers: Vec<(String, String)>,
+    body: Option<Body>,
 }
 
 impl ResponseBuilder {
@@ -24,7 +178,7 @@ impl ResponseBuilder {
     pub fn new() -> Self {
         Self {
             status: StatusCode::OK,
-            headers: HashMap::new(),
+            headers: Vec::new(),
             body: None,
         }
     }
@@ -35,22 +189,52 @@ impl ResponseBuilder {
         self
     }
 
-    /// Add a header to the response
+    /// Add a header to the response, replacing any existing value for the
+    /// same (case-insensitive) name
     pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
-        self.headers.insert(name.into(), value.into());
+        let name = name.into();
+        self.headers.retain(|(k, _)| !k.eq_ignore_ascii_case(&name));
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// Add a header to the response, keeping any existing values for the same
+    /// name (for headers that legitimately repeat, like `Vary` or `Link`)
+    pub fn append_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
         self
     }
 
     /// Set the response body as bytes
     pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
-        self.body = Some(body.into());
+        self.body = Some(body.into().into());
+        self
+    }
+
+    /// Stream the response body from `stream` instead of buffering it.
+    pub fn stream<S, E>(mut self, stream: S) -> Self
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>> + Send + 'static,
+        E: Into<BoxError> + 'static,
+    {
+        self.body = Some(Body::from_stream(stream));
         self
     }
 
     /// Set JSON response body and content-type
+    ///
+    /// Sends `Content-Type: application/json` (see [`JSON_CONTENT_TYPE`]) and
+    /// `X-Content-Type-Options: nosniff` so browsers never reinterpret the
+    /// body as another type.
     pub fn json<T: Serialize>(self, value: &T) -> Result<Self> {
-        let json = serde_json::to_vec(value)?;
-        Ok(self.header("Content-Type", "application/json").body(json))
+        let json = crate::json::to_vec(value)?;
+        Ok(self.json_headers(JSON_CONTENT_TYPE).body(json))
+    }
+
+    /// Set the JSON `Content-Type` (`content_type`) plus `nosniff`.
+    pub(crate) fn json_headers(self, content_type: &str) -> Self {
+        self.header("Content-Type", content_type)
+            .header("X-Content-Type-Options", "nosniff")
     }
 
     /// Set text response body and content-type
@@ -68,11 +252,18 @@ impl ResponseBuilder {
     }
 
     /// Build the final HTTP response
+    ///
+    /// For statuses that forbid a body (204, 205, 304) any body and
+    /// `Content-Type`/`Content-Length` header is dropped.
     pub fn build(self) -> Result<Response> {
         let mut response = HyperResponse::builder().status(self.status);
+        let bodiless = is_bodiless_status(self.status);
 
         // Add all headers
         for (name, value) in self.headers {
+            if bodiless && is_content_header(&name) {
+                continue;
+            }
             response = response.header(
                 name.as_str(),
                 HeaderValue::from_str(&value)
@@ -81,19 +272,237 @@ impl ResponseBuilder {
         }
 
         // Set body
-        let body = self.body.unwrap_or_default();
+        let body = if bodiless {
+            Body::empty()
+        } else {
+            self.body.unwrap_or_default()
+        };
         response
-            .body(Full::new(Bytes::from(body)))
+            .body(body)
             .map_err(|e| UltimoError::Internal(format!("Failed to build response: {}", e)))
     }
 }
 
+/// Whether `status` must not carry a response body (204, 205, 304).
+pub fn is_bodiless_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::NO_CONTENT | StatusCode::RESET_CONTENT | StatusCode::NOT_MODIFIED
+    )
+}
+
+fn is_content_header(name: &str) -> bool {
+    name.eq_ignore_ascii_case("content-type") || name.eq_ignore_ascii_case("content-length")
+}
+
+/// Drop the body and content headers from a response whose status forbids a
+/// body. Applied to every outgoing response, so middleware that builds its own
+/// `hyper::Response` is covered too.
+pub(crate) fn strip_bodiless(mut response: Response) -> Response {
+    if is_bodiless_status(response.status()) {
+        let headers = response.headers_mut();
+        headers.remove(hyper::header::CONTENT_TYPE);
+        headers.remove(hyper::header::CONTENT_LENGTH);
+        *response.body_mut() = Body::empty();
+    }
+    response
+}
+
+/// Set `Content-Length` to the exact length of a buffered body, replacing
+/// any stale value (e.g. from middleware that rewrote the body). Streamed
+/// bodies and statuses that forbid a body are left alone.
+pub(crate) fn set_content_length(mut response: Response) -> Response {
+    use hyper::body::Body as _;
+
+    if is_bodiless_status(response.status()) {
+        return response;
+    }
+    if let Some(length) = response.body().size_hint().exact() {
+        response
+            .headers_mut()
+            .insert(hyper::header::CONTENT_LENGTH, HeaderValue::from(length));
+    }
+    response
+}
+
+/// Drop the body of a response to a `HEAD` request, keeping its headers. The
+/// length the body would have had is recorded in `Content-Length` when known.
+pub(crate) fn strip_head_body(mut response: Response) -> Response {
+    use hyper::body::Body as _;
+
+    let length = response.body().size_hint().exact();
+    if let (Some(length), false) = (length, is_bodiless_status(response.status())) {
+        response
+            .headers_mut()
+            .entry(hyper::header::CONTENT_LENGTH)
+            .or_insert_with(|| HeaderValue::from(length));
+    }
+    *response.body_mut() = Body::empty();
+    response
+}
+
 impl Default for ResponseBuilder {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// A `Cache-Control` response header value.
+///
+/// ```rust
+/// use ultimo::response::CacheControl;
+///
+/// let assets = CacheControl::new().public().max_age(86_400);
+/// assert_eq!(assets.to_string(), "public, max-age=86400");
+/// assert_eq!(CacheControl::new().no_store().to_string(), "no-store");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    public: Option<bool>,
+    max_age: Option<u64>,
+}
+
+impl CacheControl {
+    /// An empty directive list; add directives with the builder methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `no-store`: never cache the response.
+    pub fn no_store(mut self) -> Self {
+        self.no_store = true;
+        self
+    }
+
+    /// `no-cache`: cache, but revalidate with the server before each reuse.
+    pub fn no_cache(mut self) -> Self {
+        self.no_cache = true;
+        self
+    }
+
+    /// `public`: shared caches (CDNs, proxies) may store the response.
+    pub fn public(mut self) -> Self {
+        self.public = Some(true);
+        self
+    }
+
+    /// `private`: only the client's own cache may store the response.
+    pub fn private(mut self) -> Self {
+        self.public = Some(false);
+        self
+    }
+
+    /// `max-age`: the response stays fresh for `secs` seconds.
+    pub fn max_age(mut self, secs: u64) -> Self {
+        self.max_age = Some(secs);
+        self
+    }
+}
+
+impl std::fmt::Display for CacheControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut directives = Vec::new();
+        if self.no_store {
+            directives.push("no-store".to_string());
+        }
+        if self.no_cache {
+            directives.push("no-cache".to_string());
+        }
+        match self.public {
+            Some(true) => directives.push("public".to_string()),
+            Some(false) => directives.push("private".to_string()),
+            None => {}
+        }
+        if let Some(secs) = self.max_age {
+            directives.push(format!("max-age={}", secs));
+        }
+        f.write_str(&directives.join(", "))
+    }
+}
+
+/// Values a handler can return in place of a built [`Response`].
+///
+/// Handlers may return `Result<impl IntoResponse>`, so simple ones need not
+/// go through `ctx.json(...)` / `ctx.text(...)`:
+///
+/// ```rust,ignore
+/// use ultimo::response::{Json, StatusCode};
+///
+/// app.get("/users/:id", |ctx: Context| async move {
+///     let user = find_user(ctx.req.param("id")?).await?;
+///     Ok(Json(user))
+/// });
+/// app.post("/users", |ctx: Context| async move {
+///     let user = create_user(ctx.req.json().await?).await?;
+///     Ok((StatusCode::CREATED, Json(user)))
+/// });
+/// app.get("/ping", |_ctx: Context| async move { Ok("pong") });
+/// ```
+///
+/// Status codes and headers set with `ctx.status` / `ctx.header` only apply
+/// to responses built by `ctx`; cookies always apply.
+pub trait IntoResponse {
+    /// Convert `self` into a response.
+    fn into_response(self) -> Result<Response>;
+}
+
+/// A `Serialize` value returned from a handler as a JSON response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Result<Response> {
+        Ok(self)
+    }
+}
+
+/// `200` with a `text/plain` body.
+impl IntoResponse for String {
+    fn into_response(self) -> Result<Response> {
+        helpers::text(self)
+    }
+}
+
+/// `200` with a `text/plain` body.
+impl IntoResponse for &'static str {
+    fn into_response(self) -> Result<Response> {
+        helpers::text(self)
+    }
+}
+
+/// `200` with the value as a JSON body.
+impl IntoResponse for serde_json::Value {
+    fn into_response(self) -> Result<Response> {
+        helpers::json(&self)
+    }
+}
+
+/// `200` with the value serialized as a JSON body.
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Result<Response> {
+        helpers::json(&self.0)
+    }
+}
+
+/// The status with an empty body.
+impl IntoResponse for StatusCode {
+    fn into_response(self) -> Result<Response> {
+        ResponseBuilder::new().status(self.as_u16()).build()
+    }
+}
+
+/// The inner response with its status replaced.
+impl<T: IntoResponse> IntoResponse for (StatusCode, T) {
+    fn into_response(self) -> Result<Response> {
+        let (status, inner) = self;
+        let mut response = inner.into_response()?;
+        *response.status_mut() = status;
+        Ok(strip_bodiless(response))
+    }
+}
+
 /// Helper functions for common responses
 pub mod helpers {
     use super::*;
@@ -133,6 +542,23 @@ pub mod helpers {
             .build()
     }
 
+    /// Create a 405 response with `Allow` listing the path's methods
+    pub fn method_not_allowed(allow: &[crate::router::Method]) -> Result<Response> {
+        let allow = allow
+            .iter()
+            .map(|m| m.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        ResponseBuilder::new()
+            .status(405)
+            .header("Allow", &allow)
+            .json(&serde_json::json!({
+                "error": "MethodNotAllowed",
+                "message": format!("Method not allowed; use one of: {}", allow)
+            }))?
+            .build()
+    }
+
     /// Create an error response from UltimoError
     pub fn error_response(error: &UltimoError) -> Result<Response> {
         let status = error.status_code();
@@ -144,6 +570,7 @@ pub mod helpers {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use futures_util::StreamExt;
     use serde_json::json;
 
     #[test]
@@ -152,6 +579,127 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_json_pins_content_type_and_nosniff() {
+        let resp = helpers::json(&json!({"message": "Hello"})).unwrap();
+        let content_types: Vec<_> = resp.headers().get_all("content-type").iter().collect();
+        assert_eq!(content_types, ["application/json"]);
+        assert_eq!(resp.headers()["x-content-type-options"], "nosniff");
+    }
+
+    #[test]
+    fn test_json_replaces_earlier_content_type_of_any_case() {
+        let resp = ResponseBuilder::new()
+            .header("content-type", "text/html")
+            .json(&json!({}))
+            .unwrap()
+            .build()
+            .unwrap();
+        let content_types: Vec<_> = resp.headers().get_all("content-type").iter().collect();
+        assert_eq!(content_types, ["application/json"]);
+    }
+
+    #[test]
+    fn test_cache_control_directives() {
+        assert_eq!(CacheControl::new().to_string(), "");
+        assert_eq!(
+            CacheControl::new()
+                .private()
+                .no_cache()
+                .max_age(0)
+                .to_string(),
+            "no-cache, private, max-age=0"
+        );
+        assert_eq!(CacheControl::new().private().public().to_string(), "public");
+    }
+
+    #[test]
+    fn test_into_response_values() {
+        let resp = "pong".into_response().unwrap();
+        assert_eq!(resp.headers()["content-type"], "text/plain; charset=utf-8");
+        assert_eq!(resp.into_body().try_into_bytes().unwrap(), "pong");
+
+        let resp = (StatusCode::CREATED, Json(vec![1, 2]))
+            .into_response()
+            .unwrap();
+        assert_eq!(resp.status(), 201);
+        assert_eq!(resp.headers()["content-type"], "application/json");
+        assert_eq!(resp.into_body().try_into_bytes().unwrap(), "[1,2]");
+
+        let resp = (StatusCode::NO_CONTENT, json!({ "ignored": true }))
+            .into_response()
+            .unwrap();
+        assert_eq!(resp.status(), 204);
+        assert!(resp.headers().get("content-type").is_none());
+        assert!(resp.into_body().try_into_bytes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_empty_json_object_and_array_bodies() {
+        for (value, expected) in [(json!({}), "{}"), (json!([]), "[]")] {
+            let body = helpers::json(&value).unwrap().into_body();
+            assert_eq!(body.try_into_bytes().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_append_header_keeps_repeated_values() {
+        let resp = ResponseBuilder::new()
+            .append_header("Vary", "Origin")
+            .append_header("vary", "Accept-Encoding")
+            .header("X-One", "a")
+            .header("x-one", "b")
+            .build()
+            .unwrap();
+        let vary: Vec<_> = resp.headers().get_all("vary").iter().collect();
+        assert_eq!(vary, ["Origin", "Accept-Encoding"]);
+        let one: Vec<_> = resp.headers().get_all("x-one").iter().collect();
+        assert_eq!(one, ["b"]);
+    }
+
+    #[test]
+    fn test_buffered_body_reports_exact_size() {
+        let body = Body::from("hello");
+        assert_eq!(hyper::body::Body::size_hint(&body).exact(), Some(5));
+        assert!(!body.is_stream());
+        assert_eq!(body.try_into_bytes().unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn test_streamed_body_yields_chunks_in_order() {
+        use http_body_util::BodyExt;
+        let chunks = futures_util::stream::iter(["a", "b", "c"])
+            .map(|c| Ok::<_, std::convert::Infallible>(Bytes::from(c)));
+        let body = Body::from_stream(futures_util::StreamExt::boxed(chunks));
+        assert!(body.is_stream());
+        assert_eq!(hyper::body::Body::size_hint(&body).exact(), None);
+        let body = body.try_into_bytes().unwrap_err();
+        assert_eq!(body.collect().await.unwrap().to_bytes(), "abc");
+    }
+
+    #[test]
+    fn test_bodiless_statuses_drop_body_and_content_type() {
+        for status in [204, 205, 304] {
+            let response = ResponseBuilder::new()
+                .status(status)
+                .json(&json!({"error": "ignored"}))
+                .unwrap()
+                .header("ETag", "\"abc\"")
+                .build()
+                .unwrap();
+            assert!(response.headers().get("content-type").is_none());
+            assert!(response.headers().get("etag").is_some());
+            assert!(hyper::body::Body::is_end_stream(response.body()));
+        }
+    }
+
+    #[test]
+    fn test_strip_bodiless_leaves_other_statuses() {
+        let response = strip_bodiless(helpers::text("kept").unwrap());
+        assert!(response.headers().get("content-type").is_some());
+        assert!(!hyper::body::Body::is_end_stream(response.body()));
+    }
+
     #[test]
     fn test_text_response() {
         let result = helpers::text("Hello World");
@@ -172,6 +720,24 @@ mod tests {
         assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
     }
 
+    #[test]
+    fn test_error_response_status_per_variant() {
+        for (err, status) in [
+            (
+                UltimoError::Unauthorized("x".into()),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (UltimoError::Forbidden("x".into()), StatusCode::FORBIDDEN),
+            (UltimoError::Conflict("x".into()), StatusCode::CONFLICT),
+            (
+                UltimoError::UnprocessableEntity("x".into()),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+        ] {
+            assert_eq!(helpers::error_response(&err).unwrap().status(), status);
+        }
+    }
+
     #[test]
     fn test_response_builder() {
         let result = ResponseBuilder::new()
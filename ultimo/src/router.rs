@@ -4,9 +4,20 @@
 //! - Static paths (/users)
 //! - Path parameters (/users/:id)
 //! - Multiple parameters (/users/:userId/posts/:postId)
-//! - HTTP method matching
+//! - Catch-all wildcards (/files/*path)
+//! - HTTP method matching, with `HEAD` answered by the `GET` route unless a
+//!   `HEAD` route is registered
+//!
+//! When several routes match a path, the most specific wins: segments are
+//! compared left to right and the first difference decides, with
+//! static > `:param` > `*wildcard`. Routes of identical shape resolve to the
+//! first registration, so the result never depends on insertion order
+//! otherwise.
 
+use smallvec::SmallVec;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// HTTP method enum
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -34,6 +45,41 @@ impl Method {
             _ => None,
         }
     }
+
+    /// The method name as sent on the wire (`"GET"`, ...)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::GET => "GET",
+            Method::POST => "POST",
+            Method::PUT => "PUT",
+            Method::DELETE => "DELETE",
+            Method::PATCH => "PATCH",
+            Method::HEAD => "HEAD",
+            Method::OPTIONS => "OPTIONS",
+        }
+    }
+}
+
+/// How the router treats a trailing slash on the request path.
+///
+/// A route's canonical form is the pattern it was registered with, so
+/// `/users` and `/users/` are different canonical paths. The root `/` never
+/// counts as having a trailing slash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingSlash {
+    /// Only the canonical form matches; `/users/` does not reach `/users`.
+    Strict,
+    /// Like `Strict`, but a request for the other form is answered with a
+    /// `301` redirect to the canonical one.
+    Redirect,
+    /// Both forms match (the default).
+    #[default]
+    Ignore,
+}
+
+/// Whether `path` ends in a slash, not counting the root `/`.
+fn has_trailing_slash(path: &str) -> bool {
+    path.len() > 1 && path.ends_with('/')
 }
 
 /// Path parameter map
@@ -56,15 +102,17 @@ enum Segment {
 pub struct Route {
     segments: Vec<Segment>,
     raw_path: String,
+    /// Whether the pattern was registered with a trailing slash.
+    trailing_slash: bool,
 }
 
 impl Route {
     /// Create a new route from a path pattern
     pub fn new(path: &str) -> Self {
-        let segments = Self::parse_path(path);
         Self {
-            segments,
+            segments: Self::parse_path(path),
             raw_path: path.to_string(),
+            trailing_slash: has_trailing_slash(path),
         }
     }
 
@@ -145,17 +193,9 @@ impl Route {
         &self.raw_path
     }
 
-    /// Route specificity: the number of static segments. Higher is more
-    /// specific, so a fully-static route outranks one with parameters.
-    fn specificity(&self) -> usize {
-        self.segments
-            .iter()
-            .filter(|s| matches!(s, Segment::Static(_)))
-            .count()
-    }
-
-    /// The normalized lookup key for a fully-static route (segments joined by
-    /// `/`), or `None` if the route has any parameter. Matches `normalize_path`.
+    /// The lookup key for a fully-static route (segments joined by `/`,
+    /// without any trailing slash), or `None` if the route has any parameter.
+    /// Matches `normalize_path` with the trailing slash removed.
     fn static_key(&self) -> Option<String> {
         let mut parts: Vec<&str> = Vec::with_capacity(self.segments.len());
         for seg in &self.segments {
@@ -169,12 +209,127 @@ impl Route {
 }
 
 /// Normalize a request path to a static-route key: non-empty segments joined by
-/// `/`. Trailing and duplicate slashes are ignored, matching `Route::matches`.
-fn normalize_path(path: &str) -> String {
-    path.split('/')
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<_>>()
-        .join("/")
+/// `/`, without the trailing slash. Duplicate slashes are ignored, matching
+/// `Route::matches`. Borrows from `path` unless it has empty segments.
+fn normalize_path(path: &str) -> Cow<'_, str> {
+    match path.strip_prefix('/') {
+        Some(rest) if !rest.contains("//") => Cow::Borrowed(rest.strip_suffix('/').unwrap_or(rest)),
+        _ => Cow::Owned(
+            path.split('/')
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+                .join("/"),
+        ),
+    }
+}
+
+/// A fully-static route, as stored in the static index.
+#[derive(Debug, Clone)]
+struct StaticRoute {
+    handler_id: usize,
+    pattern: Arc<str>,
+    /// Whether the pattern was registered with a trailing slash.
+    trailing_slash: bool,
+}
+
+/// Path segments and `:param` captures of one lookup; inline for typical
+/// depths, so matching doesn't allocate.
+type Segments<'p> = SmallVec<[&'p str; 8]>;
+
+/// A route that ends at a [`Node`], as stored in the tree.
+#[derive(Debug, Clone)]
+struct Leaf {
+    handler_id: usize,
+    pattern: Arc<str>,
+    /// Parameter names in path order; for a wildcard leaf the last one is the
+    /// wildcard's.
+    names: Vec<String>,
+    /// Whether the pattern was registered with a trailing slash.
+    trailing_slash: bool,
+}
+
+/// One path segment position in the route tree.
+///
+/// Children are split by segment kind so lookup can try them in precedence
+/// order (static, then `:param`, then `*wildcard`) and stop at the first
+/// complete match, which is then the most specific one.
+#[derive(Debug, Default)]
+struct Node {
+    statics: HashMap<String, Node>,
+    param: Option<Box<Node>>,
+    /// Routes ending here, in registration order.
+    leaves: Vec<Leaf>,
+    /// Routes with a `*wildcard` at this position, in registration order.
+    wildcards: Vec<Leaf>,
+}
+
+impl Node {
+    fn insert(&mut self, route: &Route, handler_id: usize, pattern: Arc<str>) {
+        let mut node = self;
+        let mut names = Vec::new();
+        for (i, segment) in route.segments.iter().enumerate() {
+            match segment {
+                Segment::Static(s) => node = node.statics.entry(s.clone()).or_default(),
+                Segment::Param(name) => {
+                    names.push(name.clone());
+                    node = node.param.get_or_insert_with(Box::default);
+                }
+                Segment::Wildcard(name) => {
+                    assert!(
+                        i + 1 == route.segments.len(),
+                        "wildcard `*{}` must be the last segment of route `{}`",
+                        name,
+                        route.raw_path
+                    );
+                    names.push(name.clone());
+                    node.wildcards.push(Leaf {
+                        handler_id,
+                        pattern,
+                        names,
+                        trailing_slash: route.trailing_slash,
+                    });
+                    return;
+                }
+            }
+        }
+        node.leaves.push(Leaf {
+            handler_id,
+            pattern,
+            names,
+            trailing_slash: route.trailing_slash,
+        });
+    }
+
+    /// Depth-first match of `segments[depth..]`, collecting `:param` values
+    /// into `captured`. Returns the leaf and, for a wildcard leaf, the index
+    /// where the wildcard's capture starts.
+    fn find<'a, 'p>(
+        &'a self,
+        segments: &[&'p str],
+        depth: usize,
+        captured: &mut Segments<'p>,
+        allowed: &impl Fn(&Leaf) -> bool,
+    ) -> Option<(&'a Leaf, Option<usize>)> {
+        let Some(&segment) = segments.get(depth) else {
+            return self.leaves.iter().find(|l| allowed(l)).map(|l| (l, None));
+        };
+        if let Some(child) = self.statics.get(segment) {
+            if let Some(hit) = child.find(segments, depth + 1, captured, allowed) {
+                return Some(hit);
+            }
+        }
+        if let Some(child) = &self.param {
+            captured.push(segment);
+            if let Some(hit) = child.find(segments, depth + 1, captured, allowed) {
+                return Some(hit);
+            }
+            captured.pop();
+        }
+        self.wildcards
+            .iter()
+            .find(|l| allowed(l))
+            .map(|l| (l, Some(depth)))
+    }
 }
 
 /// Router entry combining method, route, and handler index
@@ -185,22 +340,38 @@ pub struct RouterEntry {
     pub handler_id: usize,
 }
 
+/// A successful route lookup.
+#[derive(Debug, Clone)]
+pub struct RouteMatch {
+    pub handler_id: usize,
+    pub params: Params,
+    /// The pattern the route was registered with, e.g. `/users/:id`.
+    pub pattern: Arc<str>,
+}
+
 /// Main router struct.
 ///
 /// Lookup is split for speed: fully-static routes go in an O(1) hash index
-/// keyed by `(method, normalized-path)`, and only parameterized routes are
-/// scanned. Because a fully-static match is always the most specific possible
-/// for a path, a hit in the static index wins outright — so the precedence
-/// guarantee (static beats param, ties by registration order) is preserved
-/// while avoiding an O(N) scan over every registered route.
+/// keyed by `(method, normalized-path)`, and parameterized routes go in a
+/// per-method segment tree, so a lookup costs O(path length) rather than a
+/// scan over every registered route. Because a fully-static match is always
+/// the most specific possible for a path, a hit in the static index wins
+/// outright; the tree is walked in precedence order, so the guarantee
+/// (static > param > wildcard, ties by registration order) is preserved.
 #[derive(Debug)]
 pub struct Router {
     /// All routes in registration order — for `routes()` / introspection.
     routes: Vec<RouterEntry>,
-    /// O(1) exact lookup for fully-static routes. First registration wins.
-    static_index: HashMap<(Method, String), usize>,
-    /// Parameterized routes only, scanned when there's no static match.
-    dynamic: Vec<RouterEntry>,
+    /// O(1) exact lookup for fully-static routes, keyed by the path without
+    /// its trailing slash; each key holds the first registration of each
+    /// slash form, in registration order.
+    static_index: HashMap<Method, HashMap<String, Vec<StaticRoute>>>,
+    /// Parameterized routes only, searched when there's no static match.
+    dynamic: HashMap<Method, Node>,
+    /// Trailing-slash policy applied by `find_route`.
+    trailing_slash: TrailingSlash,
+    /// Whether `HEAD` falls back to the `GET` route.
+    auto_head: bool,
 }
 
 impl Router {
@@ -209,24 +380,69 @@ impl Router {
         Self {
             routes: Vec::new(),
             static_index: HashMap::new(),
-            dynamic: Vec::new(),
+            dynamic: HashMap::new(),
+            trailing_slash: TrailingSlash::default(),
+            auto_head: true,
         }
     }
 
+    /// Set how a trailing slash on the request path is treated.
+    pub fn set_trailing_slash(&mut self, mode: TrailingSlash) {
+        self.trailing_slash = mode;
+    }
+
+    /// The configured trailing-slash policy.
+    pub fn trailing_slash(&self) -> TrailingSlash {
+        self.trailing_slash
+    }
+
+    /// Set whether a `HEAD` request with no `HEAD` route of its own is
+    /// matched against the `GET` routes. On by default.
+    pub fn set_auto_head(&mut self, enabled: bool) {
+        self.auto_head = enabled;
+    }
+
+    /// Whether `route` may answer a request whose path does (`slash`) or
+    /// does not end in a slash, under the configured policy.
+    fn slash_allowed(&self, route: &Route, slash: bool) -> bool {
+        self.trailing_slash == TrailingSlash::Ignore || route.trailing_slash == slash
+    }
+
     /// Add a route to the router
     pub fn add_route(&mut self, method: Method, path: &str, handler_id: usize) {
         let route = Route::new(path);
+        let pattern: Arc<str> = path.into();
         let entry = RouterEntry {
             method,
             route: route.clone(),
             handler_id,
         };
         match route.static_key() {
-            // First registration wins (preserves the prior tie-break semantics).
+            // First registration of each form wins (preserves the prior
+            // tie-break semantics).
             Some(key) => {
-                self.static_index.entry((method, key)).or_insert(handler_id);
+                let forms = self
+                    .static_index
+                    .entry(method)
+                    .or_default()
+                    .entry(key)
+                    .or_default();
+                if !forms
+                    .iter()
+                    .any(|f| f.trailing_slash == route.trailing_slash)
+                {
+                    forms.push(StaticRoute {
+                        handler_id,
+                        pattern,
+                        trailing_slash: route.trailing_slash,
+                    });
+                }
             }
-            None => self.dynamic.push(entry.clone()),
+            None => self
+                .dynamic
+                .entry(method)
+                .or_default()
+                .insert(&route, handler_id, pattern),
         }
         self.routes.push(entry);
     }
@@ -234,31 +450,123 @@ impl Router {
     /// Find the best-matching route for the given method and path.
     ///
     /// A fully-static match is the most specific possible for a path, so it wins
-    /// outright (O(1) via the static index). Otherwise only parameterized routes
-    /// are scanned; the most specific wins, ties broken by registration order.
+    /// outright (O(1) via the static index). Otherwise the parameterized routes'
+    /// tree is walked; the most specific wins (see the module docs), ties
+    /// broken by registration order.
+    ///
+    /// The trailing slash is resolved first, per [`TrailingSlash`]: under
+    /// `Ignore` both forms are looked up, otherwise only routes registered in
+    /// the request's form can match.
+    ///
+    /// A `HEAD` request that matches no `HEAD` route falls back to the `GET`
+    /// routes unless disabled with [`set_auto_head`](Self::set_auto_head).
     pub fn find_route(&self, method: Method, path: &str) -> Option<(usize, Params)> {
-        // Fast path: exact static match.
-        let key = normalize_path(path);
-        if let Some(&handler_id) = self.static_index.get(&(method, key)) {
-            return Some((handler_id, Params::new()));
+        self.match_route(method, path)
+            .map(|found| (found.handler_id, found.params))
+    }
+
+    /// Like [`find_route`](Self::find_route), but also returns the pattern
+    /// of the matched route.
+    pub fn match_route(&self, method: Method, path: &str) -> Option<RouteMatch> {
+        let found = self.find_method_route(method, path);
+        if found.is_none() && method == Method::HEAD && self.auto_head {
+            return self.find_method_route(Method::GET, path);
         }
-        // Slow path: scan only the parameterized routes.
-        let mut best: Option<(usize, Params, usize)> = None;
-        for entry in &self.dynamic {
-            if entry.method == method {
-                if let Some(params) = entry.route.matches(path) {
-                    let spec = entry.route.specificity();
-                    let better = match &best {
-                        Some((_, _, best_spec)) => spec > *best_spec,
-                        None => true,
-                    };
-                    if better {
-                        best = Some((entry.handler_id, params, spec));
-                    }
-                }
+        found
+    }
+
+    fn find_method_route(&self, method: Method, path: &str) -> Option<RouteMatch> {
+        // Fast path: exact static match. Under `Ignore` either form may be
+        // registered, and the first registration wins.
+        let slash = has_trailing_slash(path);
+        let hit = self
+            .static_index
+            .get(&method)
+            .and_then(|index| index.get(normalize_path(path).as_ref()))
+            .and_then(|forms| {
+                forms
+                    .iter()
+                    .filter(|f| {
+                        self.trailing_slash == TrailingSlash::Ignore || f.trailing_slash == slash
+                    })
+                    .min_by_key(|f| f.handler_id)
+            });
+        if let Some(hit) = hit {
+            return Some(RouteMatch {
+                handler_id: hit.handler_id,
+                params: Params::new(),
+                pattern: hit.pattern.clone(),
+            });
+        }
+        // Slow path: walk the parameterized routes' tree.
+        let tree = self.dynamic.get(&method)?;
+        let segments: Segments = path.split('/').filter(|s| !s.is_empty()).collect();
+        let mut captured = Segments::new();
+        let allowed = |leaf: &Leaf| {
+            self.trailing_slash == TrailingSlash::Ignore || leaf.trailing_slash == slash
+        };
+        let (leaf, wildcard_from) = tree.find(&segments, 0, &mut captured, &allowed)?;
+        let mut params = Params::with_capacity(leaf.names.len());
+        for (name, value) in leaf.names.iter().zip(&captured) {
+            params.insert(name.clone(), (*value).to_string());
+        }
+        if let (Some(from), Some(name)) = (wildcard_from, leaf.names.last()) {
+            params.insert(name.clone(), segments[from..].join("/"));
+        }
+        Some(RouteMatch {
+            handler_id: leaf.handler_id,
+            params,
+            pattern: leaf.pattern.clone(),
+        })
+    }
+
+    /// Under [`TrailingSlash::Redirect`], the canonical path to redirect to
+    /// when `path` only matches with its trailing slash added or removed.
+    ///
+    /// Returns `None` in the other modes, or when neither form matches.
+    /// Leading slashes are collapsed to one, so the target is always a path
+    /// on this host: `//evil.com/` would otherwise redirect to `//evil.com`,
+    /// which browsers read as another host. Paths containing `\` or control
+    /// characters are never redirected, since browsers also read `/\evil.com`
+    /// as `//evil.com`.
+    pub fn redirect_path(&self, method: Method, path: &str) -> Option<String> {
+        if self.trailing_slash != TrailingSlash::Redirect
+            || path == "/"
+            || path.chars().any(|c| c == '\\' || c.is_control())
+        {
+            return None;
+        }
+        let target = match path.strip_suffix('/') {
+            Some(bare) => bare.trim_end_matches('/').to_string(),
+            None => format!("{}/", path),
+        };
+        let rest = target.trim_start_matches('/');
+        if rest.is_empty() {
+            return None;
+        }
+        let target = format!("/{}", rest);
+        self.find_route(method, &target).map(|_| target)
+    }
+
+    /// Methods with a route matching `path`, in registration order.
+    ///
+    /// Empty means no route matches the path at all (404); otherwise a failed
+    /// [`find_route`](Self::find_route) means the method is not allowed (405).
+    pub fn allowed_methods(&self, path: &str) -> Vec<Method> {
+        let mut methods = Vec::new();
+        let slash = has_trailing_slash(path);
+        for entry in &self.routes {
+            if !methods.contains(&entry.method)
+                && self.slash_allowed(&entry.route, slash)
+                && entry.route.matches(path).is_some()
+            {
+                methods.push(entry.method);
             }
         }
-        best.map(|(id, params, _)| (id, params))
+        if self.auto_head && methods.contains(&Method::GET) && !methods.contains(&Method::HEAD) {
+            methods.push(Method::HEAD);
+        }
+        methods
     }
 
     /// Get all registered routes (useful for debugging)
@@ -376,6 +684,81 @@ mod tests {
         assert!(r.find_route(Method::GET, "/users").is_some());
     }
 
+    #[test]
+    fn trailing_slash_ignore_matches_both_forms() {
+        let mut r = Router::new();
+        r.add_route(Method::GET, "/users", 1);
+        r.add_route(Method::GET, "/posts/", 2);
+        r.add_route(Method::GET, "/users/:id", 3);
+        assert_eq!(r.find_route(Method::GET, "/users/").unwrap().0, 1);
+        assert_eq!(r.find_route(Method::GET, "/posts").unwrap().0, 2);
+        let (id, params) = r.find_route(Method::GET, "/users/7/").unwrap();
+        assert_eq!(id, 3);
+        assert_eq!(params["id"], "7");
+        assert!(r.redirect_path(Method::GET, "/users/").is_none());
+    }
+
+    #[test]
+    fn trailing_slash_strict_matches_registered_form_only() {
+        let mut r = Router::new();
+        r.set_trailing_slash(TrailingSlash::Strict);
+        r.add_route(Method::GET, "/users", 1);
+        r.add_route(Method::GET, "/users/", 2);
+        r.add_route(Method::GET, "/posts/:id", 3);
+        r.add_route(Method::GET, "/", 4);
+        assert_eq!(r.find_route(Method::GET, "/users").unwrap().0, 1);
+        assert_eq!(r.find_route(Method::GET, "/users/").unwrap().0, 2);
+        assert_eq!(r.find_route(Method::GET, "/posts/9").unwrap().1["id"], "9");
+        assert!(r.find_route(Method::GET, "/posts/9/").is_none());
+        assert!(r.allowed_methods("/posts/9/").is_empty());
+        assert_eq!(r.find_route(Method::GET, "/").unwrap().0, 4);
+        assert!(r.redirect_path(Method::GET, "/posts/9/").is_none());
+    }
+
+    #[test]
+    fn trailing_slash_redirect_points_at_canonical_form() {
+        let mut r = Router::new();
+        r.set_trailing_slash(TrailingSlash::Redirect);
+        r.add_route(Method::GET, "/users", 1);
+        r.add_route(Method::GET, "/docs/", 2);
+        r.add_route(Method::GET, "/posts/:id", 3);
+        assert!(r.find_route(Method::GET, "/users/").is_none());
+        assert_eq!(
+            r.redirect_path(Method::GET, "/users/").as_deref(),
+            Some("/users")
+        );
+        assert_eq!(
+            r.redirect_path(Method::GET, "/docs").as_deref(),
+            Some("/docs/")
+        );
+        assert_eq!(
+            r.redirect_path(Method::GET, "/posts/9/").as_deref(),
+            Some("/posts/9")
+        );
+        assert!(r.redirect_path(Method::POST, "/users/").is_none());
+        assert!(r.redirect_path(Method::GET, "/missing/").is_none());
+        assert!(r.redirect_path(Method::GET, "/").is_none());
+    }
+
+    #[test]
+    fn trailing_slash_redirect_stays_on_this_host() {
+        let mut r = Router::new();
+        r.set_trailing_slash(TrailingSlash::Redirect);
+        r.add_route(Method::GET, "/:slug", 1);
+        assert_eq!(
+            r.redirect_path(Method::GET, "//evil.com/").as_deref(),
+            Some("/evil.com")
+        );
+        assert_eq!(
+            r.redirect_path(Method::GET, "///evil.com//").as_deref(),
+            Some("/evil.com")
+        );
+        assert!(r.redirect_path(Method::GET, "//").is_none());
+        assert!(r.redirect_path(Method::GET, "/\\evil.com/").is_none());
+        assert!(r.redirect_path(Method::GET, "/\\/evil.com/").is_none());
+        assert!(r.redirect_path(Method::GET, "/evil.com\t/").is_none());
+    }
+
     #[test]
     fn no_match_returns_none() {
         let mut r = Router::new();
@@ -397,6 +780,95 @@ mod tests {
         assert!(r.find_route(Method::GET, "/route/999").is_none());
     }
 
+    #[test]
+    fn many_param_routes_resolve_correctly() {
+        let mut r = Router::new();
+        for i in 0..500 {
+            r.add_route(Method::GET, &format!("/resource{i}/:id"), i);
+            r.add_route(
+                Method::GET,
+                &format!("/resource{i}/:id/items/*rest"),
+                1000 + i,
+            );
+        }
+        let (id, params) = r.find_route(Method::GET, "/resource250/abc").unwrap();
+        assert_eq!(id, 250);
+        assert_eq!(params["id"], "abc");
+        let (id, params) = r
+            .find_route(Method::GET, "/resource499/7/items/a/b")
+            .unwrap();
+        assert_eq!(id, 1499);
+        assert_eq!(params["id"], "7");
+        assert_eq!(params["rest"], "a/b");
+        assert!(r.find_route(Method::GET, "/resource500/1").is_none());
+        assert!(r.find_route(Method::GET, "/resource1/1/items").is_none());
+    }
+
+    #[test]
+    fn tree_lookup_agrees_with_exhaustive_matching() {
+        let routes = [
+            "/a/:x",
+            "/a/b",
+            "/a/*rest",
+            "/a/:x/c",
+            "/a/b/:y",
+            "/:p/b/c",
+            "/:p/:q/:r",
+            "/:p/*rest",
+            "/a/b/c/",
+            "/x/:y/",
+        ];
+        let paths = [
+            "/a", "/a/b", "/a/z", "/a/b/c", "/a/z/c", "/a/b/d", "/q/b/c", "/q/r/s", "/q/r/s/t",
+            "/a/b/c/", "/x/1", "/x/1/", "/x/1/2", "/", "/a//b",
+        ];
+        // Reference: specificity per segment (static 2 > param 1 > wildcard 0),
+        // compared lexicographically; ties go to the first registration.
+        let rank = |route: &Route| -> Vec<u8> {
+            route
+                .segments
+                .iter()
+                .map(|s| match s {
+                    Segment::Static(_) => 2,
+                    Segment::Param(_) => 1,
+                    Segment::Wildcard(_) => 0,
+                })
+                .collect()
+        };
+        for mode in [
+            TrailingSlash::Ignore,
+            TrailingSlash::Strict,
+            TrailingSlash::Redirect,
+        ] {
+            let mut r = Router::new();
+            r.set_trailing_slash(mode);
+            for (i, route) in routes.iter().enumerate() {
+                r.add_route(Method::GET, route, i);
+            }
+            for path in paths {
+                let slash = has_trailing_slash(path);
+                let mut expected: Option<(usize, Params, Vec<u8>)> = None;
+                for (i, pattern) in routes.iter().enumerate() {
+                    let route = Route::new(pattern);
+                    if !(mode == TrailingSlash::Ignore || route.trailing_slash == slash) {
+                        continue;
+                    }
+                    if let Some(params) = route.matches(path) {
+                        let spec = rank(&route);
+                        if expected.as_ref().is_none_or(|(_, _, best)| spec > *best) {
+                            expected = Some((i, params, spec));
+                        }
+                    }
+                }
+                assert_eq!(
+                    r.find_route(Method::GET, path),
+                    expected.map(|(i, params, _)| (i, params)),
+                    "{path} under {mode:?}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn duplicate_static_route_keeps_first_registration() {
         // Two registrations of the same static path: the first wins.
@@ -424,6 +896,64 @@ mod tests {
         assert_eq!(r.find_route(Method::POST, "/x").unwrap().0, 2);
     }
 
+    #[test]
+    fn allowed_methods_lists_methods_for_matching_path() {
+        let mut r = Router::new();
+        r.add_route(Method::GET, "/users/:id", 0);
+        r.add_route(Method::DELETE, "/users/:id", 1);
+        r.add_route(Method::GET, "/users/:id", 2);
+        r.add_route(Method::POST, "/users", 3);
+
+        assert!(r.find_route(Method::POST, "/users/1").is_none());
+        assert_eq!(
+            r.allowed_methods("/users/1"),
+            vec![Method::GET, Method::DELETE, Method::HEAD]
+        );
+        assert_eq!(r.allowed_methods("/users"), vec![Method::POST]);
+        assert!(r.allowed_methods("/posts").is_empty());
+    }
+
+    #[test]
+    fn match_route_returns_registered_pattern() {
+        let mut r = Router::new();
+        r.add_route(Method::GET, "/users", 0);
+        r.add_route(Method::GET, "/users/:id", 1);
+        r.add_route(Method::GET, "/files/*path", 2);
+
+        assert_eq!(
+            &*r.match_route(Method::GET, "/users").unwrap().pattern,
+            "/users"
+        );
+        let found = r.match_route(Method::GET, "/users/42").unwrap();
+        assert_eq!((found.handler_id, &*found.pattern), (1, "/users/:id"));
+        assert_eq!(found.params["id"], "42");
+        let found = r.match_route(Method::HEAD, "/files/a/b.txt").unwrap();
+        assert_eq!(&*found.pattern, "/files/*path");
+    }
+
+    #[test]
+    fn head_falls_back_to_get_routes() {
+        let mut r = Router::new();
+        r.add_route(Method::GET, "/users", 0);
+        r.add_route(Method::GET, "/users/:id", 1);
+        r.add_route(Method::GET, "/files/*path", 2);
+        r.add_route(Method::HEAD, "/files/*path", 3);
+        r.add_route(Method::POST, "/posts", 4);
+
+        assert_eq!(r.find_route(Method::HEAD, "/users").unwrap().0, 0);
+        let (id, params) = r.find_route(Method::HEAD, "/users/7").unwrap();
+        assert_eq!((id, params["id"].as_str()), (1, "7"));
+        // An explicit HEAD route wins over the GET fallback.
+        assert_eq!(r.find_route(Method::HEAD, "/files/a.txt").unwrap().0, 3);
+        assert!(r.find_route(Method::HEAD, "/posts").is_none());
+        assert_eq!(r.allowed_methods("/posts"), vec![Method::POST]);
+
+        r.set_auto_head(false);
+        assert!(r.find_route(Method::HEAD, "/users").is_none());
+        assert_eq!(r.allowed_methods("/users"), vec![Method::GET]);
+        assert_eq!(r.find_route(Method::HEAD, "/files/a.txt").unwrap().0, 3);
+    }
+
     // --- Wildcard segment tests ---
 
     #[test]
@@ -461,10 +991,22 @@ mod tests {
     }
 
     #[test]
-    fn wildcard_specificity_equals_static_prefix_count() {
-        let route = Route::new("/assets/public/*path");
-        // "assets" and "public" are static segments; wildcard counts as 0
-        assert_eq!(route.specificity(), 2);
+    fn wildcard_specificity_ranks_below_static_prefix() {
+        // "assets" and "public" are static segments; wildcard ranks lowest
+        let routes = [
+            "/assets/public/*path",
+            "/assets/:dir/*path",
+            "/assets/*path",
+        ];
+        assert_winner(&routes, "/assets/public/a.css", "/assets/public/*path");
+        assert_winner(&routes, "/assets/img/a.png", "/assets/:dir/*path");
+        assert_winner(&routes, "/assets/a.png", "/assets/*path");
+    }
+
+    #[test]
+    #[should_panic(expected = "must be the last segment")]
+    fn wildcard_before_other_segments_is_rejected() {
+        Router::new().add_route(Method::GET, "/files/*path/edit", 0);
     }
 
     #[test]
@@ -475,4 +1017,86 @@ mod tests {
         assert_eq!(id, 42);
         assert_eq!(params["path"], "js/app.js");
     }
+
+    // --- Precedence matrix: static > param > wildcard ---
+
+    /// Register `routes` (handler id = index) in the given order and in
+    /// reverse, asserting `path` resolves to `expected` both times.
+    fn assert_winner(routes: &[&str], path: &str, expected: &str) {
+        for reversed in [false, true] {
+            let mut r = Router::new();
+            let mut order: Vec<usize> = (0..routes.len()).collect();
+            if reversed {
+                order.reverse();
+            }
+            for &i in &order {
+                r.add_route(Method::GET, routes[i], i);
+            }
+            let (id, _) = r
+                .find_route(Method::GET, path)
+                .unwrap_or_else(|| panic!("{path} matched nothing"));
+            assert_eq!(
+                routes[id],
+                expected,
+                "{path} (registered {}in order)",
+                if reversed { "in reverse " } else { "" }
+            );
+        }
+    }
+
+    #[test]
+    fn precedence_static_beats_wildcard() {
+        assert_winner(
+            &["/files/*path", "/files/special"],
+            "/files/special",
+            "/files/special",
+        );
+        assert_winner(
+            &["/files/*path", "/files/special"],
+            "/files/other",
+            "/files/*path",
+        );
+    }
+
+    #[test]
+    fn precedence_param_beats_wildcard() {
+        let routes = ["/files/*path", "/files/:name"];
+        assert_winner(&routes, "/files/a.txt", "/files/:name");
+        assert_winner(&routes, "/files/dir/a.txt", "/files/*path");
+    }
+
+    #[test]
+    fn precedence_static_beats_param_beats_wildcard() {
+        let routes = ["/files/*path", "/files/:name", "/files/special"];
+        assert_winner(&routes, "/files/special", "/files/special");
+        assert_winner(&routes, "/files/other", "/files/:name");
+        assert_winner(&routes, "/files/a/b", "/files/*path");
+    }
+
+    #[test]
+    fn precedence_is_decided_by_first_differing_segment() {
+        let routes = ["/:org/repos", "/acme/:section"];
+        assert_winner(&routes, "/acme/repos", "/acme/:section");
+        assert_winner(&routes, "/other/repos", "/:org/repos");
+
+        let routes = ["/a/:x/:y", "/a/*rest", "/a/:x/c"];
+        assert_winner(&routes, "/a/b/c", "/a/:x/c");
+        assert_winner(&routes, "/a/b/d", "/a/:x/:y");
+        assert_winner(&routes, "/a/b/c/d", "/a/*rest");
+    }
+
+    #[test]
+    fn precedence_prefers_longer_static_prefix_for_wildcards() {
+        let routes = ["/static/*path", "/static/img/*path"];
+        assert_winner(&routes, "/static/img/logo.png", "/static/img/*path");
+        assert_winner(&routes, "/static/css/app.css", "/static/*path");
+    }
+
+    #[test]
+    fn identical_shapes_keep_first_registration() {
+        let mut r = Router::new();
+        r.add_route(Method::GET, "/users/:id", 1);
+        r.add_route(Method::GET, "/users/:name", 2);
+        assert_eq!(r.find_route(Method::GET, "/users/7").unwrap().0, 1);
+    }
 }
@@ -2,6 +2,8 @@
 //!
 //! Provides type-safe RPC functionality with automatic TypeScript client generation.
 
+mod query;
+
 use crate::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -14,7 +16,8 @@ use std::sync::Arc;
 pub use ts_rs::TS;
 
 /// RPC mode determines how procedures are exposed as HTTP endpoints
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum RpcMode {
     /// Single JSON-RPC endpoint: POST /rpc with {"method": "...", "params": {}}
     ///
@@ -43,17 +46,28 @@ pub type RpcHandler = Arc<dyn Fn(serde_json::Value) -> RpcHandlerFuture + Send +
 pub type RpcHandlerFuture =
     std::pin::Pin<Box<dyn std::future::Future<Output = Result<serde_json::Value>> + Send>>;
 
+/// Guard run before a procedure with its raw input; an `Err` rejects the call
+/// and is returned in place of the procedure's output.
+pub type RpcGuard = Arc<dyn Fn(&serde_json::Value) -> Result<()> + Send + Sync>;
+
 /// RPC registry for managing procedures
 #[derive(Clone)]
 pub struct RpcRegistry {
     mode: RpcMode,
     procedures: Arc<std::sync::Mutex<HashMap<String, RpcHandler>>>,
+    /// Query procedures again, reading their input from query-string values
+    /// (see [`call_query`](Self::call_query)).
+    query_procedures: Arc<std::sync::Mutex<HashMap<String, RpcHandler>>>,
     type_definitions: Arc<std::sync::Mutex<Vec<TypeDefinition>>>,
     metadata: Arc<std::sync::Mutex<HashMap<String, ProcedureMetadata>>>,
     /// TS interface/type declarations collected from registered procedure
     /// input/output types (keyed by TS name → declaration). Populated by the
     /// `client-gen` query/mutation methods; empty otherwise.
     type_decls: Arc<std::sync::Mutex<std::collections::BTreeMap<String, String>>>,
+    /// Guards run before every procedure, in the order added.
+    default_guards: Arc<std::sync::Mutex<Vec<RpcGuard>>>,
+    /// Per-procedure guards, run after the registry-wide ones.
+    guards: Arc<std::sync::Mutex<HashMap<String, Vec<RpcGuard>>>>,
 }
 
 /// Type definition for TypeScript generation
@@ -73,6 +87,73 @@ pub struct ProcedureMetadata {
     pub is_query: bool, // true = GET (idempotent), false = POST (mutation)
 }
 
+/// Version of the [`RpcSchema`] JSON format, bumped on incompatible changes.
+pub const RPC_SCHEMA_VERSION: u32 = 1;
+
+/// Environment variable that makes [`Ultimo::listen`](crate::Ultimo::listen)
+/// write the mounted registry's [`RpcSchema`] to the file it names and return
+/// instead of serving. `ultimo generate` sets it when running the app.
+pub const EMIT_RPC_SCHEMA_ENV: &str = "ULTIMO_EMIT_RPC_SCHEMA";
+
+/// A registry's procedures and TypeScript types, as plain data.
+///
+/// This is the JSON interchange format between an app and `ultimo generate`:
+/// the app writes it (see [`Ultimo::mount_rpc`](crate::Ultimo::mount_rpc))
+/// and the CLI turns it into a client, so neither needs the other's code.
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "mode": "jsonrpc",
+///   "base_path": "/rpc",
+///   "procedures": [
+///     { "name": "getUser", "kind": "query", "input": "GetUserInput", "output": "User" }
+///   ],
+///   "types": { "User": "type User = { id: number, name: string, };" }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcSchema {
+    /// Format version; [`RPC_SCHEMA_VERSION`] for schemas this crate writes.
+    pub version: u32,
+    /// How the procedures are exposed over HTTP, which decides the shape of
+    /// the generated client.
+    pub mode: RpcMode,
+    /// Path the procedures are mounted at, used as the client's default base
+    /// URL. Without it the client defaults to `/api/rpc` (JSON-RPC) or `/api`
+    /// (REST).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_path: Option<String>,
+    /// Procedures in registration order.
+    pub procedures: Vec<ProcedureSchema>,
+    /// TypeScript declarations of the input/output types, keyed by type name.
+    #[serde(default)]
+    pub types: std::collections::BTreeMap<String, String>,
+}
+
+/// One procedure of an [`RpcSchema`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcedureSchema {
+    /// Procedure name, as registered and as called by the client.
+    pub name: String,
+    /// Query or mutation.
+    pub kind: ProcedureKind,
+    /// TypeScript type of the input.
+    pub input: String,
+    /// TypeScript type of the output.
+    pub output: String,
+}
+
+/// Whether a procedure reads (`GET` in REST mode) or writes (`POST`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcedureKind {
+    /// Reads data; `GET` with query-string input in REST mode.
+    Query,
+    /// Changes state; `POST` with a JSON body in REST mode.
+    Mutation,
+}
+
 /// Collect the TypeScript declarations of `T` and all of its transitive
 /// struct/enum dependencies into `out` (keyed by TS type name, so duplicates
 /// across procedures collapse). Primitives and containers (`number`,
@@ -113,7 +194,11 @@ fn collect_type_decls<T: ts_rs::TS + 'static>(
             if is_named_type && U::inline(self.cfg) != name {
                 self.decls.insert(name, U::decl(self.cfg));
             }
-            U::visit_dependencies(self); // always recurse into inner types
+            // Always recurse into inner types. A container's element type is
+            // one of its generics rather than a dependency, so a top-level
+            // `Vec<User>` only reaches `User` through `visit_generics`.
+            U::visit_dependencies(self);
+            U::visit_generics(self);
         }
     }
 
@@ -137,12 +222,51 @@ impl RpcRegistry {
         Self {
             mode,
             procedures: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            query_procedures: Arc::new(std::sync::Mutex::new(HashMap::new())),
             type_definitions: Arc::new(std::sync::Mutex::new(Vec::new())),
             metadata: Arc::new(std::sync::Mutex::new(HashMap::new())),
             type_decls: Arc::new(std::sync::Mutex::new(std::collections::BTreeMap::new())),
+            default_guards: Arc::new(std::sync::Mutex::new(Vec::new())),
+            guards: Arc::new(std::sync::Mutex::new(HashMap::new())),
         }
     }
 
+    /// Add a guard that runs before every procedure in this registry.
+    ///
+    /// ```rust
+    /// use ultimo::prelude::*;
+    ///
+    /// let rpc = RpcRegistry::new().with_guard(|input| {
+    ///     if input.get("token").is_some() {
+    ///         Ok(())
+    ///     } else {
+    ///         Err(UltimoError::Unauthorized("missing token".into()))
+    ///     }
+    /// });
+    /// ```
+    pub fn with_guard<G>(self, guard: G) -> Self
+    where
+        G: Fn(&serde_json::Value) -> Result<()> + Send + Sync + 'static,
+    {
+        self.default_guards.lock().unwrap().push(Arc::new(guard));
+        self
+    }
+
+    /// Add a guard to the procedure `name`. Guards compose: each one added runs
+    /// in order, after the registry-wide guards, and the first `Err` wins.
+    pub fn guard<G>(&self, name: impl Into<String>, guard: G) -> &Self
+    where
+        G: Fn(&serde_json::Value) -> Result<()> + Send + Sync + 'static,
+    {
+        self.guards
+            .lock()
+            .unwrap()
+            .entry(name.into())
+            .or_default()
+            .push(Arc::new(guard));
+        self
+    }
+
     /// Get the current RPC mode
     pub fn mode(&self) -> RpcMode {
         self.mode
@@ -209,6 +333,48 @@ impl RpcRegistry {
         self.procedure(name, handler, ts_input, ts_output, false)
     }
 
+    /// [`query_with_types`](Self::query_with_types) with a guard that runs
+    /// before the handler (see [`guard`](Self::guard)).
+    pub fn query_with_guard<F, Fut, I, O, G>(
+        &self,
+        name: impl Into<String>,
+        handler: F,
+        guard: G,
+        ts_input: String,
+        ts_output: String,
+    ) where
+        F: Fn(I) -> Fut + Send + Sync + Clone + 'static,
+        Fut: std::future::Future<Output = Result<O>> + Send + 'static,
+        I: for<'de> Deserialize<'de> + 'static,
+        O: Serialize + 'static,
+        G: Fn(&serde_json::Value) -> Result<()> + Send + Sync + 'static,
+    {
+        let name = name.into();
+        self.guard(name.clone(), guard);
+        self.procedure(name, handler, ts_input, ts_output, true)
+    }
+
+    /// [`mutation_with_types`](Self::mutation_with_types) with a guard that
+    /// runs before the handler (see [`guard`](Self::guard)).
+    pub fn mutation_with_guard<F, Fut, I, O, G>(
+        &self,
+        name: impl Into<String>,
+        handler: F,
+        guard: G,
+        ts_input: String,
+        ts_output: String,
+    ) where
+        F: Fn(I) -> Fut + Send + Sync + Clone + 'static,
+        Fut: std::future::Future<Output = Result<O>> + Send + 'static,
+        I: for<'de> Deserialize<'de> + 'static,
+        O: Serialize + 'static,
+        G: Fn(&serde_json::Value) -> Result<()> + Send + Sync + 'static,
+    {
+        let name = name.into();
+        self.guard(name.clone(), guard);
+        self.procedure(name, handler, ts_input, ts_output, false)
+    }
+
     /// Register a query procedure (idempotent; GET in REST mode). Input/output
     /// TypeScript types are derived from the Rust types via `ts-rs`.
     #[cfg(feature = "client-gen")]
@@ -220,8 +386,8 @@ impl RpcRegistry {
         O: Serialize + ts_rs::TS + 'static,
     {
         let cfg = ts_rs::Config::default();
-        let ts_input = <I as ts_rs::TS>::name(&cfg);
-        let ts_output = <O as ts_rs::TS>::name(&cfg);
+        let ts_input = array_shorthand(<I as ts_rs::TS>::name(&cfg));
+        let ts_output = array_shorthand(<O as ts_rs::TS>::name(&cfg));
         {
             let mut decls = self.type_decls.lock().unwrap();
             collect_type_decls::<I>(&mut decls);
@@ -241,8 +407,8 @@ impl RpcRegistry {
         O: Serialize + ts_rs::TS + 'static,
     {
         let cfg = ts_rs::Config::default();
-        let ts_input = <I as ts_rs::TS>::name(&cfg);
-        let ts_output = <O as ts_rs::TS>::name(&cfg);
+        let ts_input = array_shorthand(<I as ts_rs::TS>::name(&cfg));
+        let ts_output = array_shorthand(<O as ts_rs::TS>::name(&cfg));
         {
             let mut decls = self.type_decls.lock().unwrap();
             collect_type_decls::<I>(&mut decls);
@@ -268,6 +434,7 @@ impl RpcRegistry {
         let name = name.into();
         let name_clone = name.clone();
 
+        let query_fn = is_query.then(|| handler.clone());
         let wrapped_handler: RpcHandler = Arc::new(move |input| {
             let handler = handler.clone();
             Box::pin(async move {
@@ -285,6 +452,25 @@ impl RpcRegistry {
             .unwrap()
             .insert(name.clone(), wrapped_handler);
 
+        if let Some(handler) = query_fn {
+            let query_handler: RpcHandler = Arc::new(move |input| {
+                let handler = handler.clone();
+                Box::pin(async move {
+                    let input = I::deserialize(query::QueryValue(input)).map_err(|e| {
+                        crate::UltimoError::BadRequest(format!("Invalid input: {}", e))
+                    })?;
+                    let output = handler(input).await?;
+                    serde_json::to_value(output).map_err(|e| {
+                        crate::UltimoError::Internal(format!("Serialization error: {}", e))
+                    })
+                })
+            });
+            self.query_procedures
+                .lock()
+                .unwrap()
+                .insert(name.clone(), query_handler);
+        }
+
         // Store type definition metadata
         let type_def = TypeDefinition {
             name: name_clone.clone(),
@@ -305,10 +491,32 @@ impl RpcRegistry {
         self.metadata.lock().unwrap().insert(name, metadata);
     }
 
-    /// Call an RPC procedure
+    /// Call an RPC procedure. Registry-wide guards run first, then the
+    /// procedure's own; the first rejection is returned without calling it.
     pub async fn call(&self, name: &str, input: serde_json::Value) -> Result<serde_json::Value> {
+        self.dispatch(&self.procedures, name, input).await
+    }
+
+    /// [`call`](Self::call) for a query procedure whose input was built from
+    /// query-string values (strings, or arrays of strings for repeated keys).
+    /// Strings are parsed only where the input type expects a number or
+    /// boolean, so string fields keep values like `"0123"` intact.
+    pub(crate) async fn call_query(
+        &self,
+        name: &str,
+        input: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        self.dispatch(&self.query_procedures, name, input).await
+    }
+
+    async fn dispatch(
+        &self,
+        procedures: &std::sync::Mutex<HashMap<String, RpcHandler>>,
+        name: &str,
+        input: serde_json::Value,
+    ) -> Result<serde_json::Value> {
         let handler = {
-            let procedures = self.procedures.lock().unwrap();
+            let procedures = procedures.lock().unwrap();
             procedures
                 .get(name)
                 .ok_or_else(|| {
@@ -317,235 +525,72 @@ impl RpcRegistry {
                 .clone()
         };
 
+        let default_guards = self.default_guards.lock().unwrap().clone();
+        let guards = self
+            .guards
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .unwrap_or_default();
+        for guard in default_guards.iter().chain(&guards) {
+            guard(&input)?;
+        }
+
         handler(input).await
     }
 
+    /// Call several procedures concurrently, returning results in request
+    /// order. A failing call only affects its own slot.
+    pub async fn call_batch(&self, requests: Vec<RpcRequest>) -> Vec<Result<serde_json::Value>> {
+        let calls = requests
+            .into_iter()
+            .map(|req| async move { self.call(&req.method, req.params).await });
+        futures_util::future::join_all(calls).await
+    }
+
     /// Get all registered procedure names
     pub fn list_procedures(&self) -> Vec<String> {
         self.procedures.lock().unwrap().keys().cloned().collect()
     }
 
-    /// Generate TypeScript client code
-    pub fn generate_typescript_client(&self) -> String {
-        match self.mode {
-            RpcMode::JsonRpc => self.generate_json_rpc_client(),
-            RpcMode::Rest => self.generate_rest_client(),
-        }
-    }
-
-    /// Generate JSON-RPC style client (single endpoint)
-    fn generate_json_rpc_client(&self) -> String {
-        let type_defs = self.type_definitions.lock().unwrap();
-
-        let mut client = String::from(
-            r#"// Auto-generated TypeScript client for Ultimo RPC (JSON-RPC 2.0)
-// DO NOT EDIT - This file is automatically generated
-
-export interface JsonRpcError {
-  code: number;
-  message: string;
-  data?: unknown;
-}
-
-export class JsonRpcClientError extends Error {
-  constructor(public readonly error: JsonRpcError) {
-    super(error.message);
-    this.name = 'JsonRpcClientError';
-  }
-}
-
-export class UltimoRpcClient {
-  private _idCounter = 0;
-
-  constructor(private baseUrl: string = '/api/rpc') {}
-
-  private nextId(): number {
-    return ++this._idCounter;
-  }
-
-  private async call<T>(method: string, params: unknown): Promise<T> {
-    const id = this.nextId();
-    const response = await fetch(this.baseUrl, {
-      method: 'POST',
-      headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({ jsonrpc: '2.0', method, params, id }),
-    });
-
-    if (!response.ok) {
-      throw new Error(`HTTP ${response.status}: ${response.statusText}`);
-    }
-
-    const data = await response.json();
-    if (data.error) {
-      throw new JsonRpcClientError(data.error);
+    /// Metadata for every registered procedure, sorted by name.
+    pub fn procedure_metadata(&self) -> Vec<ProcedureMetadata> {
+        let mut metadata: Vec<_> = self.metadata.lock().unwrap().values().cloned().collect();
+        metadata.sort_by(|a, b| a.name.cmp(&b.name));
+        metadata
     }
-    return data.result as T;
-  }
 
-  /** Send a notification (fire-and-forget, no response expected) */
-  async notify(method: string, params?: unknown): Promise<void> {
-    await fetch(this.baseUrl, {
-      method: 'POST',
-      headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({ jsonrpc: '2.0', method, params }),
-    });
-  }
-
-  /** Execute multiple RPC calls in a single HTTP request */
-  async batch<T extends Array<{ method: string; params?: unknown }>>(
-    calls: T
-  ): Promise<Array<{ result?: unknown; error?: JsonRpcError }>> {
-    const requests = calls.map((call) => ({
-      jsonrpc: '2.0' as const,
-      method: call.method,
-      params: call.params,
-      id: this.nextId(),
-    }));
-
-    const response = await fetch(this.baseUrl, {
-      method: 'POST',
-      headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify(requests),
-    });
-
-    if (!response.ok) {
-      throw new Error(`HTTP ${response.status}: ${response.statusText}`);
-    }
-
-    const data: Array<{ result?: unknown; error?: JsonRpcError; id: number }> =
-      await response.json();
-
-    // Sort by id to match input order
-    const sorted = requests.map((req) => {
-      const res = data.find((r) => r.id === req.id);
-      return res ? { result: res.result, error: res.error } : { error: { code: -1, message: 'No response' } };
-    });
-
-    return sorted;
-  }
-
-"#,
-        );
-
-        // Generate method for each procedure
-        for def in type_defs.iter() {
-            client.push_str(&format!(
-                r#"  async {}(params: {}): Promise<{}> {{
-    return this.call('{}', params);
-  }}
-
-"#,
-                def.name, def.ts_input, def.ts_output, def.name
-            ));
-        }
-
-        client.push_str("}\n");
-        self.append_type_definitions(&mut client);
-        client
+    /// Generate TypeScript client code
+    pub fn generate_typescript_client(&self) -> String {
+        self.schema().generate_typescript_client()
     }
 
-    /// Generate REST style client (individual endpoints)
-    fn generate_rest_client(&self) -> String {
-        let type_defs = self.type_definitions.lock().unwrap();
+    /// Describe the registered procedures and their types as an
+    /// [`RpcSchema`], e.g. to hand to `ultimo generate`.
+    pub fn schema(&self) -> RpcSchema {
         let metadata = self.metadata.lock().unwrap();
-
-        let mut client = String::from(
-            r#"// Auto-generated TypeScript client for Ultimo RPC (REST Mode)
-// DO NOT EDIT - This file is automatically generated
-
-export class UltimoRpcClient {
-  constructor(private baseUrl: string = '/api') {}
-
-  private async get<T>(path: string, params?: Record<string, any>): Promise<T> {
-    const url = new URL(this.baseUrl + path, window.location.origin);
-    if (params) {
-      Object.entries(params).forEach(([key, value]) => {
-        url.searchParams.append(key, String(value));
-      });
-    }
-
-    const response = await fetch(url.toString(), {
-      method: 'GET',
-      headers: {
-        'Content-Type': 'application/json',
-      },
-    });
-
-    if (!response.ok) {
-      const error = await response.json().catch(() => ({ message: response.statusText }));
-      throw new Error(error.message || 'Request failed');
-    }
-
-    return response.json();
-  }
-
-  private async post<T>(path: string, body: any): Promise<T> {
-    const response = await fetch(this.baseUrl + path, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-      },
-      body: JSON.stringify(body),
-    });
-
-    if (!response.ok) {
-      const error = await response.json().catch(() => ({ message: response.statusText }));
-      throw new Error(error.message || 'Request failed');
-    }
-
-    return response.json();
-  }
-
-"#,
-        );
-
-        // Generate method for each procedure
-        for def in type_defs.iter() {
-            let meta = metadata.get(&def.name);
-            let is_query = meta.map(|m| m.is_query).unwrap_or(false);
-
-            if is_query {
-                // Query: Use GET
-                client.push_str(&format!(
-                    r#"  async {}(params: {}): Promise<{}> {{
-    return this.get('/{}', params);
-  }}
-
-"#,
-                    def.name, def.ts_input, def.ts_output, def.name,
-                ));
-            } else {
-                // Mutation: Use POST
-                client.push_str(&format!(
-                    r#"  async {}(params: {}): Promise<{}> {{
-    return this.post('/{}'{}params);
-  }}
-
-"#,
-                    def.name,
-                    def.ts_input,
-                    def.ts_output,
-                    def.name,
-                    if def.ts_input == "{}" { "" } else { ", " }
-                ));
-            }
-        }
-
-        client.push_str("}\n");
-        self.append_type_definitions(&mut client);
-        client
-    }
-
-    /// Append collected type declarations to the generated client.
-    fn append_type_definitions(&self, client: &mut String) {
-        let decls = self.type_decls.lock().unwrap();
-        if decls.is_empty() {
-            return;
-        }
-        client.push_str("\n// Type Definitions\n");
-        for decl in decls.values() {
-            client.push_str(decl);
-            client.push('\n');
+        let procedures = self
+            .type_definitions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|def| ProcedureSchema {
+                name: def.name.clone(),
+                kind: match metadata.get(&def.name) {
+                    Some(meta) if meta.is_query => ProcedureKind::Query,
+                    _ => ProcedureKind::Mutation,
+                },
+                input: def.ts_input.clone(),
+                output: def.ts_output.clone(),
+            })
+            .collect();
+        RpcSchema {
+            version: RPC_SCHEMA_VERSION,
+            mode: self.mode,
+            base_path: None,
+            procedures,
+            types: self.type_decls.lock().unwrap().clone(),
         }
     }
 
@@ -595,6 +640,13 @@ export class UltimoRpcClient {
             )
             .build();
 
+        // Types derived via `client-gen` become named components first, so
+        // procedure schemas (and other components) can `$ref` them.
+        {
+            let decls = self.type_decls.lock().unwrap();
+            spec.add_ts_schemas(decls.values().filter_map(|decl| split_type_decl(decl)));
+        }
+
         let type_defs = self.type_definitions.lock().unwrap();
         let metadata = self.metadata.lock().unwrap();
 
@@ -621,7 +673,7 @@ export class UltimoRpcClient {
 
                     // Add response
                     let response_schema =
-                        crate::openapi::OpenApiSpec::ts_to_schema(&type_def.ts_output);
+                        component_schema(&mut spec, &type_def.ts_output, &type_def.name, "Output");
                     let mut content = HashMap::new();
                     content.insert(
                         "application/json".to_string(),
@@ -660,6 +712,7 @@ export class UltimoRpcClient {
                                         properties: None,
                                         required: None,
                                         items: None,
+                                        nullable: None,
                                         reference: None,
                                     },
                                 });
@@ -671,8 +724,12 @@ export class UltimoRpcClient {
                         }
                     } else {
                         // POST: Add request body
-                        let request_schema =
-                            crate::openapi::OpenApiSpec::ts_to_schema(&type_def.ts_input);
+                        let request_schema = component_schema(
+                            &mut spec,
+                            &type_def.ts_input,
+                            &type_def.name,
+                            "Input",
+                        );
                         let mut content = HashMap::new();
                         content.insert(
                             "application/json".to_string(),
@@ -734,6 +791,7 @@ export class UltimoRpcClient {
                                                     properties: None,
                                                     required: None,
                                                     items: None,
+                                                    nullable: None,
                                                     reference: None,
                                                 }),
                                             );
@@ -741,6 +799,7 @@ export class UltimoRpcClient {
                                         }),
                                         required: Some(vec!["result".to_string()]),
                                         items: None,
+                                        nullable: None,
                                         reference: None,
                                     },
                                     example: None,
@@ -785,6 +844,7 @@ export class UltimoRpcClient {
                                                     properties: None,
                                                     required: None,
                                                     items: None,
+                                                    nullable: None,
                                                     reference: None,
                                                 }),
                                             );
@@ -796,6 +856,7 @@ export class UltimoRpcClient {
                                                     properties: None,
                                                     required: None,
                                                     items: None,
+                                                    nullable: None,
                                                     reference: None,
                                                 }),
                                             );
@@ -803,6 +864,7 @@ export class UltimoRpcClient {
                                         }),
                                         required: Some(vec!["method".to_string(), "params".to_string()]),
                                         items: None,
+                                        nullable: None,
                                         reference: None,
                                     },
                                     example: Some(serde_json::json!({
@@ -818,25 +880,277 @@ export class UltimoRpcClient {
                     responses,
                 };
 
-                let path_item = PathItem {
-                    get: None,
-                    post: Some(operation),
-                    put: None,
-                    delete: None,
-                    patch: None,
-                };
+                let path_item = PathItem {
+                    get: None,
+                    post: Some(operation),
+                    put: None,
+                    delete: None,
+                    patch: None,
+                };
+
+                spec.add_path(path, path_item);
+            }
+        }
+
+        spec
+    }
+}
+
+impl Default for RpcRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RpcSchema {
+    /// Parse a schema written by [`to_json`](Self::to_json), rejecting
+    /// versions newer than this crate understands.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let schema: Self = serde_json::from_str(json)?;
+        if schema.version > RPC_SCHEMA_VERSION {
+            return Err(crate::UltimoError::BadRequest(format!(
+                "RPC schema version {} is newer than the supported version {}; upgrade ultimo",
+                schema.version, RPC_SCHEMA_VERSION
+            )));
+        }
+        Ok(schema)
+    }
+
+    /// Serialize to pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("RpcSchema serializes")
+    }
+
+    /// Generate the TypeScript client for the schema's mode.
+    pub fn generate_typescript_client(&self) -> String {
+        match self.mode {
+            RpcMode::JsonRpc => self.generate_json_rpc_client(),
+            RpcMode::Rest => self.generate_rest_client(),
+        }
+    }
+
+    /// Default base URL of the generated client.
+    fn base_url(&self) -> &str {
+        match (&self.base_path, self.mode) {
+            (Some(path), _) => path,
+            (None, RpcMode::JsonRpc) => "/api/rpc",
+            (None, RpcMode::Rest) => "/api",
+        }
+    }
+
+    /// Generate JSON-RPC style client (single endpoint)
+    fn generate_json_rpc_client(&self) -> String {
+        let mut client = String::from(
+            r#"// Auto-generated TypeScript client for Ultimo RPC (JSON-RPC 2.0)
+// DO NOT EDIT - This file is automatically generated
+
+export interface JsonRpcError {
+  code: number;
+  message: string;
+  data?: unknown;
+}
+
+export class JsonRpcClientError extends Error {
+  constructor(public readonly error: JsonRpcError) {
+    super(error.message);
+    this.name = 'JsonRpcClientError';
+  }
+}
+
+export class UltimoRpcClient {
+  private _idCounter = 0;
+
+"#,
+        );
+        client.push_str(&format!(
+            "  constructor(private baseUrl: string = '{}') {{}}\n",
+            self.base_url()
+        ));
+        client.push_str(
+            r#"
+  private nextId(): number {
+    return ++this._idCounter;
+  }
+
+  private async call<T>(method: string, params: unknown): Promise<T> {
+    const id = this.nextId();
+    const response = await fetch(this.baseUrl, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ jsonrpc: '2.0', method, params, id }),
+    });
+
+    if (!response.ok) {
+      throw new Error(`HTTP ${response.status}: ${response.statusText}`);
+    }
+
+    const data = await response.json();
+    if (data.error) {
+      throw new JsonRpcClientError(data.error);
+    }
+    return data.result as T;
+  }
+
+  /** Send a notification (fire-and-forget, no response expected) */
+  async notify(method: string, params?: unknown): Promise<void> {
+    await fetch(this.baseUrl, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ jsonrpc: '2.0', method, params }),
+    });
+  }
+
+  /** Execute multiple RPC calls in a single HTTP request */
+  async batch<T extends Array<{ method: string; params?: unknown }>>(
+    calls: T
+  ): Promise<Array<{ result?: unknown; error?: JsonRpcError }>> {
+    const requests = calls.map((call) => ({
+      jsonrpc: '2.0' as const,
+      method: call.method,
+      params: call.params,
+      id: this.nextId(),
+    }));
+
+    const response = await fetch(this.baseUrl, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(requests),
+    });
+
+    if (!response.ok) {
+      throw new Error(`HTTP ${response.status}: ${response.statusText}`);
+    }
+
+    const data: Array<{ result?: unknown; error?: JsonRpcError; id: number }> =
+      await response.json();
+
+    // Sort by id to match input order
+    const sorted = requests.map((req) => {
+      const res = data.find((r) => r.id === req.id);
+      return res ? { result: res.result, error: res.error } : { error: { code: -1, message: 'No response' } };
+    });
+
+    return sorted;
+  }
+
+"#,
+        );
+
+        // Generate method for each procedure
+        for def in &self.procedures {
+            client.push_str(&format!(
+                r#"  async {}(params: {}): Promise<{}> {{
+    return this.call('{}', params);
+  }}
+
+"#,
+                def.name, def.input, def.output, def.name
+            ));
+        }
+
+        client.push_str("}\n");
+        self.append_type_definitions(&mut client);
+        client
+    }
+
+    /// Generate REST style client (individual endpoints)
+    fn generate_rest_client(&self) -> String {
+        let mut client = String::from(
+            r#"// Auto-generated TypeScript client for Ultimo RPC (REST Mode)
+// DO NOT EDIT - This file is automatically generated
+
+export class UltimoRpcClient {
+"#,
+        );
+        client.push_str(&format!(
+            "  constructor(private baseUrl: string = '{}') {{}}\n",
+            self.base_url()
+        ));
+        client.push_str(
+            r#"
+  private async get<T>(path: string, params?: Record<string, any>): Promise<T> {
+    const url = new URL(this.baseUrl + path, window.location.origin);
+    if (params) {
+      Object.entries(params).forEach(([key, value]) => {
+        url.searchParams.append(key, String(value));
+      });
+    }
+
+    const response = await fetch(url.toString(), {
+      method: 'GET',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+    });
+
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ message: response.statusText }));
+      throw new Error(error.message || 'Request failed');
+    }
+
+    return response.json();
+  }
+
+  private async post<T>(path: string, body: any): Promise<T> {
+    const response = await fetch(this.baseUrl + path, {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify(body),
+    });
 
-                spec.add_path(path, path_item);
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ message: response.statusText }));
+      throw new Error(error.message || 'Request failed');
+    }
+
+    return response.json();
+  }
+
+"#,
+        );
+
+        // Generate method for each procedure
+        for def in &self.procedures {
+            if def.kind == ProcedureKind::Query {
+                // Query: Use GET
+                client.push_str(&format!(
+                    r#"  async {}(params: {}): Promise<{}> {{
+    return this.get('/{}', params);
+  }}
+
+"#,
+                    def.name, def.input, def.output, def.name,
+                ));
+            } else {
+                // Mutation: Use POST
+                client.push_str(&format!(
+                    r#"  async {}(params: {}): Promise<{}> {{
+    return this.post('/{}', params);
+  }}
+
+"#,
+                    def.name, def.input, def.output, def.name,
+                ));
             }
         }
 
-        spec
+        client.push_str("}\n");
+        self.append_type_definitions(&mut client);
+        client
     }
-}
 
-impl Default for RpcRegistry {
-    fn default() -> Self {
-        Self::new()
+    /// Append collected type declarations to the generated client.
+    fn append_type_definitions(&self, client: &mut String) {
+        if self.types.is_empty() {
+            return;
+        }
+        client.push_str("\n// Type Definitions\n");
+        for decl in self.types.values() {
+            client.push_str(decl);
+            client.push('\n');
+        }
     }
 }
 
@@ -1000,15 +1314,28 @@ impl RpcRegistry {
                     );
                 }
 
-                let futures: Vec<_> = requests
+                // Validate every entry, then run the valid calls together.
+                let mut calls = Vec::new();
+                let slots: Vec<_> = requests
                     .into_iter()
-                    .map(|req| self.dispatch_single(req))
+                    .map(|req| match Self::prepare(req) {
+                        Prepared::Call(req, reply) => {
+                            calls.push(req);
+                            Ok(reply)
+                        }
+                        Prepared::Done(response) => Err(response),
+                    })
                     .collect();
-
-                let results = futures_util::future::join_all(futures).await;
+                let mut results = self.call_batch(calls).await.into_iter();
 
                 // Filter out None responses (notifications)
-                let responses: Vec<serde_json::Value> = results.into_iter().flatten().collect();
+                let responses: Vec<serde_json::Value> = slots
+                    .into_iter()
+                    .filter_map(|slot| match slot {
+                        Ok(reply) => reply.render(results.next().expect("one result per call")),
+                        Err(response) => response,
+                    })
+                    .collect();
 
                 if responses.is_empty() {
                     JsonRpcOutput::None
@@ -1040,29 +1367,33 @@ impl RpcRegistry {
 
     /// Dispatch a single request value. Returns None for notifications.
     async fn dispatch_single(&self, value: serde_json::Value) -> Option<serde_json::Value> {
-        // Check if this is a JSON-RPC 2.0 request (has "jsonrpc" field)
-        let is_2_0 = value.get("jsonrpc").and_then(|v| v.as_str()) == Some("2.0");
-
-        if is_2_0 {
-            self.dispatch_jsonrpc2(value).await
-        } else {
-            // Legacy format: {method, params} → {result}
-            self.dispatch_legacy(value).await
+        match Self::prepare(value) {
+            Prepared::Call(req, reply) => reply.render(self.call(&req.method, req.params).await),
+            Prepared::Done(response) => response,
         }
     }
 
-    /// Dispatch a JSON-RPC 2.0 request
-    async fn dispatch_jsonrpc2(&self, value: serde_json::Value) -> Option<serde_json::Value> {
-        let id = value.get("id").cloned();
-        let is_notification = id.is_none();
+    /// Validate a request value, extracting the call to make or the response
+    /// to send without calling anything.
+    fn prepare(value: serde_json::Value) -> Prepared {
+        // Check if this is a JSON-RPC 2.0 request (has "jsonrpc" field)
+        let is_2_0 = value.get("jsonrpc").and_then(|v| v.as_str()) == Some("2.0");
+        let params = value
+            .get("params")
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+        let method = value
+            .get("method")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
 
-        let method = match value.get("method").and_then(|v| v.as_str()) {
-            Some(m) => m.to_string(),
-            None => {
-                if is_notification {
-                    return None;
-                }
-                return Some(
+        if is_2_0 {
+            let id = value.get("id").cloned();
+            match method {
+                Some(method) => Prepared::Call(RpcRequest { method, params }, Reply::JsonRpc2(id)),
+                // Notifications never get a response, not even an error
+                None if id.is_none() => Prepared::Done(None),
+                None => Prepared::Done(Some(
                     serde_json::to_value(JsonRpcErrorResponse {
                         jsonrpc: "2.0",
                         error: JsonRpcError {
@@ -1073,75 +1404,157 @@ impl RpcRegistry {
                         id: id.unwrap_or(serde_json::Value::Null),
                     })
                     .unwrap(),
-                );
-            }
-        };
-
-        let params = value
-            .get("params")
-            .cloned()
-            .unwrap_or(serde_json::Value::Null);
-
-        match self.call(&method, params).await {
-            Ok(result) => {
-                if is_notification {
-                    None
-                } else {
-                    Some(
-                        serde_json::to_value(JsonRpcResponse {
-                            jsonrpc: "2.0",
-                            result,
-                            id: id.unwrap(),
-                        })
-                        .unwrap(),
-                    )
-                }
+                )),
             }
-            Err(e) => {
-                if is_notification {
-                    None
-                } else {
-                    let (code, message) = Self::error_to_jsonrpc_code(&e);
-                    Some(
-                        serde_json::to_value(JsonRpcErrorResponse {
-                            jsonrpc: "2.0",
-                            error: JsonRpcError {
-                                code,
-                                message,
-                                data: None,
-                            },
-                            id: id.unwrap_or(serde_json::Value::Null),
-                        })
-                        .unwrap(),
-                    )
-                }
-            }
-        }
-    }
-
-    /// Dispatch a legacy request ({method, params} → {result})
-    async fn dispatch_legacy(&self, value: serde_json::Value) -> Option<serde_json::Value> {
-        let method = match value.get("method").and_then(|v| v.as_str()) {
-            Some(m) => m.to_string(),
-            None => {
-                return Some(
+        } else {
+            // Legacy format: {method, params} → {result}
+            match method {
+                Some(method) => Prepared::Call(RpcRequest { method, params }, Reply::Legacy),
+                None => Prepared::Done(Some(
                     serde_json::to_value(RpcErrorResponse {
                         error: "Missing 'method' field".to_string(),
                         code: -1,
                     })
                     .unwrap(),
-                );
+                )),
             }
+        }
+    }
+
+    /// Map UltimoError to a JSON-RPC 2.0 error object. Validation failures
+    /// are invalid params, with the field-level details as `data`.
+    fn error_to_jsonrpc(e: &crate::UltimoError) -> JsonRpcError {
+        let (code, message, data) = match e {
+            crate::UltimoError::NotFound(msg) => (error_code::METHOD_NOT_FOUND, msg.clone(), None),
+            crate::UltimoError::BadRequest(msg) => (error_code::INVALID_PARAMS, msg.clone(), None),
+            crate::UltimoError::Validation { message, details } => (
+                error_code::INVALID_PARAMS,
+                message.clone(),
+                serde_json::to_value(details).ok(),
+            ),
+            crate::UltimoError::Json(_) => (error_code::INVALID_PARAMS, e.to_string(), None),
+            _ => (error_code::INTERNAL_ERROR, e.to_string(), None),
         };
+        JsonRpcError {
+            code,
+            message,
+            data,
+        }
+    }
+}
 
-        let params = value
-            .get("params")
-            .cloned()
-            .unwrap_or(serde_json::Value::Null);
+/// Split a ts-rs declaration (`type User = { … };`) into its name and body.
+/// Generic declarations are skipped.
+fn split_type_decl(decl: &str) -> Option<(&str, &str)> {
+    let rest = decl
+        .trim()
+        .trim_start_matches("export ")
+        .strip_prefix("type ")?;
+    let (name, body) = rest.split_once('=')?;
+    let name = name.trim();
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((name, body.trim().trim_end_matches(';')))
+}
+
+/// Whether a procedure's TypeScript type is an array (`T[]` or `Array<T>`),
+/// which REST mode posts as the raw JSON body.
+pub(crate) fn is_array_type(ts_type: &str) -> bool {
+    let ts_type = ts_type.trim();
+    ts_type.ends_with("[]") || (ts_type.starts_with("Array<") && ts_type.ends_with('>'))
+}
+
+/// Rewrite a derived `Array<T>` name as `T[]`, the form hand-written
+/// signatures use. Anything else is returned unchanged.
+#[cfg(feature = "client-gen")]
+fn array_shorthand(ts_type: String) -> String {
+    let Some(inner) = ts_type
+        .strip_prefix("Array<")
+        .and_then(|rest| rest.strip_suffix('>'))
+    else {
+        return ts_type;
+    };
+    // `Array<A> | Array<B>` starts and ends the same way but isn't one array.
+    let mut depth = 0i32;
+    for c in inner.chars() {
+        depth += match c {
+            '<' | '[' | '{' | '(' => 1,
+            '>' | ']' | '}' | ')' => -1,
+            _ => 0,
+        };
+        if depth < 0 {
+            return ts_type;
+        }
+    }
+    let inner = array_shorthand(inner.to_string());
+    if inner.contains('|') {
+        format!("({})[]", inner)
+    } else {
+        format!("{}[]", inner)
+    }
+}
+
+/// Schema for a procedure's input or output type. Object shapes are moved to
+/// `components.schemas` (as `{Procedure}{suffix}`, shared when identical) and
+/// referenced; named component types and primitives are returned as-is.
+fn component_schema(
+    spec: &mut crate::openapi::OpenApiSpec,
+    ts_type: &str,
+    procedure: &str,
+    suffix: &str,
+) -> crate::openapi::Schema {
+    let schema = spec.ts_to_schema_with_refs(ts_type);
+    if schema.properties.as_ref().is_none_or(|p| p.is_empty()) {
+        return schema;
+    }
+    let mut chars = procedure.chars();
+    let name = match chars.next() {
+        Some(first) => format!("{}{}{}", first.to_ascii_uppercase(), chars.as_str(), suffix),
+        None => suffix.to_string(),
+    };
+    spec.register_schema(name, schema)
+}
+
+/// A request value after validation: a call to make, or a response that is
+/// already known (invalid request, method-less notification).
+enum Prepared {
+    Call(RpcRequest, Reply),
+    Done(Option<serde_json::Value>),
+}
+
+/// How to shape the response for a call.
+enum Reply {
+    /// JSON-RPC 2.0, echoing the request id (`None` = notification).
+    JsonRpc2(Option<serde_json::Value>),
+    /// Legacy `{result}` / `{error, code}` format.
+    Legacy,
+}
 
-        match self.call(&method, params).await {
-            Ok(result) => Some(serde_json::to_value(RpcResponse { result }).unwrap()),
-            Err(e) => Some(
+impl Reply {
+    fn render(self, result: Result<serde_json::Value>) -> Option<serde_json::Value> {
+        match (self, result) {
+            (Reply::JsonRpc2(None), _) => None,
+            (Reply::JsonRpc2(Some(id)), Ok(result)) => Some(
+                serde_json::to_value(JsonRpcResponse {
+                    jsonrpc: "2.0",
+                    result,
+                    id,
+                })
+                .unwrap(),
+            ),
+            (Reply::JsonRpc2(Some(id)), Err(e)) => Some(
+                serde_json::to_value(JsonRpcErrorResponse {
+                    jsonrpc: "2.0",
+                    error: RpcRegistry::error_to_jsonrpc(&e),
+                    id,
+                })
+                .unwrap(),
+            ),
+            (Reply::Legacy, Ok(result)) => {
+                Some(serde_json::to_value(RpcResponse { result }).unwrap())
+            }
+            (Reply::Legacy, Err(e)) => Some(
                 serde_json::to_value(RpcErrorResponse {
                     error: e.to_string(),
                     code: -1,
@@ -1150,15 +1563,6 @@ impl RpcRegistry {
             ),
         }
     }
-
-    /// Map UltimoError to JSON-RPC 2.0 error code
-    fn error_to_jsonrpc_code(e: &crate::UltimoError) -> (i32, String) {
-        match e {
-            crate::UltimoError::NotFound(msg) => (error_code::METHOD_NOT_FOUND, msg.clone()),
-            crate::UltimoError::BadRequest(msg) => (error_code::INVALID_PARAMS, msg.clone()),
-            _ => (error_code::INTERNAL_ERROR, e.to_string()),
-        }
-    }
 }
 
 #[cfg(test)]
@@ -1290,6 +1694,51 @@ mod tests {
         assert!(client.contains("{ id: number; name: string }"));
     }
 
+    #[test]
+    fn schema_round_trips_and_generates_the_same_client() {
+        let registry = RpcRegistry::new_with_mode(RpcMode::Rest);
+        registry.query_with_types(
+            "getUser",
+            |_: TestInput| async move { Ok(TestOutput { result: 42 }) },
+            "{ id: number }".to_string(),
+            "{ id: number; name: string }".to_string(),
+        );
+        registry.mutation_with_types(
+            "createUser",
+            |_: TestInput| async move { Ok(TestOutput { result: 1 }) },
+            "{ name: string }".to_string(),
+            "{ id: number }".to_string(),
+        );
+
+        let schema = registry.schema();
+        let json = schema.to_json();
+        assert!(json.contains(r#""mode": "rest""#), "{}", json);
+        assert!(json.contains(r#""kind": "query""#), "{}", json);
+        let parsed = RpcSchema::from_json(&json).unwrap();
+        assert_eq!(parsed, schema);
+        assert_eq!(
+            parsed.generate_typescript_client(),
+            registry.generate_typescript_client()
+        );
+        assert!(registry
+            .generate_typescript_client()
+            .contains("constructor(private baseUrl: string = '/api') {}"));
+
+        let mut mounted = parsed;
+        mounted.base_path = Some("/v1/rpc".to_string());
+        assert!(mounted
+            .generate_typescript_client()
+            .contains("constructor(private baseUrl: string = '/v1/rpc') {}"));
+    }
+
+    #[test]
+    fn schema_from_a_newer_version_is_rejected() {
+        let json = r#"{"version":99,"mode":"jsonrpc","procedures":[]}"#;
+        assert!(RpcSchema::from_json(json).is_err());
+        let json = r#"{"version":1,"mode":"jsonrpc","procedures":[]}"#;
+        assert!(RpcSchema::from_json(json).unwrap().types.is_empty());
+    }
+
     #[tokio::test]
     async fn test_openapi_generation_rest_mode() {
         let registry = RpcRegistry::new_with_mode(RpcMode::Rest);
@@ -1326,6 +1775,38 @@ mod tests {
         assert!(openapi.paths.contains_key("/rpc"));
     }
 
+    #[tokio::test]
+    async fn test_openapi_rest_mode_shares_component_schemas() {
+        let registry = RpcRegistry::new_with_mode(RpcMode::Rest);
+        for name in ["getUser", "createUser"] {
+            registry.mutation_with_types(
+                name,
+                |_: TestInput| async move { Ok(TestOutput { result: 1 }) },
+                "{ value: number }".to_string(),
+                "{ id: number; name: string }".to_string(),
+            );
+        }
+
+        let openapi = registry.generate_openapi("API", "1.0.0", "/api");
+        let schemas = openapi.components.unwrap().schemas.unwrap();
+        let mut names: Vec<_> = schemas.keys().cloned().collect();
+        names.sort();
+        // Identical shapes are stored once, named after the first procedure
+        assert_eq!(names, ["GetUserInput", "GetUserOutput"]);
+
+        let op = openapi.paths["/api/createUser"].post.as_ref().unwrap();
+        let body = &op.request_body.as_ref().unwrap().content["application/json"];
+        assert_eq!(
+            body.schema.reference.as_deref(),
+            Some("#/components/schemas/GetUserInput")
+        );
+        let ok = op.responses["200"].content.as_ref().unwrap();
+        assert_eq!(
+            ok["application/json"].schema.reference.as_deref(),
+            Some("#/components/schemas/GetUserOutput")
+        );
+    }
+
     #[test]
     fn test_rpc_mode_equality() {
         assert_eq!(RpcMode::Rest, RpcMode::Rest);
@@ -1499,6 +1980,53 @@ mod client_gen_tests {
             "Thing body missing:\n{client}"
         );
     }
+
+    #[test]
+    fn array_shorthand_rewrites_only_whole_arrays() {
+        let short = |s: &str| array_shorthand(s.to_string());
+        assert_eq!(short("Array<Thing>"), "Thing[]");
+        assert_eq!(short("Array<Array<number>>"), "number[][]");
+        assert_eq!(short("Array<string | null>"), "(string | null)[]");
+        assert_eq!(short("Array<A> | Array<B>"), "Array<A> | Array<B>");
+        assert_eq!(short("Thing"), "Thing");
+    }
+
+    #[tokio::test]
+    async fn derived_array_input_is_posted_unwrapped() {
+        let rpc = RpcRegistry::new_with_mode(RpcMode::Rest);
+        rpc.mutation("saveThings", |things: Vec<Thing>| async move {
+            Ok(things.len() as u32)
+        });
+
+        let client = rpc.generate_typescript_client();
+        assert!(
+            client.contains("saveThings(params: Thing[]): Promise<number>"),
+            "signature missing:\n{client}"
+        );
+        assert!(
+            client.contains("return this.post('/saveThings', params);"),
+            "raw array not posted:\n{client}"
+        );
+        assert!(
+            client.contains("type Thing = "),
+            "item decl missing:\n{client}"
+        );
+
+        let openapi = rpc.generate_openapi("API", "1.0.0", "/api");
+        let op = openapi.paths["/api/saveThings"].post.as_ref().unwrap();
+        let body = &op.request_body.as_ref().unwrap().content["application/json"];
+        assert_eq!(body.schema.schema_type.as_deref(), Some("array"));
+        assert_eq!(
+            body.schema.items.as_ref().unwrap().reference.as_deref(),
+            Some("#/components/schemas/Thing")
+        );
+
+        let output = rpc
+            .call("saveThings", serde_json::json!([{ "id": 1, "label": "a" }]))
+            .await
+            .unwrap();
+        assert_eq!(output, serde_json::json!(1));
+    }
 }
 
 #[cfg(test)]
@@ -1577,6 +2105,115 @@ mod jsonrpc2_tests {
         assert_eq!(responses[2]["result"]["x"], 99);
     }
 
+    fn require_admin(input: &serde_json::Value) -> Result<()> {
+        if input.get("role").and_then(|v| v.as_str()) == Some("admin") {
+            Ok(())
+        } else {
+            Err(crate::UltimoError::Forbidden("admin only".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_procedure_guard_rejects_before_handler() {
+        let registry = setup_registry();
+        registry.mutation_with_guard(
+            "deleteUser",
+            |_input: serde_json::Value| async move { Ok(json!({"deleted": true})) },
+            require_admin,
+            "{}".to_string(),
+            "{ deleted: boolean }".to_string(),
+        );
+
+        let denied = registry.call("deleteUser", json!({"role": "user"})).await;
+        assert!(matches!(denied, Err(crate::UltimoError::Forbidden(_))));
+        let allowed = registry.call("deleteUser", json!({"role": "admin"})).await;
+        assert_eq!(allowed.unwrap(), json!({"deleted": true}));
+
+        // Unguarded procedures are unaffected
+        assert_eq!(registry.call("echo", json!(1)).await.unwrap(), json!(1));
+    }
+
+    #[tokio::test]
+    async fn test_guards_compose_with_registry_default() {
+        let registry = setup_registry().with_guard(|input| {
+            if input.get("token").is_some() {
+                Ok(())
+            } else {
+                Err(crate::UltimoError::Unauthorized(
+                    "missing token".to_string(),
+                ))
+            }
+        });
+        registry.guard("echo", require_admin);
+
+        let no_token = registry.call("add", json!({"a": 1})).await;
+        assert!(matches!(no_token, Err(crate::UltimoError::Unauthorized(_))));
+        assert_eq!(
+            registry
+                .call("add", json!({"a": 1, "b": 1, "token": "t"}))
+                .await
+                .unwrap(),
+            json!(2)
+        );
+
+        // Registry guard runs first, then the procedure's own
+        let not_admin = registry.call("echo", json!({"token": "t"})).await;
+        assert!(matches!(not_admin, Err(crate::UltimoError::Forbidden(_))));
+        let ok = json!({"token": "t", "role": "admin"});
+        assert_eq!(registry.call("echo", ok.clone()).await.unwrap(), ok);
+    }
+
+    #[tokio::test]
+    async fn test_call_batch_is_positional() {
+        let registry = setup_registry();
+        let results = registry
+            .call_batch(vec![
+                RpcRequest {
+                    method: "add".into(),
+                    params: json!({"a": 1, "b": 2}),
+                },
+                RpcRequest {
+                    method: "missing".into(),
+                    params: json!({}),
+                },
+                RpcRequest {
+                    method: "echo".into(),
+                    params: json!({"x": 1}),
+                },
+            ])
+            .await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), &json!(3));
+        assert!(matches!(results[1], Err(crate::UltimoError::NotFound(_))));
+        assert_eq!(results[2].as_ref().unwrap(), &json!({"x": 1}));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_batch_request() {
+        let registry = setup_registry();
+        let body = serde_json::to_vec(&json!([
+            {"method": "add", "params": {"a": 1, "b": 2}},
+            {"method": "missing", "params": {}},
+            {"params": {}},
+            {"jsonrpc": "2.0", "method": "add", "params": {"a": 2, "b": 2}, "id": "x"}
+        ]))
+        .unwrap();
+
+        let output = registry.handle_request(&body).await;
+        let responses: Vec<serde_json::Value> =
+            serde_json::from_slice(&output.into_body().unwrap()).unwrap();
+
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0], json!({"result": 3}));
+        assert_eq!(responses[1]["code"], -1);
+        assert_eq!(responses[2]["error"], "Missing 'method' field");
+        assert_eq!(
+            responses[3],
+            json!({"jsonrpc": "2.0", "result": 4, "id": "x"})
+        );
+    }
+
     #[tokio::test]
     async fn test_batch_with_notification() {
         let registry = setup_registry();
@@ -1672,6 +2309,44 @@ mod jsonrpc2_tests {
         assert_eq!(response["id"], 7);
     }
 
+    #[tokio::test]
+    async fn test_validation_error_carries_details_as_data() {
+        let registry = setup_registry();
+        registry.register("validate", |_input: serde_json::Value| async move {
+            Err::<serde_json::Value, _>(crate::UltimoError::Validation {
+                message: "Validation failed".to_string(),
+                details: vec![crate::error::ValidationError {
+                    field: "email".to_string(),
+                    message: "invalid email".to_string(),
+                }],
+            })
+        });
+        let body = serde_json::to_vec(&json!({
+            "jsonrpc": "2.0",
+            "method": "validate",
+            "params": {},
+            "id": 1
+        }))
+        .unwrap();
+
+        let output = registry.handle_request(&body).await;
+        let response: serde_json::Value =
+            serde_json::from_slice(&output.into_body().unwrap()).unwrap();
+
+        assert_eq!(
+            response,
+            json!({
+                "jsonrpc": "2.0",
+                "error": {
+                    "code": error_code::INVALID_PARAMS,
+                    "message": "Validation failed",
+                    "data": [{"field": "email", "message": "invalid email"}]
+                },
+                "id": 1
+            })
+        );
+    }
+
     #[tokio::test]
     async fn test_legacy_format_backward_compat() {
         let registry = setup_registry();
@@ -2,26 +2,30 @@
 //!
 //! Enabled by the `static-files` Cargo feature.
 
-use crate::{error::UltimoError, response::Response};
-use bytes::Bytes;
-use http_body_util::Full;
-use hyper::{header, StatusCode};
+use crate::{
+    error::UltimoError,
+    response::{Body, Response},
+};
+use hyper::{header, HeaderMap, StatusCode};
 use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Serve a single file from `root / rel_path`.
 ///
 /// - Detects MIME type from the file extension via `mime_guess`.
-/// - Sets `ETag: "{size}-{mtime_secs}"`.
-/// - Returns 304 Not Modified if `if_none_match` matches the computed ETag.
+/// - Sets `ETag: "{size}-{mtime_secs}"`, `Last-Modified` from the file mtime
+///   and, if given, `Cache-Control: {cache_control}`.
+/// - Returns 304 Not Modified if the request's `If-None-Match` matches the
+///   computed ETag or, without one, if the file is unchanged since
+///   `If-Modified-Since`.
 /// - Returns 404 (as `Err(UltimoError::NotFound)`) if the file is missing,
 ///   is a directory, or if `rel_path` would escape `root` (path traversal).
 pub(crate) async fn serve_file(
     root: &Path,
     rel_path: &str,
-    if_none_match: Option<String>,
+    request_headers: Option<&HeaderMap>,
+    cache_control: Option<&str>,
 ) -> crate::error::Result<Response> {
-    use std::time::UNIX_EPOCH;
-
     // Canonicalize the root so we have an absolute, symlink-resolved base.
     let canonical_root = tokio::fs::canonicalize(root)
         .await
@@ -54,22 +58,34 @@ pub(crate) async fn serve_file(
     }
 
     // Compute ETag: "{file_size}-{mtime_as_unix_seconds}"
-    let mtime_secs = metadata
-        .modified()
-        .ok()
+    let modified = last_modified(&metadata);
+    let mtime_secs = modified
         .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
         .map(|d| d.as_secs())
         .unwrap_or(0);
     let etag = format!("\"{}-{}\"", metadata.len(), mtime_secs);
 
-    // Conditional GET: 304 if the client's cached ETag matches.
-    if let Some(ref inm) = if_none_match {
-        if inm.trim() == etag.as_str() {
-            return Ok(hyper::Response::builder()
-                .status(StatusCode::NOT_MODIFIED)
-                .body(Full::new(Bytes::new()))
-                .unwrap());
-        }
+    let mut response = hyper::Response::builder();
+    response = response.header(header::ETAG, &etag);
+    if let Some(modified) = modified {
+        response = response.header(header::LAST_MODIFIED, httpdate::fmt_http_date(modified));
+    }
+    if let Some(cache_control) = cache_control {
+        response = response.header(header::CACHE_CONTROL, cache_control);
+    }
+
+    // Conditional GET: 304 if the client's cached copy is current. An
+    // `If-None-Match` takes precedence over `If-Modified-Since`.
+    let header = |name| request_headers.and_then(|h| h.get(name)?.to_str().ok());
+    let not_modified = match header(header::IF_NONE_MATCH) {
+        Some(inm) => inm.trim() == etag.as_str(),
+        None => modified.is_some_and(|m| not_modified_since(header(header::IF_MODIFIED_SINCE), m)),
+    };
+    if not_modified {
+        return Ok(response
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap());
     }
 
     // Read file contents.
@@ -82,11 +98,25 @@ pub(crate) async fn serve_file(
         .first_or_octet_stream()
         .to_string();
 
-    Ok(hyper::Response::builder()
+    Ok(response
         .status(StatusCode::OK)
         .header(header::CONTENT_TYPE, mime)
-        .header(header::ETAG, etag)
         .header(header::CONTENT_LENGTH, content.len())
-        .body(Full::new(Bytes::from(content)))
+        .body(Body::from(content))
         .unwrap())
 }
+
+/// The file's modification time truncated to whole seconds, the precision
+/// of `Last-Modified`.
+pub(crate) fn last_modified(metadata: &std::fs::Metadata) -> Option<SystemTime> {
+    let secs = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(secs.as_secs()))
+}
+
+/// Whether a resource last modified at `modified` is unchanged since the
+/// `If-Modified-Since` date, if one was sent and parses.
+pub(crate) fn not_modified_since(if_modified_since: Option<&str>, modified: SystemTime) -> bool {
+    if_modified_since
+        .and_then(|date| httpdate::parse_http_date(date).ok())
+        .is_some_and(|since| modified <= since)
+}
@@ -18,6 +18,31 @@
 //! assert_eq!(res.text(), "pong");
 //! # }
 //! ```
+//!
+//! [`TestResponse`] assertions chain, and panic with the expected and actual
+//! values on mismatch. JSON is compared structurally, so key order doesn't
+//! matter, and a failure names the first differing path:
+//!
+//! ```
+//! use serde_json::json;
+//! use ultimo::testing::TestClient;
+//! use ultimo::{Context, Ultimo};
+//!
+//! # async fn run() {
+//! let mut app = Ultimo::new_without_defaults();
+//! app.get("/user", |ctx: Context| async move {
+//!     ctx.json(json!({ "id": 1, "name": "alice" })).await
+//! });
+//!
+//! TestClient::new(app)
+//!     .get("/user")
+//!     .send()
+//!     .await
+//!     .assert_status(200)
+//!     .assert_header("content-type", "application/json")
+//!     .assert_json_eq(json!({ "name": "alice", "id": 1 }));
+//! # }
+//! ```
 
 mod client;
 mod fixtures;
@@ -30,6 +55,8 @@ mod database;
 pub use client::{TestClient, TestRequest};
 pub use fixtures::{load_fixture, Fixture};
 pub use middleware::{run_middleware, test_context, TestContextBuilder};
+#[doc(hidden)]
+pub use response::json_diff;
 pub use response::TestResponse;
 
 #[cfg(feature = "sqlx")]
@@ -46,11 +73,9 @@ macro_rules! assert_json_eq {
     ($actual:expr, $expected:expr $(,)?) => {{
         let actual = ::serde_json::to_value(&$actual).expect("actual is serializable");
         let expected = ::serde_json::to_value(&$expected).expect("expected is serializable");
-        assert_eq!(
-            actual, expected,
-            "JSON mismatch\n  actual:   {}\n  expected: {}",
-            actual, expected
-        );
+        if let Some(diff) = $crate::testing::json_diff(&actual, &expected) {
+            panic!("JSON mismatch {}", diff);
+        }
     }};
 }
 
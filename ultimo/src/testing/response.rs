@@ -6,6 +6,7 @@ use http_body_util::BodyExt;
 use hyper::{HeaderMap, StatusCode};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use serde_json::Value;
 
 /// A fully-buffered response. All accessors are synchronous.
 pub struct TestResponse {
@@ -74,7 +75,12 @@ impl TestResponse {
         self
     }
     pub fn assert_header(&self, name: &str, value: &str) -> &Self {
-        assert_eq!(self.header(name), Some(value), "header {name} mismatch");
+        assert_eq!(
+            self.header(name),
+            Some(value),
+            "expected header {name}: {value}, got {:?}",
+            self.header(name)
+        );
         self
     }
     pub fn assert_text(&self, expected: &str) -> &Self {
@@ -82,10 +88,113 @@ impl TestResponse {
         self
     }
     pub fn assert_json<T: Serialize>(&self, expected: &T) -> &Self {
-        let got: serde_json::Value = serde_json::from_slice(&self.body)
-            .unwrap_or_else(|e| panic!("response is not JSON: {e}"));
+        self.assert_json_eq(expected)
+    }
+    /// Assert the body equals `expected` as JSON. Object key order is
+    /// ignored; on mismatch the panic names the first differing path and
+    /// shows both documents.
+    pub fn assert_json_eq(&self, expected: impl Serialize) -> &Self {
+        let got: Value = serde_json::from_slice(&self.body)
+            .unwrap_or_else(|e| panic!("response is not JSON: {e}\nbody: {}", self.text()));
         let want = serde_json::to_value(expected).expect("expected is serializable");
-        assert_eq!(got, want, "response JSON mismatch");
+        if let Some(diff) = json_diff(&got, &want) {
+            panic!("response JSON mismatch {diff}");
+        }
         self
     }
 }
+
+/// Describe how `actual` differs from `expected`, or `None` if they are equal.
+#[doc(hidden)]
+pub fn json_diff(actual: &Value, expected: &Value) -> Option<String> {
+    let at = first_difference("$".to_string(), actual, expected)?;
+    let pretty = |v: &Value| serde_json::to_string_pretty(v).unwrap_or_default();
+    Some(format!(
+        "{at}\n--- expected\n{}\n--- actual\n{}",
+        pretty(expected),
+        pretty(actual)
+    ))
+}
+
+fn first_difference(path: String, actual: &Value, expected: &Value) -> Option<String> {
+    match (actual, expected) {
+        (Value::Object(a), Value::Object(e)) => {
+            for (key, want) in e {
+                let here = format!("{path}.{key}");
+                match a.get(key) {
+                    Some(got) => {
+                        if let Some(diff) = first_difference(here, got, want) {
+                            return Some(diff);
+                        }
+                    }
+                    None => return Some(format!("at {here}: missing, expected {want}")),
+                }
+            }
+            a.keys()
+                .find(|key| !e.contains_key(*key))
+                .map(|key| format!("at {path}.{key}: unexpected key"))
+        }
+        (Value::Array(a), Value::Array(e)) => {
+            for (i, (got, want)) in a.iter().zip(e).enumerate() {
+                if let Some(diff) = first_difference(format!("{path}[{i}]"), got, want) {
+                    return Some(diff);
+                }
+            }
+            (a.len() != e.len())
+                .then(|| format!("at {path}: expected {} elements, got {}", e.len(), a.len()))
+        }
+        _ => (actual != expected).then(|| format!("at {path}: expected {expected}, got {actual}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn json_diff_ignores_key_order() {
+        assert_eq!(
+            json_diff(
+                &json!({ "a": 1, "b": [1, 2] }),
+                &json!({ "b": [1, 2], "a": 1 })
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn json_diff_names_first_differing_path() {
+        let at = |a: Value, e: Value| {
+            json_diff(&a, &e)
+                .unwrap()
+                .lines()
+                .next()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            at(
+                json!({ "user": { "name": "al" } }),
+                json!({ "user": { "name": "bo" } })
+            ),
+            r#"at $.user.name: expected "bo", got "al""#
+        );
+        assert_eq!(
+            at(json!({ "a": 1 }), json!({ "a": 1, "b": 2 })),
+            "at $.b: missing, expected 2"
+        );
+        assert_eq!(
+            at(json!({ "a": 1, "c": 3 }), json!({ "a": 1 })),
+            "at $.c: unexpected key"
+        );
+        assert_eq!(
+            at(json!([1, 2, 3]), json!([1, 2])),
+            "at $: expected 2 elements, got 3"
+        );
+        assert_eq!(
+            at(json!([1, 5]), json!([1, 2])),
+            "at $[1]: expected 2, got 5"
+        );
+    }
+}
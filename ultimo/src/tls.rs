@@ -0,0 +1,273 @@
+//! TLS termination for [`Ultimo::listen_tls`](crate::Ultimo::listen_tls),
+//! with optional client-certificate authentication (mutual TLS)
+
+use crate::error::{Result, UltimoError};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Arc;
+use tokio_rustls::rustls::crypto::{ring, CryptoProvider};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::{danger::ClientCertVerifier, WebPkiClientVerifier};
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+/// Whether TLS clients must present a certificate, see
+/// [`Ultimo::tls_client_auth`](crate::Ultimo::tls_client_auth).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientAuth {
+    /// Ask for a certificate but also accept clients without one; their
+    /// [`Context::client_cert`](crate::Context::client_cert) is `None`.
+    Optional,
+    /// Fail the handshake unless the client presents a valid certificate.
+    Required,
+}
+
+/// The certificate a client authenticated with over mutual TLS, from
+/// [`Context::client_cert`](crate::Context::client_cert).
+///
+/// The chain has already been verified against the configured CA by the time
+/// a handler sees it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertInfo {
+    common_name: Option<String>,
+    dns_names: Vec<String>,
+    fingerprint: String,
+    chain: Vec<Vec<u8>>,
+}
+
+impl ClientCertInfo {
+    /// Read the leaf of a verified chain (leaf first). `None` for an empty
+    /// chain.
+    pub(crate) fn from_chain(chain: &[CertificateDer<'_>]) -> Option<Self> {
+        let leaf = chain.first()?;
+        let (common_name, dns_names) = match webpki::EndEntityCert::try_from(leaf) {
+            Ok(cert) => (
+                common_name(cert.subject()),
+                cert.valid_dns_names().map(str::to_string).collect(),
+            ),
+            Err(_) => (None, Vec::new()),
+        };
+        let fingerprint = Sha256::digest(leaf.as_ref())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Some(Self {
+            common_name,
+            dns_names,
+            fingerprint,
+            chain: chain.iter().map(|cert| cert.to_vec()).collect(),
+        })
+    }
+
+    /// The subject's common name (`CN`), if it has one.
+    pub fn common_name(&self) -> Option<&str> {
+        self.common_name.as_deref()
+    }
+
+    /// The DNS names in the subject alternative name extension.
+    pub fn dns_names(&self) -> &[String] {
+        &self.dns_names
+    }
+
+    /// SHA-256 of the leaf certificate's DER encoding, as lowercase hex.
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// The DER-encoded certificate chain the client presented, leaf first.
+    pub fn chain(&self) -> &[Vec<u8>] {
+        &self.chain
+    }
+}
+
+/// Build an acceptor from a PEM certificate chain (leaf first) and a PEM
+/// private key. ALPN offers `h2` only when HTTP/2 is enabled. With
+/// `client_auth`, clients are asked for a certificate signed by one of the
+/// PEM CA certificates at the given path.
+pub(crate) fn acceptor(
+    cert_path: &Path,
+    key_path: &Path,
+    http2: bool,
+    client_auth: Option<(&Path, ClientAuth)>,
+) -> Result<TlsAcceptor> {
+    let certs = read_certs(cert_path)?;
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| tls_error(format!("reading {}: {}", key_path.display(), e)))?;
+
+    // An explicit provider, so a dependency enabling another rustls backend
+    // can't make the process-wide default ambiguous.
+    let provider = Arc::new(ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| tls_error(e.to_string()))?;
+    let builder = match client_auth {
+        Some((ca_path, mode)) => {
+            builder.with_client_cert_verifier(client_verifier(ca_path, mode, provider)?)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| tls_error(e.to_string()))?;
+    config.alpn_protocols = if http2 {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    } else {
+        vec![b"http/1.1".to_vec()]
+    };
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// A verifier accepting client certificates issued by the CAs in `ca_path`.
+fn client_verifier(
+    ca_path: &Path,
+    mode: ClientAuth,
+    provider: Arc<CryptoProvider>,
+) -> Result<Arc<dyn ClientCertVerifier>> {
+    let mut roots = RootCertStore::empty();
+    for cert in read_certs(ca_path)? {
+        roots
+            .add(cert)
+            .map_err(|e| tls_error(format!("reading {}: {}", ca_path.display(), e)))?;
+    }
+    let builder = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+    let builder = match mode {
+        ClientAuth::Optional => builder.allow_unauthenticated(),
+        ClientAuth::Required => builder,
+    };
+    builder.build().map_err(|e| tls_error(e.to_string()))
+}
+
+/// Every certificate in a PEM file; an error if there are none.
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+        .map_err(|e| tls_error(format!("reading {}: {}", path.display(), e)))?;
+    if certs.is_empty() {
+        return Err(tls_error(format!(
+            "no certificates found in {}",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+/// Object identifier of the X.520 `commonName` attribute (2.5.4.3).
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+/// The first `CN` in a DER-encoded X.501 `Name` (without its outer
+/// `SEQUENCE` header): a sequence of sets of `(type, value)` sequences.
+fn common_name(mut name: &[u8]) -> Option<String> {
+    while let Some((_, rdn, rest)) = der_next(name) {
+        name = rest;
+        let mut attributes = rdn;
+        while let Some((_, attribute, rest)) = der_next(attributes) {
+            attributes = rest;
+            let (_, oid, value) = der_next(attribute)?;
+            if oid == OID_COMMON_NAME {
+                let (_, value, _) = der_next(value)?;
+                return String::from_utf8(value.to_vec()).ok();
+            }
+        }
+    }
+    None
+}
+
+/// Split the first DER element off `input` as `(tag, contents, rest)`.
+fn der_next(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&first, mut input) = input.split_first()?;
+    let len = if first < 0x80 {
+        usize::from(first)
+    } else {
+        let octets = usize::from(first & 0x7f);
+        if octets == 0 || octets > 4 || input.len() < octets {
+            return None;
+        }
+        let (len, rest) = input.split_at(octets);
+        input = rest;
+        len.iter()
+            .fold(0, |len, &byte| len << 8 | usize::from(byte))
+    };
+    if input.len() < len {
+        return None;
+    }
+    let (contents, rest) = input.split_at(len);
+    Some((tag, contents, rest))
+}
+
+fn tls_error(message: String) -> UltimoError {
+    UltimoError::Internal(format!("TLS: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls-cert.pem");
+    const KEY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls-key.pem");
+    const CLIENT_CA: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/tls-client-ca.pem"
+    );
+    const CLIENT_CERT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/tls-client-cert.pem"
+    );
+
+    #[test]
+    fn alpn_offers_h2_only_with_http2() {
+        let alpn = |http2| {
+            acceptor(Path::new(CERT), Path::new(KEY), http2, None)
+                .unwrap()
+                .config()
+                .alpn_protocols
+                .clone()
+        };
+        assert_eq!(alpn(false), vec![b"http/1.1".to_vec()]);
+        assert_eq!(alpn(true), vec![b"h2".to_vec(), b"http/1.1".to_vec()]);
+    }
+
+    #[test]
+    fn missing_files_are_reported_by_path() {
+        let err = acceptor(Path::new("missing.pem"), Path::new(KEY), false, None)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("missing.pem"), "{}", err);
+    }
+
+    #[test]
+    fn key_file_without_certificates_is_rejected() {
+        let err = acceptor(Path::new(KEY), Path::new(KEY), false, None)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("no certificates"), "{}", err);
+    }
+
+    #[test]
+    fn client_cert_info_reads_the_leaf() {
+        let chain = read_certs(Path::new(CLIENT_CERT)).unwrap();
+        let info = ClientCertInfo::from_chain(&chain).unwrap();
+        assert_eq!(info.common_name(), Some("client-1"));
+        assert_eq!(info.dns_names(), ["client-1.example.com"]);
+        assert_eq!(
+            info.fingerprint(),
+            "18581569bf99ec305ce6b2a39b6e5e6ef3eeabd349dbb6ff8a65302942596705"
+        );
+        assert_eq!(info.chain(), [chain[0].to_vec()]);
+        assert!(ClientCertInfo::from_chain(&[]).is_none());
+    }
+
+    #[test]
+    fn client_auth_reads_the_ca_file() {
+        for mode in [ClientAuth::Optional, ClientAuth::Required] {
+            let client_auth = Some((Path::new(CLIENT_CA), mode));
+            assert!(acceptor(Path::new(CERT), Path::new(KEY), false, client_auth).is_ok());
+        }
+        let client_auth = Some((Path::new("missing-ca.pem"), ClientAuth::Required));
+        let err = acceptor(Path::new(CERT), Path::new(KEY), false, client_auth)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("missing-ca.pem"), "{}", err);
+    }
+}
@@ -1,6 +1,6 @@
 //! WebSocket connection handling
 
-use super::frame::{Frame, Message, OpCode};
+use super::frame::{CloseCode, CloseFrame, Frame, Message, OpCode};
 use super::pubsub::ChannelManager;
 use super::WebSocketConfig;
 use bytes::{Bytes, BytesMut};
@@ -9,6 +9,7 @@ use hyper_util::rt::TokioIo;
 use serde::Serialize;
 use std::io::{self, ErrorKind};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -23,6 +24,7 @@ pub struct WebSocket<T = ()> {
     connection_id: uuid::Uuid,
     remote_addr: Option<SocketAddr>,
     config: Arc<WebSocketConfig>,
+    backpressure: Arc<Backpressure>,
 }
 
 impl<T> WebSocket<T> {
@@ -42,9 +44,29 @@ impl<T> WebSocket<T> {
             connection_id,
             remote_addr,
             config,
+            backpressure: Arc::default(),
         }
     }
 
+    /// Report refused sends to the connection, which then fires `on_drain`.
+    pub(crate) fn with_backpressure(mut self, backpressure: Arc<Backpressure>) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+
+    /// Queue `message` for the connection to write.
+    fn queue(&self, message: Message) -> Result<(), std::io::Error> {
+        self.sender.try_send(message).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => {
+                self.backpressure.refused(&self.sender);
+                std::io::Error::new(std::io::ErrorKind::WouldBlock, "write buffer full")
+            }
+            mpsc::error::TrySendError::Closed(_) => {
+                std::io::Error::new(std::io::ErrorKind::BrokenPipe, "connection closed")
+            }
+        })
+    }
+
     /// Get reference to typed context data
     pub fn data(&self) -> &T {
         &self.data
@@ -65,16 +87,7 @@ impl<T> WebSocket<T> {
     /// Returns `Err` if the connection is closed or the write buffer is full.
     /// When the buffer is full, consider waiting for `on_drain` callback before retrying.
     pub async fn send(&self, text: impl Into<String>) -> Result<(), std::io::Error> {
-        self.sender
-            .try_send(Message::Text(text.into()))
-            .map_err(|e| match e {
-                mpsc::error::TrySendError::Full(_) => {
-                    std::io::Error::new(std::io::ErrorKind::WouldBlock, "write buffer full")
-                }
-                mpsc::error::TrySendError::Closed(_) => {
-                    std::io::Error::new(std::io::ErrorKind::BrokenPipe, "connection closed")
-                }
-            })
+        self.queue(Message::Text(text.into()))
     }
 
     /// Send binary message
@@ -82,16 +95,7 @@ impl<T> WebSocket<T> {
     /// Returns `Err` if the connection is closed or the write buffer is full.
     /// When the buffer is full, consider waiting for `on_drain` callback before retrying.
     pub async fn send_binary(&self, data: impl Into<Bytes>) -> Result<(), std::io::Error> {
-        self.sender
-            .try_send(Message::Binary(data.into()))
-            .map_err(|e| match e {
-                mpsc::error::TrySendError::Full(_) => {
-                    std::io::Error::new(std::io::ErrorKind::WouldBlock, "write buffer full")
-                }
-                mpsc::error::TrySendError::Closed(_) => {
-                    std::io::Error::new(std::io::ErrorKind::BrokenPipe, "connection closed")
-                }
-            })
+        self.queue(Message::Binary(data.into()))
     }
 
     /// Send JSON message
@@ -128,33 +132,41 @@ impl<T> WebSocket<T> {
             .await
     }
 
-    /// Close the WebSocket connection
-    pub async fn close(
-        &self,
-        code: Option<u16>,
-        reason: Option<&str>,
-    ) -> Result<(), std::io::Error> {
-        let close_frame = if let Some(c) = code {
-            let r = reason.unwrap_or("");
-            let mut payload = BytesMut::new();
-            payload.extend_from_slice(&c.to_be_bytes());
-            payload.extend_from_slice(r.as_bytes());
-            Message::Close(Some(super::frame::CloseFrame {
-                code: c,
-                reason: r.to_string(),
-            }))
-        } else {
-            Message::Close(None)
-        };
+    /// Close the connection with `code` and `reason`.
+    ///
+    /// Queues a close frame behind any messages already sent; once it is
+    /// written the connection is shut down and `on_close` receives `code`.
+    ///
+    /// [`CloseCode::NoStatus`] sends a close frame with no payload, so
+    /// `reason` is dropped. Codes that must not appear on the wire
+    /// ([`CloseCode::Abnormal`], 1004, 1015, and `Other` codes outside
+    /// 3000-4999) are rejected with [`InvalidInput`](std::io::ErrorKind::InvalidInput).
+    /// `reason` is cut to 123 bytes, the most a control frame can carry.
+    ///
+    /// ```rust,ignore
+    /// ws.close(CloseCode::PolicyViolation, "authentication expired").await?;
+    /// ```
+    pub async fn close(&self, code: CloseCode, reason: &str) -> Result<(), std::io::Error> {
+        if code == CloseCode::NoStatus {
+            return self.queue(Message::Close(None));
+        }
+        if !code.is_sendable() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("close code {} cannot be sent", u16::from(code)),
+            ));
+        }
 
-        self.sender.try_send(close_frame).map_err(|e| match e {
-            mpsc::error::TrySendError::Full(_) => {
-                std::io::Error::new(std::io::ErrorKind::WouldBlock, "write buffer full")
-            }
-            mpsc::error::TrySendError::Closed(_) => {
-                std::io::Error::new(std::io::ErrorKind::BrokenPipe, "connection closed")
-            }
-        })
+        let mut end = reason.len().min(MAX_CLOSE_REASON_LEN);
+        while !reason.is_char_boundary(end) {
+            end -= 1;
+        }
+        let close_frame = Message::Close(Some(CloseFrame {
+            code,
+            reason: reason[..end].to_string(),
+        }));
+
+        self.queue(close_frame)
     }
 
     /// Get remote address
@@ -183,15 +195,58 @@ impl<T> WebSocket<T> {
     }
 }
 
+/// Longest close reason that fits a control frame's 125-byte payload after
+/// the two-byte code.
+const MAX_CLOSE_REASON_LEN: usize = 123;
+
+/// Outbound-queue backpressure shared by a connection and its [`WebSocket`].
+///
+/// A send refused because the queue was full arms a single `on_drain`
+/// notification, delivered once the queue is down to half its capacity.
+/// Only refusals count, so a queue that merely fills up (as a one-slot queue
+/// does with every message) does not notify.
+#[derive(Default)]
+pub(crate) struct Backpressure {
+    refused: AtomicBool,
+    /// Weak, so a `WebSocket` outliving its connection does not keep the
+    /// drain channel (and with it the handler's event loop) open.
+    drain_tx: Option<mpsc::WeakUnboundedSender<()>>,
+}
+
+impl Backpressure {
+    /// Record a refused send. Checks right away too, since the connection may
+    /// have drained the queue already and be waiting for the next message.
+    fn refused(&self, queue: &mpsc::Sender<Message>) {
+        self.refused.store(true, Ordering::SeqCst);
+        self.check(queue);
+    }
+
+    /// Notify `on_drain` if a send was refused and the queue is down to half.
+    fn check(&self, queue: &mpsc::Sender<Message>) {
+        let queued = queue.max_capacity() - queue.capacity();
+        if queued <= queue.max_capacity() / 2 && self.refused.swap(false, Ordering::SeqCst) {
+            if let Some(drain_tx) = self.drain_tx.as_ref().and_then(|tx| tx.upgrade()) {
+                let _ = drain_tx.send(());
+            }
+            tracing::trace!("Write buffer drained, notified handler");
+        }
+    }
+}
+
 /// WebSocket connection handler that manages the connection lifecycle
 pub(crate) struct ConnectionHandler {
     upgraded: Upgraded,
     receiver: mpsc::Receiver<Message>,
     incoming_tx: mpsc::UnboundedSender<Message>,
     drain_tx: mpsc::UnboundedSender<()>,
+    backpressure: Arc<Backpressure>,
+    /// A sender kept only to read the outbound queue's free capacity.
+    queue_probe: mpsc::Sender<Message>,
     channel_manager: Arc<ChannelManager>,
     connection_id: uuid::Uuid,
     config: Arc<WebSocketConfig>,
+    /// Whether `permessage-deflate` was negotiated in the handshake.
+    deflate: bool,
 }
 
 /// Fragment accumulator for reassembling fragmented messages
@@ -199,6 +254,8 @@ struct FragmentAccumulator {
     opcode: Option<OpCode>,
     fragments: BytesMut,
     total_size: usize,
+    /// The first frame had RSV1 set: the reassembled payload is deflated.
+    compressed: bool,
 }
 
 impl ConnectionHandler {
@@ -206,6 +263,7 @@ impl ConnectionHandler {
         upgraded: Upgraded,
         channel_manager: Arc<ChannelManager>,
         config: Arc<WebSocketConfig>,
+        deflate: bool,
     ) -> (
         Self,
         mpsc::Sender<Message>,
@@ -224,15 +282,26 @@ impl ConnectionHandler {
             upgraded,
             receiver: rx,
             incoming_tx,
+            backpressure: Arc::new(Backpressure {
+                refused: AtomicBool::new(false),
+                drain_tx: Some(drain_tx.downgrade()),
+            }),
             drain_tx,
+            queue_probe: tx.clone(),
             channel_manager,
             connection_id,
             config,
+            deflate,
         };
 
         (handler, tx, incoming_rx, drain_rx)
     }
 
+    /// The backpressure state to hand to this connection's [`WebSocket`].
+    pub fn backpressure(&self) -> Arc<Backpressure> {
+        self.backpressure.clone()
+    }
+
     pub async fn handle(self) -> Result<(), std::io::Error> {
         tracing::info!("ConnectionHandler::handle() started");
         let mut read_buf = BytesMut::with_capacity(8192);
@@ -242,32 +311,60 @@ impl ConnectionHandler {
         let channel_manager = self.channel_manager;
         let connection_id = self.connection_id;
         let incoming_tx = self.incoming_tx;
-        let drain_tx = self.drain_tx;
+        // Held for the connection's lifetime; dropping it ends `on_drain`.
+        let _drain_tx = self.drain_tx;
+        let backpressure = self.backpressure;
+        let queue_probe = self.queue_probe;
         let config = self.config;
+        let deflate = self.deflate;
+        let mut shutdown = channel_manager.shutdown_signal();
         let mut fragment_accumulator: Option<FragmentAccumulator> = None;
 
-        // Track write buffer state for backpressure
-        let mut was_full = false;
-
-        // Setup ping interval if configured
-        let mut ping_interval = config
-            .ping_interval
-            .map(|interval_secs| time::interval(Duration::from_secs(interval_secs)));
-
-        let mut last_pong_received = Instant::now();
+        // Heartbeat: ping every `ping_interval` (first one after a full
+        // interval); a ping left unanswered for `ping_timeout` drops the peer.
+        let mut ping_interval = config.ping_interval.map(|interval_secs| {
+            let period = Duration::from_secs(interval_secs);
+            time::interval_at(time::Instant::now() + period, period)
+        });
         let ping_timeout = Duration::from_secs(config.ping_timeout);
+        // When the oldest still-unanswered ping was sent.
+        let mut awaiting_pong: Option<Instant> = None;
 
         tracing::info!("Entering main WebSocket loop");
-        loop {
-            // Check for ping timeout
-            if config.ping_interval.is_some() && last_pong_received.elapsed() > ping_timeout {
-                tracing::warn!("Ping timeout - closing connection");
-                let close_frame = Frame::close(Some(1001), Some("Ping timeout"));
-                let _ = writer.write_all(&close_frame.encode()).await;
-                break;
-            }
-
+        'connection: loop {
             tokio::select! {
+                // Server shutdown: going away (1001)
+                _ = async {
+                    if shutdown.wait_for(|closing| *closing).await.is_err() {
+                        std::future::pending::<()>().await;
+                    }
+                } => {
+                    let close = Frame::close(Some(CloseCode::GoingAway.into()), Some("Server shutting down"));
+                    let _ = writer.write_all(&close.encode()).await;
+                    let _ = incoming_tx.send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::GoingAway,
+                        reason: "Server shutting down".to_string(),
+                    })));
+                    break;
+                }
+
+                // Pong deadline for the outstanding ping
+                _ = async {
+                    match awaiting_pong {
+                        Some(sent) => time::sleep_until((sent + ping_timeout).into()).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    // No close handshake with an unresponsive peer (1006,
+                    // abnormal closure); just tell the handler and drop it.
+                    tracing::warn!("No pong within {:?} - dropping connection", ping_timeout);
+                    let _ = incoming_tx.send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::Abnormal,
+                        reason: "Ping timeout".to_string(),
+                    })));
+                    break;
+                }
+
                 // Ping interval
                 _ = async {
                     match &mut ping_interval {
@@ -275,6 +372,7 @@ impl ConnectionHandler {
                         None => std::future::pending().await,
                     }
                 } => {
+                    awaiting_pong.get_or_insert_with(Instant::now);
                     // Send ping frame
                     let ping = Frame::ping(Bytes::new());
                     if let Err(e) = writer.write_all(&ping.encode()).await {
@@ -290,14 +388,38 @@ impl ConnectionHandler {
                         Ok(0) => break, // Connection closed
                         Ok(_) => {
                             // Try to parse frames with size limits
-                            while let Some(frame) = Frame::parse_with_limits(&mut read_buf, Some(config.max_frame_size))? {
+                            loop {
+                                // RSV1 marks a compressed message; `Frame` doesn't keep it, so peek.
+                                let rsv1 = read_buf.first().is_some_and(|b| b & 0x40 != 0);
+                                let Some(frame) = Frame::parse_with_limits(&mut read_buf, Some(config.max_frame_size))? else {
+                                    break;
+                                };
+                                // Only the first frame of a data message may be compressed.
+                                if rsv1 && !(deflate && matches!(frame.opcode, OpCode::Text | OpCode::Binary)) {
+                                    return Err(io::Error::new(
+                                        ErrorKind::InvalidData,
+                                        "unexpected RSV1 bit",
+                                    ));
+                                }
+                                // Set when the frame breaks the protocol or a limit.
+                                let mut violation: Option<CloseFrame> = None;
                                 match frame.opcode {
                                     OpCode::Text | OpCode::Binary => {
                                         if frame.fin {
                                             // Single unfragmented message
-                                            if let Ok(message) = Message::from_frame_with_limit(frame, Some(config.max_message_size)) {
-                                                let _ = incoming_tx.send(message);
+                                            let frame = if rsv1 {
+                                                inflate(frame, config.max_message_size)
+                                            } else {
+                                                Ok(frame)
+                                            };
+                                            match frame.and_then(|frame| data_message(frame, config.max_message_size)) {
+                                                Ok(message) => {
+                                                    let _ = incoming_tx.send(message);
+                                                }
+                                                Err(close) => violation = Some(close),
                                             }
+                                        } else if frame.payload.len() > config.max_message_size {
+                                            violation = Some(message_too_big());
                                         } else {
                                             // Start of fragmented message
                                             if fragment_accumulator.is_some() {
@@ -310,6 +432,7 @@ impl ConnectionHandler {
                                                 opcode: Some(frame.opcode),
                                                 fragments: BytesMut::from(frame.payload.as_ref()),
                                                 total_size: frame.payload.len(),
+                                                compressed: rsv1,
                                             });
                                         }
                                     }
@@ -318,17 +441,14 @@ impl ConnectionHandler {
                                         let should_clear = if let Some(ref mut accumulator) = fragment_accumulator {
                                             accumulator.total_size += frame.payload.len();
 
-                                            // Check message size limit
+                                            // Check message size limit across all fragments so far
                                             if accumulator.total_size > config.max_message_size {
-                                                return Err(io::Error::new(
-                                                    ErrorKind::InvalidData,
-                                                    format!("fragmented message size {} exceeds maximum {}",
-                                                        accumulator.total_size, config.max_message_size),
-                                                ));
+                                                violation = Some(message_too_big());
+                                                false
+                                            } else {
+                                                accumulator.fragments.extend_from_slice(&frame.payload);
+                                                frame.fin // Clear accumulator if this is the final fragment
                                             }
-
-                                            accumulator.fragments.extend_from_slice(&frame.payload);
-                                            frame.fin // Clear accumulator if this is the final fragment
                                         } else {
                                             return Err(io::Error::new(
                                                 ErrorKind::InvalidData,
@@ -345,9 +465,17 @@ impl ConnectionHandler {
                                                     mask: None,
                                                     payload: accumulator.fragments.freeze(),
                                                 };
+                                                let reassembled_frame = if accumulator.compressed {
+                                                    inflate(reassembled_frame, config.max_message_size)
+                                                } else {
+                                                    Ok(reassembled_frame)
+                                                };
 
-                                                if let Ok(message) = Message::from_frame(reassembled_frame) {
-                                                    let _ = incoming_tx.send(message);
+                                                match reassembled_frame.and_then(|frame| data_message(frame, config.max_message_size)) {
+                                                    Ok(message) => {
+                                                        let _ = incoming_tx.send(message);
+                                                    }
+                                                    Err(close) => violation = Some(close),
                                                 }
                                             }
                                         }
@@ -358,9 +486,9 @@ impl ConnectionHandler {
                                             let _ = incoming_tx.send(message);
                                         }
                                         // Echo close frame back
-                                        let close_frame = Frame::close(Some(1000), Some("Normal closure"));
+                                        let close_frame = Frame::close(Some(CloseCode::Normal.into()), Some("Normal closure"));
                                         let _ = writer.write_all(&close_frame.encode()).await;
-                                        break;
+                                        break 'connection;
                                     }
                                     OpCode::Ping => {
                                         // Respond with pong
@@ -368,11 +496,21 @@ impl ConnectionHandler {
                                         let _ = writer.write_all(&pong.encode()).await;
                                     }
                                     OpCode::Pong => {
-                                        // Update last pong received time
-                                        last_pong_received = Instant::now();
+                                        awaiting_pong = None;
                                         tracing::trace!("Received pong frame");
                                     }
                                 }
+
+                                // Fail the connection: close with the violation's code
+                                // and report it to the handler.
+                                if let Some(close) = violation {
+                                    tracing::warn!("Closing WebSocket ({}): {}", close.code, close.reason);
+                                    let frame = Frame::close(Some(close.code.into()), Some(&close.reason));
+                                    let _ = writer.write_all(&frame.encode()).await;
+                                    let _ = writer.shutdown().await;
+                                    let _ = incoming_tx.send(Message::Close(Some(close)));
+                                    break 'connection;
+                                }
                             }
                         }
                         Err(e) => {
@@ -384,36 +522,120 @@ impl ConnectionHandler {
 
                 // Send frames to client
                 Some(message) = receiver.recv() => {
-                    // Check if buffer was full before this recv
-                    let is_now_draining = was_full;
-
                     // Use fragmentation if message exceeds max frame size
-                    let frames = message.to_fragmented_frames(config.max_frame_size);
+                    let frames = encode_message(&message, config.max_frame_size, deflate);
 
-                    for frame in frames {
-                        let encoded = frame.encode();
+                    for encoded in frames {
                         if let Err(e) = writer.write_all(&encoded).await {
                             tracing::error!("Error writing to socket: {}", e);
                             break;
                         }
                     }
 
-                    // Update full state - buffer is full if no capacity remains
-                    let has_capacity = !receiver.is_empty() || receiver.is_closed();
-                    was_full = !has_capacity;
-
-                    // Notify on_drain if buffer was full and now has drained
-                    if is_now_draining && has_capacity {
-                        let _ = drain_tx.send(());
-                        tracing::trace!("Write buffer drained, notified handler");
+                    // A close we initiated ends the connection once sent.
+                    if let Message::Close(_) = message {
+                        let _ = writer.shutdown().await;
+                        let _ = incoming_tx.send(message);
+                        break;
                     }
+
+                    // Backpressure: after a refused send, tell `on_drain`
+                    // once the queue is back down to half its capacity.
+                    backpressure.check(&queue_probe);
                 }
             }
         }
 
         // Cleanup on disconnect
         channel_manager.disconnect(connection_id).await;
+        drop(shutdown); // lets a graceful shutdown see this connection is gone
 
         Ok(())
     }
 }
+
+/// Convert a complete (unfragmented or reassembled) data frame into a
+/// message, or the close frame to fail the connection with: 1009 when it is
+/// larger than `max_message_size`, 1007 when a text payload isn't UTF-8.
+fn data_message(frame: Frame, max_message_size: usize) -> Result<Message, CloseFrame> {
+    if frame.payload.len() > max_message_size {
+        return Err(message_too_big());
+    }
+    Message::from_frame(frame).map_err(|_| CloseFrame {
+        code: CloseCode::InvalidPayload,
+        reason: "Invalid UTF-8 in text message".to_string(),
+    })
+}
+
+fn message_too_big() -> CloseFrame {
+    CloseFrame {
+        code: CloseCode::MessageTooBig,
+        reason: "Message too big".to_string(),
+    }
+}
+
+/// Inflate a compressed data frame's payload, capped at `max_size` bytes,
+/// or the close frame to fail the connection with: 1009 past the cap, 1007
+/// when the payload isn't valid DEFLATE data.
+#[cfg(feature = "websocket-deflate")]
+fn inflate(mut frame: Frame, max_size: usize) -> Result<Frame, CloseFrame> {
+    use super::deflate::{decompress, DecompressError};
+
+    frame.payload = match decompress(&frame.payload, max_size) {
+        Ok(payload) => payload.into(),
+        Err(DecompressError::TooBig) => return Err(message_too_big()),
+        Err(DecompressError::Invalid) => {
+            return Err(CloseFrame {
+                code: CloseCode::InvalidPayload,
+                reason: "Invalid compressed data".to_string(),
+            })
+        }
+    };
+    Ok(frame)
+}
+
+/// Without `websocket-deflate` nothing is negotiated, so RSV1 frames are
+/// rejected before they get here.
+#[cfg(not(feature = "websocket-deflate"))]
+fn inflate(frame: Frame, _max_size: usize) -> Result<Frame, CloseFrame> {
+    Ok(frame)
+}
+
+/// Encode an outgoing message as wire frames, fragmented at `max_frame_size`.
+/// Data messages are compressed when `permessage-deflate` was negotiated and
+/// it makes them smaller; control frames never are.
+fn encode_message(message: &Message, max_frame_size: usize, deflate: bool) -> Vec<Bytes> {
+    #[cfg(feature = "websocket-deflate")]
+    if deflate {
+        let data = match message {
+            Message::Text(text) => Some((OpCode::Text, text.as_bytes())),
+            Message::Binary(bytes) => Some((OpCode::Binary, bytes.as_ref())),
+            _ => None,
+        };
+        if let Some((opcode, compressed)) =
+            data.and_then(|(opcode, payload)| Some((opcode, super::deflate::compress(payload)?)))
+        {
+            let mut frames =
+                Message::Binary(compressed.into()).to_fragmented_frames(max_frame_size);
+            frames[0].opcode = opcode;
+            return frames
+                .iter()
+                .enumerate()
+                .map(|(i, frame)| {
+                    let mut encoded = BytesMut::from(frame.encode().as_ref());
+                    if i == 0 {
+                        encoded[0] |= 0x40; // RSV1: compressed message
+                    }
+                    encoded.freeze()
+                })
+                .collect();
+        }
+    }
+    #[cfg(not(feature = "websocket-deflate"))]
+    let _ = deflate;
+    message
+        .to_fragmented_frames(max_frame_size)
+        .iter()
+        .map(Frame::encode)
+        .collect()
+}
@@ -0,0 +1,165 @@
+//! `permessage-deflate` (RFC 7692) negotiation and payload codec
+//!
+//! Both directions run without context takeover: every message is compressed
+//! with a fresh raw-DEFLATE stream, so no per-connection window is kept and
+//! `server_max_window_bits` offers below 15 are declined.
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+
+/// Extension token in `Sec-WebSocket-Extensions`.
+const EXTENSION: &str = "permessage-deflate";
+
+/// Response header value confirming the extension.
+pub(crate) const ACCEPTED: &str =
+    "permessage-deflate; server_no_context_takeover; client_no_context_takeover";
+
+/// The empty stored block a sync flush ends with; stripped on send and
+/// re-appended on receive (RFC 7692 §7.2.1).
+const TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Whether any offer in the `Sec-WebSocket-Extensions` header values can be
+/// accepted.
+pub(crate) fn accepts_offer<'a>(header_values: impl IntoIterator<Item = &'a str>) -> bool {
+    header_values
+        .into_iter()
+        .flat_map(|v| v.split(','))
+        .any(|offer| {
+            let mut params = offer.split(';').map(str::trim);
+            params.next() == Some(EXTENSION)
+                && params.all(|param| {
+                    let (name, value) = match param.split_once('=') {
+                        Some((n, v)) => (n.trim(), Some(v.trim().trim_matches('"'))),
+                        None => (param, None),
+                    };
+                    match name {
+                        "server_no_context_takeover" | "client_no_context_takeover" => true,
+                        // Only the default 15-bit window is supported for our side.
+                        "server_max_window_bits" => value == Some("15"),
+                        "client_max_window_bits" => true,
+                        _ => false,
+                    }
+                })
+        })
+}
+
+/// Compress one message payload. Returns `None` when compression would not
+/// make it smaller, in which case it should be sent as-is.
+pub(crate) fn compress(payload: &[u8]) -> Option<Vec<u8>> {
+    let mut compressor = Compress::new(Compression::default(), false);
+    let mut out = Vec::with_capacity(payload.len() / 2 + 64);
+    loop {
+        let consumed = compressor.total_in() as usize;
+        out.reserve(payload.len().max(64));
+        compressor
+            .compress_vec(&payload[consumed..], &mut out, FlushCompress::Sync)
+            .ok()?;
+        // Done once all input is in and the flush fit in the spare capacity.
+        if compressor.total_in() as usize == payload.len() && out.len() < out.capacity() {
+            break;
+        }
+    }
+    if out.ends_with(&TAIL) {
+        out.truncate(out.len() - TAIL.len());
+    }
+    (out.len() < payload.len()).then_some(out)
+}
+
+/// Why [`decompress`] failed.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum DecompressError {
+    /// The inflated payload would exceed `max_size`.
+    TooBig,
+    /// The payload is not a valid DEFLATE stream.
+    Invalid,
+}
+
+/// Decompress one message payload, failing once the output would exceed
+/// `max_size` so a small frame can't inflate without bound.
+pub(crate) fn decompress(payload: &[u8], max_size: usize) -> Result<Vec<u8>, DecompressError> {
+    let mut input = Vec::with_capacity(payload.len() + TAIL.len());
+    input.extend_from_slice(payload);
+    input.extend_from_slice(&TAIL);
+
+    let mut decompressor = Decompress::new(false);
+    let mut out = Vec::with_capacity((payload.len() * 4).min(max_size.saturating_add(1)));
+    loop {
+        if out.len() > max_size {
+            return Err(DecompressError::TooBig);
+        }
+        if out.len() == out.capacity() {
+            out.reserve((out.capacity()).clamp(64, 64 * 1024));
+        }
+        let consumed = decompressor.total_in() as usize;
+        let status = decompressor
+            .decompress_vec(&input[consumed..], &mut out, FlushDecompress::Sync)
+            .map_err(|_| DecompressError::Invalid)?;
+        let input_done = decompressor.total_in() as usize == input.len();
+        if status == Status::StreamEnd || (input_done && out.len() < out.capacity()) {
+            break;
+        }
+    }
+    if out.len() > max_size {
+        return Err(DecompressError::TooBig);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_compressible_payload() {
+        let payload = "all work and no play makes jack a dull boy ".repeat(2000);
+        let compressed = compress(payload.as_bytes()).unwrap();
+        assert!(compressed.len() < payload.len() / 20);
+        assert!(!compressed.ends_with(&TAIL));
+        let inflated = decompress(&compressed, payload.len()).unwrap();
+        assert_eq!(inflated, payload.as_bytes());
+    }
+
+    #[test]
+    fn incompressible_payload_is_left_alone() {
+        assert!(compress(b"x").is_none());
+    }
+
+    #[test]
+    fn inflation_beyond_limit_is_rejected() {
+        let bomb = vec![0u8; 1024 * 1024];
+        let compressed = compress(&bomb).unwrap();
+        assert!(compressed.len() < 4096);
+        assert_eq!(
+            decompress(&compressed, 64 * 1024),
+            Err(DecompressError::TooBig)
+        );
+        assert_eq!(
+            decompress(&compressed, bomb.len()).unwrap().len(),
+            bomb.len()
+        );
+    }
+
+    #[test]
+    fn invalid_stream_is_rejected() {
+        // 0xff starts a block with the reserved BTYPE 11.
+        assert_eq!(
+            decompress(&[0xff, 0xff, 0xff], 1024),
+            Err(DecompressError::Invalid)
+        );
+    }
+
+    #[test]
+    fn negotiates_supported_offers_only() {
+        assert!(accepts_offer(["permessage-deflate"]));
+        assert!(accepts_offer([
+            "permessage-deflate; client_max_window_bits; server_no_context_takeover"
+        ]));
+        assert!(accepts_offer([
+            "permessage-deflate; server_max_window_bits=10, permessage-deflate"
+        ]));
+        assert!(!accepts_offer([
+            "permessage-deflate; server_max_window_bits=10"
+        ]));
+        assert!(!accepts_offer(["x-webkit-deflate-frame"]));
+        assert!(!accepts_offer(["permessage-deflate; unknown_param"]));
+    }
+}
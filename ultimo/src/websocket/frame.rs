@@ -259,12 +259,114 @@ pub enum Message {
     Close(Option<CloseFrame>),
 }
 
+/// Close frame payload: status code plus UTF-8 reason
 #[derive(Debug, Clone)]
 pub struct CloseFrame {
-    pub code: u16,
+    pub code: CloseCode,
     pub reason: String,
 }
 
+/// WebSocket close status code (RFC 6455 section 7.4)
+///
+/// Converts to and from the `u16` on the wire; codes without a variant
+/// (including application codes 4000-4999) are kept as [`CloseCode::Other`].
+/// Non-exhaustive, so codes registered later can get their own variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CloseCode {
+    /// 1000: the purpose of the connection has been fulfilled
+    Normal,
+    /// 1001: the endpoint is going away (server shutdown, page navigation)
+    GoingAway,
+    /// 1002: protocol error
+    ProtocolError,
+    /// 1003: received a data type it cannot accept
+    Unsupported,
+    /// 1005: the close frame carried no status code (never sent on the wire)
+    NoStatus,
+    /// 1006: the connection dropped without a close frame (never sent on the
+    /// wire)
+    Abnormal,
+    /// 1007: message data inconsistent with its type, e.g. invalid UTF-8
+    InvalidPayload,
+    /// 1008: message violates the endpoint's policy
+    PolicyViolation,
+    /// 1009: message too big to process
+    MessageTooBig,
+    /// 1010: the client expected an extension the server did not negotiate
+    MandatoryExtension,
+    /// 1011: unexpected condition on the server
+    InternalError,
+    /// 1012: the server is restarting
+    ServiceRestart,
+    /// 1013: temporary condition, try again later
+    TryAgainLater,
+    /// Any other code
+    Other(u16),
+}
+
+impl CloseCode {
+    /// Whether an endpoint may put this code in a close frame: 1005, 1006 and
+    /// 1015 are reserved for reporting, and `Other` is limited to 1014 (bad
+    /// gateway) and the 3000-4999 registered/application range (codes with
+    /// their own variant must use it).
+    pub(crate) fn is_sendable(self) -> bool {
+        match self {
+            CloseCode::NoStatus | CloseCode::Abnormal => false,
+            CloseCode::Other(code) => matches!(code, 1014 | 3000..=4999),
+            _ => true,
+        }
+    }
+}
+
+impl From<u16> for CloseCode {
+    fn from(code: u16) -> Self {
+        match code {
+            1000 => CloseCode::Normal,
+            1001 => CloseCode::GoingAway,
+            1002 => CloseCode::ProtocolError,
+            1003 => CloseCode::Unsupported,
+            1005 => CloseCode::NoStatus,
+            1006 => CloseCode::Abnormal,
+            1007 => CloseCode::InvalidPayload,
+            1008 => CloseCode::PolicyViolation,
+            1009 => CloseCode::MessageTooBig,
+            1010 => CloseCode::MandatoryExtension,
+            1011 => CloseCode::InternalError,
+            1012 => CloseCode::ServiceRestart,
+            1013 => CloseCode::TryAgainLater,
+            other => CloseCode::Other(other),
+        }
+    }
+}
+
+impl From<CloseCode> for u16 {
+    fn from(code: CloseCode) -> Self {
+        match code {
+            CloseCode::Normal => 1000,
+            CloseCode::GoingAway => 1001,
+            CloseCode::ProtocolError => 1002,
+            CloseCode::Unsupported => 1003,
+            CloseCode::NoStatus => 1005,
+            CloseCode::Abnormal => 1006,
+            CloseCode::InvalidPayload => 1007,
+            CloseCode::PolicyViolation => 1008,
+            CloseCode::MessageTooBig => 1009,
+            CloseCode::MandatoryExtension => 1010,
+            CloseCode::InternalError => 1011,
+            CloseCode::ServiceRestart => 1012,
+            CloseCode::TryAgainLater => 1013,
+            CloseCode::Other(code) => code,
+        }
+    }
+}
+
+impl std::fmt::Display for CloseCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", u16::from(*self))
+    }
+}
+
 impl Message {
     /// Create message from frame
     pub fn from_frame(frame: Frame) -> Result<Self, io::Error> {
@@ -302,7 +404,7 @@ impl Message {
             OpCode::Close => {
                 if frame.payload.len() >= 2 {
                     let mut buf = frame.payload.clone();
-                    let code = buf.get_u16();
+                    let code = CloseCode::from(buf.get_u16());
                     let reason = if buf.has_remaining() {
                         String::from_utf8(buf.to_vec()).unwrap_or_default()
                     } else {
@@ -329,7 +431,7 @@ impl Message {
             Message::Pong(data) => Frame::pong(data.clone()),
             Message::Close(close_frame) => {
                 if let Some(cf) = close_frame {
-                    Frame::close(Some(cf.code), Some(&cf.reason))
+                    Frame::close(Some(cf.code.into()), Some(&cf.reason))
                 } else {
                     Frame::close(None, None)
                 }
@@ -518,7 +620,7 @@ mod tests {
 
         match message {
             Message::Close(Some(close_frame)) => {
-                assert_eq!(close_frame.code, 1000);
+                assert_eq!(close_frame.code, CloseCode::Normal);
                 assert_eq!(close_frame.reason, "Normal closure");
             }
             _ => panic!("Expected close message with frame"),
@@ -532,13 +634,27 @@ mod tests {
 
         match message {
             Message::Close(Some(close_frame)) => {
-                assert_eq!(close_frame.code, 1001);
+                assert_eq!(close_frame.code, CloseCode::GoingAway);
                 assert_eq!(close_frame.reason, "");
             }
             _ => panic!("Expected close message"),
         }
     }
 
+    #[test]
+    fn test_close_code_is_sendable() {
+        assert!(CloseCode::Normal.is_sendable());
+        assert!(CloseCode::TryAgainLater.is_sendable());
+        assert!(CloseCode::Other(4000).is_sendable());
+        assert!(CloseCode::Other(1014).is_sendable());
+
+        assert!(!CloseCode::NoStatus.is_sendable());
+        assert!(!CloseCode::Abnormal.is_sendable());
+        for code in [999, 1000, 1004, 1015, 2999, 5000] {
+            assert!(!CloseCode::Other(code).is_sendable(), "{}", code);
+        }
+    }
+
     #[test]
     fn test_close_frame_empty() {
         let frame = Frame::close(None, None);
@@ -567,7 +683,7 @@ mod tests {
             Message::Ping(Bytes::from("ping")),
             Message::Pong(Bytes::from("pong")),
             Message::Close(Some(CloseFrame {
-                code: 1000,
+                code: CloseCode::Normal,
                 reason: "bye".to_string(),
             })),
         ];
@@ -587,6 +703,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_close_code_round_trips_u16() {
+        for code in [
+            1000, 1001, 1002, 1003, 1007, 1008, 1009, 1010, 1011, 1012, 1013, 4000,
+        ] {
+            assert_eq!(u16::from(CloseCode::from(code)), code);
+        }
+        assert_eq!(CloseCode::from(1008), CloseCode::PolicyViolation);
+        assert_eq!(CloseCode::from(4001), CloseCode::Other(4001));
+        assert_eq!(CloseCode::GoingAway.to_string(), "1001");
+    }
+
     #[test]
     fn test_opcode_is_control() {
         assert!(OpCode::Close.is_control());
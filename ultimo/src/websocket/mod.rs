@@ -4,20 +4,25 @@
 //! inspired by Bun's WebSocket API design.
 
 mod connection;
+#[cfg(feature = "websocket-deflate")]
+mod deflate;
 mod frame;
 mod pubsub;
+mod route;
 mod upgrade;
 
 pub use connection::WebSocket;
-pub use frame::{CloseFrame, Message};
+pub use frame::{CloseCode, CloseFrame, Message};
 pub use pubsub::ChannelManager;
+pub(crate) use route::BoxedWebSocketHandler;
+pub use route::WebSocketRoute;
 pub use upgrade::WebSocketUpgrade;
 
 // Test helpers - expose internal constructors for testing
 #[cfg(any(test, feature = "test-helpers"))]
 pub mod test_helpers {
     pub use super::connection::WebSocket;
-    pub use super::frame::{CloseFrame, Frame, Message, OpCode};
+    pub use super::frame::{CloseCode, CloseFrame, Frame, Message, OpCode};
     pub use super::pubsub::ChannelManager;
     use std::net::SocketAddr;
     use std::sync::Arc;
@@ -59,11 +64,20 @@ pub trait WebSocketHandler: Send + Sync {
     async fn on_message(&self, ws: &WebSocket<Self::Data>, msg: Message);
 
     /// Called when WebSocket connection is closed
-    async fn on_close(&self, ws: &WebSocket<Self::Data>, code: u16, reason: &str) {
+    ///
+    /// `code` is the peer's close code ([`CloseCode::NoStatus`] if its close
+    /// frame had none), the code the server closed with, or
+    /// [`CloseCode::Abnormal`] if the connection dropped without a close
+    /// frame.
+    async fn on_close(&self, ws: &WebSocket<Self::Data>, code: CloseCode, reason: &str) {
         let _ = (ws, code, reason);
     }
 
     /// Called when send buffer is writable again (backpressure handling)
+    ///
+    /// Fires once after a send returned
+    /// [`WouldBlock`](std::io::ErrorKind::WouldBlock), when the outbound queue
+    /// has drained to half of [`WebSocketConfig::max_write_queue_size`].
     async fn on_drain(&self, ws: &WebSocket<Self::Data>) {
         let _ = ws;
     }
@@ -74,10 +88,75 @@ pub trait WebSocketHandler: Send + Sync {
     }
 }
 
+/// A type-erased handler shared across connections.
+type SharedHandler<D> = std::sync::Arc<dyn WebSocketHandler<Data = D>>;
+
+/// Handlers for one WebSocket endpoint, selected by negotiated subprotocol.
+///
+/// Register with [`WebSocketRoute::protocols`]. `D` is the connection data
+/// produced by the route's [guard](WebSocketRoute::guard) and shared by every
+/// handler in the table. The first subprotocol the client offers that has a
+/// handler is confirmed in the handshake and serves the connection. A client
+/// that offers no subprotocol gets the [`default_handler`](Self::default_handler);
+/// without one, or if none of its offers match, the handshake fails with
+/// `400 Bad Request`.
+pub struct WebSocketProtocols<D = ()> {
+    pub(crate) handlers: Vec<(String, SharedHandler<D>)>,
+    pub(crate) default: Option<SharedHandler<D>>,
+}
+
+impl<D> Default for WebSocketProtocols<D> {
+    fn default() -> Self {
+        Self {
+            handlers: Vec::new(),
+            default: None,
+        }
+    }
+}
+
+impl<D: Send + Sync + 'static> WebSocketProtocols<D> {
+    /// Create an empty handler set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve connections negotiating `protocol` with `handler`.
+    pub fn protocol(
+        mut self,
+        protocol: impl Into<String>,
+        handler: impl WebSocketHandler<Data = D> + 'static,
+    ) -> Self {
+        self.handlers
+            .push((protocol.into(), std::sync::Arc::new(handler)));
+        self
+    }
+
+    /// Serve clients that offer no subprotocol with `handler`.
+    pub fn default_handler(mut self, handler: impl WebSocketHandler<Data = D> + 'static) -> Self {
+        self.default = Some(std::sync::Arc::new(handler));
+        self
+    }
+
+    /// Pick the handler for a client's offered subprotocols, returning the
+    /// confirmed protocol name alongside it.
+    pub(crate) fn select(&self, offered: &[String]) -> Option<(Option<&str>, SharedHandler<D>)> {
+        if offered.is_empty() {
+            return self.default.clone().map(|h| (None, h));
+        }
+        offered.iter().find_map(|offer| {
+            self.handlers
+                .iter()
+                .find(|(name, _)| name == offer)
+                .map(|(name, h)| (Some(name.as_str()), h.clone()))
+        })
+    }
+}
+
 /// Configuration options for WebSocket connections
 #[derive(Debug, Clone)]
 pub struct WebSocketConfig {
-    /// Maximum message size in bytes (default: 64 MB)
+    /// Maximum message size in bytes, across all fragments (default: 64 MB).
+    /// Larger messages close the connection with 1009.
     pub max_message_size: usize,
 
     /// Maximum frame size in bytes (default: 16 MB)
@@ -90,12 +169,18 @@ pub struct WebSocketConfig {
     pub ping_timeout: u64,
 
     /// Enable per-message deflate compression (default: false)
+    ///
+    /// Negotiates `permessage-deflate` (RFC 7692) with clients that offer it.
+    /// Requires the `websocket-deflate` feature; ignored without it.
     pub compression: bool,
 
     /// Write buffer size in bytes (default: 128 KB)
     pub write_buffer_size: usize,
 
     /// Maximum write queue size (default: 1024 messages)
+    ///
+    /// Sends beyond this fail with `WouldBlock` instead of buffering, so a
+    /// slow client cannot grow memory without bound.
     pub max_write_queue_size: usize,
 
     /// Accepted WebSocket subprotocols
@@ -3,7 +3,7 @@
 use super::frame::Message;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, watch, RwLock};
 use uuid::Uuid;
 
 /// Manages WebSocket pub/sub channels and message broadcasting
@@ -12,6 +12,8 @@ pub struct ChannelManager {
     subscriptions: Arc<RwLock<HashMap<String, HashSet<Uuid>>>>,
     /// Maps connection ID -> sender
     connections: Arc<RwLock<HashMap<Uuid, mpsc::Sender<Message>>>>,
+    /// Set to `true` on server shutdown; every live connection watches it.
+    shutdown: Arc<watch::Sender<bool>>,
 }
 
 impl ChannelManager {
@@ -20,6 +22,7 @@ impl ChannelManager {
         Self {
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
             connections: Arc::new(RwLock::new(HashMap::new())),
+            shutdown: Arc::new(watch::channel(false).0),
         }
     }
 
@@ -198,6 +201,22 @@ impl ChannelManager {
     pub async fn all_connection_ids(&self) -> Vec<Uuid> {
         self.connections.read().await.keys().copied().collect()
     }
+
+    /// Receiver a connection holds for as long as it is open; resolves its
+    /// `wait_for` once [`close_all`](Self::close_all) is called.
+    pub(crate) fn shutdown_signal(&self) -> watch::Receiver<bool> {
+        self.shutdown.subscribe()
+    }
+
+    /// Ask every open connection (subscribed or not) to close with 1001.
+    pub(crate) fn close_all(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Resolves once every connection holding a shutdown signal has ended.
+    pub(crate) async fn all_closed(&self) {
+        self.shutdown.closed().await
+    }
 }
 
 impl Default for ChannelManager {
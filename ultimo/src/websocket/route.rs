@@ -0,0 +1,178 @@
+//! WebSocket route registration
+//!
+//! [`WebSocketRoute`] collects a route's upgrade guard and connection config,
+//! then registers either a single handler or a [`WebSocketProtocols`] table.
+
+use super::{
+    CloseCode, Message, WebSocketConfig, WebSocketHandler, WebSocketProtocols, WebSocketUpgrade,
+};
+use crate::response::{Body, Response};
+use crate::Ultimo;
+use hyper::body::Incoming;
+use hyper::Request as HyperRequest;
+use std::sync::Arc;
+
+/// WebSocket handler function type
+pub(crate) type BoxedWebSocketHandler = Arc<dyn Fn(WebSocketUpgrade<()>) -> Response + Send + Sync>;
+
+/// An upgrade guard. The rejection is boxed so the default guard stays small;
+/// [`WebSocketRoute::guard`] takes it unboxed.
+type Guard<D> = Box<dyn Fn(&HyperRequest<Incoming>) -> Result<D, Box<Response>> + Send + Sync>;
+
+/// A WebSocket route being configured, started by
+/// [`Ultimo::websocket_route`](crate::Ultimo::websocket_route).
+///
+/// Set the guard and config first, then finish with [`handler`](Self::handler)
+/// or [`protocols`](Self::protocols), which register the route.
+#[must_use = "the route is registered by `handler` or `protocols`"]
+pub struct WebSocketRoute<'a, D = ()> {
+    app: &'a mut Ultimo,
+    path: String,
+    config: WebSocketConfig,
+    guard: Guard<D>,
+}
+
+impl<'a> WebSocketRoute<'a> {
+    pub(crate) fn new(app: &'a mut Ultimo, path: &str) -> Self {
+        Self {
+            app,
+            path: path.to_string(),
+            config: WebSocketConfig::default(),
+            guard: Box::new(|_req| Ok(())),
+        }
+    }
+}
+
+impl<'a, D> WebSocketRoute<'a, D>
+where
+    D: Send + Sync + 'static,
+{
+    /// Gate the handshake with `guard`.
+    ///
+    /// The guard sees the upgrade request before the handshake completes.
+    /// Returning `Ok(data)` accepts the connection and attaches `data`
+    /// (available as `ws.data()`); returning `Err(response)` sends that
+    /// response verbatim and no upgrade happens.
+    pub fn guard<E, G>(self, guard: G) -> WebSocketRoute<'a, E>
+    where
+        E: Send + Sync + 'static,
+        G: Fn(&HyperRequest<Incoming>) -> Result<E, Response> + Send + Sync + 'static,
+    {
+        WebSocketRoute {
+            app: self.app,
+            path: self.path,
+            config: self.config,
+            guard: Box::new(move |req| guard(req).map_err(Box::new)),
+        }
+    }
+
+    /// Use `config` for the route's connections.
+    pub fn config(mut self, config: WebSocketConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Serve every connection with `handler`.
+    pub fn handler<H>(self, handler: H) -> &'a mut Ultimo
+    where
+        H: WebSocketHandler<Data = D> + 'static,
+    {
+        let Self {
+            app,
+            path,
+            config,
+            guard,
+        } = self;
+        let handler = Arc::new(handler);
+        let channel_manager = app.channels();
+
+        let ws_handler = move |upgrade: WebSocketUpgrade<()>| {
+            let data = match guard(upgrade.request()) {
+                Ok(data) => data,
+                Err(rejection) => return *rejection,
+            };
+            let upgrade = upgrade
+                .attach(data)
+                .with_channel_manager(channel_manager.clone())
+                .with_config(config.clone());
+            drive_websocket(upgrade, handler.clone())
+        };
+        app.add_websocket_route(&path, Arc::new(ws_handler))
+    }
+
+    /// Serve connections with the handler registered for the negotiated
+    /// subprotocol (see [`WebSocketProtocols`]).
+    ///
+    /// The guard runs first, so rejected clients learn nothing about the
+    /// supported protocols. [`WebSocketConfig::subprotocols`] is ignored
+    /// here: `protocols` alone decides what is confirmed.
+    pub fn protocols(self, protocols: WebSocketProtocols<D>) -> &'a mut Ultimo {
+        let Self {
+            app,
+            path,
+            mut config,
+            guard,
+        } = self;
+        config.subprotocols.clear();
+        let channel_manager = app.channels();
+
+        let ws_handler = move |upgrade: WebSocketUpgrade<()>| {
+            let data = match guard(upgrade.request()) {
+                Ok(data) => data,
+                Err(rejection) => return *rejection,
+            };
+            let offered = upgrade.requested_protocols();
+            let Some((protocol, handler)) = protocols.select(&offered) else {
+                return hyper::Response::builder()
+                    .status(hyper::StatusCode::BAD_REQUEST)
+                    .body(Body::from("Unsupported WebSocket subprotocol"))
+                    .unwrap();
+            };
+            let upgrade = upgrade
+                .attach(data)
+                .with_protocols(protocol.map(str::to_string).into_iter().collect())
+                .with_channel_manager(channel_manager.clone())
+                .with_config(config.clone());
+            drive_websocket(upgrade, handler)
+        };
+        app.add_websocket_route(&path, Arc::new(ws_handler))
+    }
+}
+
+/// Complete a WebSocket handshake and run `handler`'s lifecycle callbacks for
+/// the resulting connection.
+fn drive_websocket<H>(upgrade: WebSocketUpgrade<H::Data>, handler: Arc<H>) -> Response
+where
+    H: WebSocketHandler + ?Sized + 'static,
+{
+    upgrade.on_upgrade_with_receiver(move |ws, mut incoming_rx, mut drain_rx| async move {
+        // Call on_open
+        handler.on_open(&ws).await;
+
+        // Handle incoming messages and drain notifications, remembering how
+        // the connection was closed
+        let mut closed = None;
+        loop {
+            tokio::select! {
+                Some(msg) = incoming_rx.recv() => {
+                    if let (Message::Close(frame), None) = (&msg, &closed) {
+                        closed = Some(match frame {
+                            Some(frame) => (frame.code, frame.reason.clone()),
+                            None => (CloseCode::NoStatus, String::new()),
+                        });
+                    }
+                    handler.on_message(&ws, msg).await;
+                }
+                Some(_) = drain_rx.recv() => {
+                    handler.on_drain(&ws).await;
+                }
+                else => break,
+            }
+        }
+
+        // Call on_close when connection ends
+        let (code, reason) =
+            closed.unwrap_or((CloseCode::Abnormal, "Connection closed".to_string()));
+        handler.on_close(&ws, code, &reason).await;
+    })
+}
@@ -4,10 +4,10 @@ use super::connection::{ConnectionHandler, WebSocket};
 use super::frame::Message;
 use super::pubsub::ChannelManager;
 use super::WebSocketConfig;
-use bytes::Bytes;
-use http_body_util::Full;
+use crate::response::{Body, Response};
 use hyper::header::{
-    CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_VERSION, UPGRADE,
+    CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_PROTOCOL,
+    SEC_WEBSOCKET_VERSION, UPGRADE,
 };
 use hyper::{Request as HyperRequest, Response as HyperResponse, StatusCode};
 use sha1::{Digest, Sha1};
@@ -43,6 +43,73 @@ where
         }
     }
 
+    /// The HTTP upgrade request (method, URI, headers).
+    pub fn request(&self) -> &HyperRequest<hyper::body::Incoming> {
+        &self.request
+    }
+
+    /// Subprotocols offered by the client in `Sec-WebSocket-Protocol`, in the
+    /// client's order of preference.
+    pub fn requested_protocols(&self) -> Vec<String> {
+        requested_protocols(&self.request)
+    }
+
+    /// The subprotocol to confirm: the first client offer that the server
+    /// accepts (via [`with_protocols`](Self::with_protocols) or
+    /// [`WebSocketConfig::subprotocols`]).
+    fn negotiated_protocol(&self) -> Option<String> {
+        self.requested_protocols().into_iter().find(|offer| {
+            self.protocols.contains(offer) || self.config.subprotocols.contains(offer)
+        })
+    }
+
+    /// Whether to confirm `permessage-deflate`: compression is enabled in the
+    /// config and the client offered parameters we support.
+    fn negotiated_deflate(&self) -> bool {
+        #[cfg(feature = "websocket-deflate")]
+        {
+            self.config.compression
+                && super::deflate::accepts_offer(
+                    self.request
+                        .headers()
+                        .get_all(hyper::header::SEC_WEBSOCKET_EXTENSIONS)
+                        .iter()
+                        .filter_map(|v| v.to_str().ok()),
+                )
+        }
+        #[cfg(not(feature = "websocket-deflate"))]
+        false
+    }
+
+    /// Run an upgrade guard against the handshake request.
+    ///
+    /// On `Ok(data)` the upgrade continues with `data` attached to the
+    /// connection; on `Err(response)` that response should be sent to the
+    /// client verbatim instead of upgrading, so the guard fully controls the
+    /// rejection status, headers and body.
+    // The rejection is the response itself, so it stays unboxed.
+    #[allow(clippy::result_large_err)]
+    pub fn guard<D, F>(self, guard: F) -> Result<WebSocketUpgrade<D>, Response>
+    where
+        D: Send + 'static,
+        F: FnOnce(&HyperRequest<hyper::body::Incoming>) -> Result<D, Response>,
+    {
+        let data = guard(&self.request)?;
+        Ok(self.attach(data))
+    }
+
+    /// Continue the upgrade with `data` attached, changing its data type.
+    pub(crate) fn attach<D>(self, data: D) -> WebSocketUpgrade<D> {
+        WebSocketUpgrade {
+            request: self.request,
+            data: Some(data),
+            headers: self.headers,
+            protocols: self.protocols,
+            config: self.config,
+            channel_manager: self.channel_manager,
+        }
+    }
+
     /// Set typed context data for the WebSocket
     pub fn with_data(mut self, data: T) -> Self {
         self.data = Some(data);
@@ -74,7 +141,7 @@ where
     }
 
     /// Set callback to be executed when WebSocket is upgraded
-    pub fn on_upgrade<F, Fut>(self, callback: F) -> HyperResponse<Full<Bytes>>
+    pub fn on_upgrade<F, Fut>(self, callback: F) -> Response
     where
         F: FnOnce(WebSocket<T>) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
@@ -84,7 +151,7 @@ where
         if !is_valid_upgrade_request(&self.request) {
             return HyperResponse::builder()
                 .status(StatusCode::BAD_REQUEST)
-                .body(Full::new(Bytes::from("Invalid WebSocket upgrade request")))
+                .body(Body::from("Invalid WebSocket upgrade request"))
                 .unwrap();
         }
 
@@ -94,7 +161,7 @@ where
             None => {
                 return HyperResponse::builder()
                     .status(StatusCode::BAD_REQUEST)
-                    .body(Full::new(Bytes::from("Missing Sec-WebSocket-Key header")))
+                    .body(Body::from("Missing Sec-WebSocket-Key header"))
                     .unwrap();
             }
         };
@@ -109,12 +176,24 @@ where
             .header(CONNECTION, "Upgrade")
             .header(SEC_WEBSOCKET_ACCEPT, accept_key);
 
+        if let Some(protocol) = self.negotiated_protocol() {
+            response = response.header(SEC_WEBSOCKET_PROTOCOL, protocol);
+        }
+        let deflate = self.negotiated_deflate();
+        #[cfg(feature = "websocket-deflate")]
+        if deflate {
+            response = response.header(
+                hyper::header::SEC_WEBSOCKET_EXTENSIONS,
+                super::deflate::ACCEPTED,
+            );
+        }
+
         // Add custom headers
         for (key, value) in self.headers {
             response = response.header(key, value);
         }
 
-        let response = response.body(Full::new(Bytes::new())).unwrap();
+        let response = response.body(Body::empty()).unwrap();
 
         // Spawn upgrade handler
         let data = self.data.expect("WebSocket data not set");
@@ -124,8 +203,12 @@ where
         tokio::spawn(async move {
             match hyper::upgrade::on(self.request).await {
                 Ok(upgraded) => {
-                    let (handler, sender, mut incoming_rx, mut _drain_rx) =
-                        ConnectionHandler::new(upgraded, channel_manager.clone(), config.clone());
+                    let (handler, sender, mut incoming_rx, mut _drain_rx) = ConnectionHandler::new(
+                        upgraded,
+                        channel_manager.clone(),
+                        config.clone(),
+                        deflate,
+                    );
                     let connection_id = uuid::Uuid::new_v4();
                     let remote_addr = None; // TODO: Get from request
 
@@ -136,7 +219,8 @@ where
                         connection_id,
                         remote_addr,
                         config.clone(),
-                    );
+                    )
+                    .with_backpressure(handler.backpressure());
 
                     // Spawn the connection handler
                     let handler_task = tokio::spawn(async move {
@@ -169,7 +253,7 @@ where
     }
 
     /// Set callback that receives incoming messages through a channel
-    pub fn on_upgrade_with_receiver<F, Fut>(self, callback: F) -> HyperResponse<Full<Bytes>>
+    pub fn on_upgrade_with_receiver<F, Fut>(self, callback: F) -> Response
     where
         F: FnOnce(
                 WebSocket<T>,
@@ -185,7 +269,7 @@ where
         if !is_valid_upgrade_request(&self.request) {
             return HyperResponse::builder()
                 .status(StatusCode::BAD_REQUEST)
-                .body(Full::new(Bytes::from("Invalid WebSocket upgrade request")))
+                .body(Body::from("Invalid WebSocket upgrade request"))
                 .unwrap();
         }
 
@@ -195,7 +279,7 @@ where
             None => {
                 return HyperResponse::builder()
                     .status(StatusCode::BAD_REQUEST)
-                    .body(Full::new(Bytes::from("Missing Sec-WebSocket-Key header")))
+                    .body(Body::from("Missing Sec-WebSocket-Key header"))
                     .unwrap();
             }
         };
@@ -210,12 +294,24 @@ where
             .header(CONNECTION, "Upgrade")
             .header(SEC_WEBSOCKET_ACCEPT, accept_key);
 
+        if let Some(protocol) = self.negotiated_protocol() {
+            response = response.header(SEC_WEBSOCKET_PROTOCOL, protocol);
+        }
+        let deflate = self.negotiated_deflate();
+        #[cfg(feature = "websocket-deflate")]
+        if deflate {
+            response = response.header(
+                hyper::header::SEC_WEBSOCKET_EXTENSIONS,
+                super::deflate::ACCEPTED,
+            );
+        }
+
         // Add custom headers
         for (key, value) in self.headers {
             response = response.header(key, value);
         }
 
-        let response = response.body(Full::new(Bytes::new())).unwrap();
+        let response = response.body(Body::empty()).unwrap();
 
         // Spawn upgrade handler
         let data = self.data.expect("WebSocket data not set");
@@ -225,8 +321,12 @@ where
         tokio::spawn(async move {
             match hyper::upgrade::on(self.request).await {
                 Ok(upgraded) => {
-                    let (handler, sender, incoming_rx, drain_rx) =
-                        ConnectionHandler::new(upgraded, channel_manager.clone(), config.clone());
+                    let (handler, sender, incoming_rx, drain_rx) = ConnectionHandler::new(
+                        upgraded,
+                        channel_manager.clone(),
+                        config.clone(),
+                        deflate,
+                    );
                     let connection_id = uuid::Uuid::new_v4();
                     let remote_addr = None; // TODO: Get from request
 
@@ -237,7 +337,8 @@ where
                         connection_id,
                         remote_addr,
                         config.clone(),
-                    );
+                    )
+                    .with_backpressure(handler.backpressure());
 
                     // Spawn the connection handler
                     let handler_task = tokio::spawn(async move {
@@ -264,7 +365,7 @@ where
     }
 
     /// Build the upgrade response without a callback (for manual handling)
-    pub fn build(self) -> HyperResponse<Full<Bytes>>
+    pub fn build(self) -> Response
     where
         T: Default,
     {
@@ -307,6 +408,19 @@ fn is_valid_upgrade_request(req: &HyperRequest<hyper::body::Incoming>) -> bool {
     true
 }
 
+/// Parse every `Sec-WebSocket-Protocol` header into its comma-separated offers.
+fn requested_protocols<B>(req: &HyperRequest<B>) -> Vec<String> {
+    req.headers()
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 /// Calculate WebSocket accept key from client key
 fn calculate_accept_key(key: &str) -> String {
     use base64::{engine::general_purpose, Engine as _};
@@ -327,4 +441,14 @@ mod tests {
         let accept = calculate_accept_key(key);
         assert_eq!(accept, "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
     }
+
+    #[test]
+    fn test_requested_protocols_split_across_headers() {
+        let req = HyperRequest::builder()
+            .header(SEC_WEBSOCKET_PROTOCOL, "chat, admin")
+            .header(SEC_WEBSOCKET_PROTOCOL, " metrics ,")
+            .body(())
+            .unwrap();
+        assert_eq!(requested_protocols(&req), ["chat", "admin", "metrics"]);
+    }
 }
@@ -25,10 +25,189 @@ async fn allows_body_within_limit() {
 }
 
 #[tokio::test]
-async fn no_limit_by_default() {
+async fn default_limit_is_two_megabytes() {
     let mut app = Ultimo::new_without_defaults();
-    app.post("/", |ctx: Context| async move { ctx.text("ok").await });
-    let big = "x".repeat(10_000);
-    let res = TestClient::new(app).post("/").text(&big).send().await;
+    app.post("/", |ctx: Context| async move {
+        let body = ctx.req.bytes().await?;
+        ctx.text(body.len().to_string()).await
+    });
+    let client = TestClient::new(app);
+
+    let res = client.post("/").text(&"x".repeat(10_000)).send().await;
+    assert_eq!(res.status(), 200);
+    let res = client
+        .post("/")
+        .text(&"x".repeat(2 * 1024 * 1024))
+        .send()
+        .await;
+    assert_eq!(res.status(), 200);
+    let res = client
+        .post("/")
+        .text(&"x".repeat(2 * 1024 * 1024 + 1))
+        .send()
+        .await;
+    assert_eq!(res.status(), 413);
+}
+
+fn app_with_upload_group() -> Ultimo {
+    let mut app = app();
+    app.group("/uploads", |uploads| {
+        uploads.max_body_size(1024);
+        uploads.post("/", |ctx: Context| async move {
+            let body = ctx.req.bytes().await?;
+            ctx.text(body.len().to_string()).await
+        });
+        uploads.group("/avatars", |avatars| {
+            avatars.post(
+                "/",
+                |ctx: Context| async move { ctx.text("inherited").await },
+            );
+        });
+        uploads.group("/tiny", |tiny| {
+            tiny.max_body_size(4);
+            tiny.post("/", |ctx: Context| async move { ctx.text("tiny").await });
+        });
+    });
+    app
+}
+
+#[tokio::test]
+async fn group_overrides_app_limit() {
+    let client = TestClient::new(app_with_upload_group());
+    let big = "x".repeat(100);
+
+    let res = client.post("/uploads").text(&big).send().await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text(), "100");
+
+    // Routes outside the group keep the app limit.
+    let res = client.post("/").text(&big).send().await;
+    assert_eq!(res.status(), 413);
+
+    let res = client.post("/uploads").text(&"x".repeat(2000)).send().await;
+    assert_eq!(res.status(), 413);
+}
+
+#[tokio::test]
+async fn nested_groups_inherit_or_override_limit() {
+    let client = TestClient::new(app_with_upload_group());
+    let body = "x".repeat(100);
+
+    let res = client.post("/uploads/avatars").text(&body).send().await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text(), "inherited");
+
+    let res = client.post("/uploads/tiny").text(&body).send().await;
+    assert_eq!(res.status(), 413);
+}
+
+#[tokio::test]
+async fn mounted_router_keeps_its_limit() {
+    let mut uploads = Ultimo::router();
+    uploads.max_body_size(1024);
+    uploads.post("/", |ctx: Context| async move { ctx.text("ok").await });
+
+    let mut app = app();
+    app.mount("/uploads", uploads);
+    let res = TestClient::new(app)
+        .post("/uploads")
+        .text(&"x".repeat(100))
+        .send()
+        .await;
+    assert_eq!(res.status(), 200);
+}
+
+fn app_with_route_constraints() -> Ultimo {
+    let mut app = app();
+    app.route("/imports")
+        .content_type("application/json")
+        .max_body_size(1024)
+        .post(|ctx: Context| async move {
+            let rows: Vec<u32> = ctx.req.json().await?;
+            ctx.text(rows.len().to_string()).await
+        });
+    app.route("/feedback")
+        .content_type("application/x-www-form-urlencoded")
+        .content_type("multipart/form-data")
+        .max_body_size(8)
+        .post(|ctx: Context| async move { ctx.text("thanks").await })
+        .get(|ctx: Context| async move { ctx.text("form").await });
+    app.group("/api", |api| {
+        api.route("/echo")
+            .content_type("text/plain")
+            .put(|ctx: Context| async move { ctx.text(ctx.req.text().await?).await });
+    });
+    app
+}
+
+#[tokio::test]
+async fn route_overrides_app_limit() {
+    let client = TestClient::new(app_with_route_constraints());
+    let rows = vec![1u32; 100]; // ~200 bytes, over the app's 16
+
+    let res = client.post("/imports").json(&rows).send().await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text(), "100");
+
+    let res = client
+        .post("/feedback")
+        .header("content-type", "application/x-www-form-urlencoded")
+        .text("message=too+long")
+        .send()
+        .await;
+    assert_eq!(res.status(), 413);
+}
+
+#[tokio::test]
+async fn route_rejects_unexpected_content_type() {
+    let client = TestClient::new(app_with_route_constraints());
+
+    let res = client.post("/imports").text("[1,2]").send().await;
+    assert_eq!(res.status(), 415);
+    assert_eq!(res.text(), "Expected Content-Type: application/json");
+
+    let res = client
+        .post("/imports")
+        .header("content-type", "text/csv")
+        .text("1,2")
+        .send()
+        .await;
+    assert_eq!(res.status(), 415);
+
+    let res = client
+        .post("/imports")
+        .header("content-type", "Application/JSON; charset=utf-8")
+        .text("[1,2]")
+        .send()
+        .await;
+    assert_eq!(res.status(), 200);
+
+    let res = client
+        .post("/feedback")
+        .header("content-type", "multipart/form-data; boundary=x")
+        .text("--x--")
+        .send()
+        .await;
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn route_content_type_ignores_requests_without_body() {
+    let client = TestClient::new(app_with_route_constraints());
+
+    let res = client.get("/feedback").send().await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text(), "form");
+
+    let res = client.put("/api/echo").send().await;
     assert_eq!(res.status(), 200);
+    let res = client
+        .put("/api/echo")
+        .header("content-type", "text/plain")
+        .text("hi")
+        .send()
+        .await;
+    assert_eq!(res.text(), "hi");
+    let res = client.put("/api/echo").json(&"hi").send().await;
+    assert_eq!(res.status(), 415);
 }
@@ -57,3 +57,35 @@ fn rest_client_has_derived_types_and_signatures() {
     // No dangling/hardcoded interface.
     assert!(!client.contains("export interface User"));
 }
+
+#[test]
+fn openapi_references_derived_types_as_components() {
+    let rpc = RpcRegistry::new_with_mode(RpcMode::Rest);
+    rpc.mutation("createUser", |input: CreateUserInput| async move {
+        Ok(User {
+            id: 1,
+            name: input.name,
+            email: input.email,
+            tags: vec![],
+            nickname: None,
+        })
+    });
+
+    let spec = rpc.generate_openapi("API", "1.0.0", "/api");
+    let schemas = spec.components.as_ref().unwrap().schemas.as_ref().unwrap();
+    let user = &schemas["User"];
+    assert!(user.properties.as_ref().unwrap().contains_key("tags"));
+    assert!(!user.required.as_ref().unwrap().is_empty());
+
+    let op = spec.paths["/api/createUser"].post.as_ref().unwrap();
+    let body = &op.request_body.as_ref().unwrap().content["application/json"];
+    assert_eq!(
+        body.schema.reference.as_deref(),
+        Some("#/components/schemas/CreateUserInput")
+    );
+    let ok = op.responses["200"].content.as_ref().unwrap();
+    assert_eq!(
+        ok["application/json"].schema.reference.as_deref(),
+        Some("#/components/schemas/User")
+    );
+}
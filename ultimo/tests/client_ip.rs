@@ -46,3 +46,33 @@ async fn forwarded_header_used_when_trusted() {
         .await;
     assert_eq!(res.text(), "198.51.100.5");
 }
+
+#[tokio::test]
+async fn x_real_ip_used_when_trusted() {
+    let res = TestClient::new(ip_app(true))
+        .get("/ip")
+        .header("x-real-ip", "192.0.2.44")
+        .header("forwarded", "for=198.51.100.5")
+        .send()
+        .await;
+    assert_eq!(res.text(), "192.0.2.44");
+
+    // X-Forwarded-For still wins when both are present.
+    let res = TestClient::new(ip_app(true))
+        .get("/ip")
+        .header("x-forwarded-for", "203.0.113.7")
+        .header("x-real-ip", "192.0.2.44")
+        .send()
+        .await;
+    assert_eq!(res.text(), "203.0.113.7");
+}
+
+#[tokio::test]
+async fn x_real_ip_ignored_when_not_trusted() {
+    let res = TestClient::new(ip_app(false))
+        .get("/ip")
+        .header("x-real-ip", "192.0.2.44")
+        .send()
+        .await;
+    assert_eq!(res.text(), "none");
+}
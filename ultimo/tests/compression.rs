@@ -97,7 +97,7 @@ async fn image_content_type_not_compressed() {
         Ok(hyper::Response::builder()
             .status(200)
             .header("content-type", "image/png")
-            .body(Full::new(Bytes::from(vec![0u8; 2048])))
+            .body(ultimo::response::Body::from(vec![0u8; 2048]))
             .unwrap())
     });
 
@@ -151,7 +151,7 @@ async fn already_encoded_response_not_double_compressed() {
             .status(200)
             .header("content-type", "text/plain")
             .header("content-encoding", "gzip") // already encoded
-            .body(Full::new(Bytes::from(vec![0u8; 2048])))
+            .body(ultimo::response::Body::from(vec![0u8; 2048]))
             .unwrap())
     });
 
@@ -212,3 +212,21 @@ async fn compression_builder_respects_min_size_override() {
     // min_size=99999 means nothing is compressed
     assert!(res.headers().get("content-encoding").is_none());
 }
+
+#[tokio::test]
+async fn vary_header_keeps_existing_values() {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(compression());
+    app.get("/cors", |ctx: Context| async move {
+        ctx.header("Vary", "Origin").await;
+        ctx.json(serde_json::json!({ "data": large_text() })).await
+    });
+    let req = HyperRequest::builder()
+        .uri("/cors")
+        .header("accept-encoding", "gzip")
+        .body(empty())
+        .unwrap();
+    let res = app.oneshot(req).await;
+    let vary: Vec<_> = res.headers().get_all("vary").iter().collect();
+    assert_eq!(vary, ["Origin", "Accept-Encoding"]);
+}
@@ -0,0 +1,128 @@
+//! Tests that buffered responses carry an accurate `Content-Length` and
+//! streamed ones are chunked.
+#![cfg(feature = "testing")]
+
+use futures_util::stream;
+use serde_json::json;
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo};
+
+fn app() -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.get("/user", |ctx: Context| async move {
+        ctx.json(json!({ "id": 1, "name": "Ada Lovelace" })).await
+    });
+    app.get(
+        "/hello",
+        |ctx: Context| async move { ctx.text("héllo").await },
+    );
+    app.get("/empty", |ctx: Context| async move { ctx.text("").await });
+    app.delete(
+        "/user",
+        |ctx: Context| async move { ctx.no_content().await },
+    );
+    app.get("/users", |ctx: Context| async move {
+        ctx.json_stream(stream::iter(
+            (1..=3).map(|id| Ok::<_, std::io::Error>(json!({ "id": id }))),
+        ))
+        .await
+    });
+    app.get("/rewritten", |ctx: Context| async move {
+        ctx.header("Content-Length", "999").await;
+        ctx.text("short").await
+    });
+    app
+}
+
+fn content_length(res: &ultimo::testing::TestResponse) -> Option<usize> {
+    res.header("content-length").map(|v| v.parse().unwrap())
+}
+
+#[tokio::test]
+async fn json_response_has_exact_content_length() {
+    let res = TestClient::new(app()).get("/user").send().await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(content_length(&res), Some(res.bytes().len()));
+    assert_eq!(res.bytes().len(), r#"{"id":1,"name":"Ada Lovelace"}"#.len());
+}
+
+#[tokio::test]
+async fn buffered_responses_count_bytes_not_chars() {
+    let client = TestClient::new(app());
+
+    let res = client.get("/hello").send().await;
+    assert_eq!(content_length(&res), Some("héllo".len()));
+
+    let res = client.get("/empty").send().await;
+    assert_eq!(content_length(&res), Some(0));
+
+    // Errors and unmatched routes are buffered too.
+    let res = client.get("/missing").send().await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(content_length(&res), Some(res.bytes().len()));
+}
+
+#[tokio::test]
+async fn stale_content_length_is_corrected() {
+    let res = TestClient::new(app()).get("/rewritten").send().await;
+    assert_eq!(res.text(), "short");
+    assert_eq!(content_length(&res), Some(5));
+}
+
+#[tokio::test]
+async fn head_and_no_content_lengths() {
+    let client = TestClient::new(app());
+
+    let get = client.get("/user").send().await;
+    let head = client.head("/user").send().await;
+    assert!(head.bytes().is_empty());
+    assert_eq!(content_length(&head), content_length(&get));
+
+    let res = client.delete("/user").send().await;
+    assert_eq!(res.status(), 204);
+    assert_eq!(res.header("content-length"), None);
+}
+
+#[tokio::test]
+async fn streams_have_no_content_length() {
+    let res = TestClient::new(app()).get("/users").send().await;
+    assert_eq!(res.text(), r#"[{"id":1},{"id":2},{"id":3}]"#);
+    assert_eq!(res.header("content-length"), None);
+}
+
+#[tokio::test]
+async fn on_the_wire_buffered_is_sized_and_streams_are_chunked() {
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::spawn(app().listen_on(listener));
+    let client = reqwest::Client::builder().http1_only().build().unwrap();
+
+    let res = client
+        .get(format!("http://{}/user", addr))
+        .send()
+        .await
+        .unwrap();
+    let length = res.headers()["content-length"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(res.headers().get("transfer-encoding").is_none());
+    assert_eq!(length, res.bytes().await.unwrap().len().to_string());
+
+    let res = client
+        .get(format!("http://{}/users", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.headers()["transfer-encoding"], "chunked");
+    assert!(res.headers().get("content-length").is_none());
+    assert_eq!(res.text().await.unwrap(), r#"[{"id":1},{"id":2},{"id":3}]"#);
+
+    // Same keep-alive connection after a chunked response.
+    let res = client
+        .get(format!("http://{}/rewritten", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.headers()["content-length"], "5");
+    assert_eq!(res.text().await.unwrap(), "short");
+}
@@ -41,3 +41,45 @@ async fn reads_request_cookie() {
         .await;
     assert_eq!(res.text(), "secret123");
 }
+
+#[tokio::test]
+async fn multiple_set_cookies_are_all_emitted() {
+    let mut app = Ultimo::new_without_defaults();
+    app.get("/login", |ctx: Context| async move {
+        ctx.set_cookie(
+            Cookie::new("sid", "xyz")
+                .path("/")
+                .domain("example.com")
+                .max_age(3600)
+                .http_only(true)
+                .secure(true)
+                .same_site(SameSite::Strict),
+        )
+        .await?;
+        ctx.set_cookie(Cookie::new("theme", "dark")).await?;
+        ctx.text("ok").await
+    });
+
+    let client = TestClient::new(app);
+    let res = client.get("/login").send().await;
+    let cookies: Vec<_> = res
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|v| v.to_str().unwrap())
+        .collect();
+    assert_eq!(cookies.len(), 2);
+    let sid = cookies[0];
+    for attr in [
+        "sid=xyz",
+        "Path=/",
+        "Domain=example.com",
+        "Max-Age=3600",
+        "HttpOnly",
+        "Secure",
+        "SameSite=Strict",
+    ] {
+        assert!(sid.contains(attr), "{} missing {}", sid, attr);
+    }
+    assert!(cookies[1].starts_with("theme=dark"));
+}
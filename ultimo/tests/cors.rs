@@ -0,0 +1,131 @@
+#![cfg(feature = "testing")]
+
+use ultimo::middleware::builtin::{cors, Cors};
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo};
+
+fn client(cors: Cors) -> TestClient {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(cors.build());
+    app.get("/me", |ctx: Context| async move {
+        ctx.set_header("X-Total-Count", "1").await;
+        ctx.text("me").await
+    });
+    TestClient::new(app)
+}
+
+fn credentialed() -> Cors {
+    Cors::new()
+        .allow_origins(vec!["https://app.example.com", "https://admin.example.com"])
+        .allow_methods(vec!["GET", "POST", "DELETE"])
+        .allow_headers(vec!["Content-Type", "X-Csrf-Token"])
+        .expose_headers(vec!["X-Total-Count"])
+        .allow_credentials(true)
+        .max_age(600)
+}
+
+#[tokio::test]
+async fn credentialed_preflight_echoes_listed_origin() {
+    let res = client(credentialed())
+        .options("/me")
+        .header("Origin", "https://admin.example.com")
+        .header("Access-Control-Request-Method", "DELETE")
+        .header(
+            "Access-Control-Request-Headers",
+            "content-type, x-csrf-token",
+        )
+        .send()
+        .await;
+
+    res.assert_status(204)
+        .assert_header("access-control-allow-origin", "https://admin.example.com")
+        .assert_header("access-control-allow-credentials", "true")
+        .assert_header("access-control-allow-methods", "GET, POST, DELETE")
+        .assert_header("access-control-allow-headers", "Content-Type, X-Csrf-Token")
+        .assert_header("access-control-max-age", "600")
+        .assert_header("vary", "Origin");
+}
+
+#[tokio::test]
+async fn credentialed_response_exposes_headers() {
+    let res = client(credentialed())
+        .get("/me")
+        .header("Origin", "https://app.example.com")
+        .send()
+        .await;
+
+    res.assert_text("me")
+        .assert_header("access-control-allow-origin", "https://app.example.com")
+        .assert_header("access-control-allow-credentials", "true")
+        .assert_header("access-control-expose-headers", "X-Total-Count")
+        .assert_header("vary", "Origin");
+}
+
+#[tokio::test]
+async fn unlisted_origin_gets_no_cors_headers() {
+    let client = client(credentialed());
+
+    let res = client
+        .options("/me")
+        .header("Origin", "https://evil.example.com")
+        .header("Access-Control-Request-Method", "GET")
+        .send()
+        .await;
+    assert_eq!(res.status(), 204);
+    assert!(res.header("access-control-allow-origin").is_none());
+    assert!(res.header("access-control-allow-credentials").is_none());
+
+    let res = client
+        .get("/me")
+        .header("Origin", "https://evil.example.com")
+        .send()
+        .await;
+    res.assert_text("me");
+    assert!(res.header("access-control-allow-origin").is_none());
+}
+
+#[test]
+#[should_panic(expected = "allow_credentials(true) cannot be combined")]
+fn wildcard_origin_with_credentials_is_rejected() {
+    Cors::new().allow_credentials(true).build();
+}
+
+#[tokio::test]
+async fn credentialed_cors_does_not_reflect_arbitrary_origins() {
+    let res = client(credentialed())
+        .get("/me")
+        .header("Origin", "https://attacker.example")
+        .send()
+        .await;
+    res.assert_status(200);
+    assert_eq!(res.header("access-control-allow-origin"), None);
+    assert_eq!(res.header("access-control-allow-credentials"), None);
+}
+
+#[tokio::test]
+async fn wildcard_headers_reflect_the_preflight_request() {
+    let res = client(Cors::new().allow_headers(vec!["*"]))
+        .options("/me")
+        .header("Origin", "https://app.example.com")
+        .header("Access-Control-Request-Headers", "x-trace, authorization")
+        .send()
+        .await;
+    res.assert_header("access-control-allow-headers", "x-trace, authorization")
+        .assert_header("vary", "Access-Control-Request-Headers");
+}
+
+#[tokio::test]
+async fn defaults_allow_any_origin_without_credentials() {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(cors());
+    app.get("/", |ctx: Context| async move { ctx.text("ok").await });
+
+    let res = TestClient::new(app)
+        .get("/")
+        .header("Origin", "https://app.example.com")
+        .send()
+        .await;
+    res.assert_header("access-control-allow-origin", "*");
+    assert!(res.header("access-control-allow-credentials").is_none());
+    assert!(res.header("vary").is_none());
+}
@@ -0,0 +1,35 @@
+//! Tests for `ULTIMO_EMIT_RPC_SCHEMA`, the hook `ultimo generate` runs apps
+//! with. Kept in its own test binary: the variable is process-wide, and any
+//! other server started while it is set would exit instead of serving.
+
+use std::time::Duration;
+use ultimo::rpc::EMIT_RPC_SCHEMA_ENV;
+use ultimo::{RpcRegistry, Ultimo};
+
+#[tokio::test]
+async fn listen_on_writes_the_schema_instead_of_serving() {
+    let dir = tempfile::tempdir().unwrap();
+    let schema_path = dir.path().join("schema.json");
+    std::env::set_var(EMIT_RPC_SCHEMA_ENV, &schema_path);
+
+    let rpc = RpcRegistry::new();
+    rpc.query_with_types(
+        "ping",
+        |_input: serde_json::Value| async move { Ok(serde_json::json!("pong")) },
+        "{}".to_string(),
+        "string".to_string(),
+    );
+    let mut app = Ultimo::new_without_defaults();
+    app.mount_rpc("/rpc", rpc);
+
+    let (listener, _) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), app.listen_on(listener))
+        .await
+        .expect("listen_on served instead of emitting the schema")
+        .unwrap();
+
+    let schema: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&schema_path).unwrap()).unwrap();
+    assert_eq!(schema["base_path"], "/rpc");
+    assert!(schema.to_string().contains("ping"), "{}", schema);
+}
@@ -0,0 +1,194 @@
+#![cfg(feature = "testing")]
+
+use bytes::Bytes;
+use ultimo::middleware::builtin::etag;
+use ultimo::response::ResponseBuilder;
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo};
+
+fn client() -> TestClient {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(etag());
+    app.get("/users", |ctx: Context| async move {
+        ctx.set_header("Cache-Control", "no-cache").await;
+        ctx.json(serde_json::json!([{ "id": 1 }])).await
+    });
+    app.get("/user", |ctx: Context| async move {
+        let id = ctx.req.query("id").unwrap_or_default();
+        ctx.json(serde_json::json!({ "id": id })).await
+    });
+    app.get(
+        "/hello",
+        |ctx: Context| async move { ctx.text("hello").await },
+    );
+    app.get("/tagged", |ctx: Context| async move {
+        ctx.set_header("ETag", "\"v2\"").await;
+        ctx.text("tagged").await
+    });
+    app.get("/stream", |_ctx: Context| async move {
+        let chunks =
+            futures_util::stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from("chunk"))]);
+        ResponseBuilder::new().stream(chunks).build()
+    });
+    app.get("/missing", |ctx: Context| async move {
+        ctx.status(404).await;
+        ctx.text("nope").await
+    });
+    app.post(
+        "/users",
+        |ctx: Context| async move { ctx.text("created").await },
+    );
+    TestClient::new(app)
+}
+
+#[tokio::test]
+async fn etag_is_stable_across_builds() {
+    // FNV-1a of "hello"; a change here invalidates every cached tag.
+    client()
+        .get("/hello")
+        .send()
+        .await
+        .assert_header("etag", "W/\"5-a430d84680aabd0b\"");
+}
+
+#[tokio::test]
+async fn sets_weak_etag_and_answers_304_on_match() {
+    let client = client();
+    let first = client.get("/users").send().await;
+    first.assert_ok();
+    let tag = first.header("etag").unwrap().to_string();
+    assert!(tag.starts_with("W/\""), "not a weak tag: {}", tag);
+
+    let again = client.get("/users").send().await;
+    assert_eq!(again.header("etag"), Some(tag.as_str()));
+
+    let cached = client
+        .get("/users")
+        .header("If-None-Match", &tag)
+        .send()
+        .await;
+    cached
+        .assert_status(304)
+        .assert_header("etag", &tag)
+        .assert_header("cache-control", "no-cache");
+    assert!(cached.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn changed_body_gets_a_new_tag() {
+    let client = client();
+    let one = client.get("/user").query(&[("id", "1")]).send().await;
+    let tag = one.header("etag").unwrap().to_string();
+
+    let two = client
+        .get("/user")
+        .query(&[("id", "2")])
+        .header("If-None-Match", &tag)
+        .send()
+        .await;
+    two.assert_ok();
+    assert_ne!(two.header("etag"), Some(tag.as_str()));
+    assert!(!two.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn if_none_match_lists_and_wildcards_match() {
+    let client = client();
+    let tag = client
+        .get("/users")
+        .send()
+        .await
+        .header("etag")
+        .unwrap()
+        .to_string();
+
+    let list = format!("\"other\", {}", tag);
+    let res = client
+        .get("/users")
+        .header("If-None-Match", &list)
+        .send()
+        .await;
+    res.assert_status(304);
+
+    let res = client
+        .get("/users")
+        .header("If-None-Match", "*")
+        .send()
+        .await;
+    res.assert_status(304);
+
+    // Weak comparison: a strong form of the same tag still matches.
+    let strong = tag.trim_start_matches("W/");
+    let res = client
+        .get("/users")
+        .header("If-None-Match", strong)
+        .send()
+        .await;
+    res.assert_status(304);
+}
+
+#[tokio::test]
+async fn handler_etag_is_kept_and_honored() {
+    let client = client();
+    client
+        .get("/tagged")
+        .send()
+        .await
+        .assert_text("tagged")
+        .assert_header("etag", "\"v2\"");
+    client
+        .get("/tagged")
+        .header("If-None-Match", "\"v2\"")
+        .send()
+        .await
+        .assert_status(304);
+}
+
+#[tokio::test]
+async fn head_requests_are_conditional_too() {
+    let client = client();
+    let tag = client
+        .get("/users")
+        .send()
+        .await
+        .header("etag")
+        .unwrap()
+        .to_string();
+    let res = client.head("/users").send().await;
+    res.assert_header("etag", &tag);
+    let res = client
+        .head("/users")
+        .header("If-None-Match", &tag)
+        .send()
+        .await;
+    res.assert_status(304);
+}
+
+#[tokio::test]
+async fn skips_other_methods_statuses_and_streams() {
+    let client = client();
+
+    let res = client
+        .post("/users")
+        .header("If-None-Match", "*")
+        .send()
+        .await;
+    res.assert_text("created");
+    assert!(res.header("etag").is_none());
+
+    let res = client
+        .get("/missing")
+        .header("If-None-Match", "*")
+        .send()
+        .await;
+    res.assert_status(404);
+    assert!(res.header("etag").is_none());
+
+    let res = client
+        .get("/stream")
+        .header("If-None-Match", "*")
+        .send()
+        .await;
+    res.assert_text("chunk");
+    assert!(res.header("etag").is_none());
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "testing")]
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo};
+
+fn app() -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.max_body_size(16);
+    app.post("/upload", |ctx: Context| async move {
+        let body = ctx.req.text().await?;
+        ctx.text(format!("got {}", body.len())).await
+    });
+    app
+}
+
+async fn start_server(app: Ultimo) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+    tokio::spawn(async move {
+        app.listen(&format!("127.0.0.1:{}", port)).await.ok();
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    port
+}
+
+/// Read from the socket until a complete status line + headers block arrives.
+async fn read_head(stream: &mut TcpStream) -> String {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut chunk).await.unwrap();
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+#[tokio::test]
+async fn unsupported_expectation_is_417() {
+    let res = TestClient::new(app())
+        .post("/upload")
+        .header("Expect", "something-else")
+        .text("hi")
+        .send()
+        .await;
+    assert_eq!(res.status(), 417);
+}
+
+#[tokio::test]
+async fn oversized_declared_length_is_rejected_early() {
+    let res = TestClient::new(app())
+        .post("/upload")
+        .header("Expect", "100-continue")
+        .header("Content-Length", "1000")
+        .send()
+        .await;
+    assert_eq!(res.status(), 413);
+}
+
+#[tokio::test]
+async fn acceptable_request_is_dispatched() {
+    let res = TestClient::new(app())
+        .post("/upload")
+        .header("Expect", "100-Continue")
+        .text("small")
+        .send()
+        .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text(), "got 5");
+}
+
+#[tokio::test]
+async fn live_server_sends_100_continue_before_body() {
+    let port = start_server(app()).await;
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    stream
+        .write_all(
+            b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\nExpect: 100-continue\r\n\r\n",
+        )
+        .await
+        .unwrap();
+
+    let interim = read_head(&mut stream).await;
+    assert!(interim.starts_with("HTTP/1.1 100 Continue"), "{}", interim);
+
+    stream.write_all(b"hello").await.unwrap();
+    let final_head = read_head(&mut stream).await;
+    assert!(final_head.contains("200 OK"), "{}", final_head);
+}
+
+#[tokio::test]
+async fn live_server_rejects_oversized_upload_without_continue() {
+    let port = start_server(app()).await;
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    stream
+        .write_all(
+            b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 1000\r\nExpect: 100-continue\r\n\r\n",
+        )
+        .await
+        .unwrap();
+
+    let head = read_head(&mut stream).await;
+    assert!(head.starts_with("HTTP/1.1 413"), "{}", head);
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "testing")]
+
+use serde::{Deserialize, Serialize};
+use ultimo::extract::{Header, Json, Path, Query, State};
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo};
+
+#[derive(Deserialize, Serialize)]
+struct Rename {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    page: u32,
+    per_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct Agent {
+    #[serde(rename = "user-agent")]
+    user_agent: String,
+}
+
+struct Greeting(&'static str);
+
+fn client() -> TestClient {
+    let mut app = Ultimo::new_without_defaults();
+    app.with_state(Greeting("hello"));
+    app.get("/users/:id", |Path(id): Path<u32>| async move {
+        Ok(format!("user {}", id))
+    });
+    app.get(
+        "/users/:id/posts/:slug",
+        |Path((id, slug)): Path<(u32, String)>| async move { Ok(format!("{} by {}", slug, id)) },
+    );
+    app.put(
+        "/users/:id",
+        |Path(id): Path<u32>, Json(body): Json<Rename>, ctx: Context| async move {
+            ctx.status(202).await;
+            ctx.json(serde_json::json!({ "id": id, "name": body.name }))
+                .await
+        },
+    );
+    app.get(
+        "/list",
+        |Query(page): Query<Page>, State(greeting): State<Greeting>| async move {
+            Ok(format!(
+                "{} page {} of {}",
+                greeting.0,
+                page.page,
+                page.per_page.unwrap_or(20)
+            ))
+        },
+    );
+    app.get("/agent", |Header(agent): Header<Agent>| async move {
+        Ok(agent.user_agent)
+    });
+    app.get("/none", || async { Ok("no arguments") });
+    TestClient::new(app)
+}
+
+#[tokio::test]
+async fn path_extracts_scalars_and_tuples_in_route_order() {
+    let client = client();
+    client.get("/users/42").send().await.assert_text("user 42");
+    client
+        .get("/users/7/posts/intro")
+        .send()
+        .await
+        .assert_text("intro by 7");
+    client.get("/users/abc").send().await.assert_status(400);
+}
+
+#[tokio::test]
+async fn json_body_combines_with_path_and_context() {
+    let res = client()
+        .put("/users/5")
+        .json(&Rename {
+            name: "Ada".to_string(),
+        })
+        .send()
+        .await;
+    res.assert_status(202);
+    assert_eq!(
+        res.json::<serde_json::Value>(),
+        serde_json::json!({ "id": 5, "name": "Ada" })
+    );
+
+    client()
+        .put("/users/5")
+        .header("content-type", "application/json")
+        .body("{")
+        .send()
+        .await
+        .assert_status(400);
+}
+
+#[tokio::test]
+async fn query_state_header_and_no_arguments() {
+    let client = client();
+    client
+        .get("/list")
+        .query(&[("page", "2")])
+        .send()
+        .await
+        .assert_text("hello page 2 of 20");
+    client.get("/list").send().await.assert_status(400);
+    client
+        .get("/agent")
+        .header("User-Agent", "curl/8.0")
+        .send()
+        .await
+        .assert_text("curl/8.0");
+    client.get("/none").send().await.assert_text("no arguments");
+}
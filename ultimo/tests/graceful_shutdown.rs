@@ -0,0 +1,107 @@
+//! Tests for `listen_with_shutdown`
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use ultimo::{Context, Ultimo};
+
+/// Serve `app` until the returned sender fires; the handle yields `listen`'s result.
+async fn serve(
+    app: Ultimo,
+) -> (
+    u16,
+    oneshot::Sender<()>,
+    tokio::task::JoinHandle<ultimo::Result<()>>,
+) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        app.listen_on_with_shutdown(listener, async {
+            stop_rx.await.ok();
+        })
+        .await
+    });
+    (port, stop_tx, server)
+}
+
+/// An app whose `/slow` handler reports that it started, then waits to be released.
+fn gated_app() -> (Ultimo, oneshot::Receiver<()>, oneshot::Sender<()>) {
+    let (started_tx, started_rx) = oneshot::channel::<()>();
+    let (release_tx, release_rx) = oneshot::channel::<()>();
+    let started = Arc::new(Mutex::new(Some(started_tx)));
+    let release = Arc::new(tokio::sync::Mutex::new(Some(release_rx)));
+    let mut app = Ultimo::new_without_defaults();
+    app.get("/slow", move |ctx: Context| {
+        let started = started.clone();
+        let release = release.clone();
+        async move {
+            if let Some(tx) = started.lock().unwrap().take() {
+                tx.send(()).ok();
+            }
+            if let Some(rx) = release.lock().await.take() {
+                rx.await.ok();
+            }
+            ctx.text("done").await
+        }
+    });
+    (app, started_rx, release_tx)
+}
+
+#[tokio::test]
+async fn in_flight_request_finishes_before_listen_returns() {
+    let (mut app, started, release) = gated_app();
+    let hook_ran = Arc::new(AtomicBool::new(false));
+    let flag = hook_ran.clone();
+    app.on_shutdown(move || async move {
+        flag.store(true, Ordering::SeqCst);
+        Ok(())
+    });
+    let (port, stop, server) = serve(app).await;
+
+    let request = tokio::spawn(async move {
+        reqwest::get(format!("http://127.0.0.1:{}/slow", port))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+    });
+    // Shut down only once the handler is running, then let it finish.
+    started.await.unwrap();
+    stop.send(()).unwrap();
+    release.send(()).unwrap();
+
+    assert_eq!(request.await.unwrap(), "done");
+    tokio::time::timeout(Duration::from_secs(2), server)
+        .await
+        .expect("listen_with_shutdown did not return")
+        .unwrap()
+        .unwrap();
+    assert!(hook_ran.load(Ordering::SeqCst));
+
+    // No longer accepting.
+    assert!(tokio::net::TcpStream::connect(("127.0.0.1", port))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn shutdown_timeout_bounds_the_wait() {
+    // The handler is never released, so only the timeout can end the drain.
+    let (mut app, started, _release) = gated_app();
+    app.shutdown_timeout(Duration::from_millis(200));
+    let (port, stop, server) = serve(app).await;
+
+    tokio::spawn(reqwest::get(format!("http://127.0.0.1:{}/slow", port)));
+    started.await.unwrap();
+    stop.send(()).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(2), server)
+        .await
+        .expect("shutdown_timeout was not honoured")
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,90 @@
+//! Tests that a panicking handler gets a 500 response, not a dropped connection
+
+use std::sync::{Arc, Mutex};
+use ultimo::response::ResponseBuilder;
+use ultimo::{Context, Ultimo};
+
+fn app() -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.get("/panic", |ctx: Context| async move {
+        if ctx.req.path() == "/panic" {
+            panic!("secret detail");
+        }
+        ctx.text("unreachable").await
+    });
+    app.get("/ok", |ctx: Context| async move { ctx.text("ok").await });
+    app
+}
+
+#[tokio::test]
+async fn panic_becomes_500_and_connection_survives() {
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::spawn(app().listen_on(listener));
+    let client = reqwest::Client::new();
+
+    let res = client
+        .get(format!("http://{}/panic", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 500);
+    let body = res.text().await.unwrap();
+    assert!(!body.contains("secret detail"), "leaked: {}", body);
+
+    // Same keep-alive connection, still serving.
+    let res = client
+        .get(format!("http://{}/ok", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "ok");
+}
+
+#[tokio::test]
+async fn poisoned_mutex_in_handler_is_a_500() {
+    let users = Arc::new(Mutex::new(Vec::<String>::new()));
+    let poisoner = users.clone();
+    std::thread::spawn(move || {
+        let _guard = poisoner.lock().unwrap();
+        panic!("poison the lock");
+    })
+    .join()
+    .unwrap_err();
+
+    let mut app = Ultimo::new_without_defaults();
+    app.get("/users", move |ctx: Context| {
+        let users = users.clone();
+        async move {
+            let count = users.lock().unwrap().len();
+            ctx.json(count).await
+        }
+    });
+
+    let req = hyper::Request::get("/users")
+        .body(http_body_util::Full::default())
+        .unwrap();
+    assert_eq!(app.oneshot(req).await.status(), 500);
+}
+
+#[tokio::test]
+async fn panics_go_through_the_error_hook() {
+    let mut app = app();
+    app.on_error(|err, info| {
+        ResponseBuilder::new()
+            .status(err.status_code())
+            .text(format!("hooked {}", info.path))
+            .build()
+            .unwrap()
+    });
+
+    let req = hyper::Request::get("/panic")
+        .body(http_body_util::Full::default())
+        .unwrap();
+    let res = app.oneshot(req).await;
+    assert_eq!(res.status(), 500);
+    let body = http_body_util::BodyExt::collect(res.into_body())
+        .await
+        .unwrap()
+        .to_bytes();
+    assert_eq!(&body[..], b"hooked /panic");
+}
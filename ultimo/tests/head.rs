@@ -0,0 +1,74 @@
+#![cfg(feature = "testing")]
+
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo};
+
+fn app() -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.get("/users", |ctx: Context| async move {
+        ctx.set_header("X-Total-Count", "2").await;
+        ctx.json(serde_json::json!([{ "id": 1 }, { "id": 2 }]))
+            .await
+    });
+    app.get("/users/:id", |ctx: Context| async move {
+        let id = ctx.req.param("id")?.to_string();
+        ctx.text(id).await
+    });
+    app.post(
+        "/users",
+        |ctx: Context| async move { ctx.text("created").await },
+    );
+    app
+}
+
+#[tokio::test]
+async fn head_runs_get_handler_without_body() {
+    let client = TestClient::new(app());
+    let get = client.get("/users").send().await;
+    let head = client.head("/users").send().await;
+
+    head.assert_status(200)
+        .assert_header("x-total-count", "2")
+        .assert_header("content-type", get.header("content-type").unwrap())
+        .assert_header("content-length", &get.bytes().len().to_string());
+    assert!(head.bytes().is_empty());
+
+    let head = client.head("/users/42").send().await;
+    head.assert_status(200).assert_header("content-length", "2");
+    assert!(head.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn head_without_get_route_is_405() {
+    let mut app = Ultimo::new_without_defaults();
+    app.post(
+        "/submit",
+        |ctx: Context| async move { ctx.text("ok").await },
+    );
+    let res = TestClient::new(app).head("/submit").send().await;
+    res.assert_status(405).assert_header("allow", "POST");
+    assert!(res.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn auto_head_can_be_disabled() {
+    let mut app = app();
+    app.auto_head(false);
+    let res = TestClient::new(app).head("/users").send().await;
+    res.assert_status(405).assert_header("allow", "GET, POST");
+}
+
+#[tokio::test]
+async fn live_head_keeps_content_length() {
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::spawn(app().listen_on(listener));
+
+    let res = reqwest::Client::new()
+        .head(format!("http://{}/users/12345", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-length"], "5");
+    assert!(res.bytes().await.unwrap().is_empty());
+}
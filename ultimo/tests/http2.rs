@@ -0,0 +1,159 @@
+//! Tests for serving HTTP/2 with `app.http2(true)`
+
+use std::net::SocketAddr;
+use std::time::Duration;
+use ultimo::{Context, Ultimo};
+
+async fn serve(http2: bool) -> SocketAddr {
+    let mut app = Ultimo::new_without_defaults();
+    app.http2(http2);
+    app.get("/slow/:id", |ctx: Context| async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let id = ctx.req.param("id")?.to_string();
+        ctx.text(id).await
+    });
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::spawn(app.listen_on(listener));
+    addr
+}
+
+fn h2_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .http2_prior_knowledge()
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn serves_http2_and_multiplexes_streams() {
+    let addr = serve(true).await;
+    let client = h2_client();
+
+    // Ten slow requests share one connection; multiplexed, they overlap.
+    let started = std::time::Instant::now();
+    let requests = (0..10).map(|i| {
+        let client = client.clone();
+        async move {
+            let res = client
+                .get(format!("http://{}/slow/{}", addr, i))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.version(), reqwest::Version::HTTP_2);
+            assert_eq!(res.text().await.unwrap(), i.to_string());
+        }
+    });
+    futures_util::future::join_all(requests).await;
+    assert!(
+        started.elapsed() < Duration::from_millis(1500),
+        "requests were not multiplexed: {:?}",
+        started.elapsed()
+    );
+}
+
+#[tokio::test]
+async fn http1_still_served_when_http2_is_enabled() {
+    let addr = serve(true).await;
+    let res = reqwest::Client::builder()
+        .http1_only()
+        .build()
+        .unwrap()
+        .get(format!("http://{}/slow/1", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.version(), reqwest::Version::HTTP_11);
+    assert_eq!(res.text().await.unwrap(), "1");
+}
+
+#[tokio::test]
+async fn http2_is_off_by_default() {
+    let addr = serve(false).await;
+    let res = h2_client()
+        .get(format!("http://{}/slow/1", addr))
+        .send()
+        .await;
+    assert!(res.is_err(), "HTTP/2 served without opting in: {:?}", res);
+}
+
+/// POST `body` over h2c as a stream of data frames, with no `Content-Length`
+/// (which HTTP/2 does not require), and return the response status.
+async fn h2_post_without_length(addr: SocketAddr, content_type: &str, body: &'static str) -> u16 {
+    use http_body_util::StreamBody;
+    use hyper::body::Frame;
+
+    let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let (mut sender, conn) = hyper::client::conn::http2::handshake(
+        hyper_util::rt::TokioExecutor::new(),
+        hyper_util::rt::TokioIo::new(tcp),
+    )
+    .await
+    .unwrap();
+    tokio::spawn(conn);
+
+    let frames = futures_util::stream::iter([Ok::<_, std::convert::Infallible>(Frame::data(
+        bytes::Bytes::from(body),
+    ))]);
+    let req = hyper::Request::post(format!("http://{}/json", addr))
+        .header("content-type", content_type)
+        .body(StreamBody::new(frames))
+        .unwrap();
+    assert!(req.headers().get("content-length").is_none());
+    sender.send_request(req).await.unwrap().status().as_u16()
+}
+
+#[tokio::test]
+async fn content_type_constraint_applies_to_http2_bodies_without_length() {
+    let mut app = Ultimo::new_without_defaults();
+    app.http2(true);
+    app.route("/json")
+        .content_type("application/json")
+        .post(|ctx: Context| async move { ctx.text(ctx.req.text().await?).await });
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::spawn(app.listen_on(listener));
+
+    assert_eq!(
+        h2_post_without_length(addr, "text/plain", "hello").await,
+        415
+    );
+    assert_eq!(
+        h2_post_without_length(addr, "application/json", "{}").await,
+        200
+    );
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn websocket_upgrades_work_with_http2_enabled() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+    use ultimo::websocket::{Message, WebSocket, WebSocketHandler};
+
+    struct Echo;
+
+    #[async_trait::async_trait]
+    impl WebSocketHandler for Echo {
+        type Data = ();
+
+        async fn on_message(&self, ws: &WebSocket<()>, msg: Message) {
+            if let Message::Text(text) = msg {
+                ws.send(text).await.ok();
+            }
+        }
+    }
+
+    let mut app = Ultimo::new_without_defaults();
+    app.http2(true);
+    app.websocket("/ws", Echo);
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::spawn(app.listen_on(listener));
+
+    let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+        .await
+        .unwrap();
+    client.send(WsMessage::Text("hi".into())).await.unwrap();
+    match tokio::time::timeout(Duration::from_secs(2), client.next()).await {
+        Ok(Some(Ok(WsMessage::Text(text)))) => assert_eq!(text, "hi"),
+        other => panic!("expected an echo, got {:?}", other),
+    }
+}
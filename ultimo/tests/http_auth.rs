@@ -0,0 +1,174 @@
+//! Integration tests for the `basic_auth` / `bearer_auth` middlewares.
+#![cfg(feature = "testing")]
+
+use base64::Engine;
+use ultimo::middleware::builtin::{basic_auth, bearer_auth, BasicAuth};
+use ultimo::prelude::*;
+use ultimo::testing::TestClient;
+
+#[derive(Clone)]
+struct User {
+    name: String,
+}
+
+fn basic(user: &str, pass: &str) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(format!("{user}:{pass}"));
+    format!("Basic {encoded}")
+}
+
+fn basic_app() -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(basic_auth(|user, pass| async move {
+        if pass == "secret" {
+            Ok(User { name: user })
+        } else {
+            Err(UltimoError::Unauthorized("bad credentials".into()))
+        }
+    }));
+    app.get("/me", |ctx: Context| async move {
+        let user: User = ctx.identity().await.unwrap();
+        ctx.text(user.name).await
+    });
+    app
+}
+
+#[tokio::test]
+async fn basic_auth_exposes_identity() {
+    let client = TestClient::new(basic_app());
+    let res = client
+        .get("/me")
+        .header("Authorization", &basic("alice", "secret"))
+        .send()
+        .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text(), "alice");
+}
+
+#[tokio::test]
+async fn basic_auth_rejects_bad_or_missing_credentials() {
+    let client = TestClient::new(basic_app());
+
+    let res = client.get("/me").send().await;
+    assert_eq!(res.status(), 401);
+    assert_eq!(
+        res.header("www-authenticate"),
+        Some("Basic realm=\"Restricted\", charset=\"UTF-8\"")
+    );
+
+    let res = client
+        .get("/me")
+        .header("Authorization", &basic("alice", "wrong"))
+        .send()
+        .await;
+    assert_eq!(res.status(), 401);
+
+    let res = client
+        .get("/me")
+        .header("Authorization", "Basic not-base64!")
+        .send()
+        .await;
+    assert_eq!(res.status(), 401);
+}
+
+#[tokio::test]
+async fn basic_auth_custom_realm() {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(
+        BasicAuth::new(|_, _| async { Err::<(), _>(UltimoError::Unauthorized("no".into())) })
+            .realm("admin")
+            .build(),
+    );
+    app.get("/", |ctx: Context| async move { ctx.text("ok").await });
+
+    let res = TestClient::new(app).get("/").send().await;
+    assert_eq!(
+        res.header("www-authenticate"),
+        Some("Basic realm=\"admin\", charset=\"UTF-8\"")
+    );
+}
+
+fn bearer_app() -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(bearer_auth(|token| async move {
+        match token.as_str() {
+            "token-123" => Ok(42u64),
+            _ => Err(UltimoError::Unauthorized("unknown token".into())),
+        }
+    }));
+    app.get("/me", |ctx: Context| async move {
+        let id: u64 = ctx.identity().await.unwrap();
+        // A different type than the verifier produced isn't visible.
+        assert!(ctx.identity::<String>().await.is_none());
+        ctx.text(id.to_string()).await
+    });
+    app
+}
+
+#[tokio::test]
+async fn bearer_auth_exposes_identity() {
+    let client = TestClient::new(bearer_app());
+    let res = client
+        .get("/me")
+        .header("Authorization", "bearer token-123")
+        .send()
+        .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text(), "42");
+}
+
+#[tokio::test]
+async fn bearer_auth_challenges() {
+    let client = TestClient::new(bearer_app());
+
+    let res = client.get("/me").send().await;
+    assert_eq!(res.status(), 401);
+    assert_eq!(res.header("www-authenticate"), Some("Bearer realm=\"api\""));
+
+    let res = client
+        .get("/me")
+        .header("Authorization", "Bearer nope")
+        .send()
+        .await;
+    assert_eq!(res.status(), 401);
+    assert_eq!(
+        res.header("www-authenticate"),
+        Some("Bearer realm=\"api\", error=\"invalid_token\"")
+    );
+
+    // Basic credentials don't satisfy bearer auth.
+    let res = client
+        .get("/me")
+        .header("Authorization", &basic("a", "b"))
+        .send()
+        .await;
+    assert_eq!(res.status(), 401);
+}
+
+#[tokio::test]
+async fn verifier_failures_other_than_401_are_propagated() {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(basic_auth(|_user, _pass| async move {
+        Err::<User, _>(UltimoError::Internal("user store unavailable".into()))
+    }));
+    app.get("/me", |ctx: Context| async move { ctx.text("me").await });
+    let res = TestClient::new(app)
+        .get("/me")
+        .header("Authorization", &basic("alice", "secret"))
+        .send()
+        .await;
+    assert_eq!(res.status(), 500);
+    assert_eq!(res.header("www-authenticate"), None);
+
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(bearer_auth(|_token| async move {
+        Err::<User, _>(UltimoError::Forbidden("token revoked".into()))
+    }));
+    app.get("/me", |ctx: Context| async move { ctx.text("me").await });
+    let res = TestClient::new(app)
+        .get("/me")
+        .header("Authorization", "Bearer t")
+        .send()
+        .await;
+    assert_eq!(res.status(), 403);
+    assert_eq!(res.header("www-authenticate"), None);
+}
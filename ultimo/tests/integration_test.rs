@@ -5,6 +5,7 @@
 
 use serde::{Deserialize, Serialize};
 use ultimo::prelude::*;
+use ultimo::response::Response;
 
 #[derive(Deserialize, Validate)]
 struct TestInput {
@@ -230,7 +231,7 @@ async fn test_error_handling_in_routes() {
 
     // Route that returns an error
     app.get("/error", |_ctx: Context| async move {
-        Err(UltimoError::BadRequest("Something went wrong".to_string()))
+        Err::<Response, _>(UltimoError::BadRequest("Something went wrong".to_string()))
     });
 
     // Route with validation error
@@ -0,0 +1,68 @@
+#![cfg(feature = "testing")]
+
+use serde::Serialize;
+use ultimo::cookie::Cookie;
+use ultimo::response::StatusCode;
+use ultimo::testing::TestClient;
+use ultimo::{Context, Json, Ultimo, UltimoError};
+
+#[derive(Serialize)]
+struct User {
+    id: u32,
+    name: &'static str,
+}
+
+fn client() -> TestClient {
+    let mut app = Ultimo::new_without_defaults();
+    app.get("/ping", |_ctx: Context| async move { Ok("pong") });
+    app.get("/users/:id", |ctx: Context| async move {
+        let id = ctx.req.param("id")?.parse().unwrap_or(0);
+        if id == 0 {
+            return Err(UltimoError::NotFound("no such user".into()));
+        }
+        Ok(Json(User { id, name: "Ada" }))
+    });
+    app.post("/users", |_ctx: Context| async move {
+        Ok((
+            StatusCode::CREATED,
+            Json(User {
+                id: 7,
+                name: "Grace",
+            }),
+        ))
+    });
+    app.delete("/users/:id", |ctx: Context| async move {
+        ctx.set_cookie(Cookie::new("deleted", "1")).await?;
+        Ok(StatusCode::NO_CONTENT)
+    });
+    TestClient::new(app)
+}
+
+#[tokio::test]
+async fn handlers_return_plain_values() {
+    let client = client();
+    client.get("/ping").send().await.assert_text("pong");
+
+    let res = client.get("/users/1").send().await;
+    res.assert_ok()
+        .assert_header("content-type", "application/json");
+    assert_eq!(
+        res.json::<serde_json::Value>(),
+        serde_json::json!({ "id": 1, "name": "Ada" })
+    );
+
+    let res = client.post("/users").send().await;
+    res.assert_status(201);
+    assert_eq!(res.json::<serde_json::Value>()["name"], "Grace");
+}
+
+#[tokio::test]
+async fn errors_and_cookies_still_apply() {
+    let client = client();
+    client.get("/users/0").send().await.assert_status(404);
+
+    let res = client.delete("/users/1").send().await;
+    res.assert_status(204);
+    assert!(res.header("set-cookie").unwrap().starts_with("deleted=1"));
+    assert!(res.bytes().is_empty());
+}
@@ -0,0 +1,158 @@
+#![cfg(feature = "testing")]
+
+use futures_util::StreamExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use ultimo::prelude::*;
+
+fn app() -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.max_body_size(64);
+    app.post("/ignore", |ctx: Context| async move {
+        ctx.text("ignored").await
+    });
+    app.post("/text", |ctx: Context| async move {
+        let body = ctx.req.text().await?;
+        ctx.text(format!("got {}", body.len())).await
+    });
+    app.post("/stream", |ctx: Context| async move {
+        let mut stream = ctx.req.body_stream().await?;
+        let (mut chunks, mut total) = (0, 0);
+        while let Some(chunk) = stream.next().await {
+            chunks += 1;
+            total += chunk?.len();
+        }
+        // A streamed connection body can't be read again; a buffered one can.
+        let again = match ctx.req.bytes().await {
+            Ok(_) => 200,
+            Err(e) => e.status_code(),
+        };
+        ctx.text(format!(
+            "{} bytes in {} chunks, then {}",
+            total, chunks, again
+        ))
+        .await
+    });
+    app
+}
+
+async fn start_server() -> u16 {
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::spawn(async move {
+        app().listen_on(listener).await.ok();
+    });
+    addr.port()
+}
+
+/// Read one response (headers + `Content-Length` body) off the socket.
+async fn read_response(stream: &mut TcpStream) -> String {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    loop {
+        let text = String::from_utf8_lossy(&buf).into_owned();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let len = head
+                .lines()
+                .find_map(|l| {
+                    l.to_ascii_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            if body.len() >= len {
+                return text;
+            }
+        }
+        let n = stream.read(&mut chunk).await.unwrap();
+        if n == 0 {
+            return text;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+async fn send(port: u16, request: &[u8]) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    stream.write_all(request).await.unwrap();
+    read_response(&mut stream).await
+}
+
+#[tokio::test]
+async fn handler_that_ignores_body_does_not_wait_for_it() {
+    let port = start_server().await;
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    // Declare a body but never send it: only a lazy server can answer.
+    stream
+        .write_all(b"POST /ignore HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10\r\n\r\n")
+        .await
+        .unwrap();
+    let res = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        read_response(&mut stream),
+    )
+    .await
+    .expect("response should not wait for the body");
+    assert!(res.starts_with("HTTP/1.1 200"), "{}", res);
+    assert!(res.ends_with("ignored"), "{}", res);
+}
+
+#[tokio::test]
+async fn body_is_read_when_handler_asks_for_it() {
+    let port = start_server().await;
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    stream
+        .write_all(b"POST /text HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\n")
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    stream.write_all(b"hello").await.unwrap();
+    let res = read_response(&mut stream).await;
+    assert!(res.ends_with("got 5"), "{}", res);
+}
+
+#[tokio::test]
+async fn chunked_body_over_limit_is_413_when_read() {
+    let port = start_server().await;
+    let chunk = "x".repeat(40);
+    let request = format!(
+        "POST /text HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n28\r\n{c}\r\n28\r\n{c}\r\n0\r\n\r\n",
+        c = chunk
+    );
+    let res = send(port, request.as_bytes()).await;
+    assert!(res.starts_with("HTTP/1.1 413"), "{}", res);
+}
+
+#[tokio::test]
+async fn body_stream_yields_chunks_without_buffering() {
+    let port = start_server().await;
+    let res = send(
+        port,
+        b"POST /stream HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n",
+    )
+    .await;
+    assert!(res.starts_with("HTTP/1.1 200"), "{}", res);
+    assert!(res.contains("11 bytes in "), "{}", res);
+    assert!(res.ends_with("then 400"), "{}", res);
+}
+
+#[tokio::test]
+async fn body_stream_enforces_limit() {
+    let port = start_server().await;
+    let body = "y".repeat(100);
+    let request = format!(
+        "POST /stream HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n64\r\n{}\r\n0\r\n\r\n",
+        body
+    );
+    let res = send(port, request.as_bytes()).await;
+    assert!(res.starts_with("HTTP/1.1 413"), "{}", res);
+}
+
+#[tokio::test]
+async fn in_process_body_can_be_streamed() {
+    ultimo::testing::TestClient::new(app())
+        .post("/stream")
+        .text("in-process")
+        .send()
+        .await
+        .assert_text("10 bytes in 1 chunks, then 200");
+}
@@ -0,0 +1,65 @@
+//! Tests for serving on caller-provided listeners
+
+use std::time::Duration;
+use ultimo::{Context, Ultimo};
+
+fn app() -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.get(
+        "/ping",
+        |ctx: Context| async move { ctx.text("pong").await },
+    );
+    app
+}
+
+#[tokio::test]
+async fn bind_reports_ephemeral_port() {
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    assert_ne!(addr.port(), 0);
+    assert_eq!(listener.local_addr().unwrap(), addr);
+}
+
+#[tokio::test]
+async fn bind_rejects_invalid_address() {
+    assert!(Ultimo::bind("not an address").await.is_err());
+}
+
+#[tokio::test]
+async fn listen_on_serves_tokio_listener() {
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::spawn(app().listen_on(listener));
+
+    let body = reqwest::get(format!("http://{}/ping", addr))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "pong");
+}
+
+#[tokio::test]
+async fn listen_on_std_serves_std_listener() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(app().listen_on_std(listener));
+
+    let body = reqwest::get(format!("http://{}/ping", addr))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "pong");
+}
+
+#[tokio::test]
+async fn listen_on_with_shutdown_returns_when_signalled() {
+    let (listener, _) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    let server =
+        app().listen_on_with_shutdown(listener, tokio::time::sleep(Duration::from_millis(50)));
+    tokio::time::timeout(Duration::from_secs(2), server)
+        .await
+        .expect("server did not stop")
+        .unwrap();
+}
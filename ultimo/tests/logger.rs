@@ -0,0 +1,198 @@
+#![cfg(feature = "testing")]
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tracing::Level;
+use ultimo::middleware::builtin::{logger, logger_with, request_id, LoggerConfig};
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo, UltimoError};
+
+/// Log sink shared with the subscriber.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Capture {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+fn app(middleware: ultimo::middleware::BoxedMiddleware) -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(middleware);
+    app.get("/users/:id", |ctx: Context| async move {
+        tracing::info!("inside handler");
+        ctx.text("ok").await
+    });
+    app.get("/fail", |_ctx: Context| async move {
+        Err::<ultimo::response::Response, _>(UltimoError::NotFound("gone".into()))
+    });
+    app
+}
+
+/// Run `f` with a subscriber capturing everything at `max_level` and above.
+async fn capture<F, Fut>(max_level: Level, f: F) -> String
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let sink = Capture::default();
+    let writer = sink.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(max_level)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+    f().await;
+    sink.text()
+}
+
+#[tokio::test]
+async fn logs_method_path_status_and_latency_in_a_span() {
+    let out = capture(Level::INFO, || async {
+        TestClient::new(app(logger()))
+            .get("/users/7")
+            .send()
+            .await
+            .assert_ok();
+    })
+    .await;
+
+    assert!(out.contains("--> GET /users/7"), "{}", out);
+    assert!(out.contains("<-- GET /users/7 200"), "{}", out);
+    assert!(out.contains("status=200"), "{}", out);
+    assert!(out.contains("latency_ms="), "{}", out);
+    // Handler logs are tagged with the request span.
+    assert!(
+        out.contains(
+            "request{method=GET path=/users/7 route=\"/users/:id\"}: logger: inside handler"
+        ),
+        "{}",
+        out
+    );
+}
+
+#[tokio::test]
+async fn errors_are_logged_with_their_status() {
+    let out = capture(Level::INFO, || async {
+        TestClient::new(app(logger()))
+            .get("/fail")
+            .send()
+            .await
+            .assert_status(404);
+    })
+    .await;
+
+    assert!(out.contains("ERROR"), "{}", out);
+    assert!(out.contains("<-- GET /fail ERROR"), "{}", out);
+    assert!(out.contains("status=404"), "{}", out);
+}
+
+#[tokio::test]
+async fn level_is_configurable() {
+    let config = LoggerConfig::default().level(Level::DEBUG);
+
+    let out = capture(Level::INFO, || async {
+        TestClient::new(app(logger_with(config.clone())))
+            .get("/users/1")
+            .send()
+            .await;
+    })
+    .await;
+    assert!(!out.contains("--> GET"), "{}", out);
+
+    let out = capture(Level::DEBUG, || async {
+        TestClient::new(app(logger_with(config.clone())))
+            .get("/users/1")
+            .send()
+            .await;
+    })
+    .await;
+    assert!(out.contains("DEBUG"), "{}", out);
+    assert!(out.contains("<-- GET /users/1 200"), "{}", out);
+}
+
+#[tokio::test]
+async fn headers_are_logged_with_credentials_redacted() {
+    let config = LoggerConfig::default().include_headers(true);
+    let out = capture(Level::INFO, || async {
+        TestClient::new(app(logger_with(config)))
+            .get("/users/1")
+            .header("x-trace", "abc")
+            .header("authorization", "Bearer secret-token")
+            .send()
+            .await;
+    })
+    .await;
+
+    assert!(out.contains("x-trace: abc"), "{}", out);
+    assert!(out.contains("authorization: [redacted]"), "{}", out);
+    assert!(!out.contains("secret-token"), "{}", out);
+
+    let out = capture(Level::INFO, || async {
+        TestClient::new(app(logger()))
+            .get("/users/1")
+            .header("x-trace", "abc")
+            .send()
+            .await;
+    })
+    .await;
+    assert!(!out.contains("x-trace"), "{}", out);
+}
+
+#[tokio::test]
+async fn span_carries_request_id() {
+    let out = capture(Level::INFO, || async {
+        let mut app = app(request_id());
+        app.use_middleware(logger());
+        TestClient::new(app)
+            .get("/users/3")
+            .header("x-request-id", "req-99")
+            .send()
+            .await
+            .assert_ok();
+    })
+    .await;
+
+    assert!(
+        out.contains(
+            "request{method=GET path=/users/3 route=\"/users/:id\" request_id=\"req-99\"}: logger: inside handler"
+        ),
+        "{}",
+        out
+    );
+}
+
+#[tokio::test]
+async fn span_skips_unusable_client_request_ids() {
+    let long = "x".repeat(200);
+    let out = capture(Level::INFO, || async {
+        TestClient::new(app(logger()))
+            .get("/users/3")
+            .header("x-request-id", &long)
+            .send()
+            .await
+            .assert_ok();
+    })
+    .await;
+
+    assert!(!out.contains(&long), "{}", out);
+    assert!(
+        out.contains(
+            "request{method=GET path=/users/3 route=\"/users/:id\"}: logger: inside handler"
+        ),
+        "{}",
+        out
+    );
+}
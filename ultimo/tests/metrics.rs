@@ -0,0 +1,87 @@
+#![cfg(feature = "testing")]
+
+use ultimo::middleware::builtin::metrics;
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo, UltimoError};
+
+fn client() -> TestClient {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(metrics());
+    app.serve_metrics("/metrics");
+    app.get("/users/:id", |ctx: Context| async move {
+        let id = ctx.req.param("id")?.to_string();
+        if id == "0" {
+            return Err(UltimoError::NotFound("no such user".into()));
+        }
+        ctx.text(id).await
+    });
+    TestClient::new(app)
+}
+
+#[tokio::test]
+async fn counts_requests_by_route_pattern_and_status() {
+    let client = client();
+    client.get("/users/1").send().await.assert_ok();
+    client.get("/users/2").send().await.assert_ok();
+    client.get("/users/0").send().await.assert_status(404);
+
+    let res = client.get("/metrics").send().await;
+    res.assert_ok()
+        .assert_header("content-type", "text/plain; version=0.0.4; charset=utf-8");
+    let text = res.text();
+
+    let ok = r#"method="GET",path="/users/:id",status="200""#;
+    assert!(
+        text.contains(&format!("http_requests_total{{{}}} 2\n", ok)),
+        "{}",
+        text
+    );
+    assert!(text.contains(r#"http_requests_total{method="GET",path="/users/:id",status="404"} 1"#));
+    assert!(text.contains(&format!(
+        "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 2\n",
+        ok
+    )));
+    assert!(text.contains(&format!(
+        "http_request_duration_seconds_count{{{}}} 2\n",
+        ok
+    )));
+    assert!(text.contains(r#"http_requests_in_flight{method="GET",path="/users/:id"} 0"#));
+    assert!(!text.contains("/users/1"), "raw paths leaked into labels");
+}
+
+#[tokio::test]
+async fn records_body_sizes_per_route_pattern() {
+    let client = client();
+    client.get("/users/12345").send().await.assert_text("12345");
+    client.get("/users/7").send().await.assert_text("7");
+
+    let text = client.get("/metrics").send().await.text();
+    let series = r#"method="GET",path="/users/:id""#;
+    for line in [
+        format!("http_request_size_bytes_sum{{{}}} 0\n", series),
+        format!("http_request_size_bytes_count{{{}}} 2\n", series),
+        format!("http_response_size_bytes_sum{{{}}} 6\n", series),
+        format!("http_response_size_bytes_count{{{}}} 2\n", series),
+    ] {
+        assert!(text.contains(&line), "missing {:?}:\n{}", line, text);
+    }
+}
+
+#[tokio::test]
+async fn scrape_itself_is_in_flight() {
+    let client = client();
+    let text = client.get("/metrics").send().await.text();
+    assert!(
+        text.contains(r#"http_requests_in_flight{method="GET",path="/metrics"} 1"#),
+        "{}",
+        text
+    );
+}
+
+#[tokio::test]
+async fn middleware_without_endpoint_is_a_no_op() {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(metrics());
+    app.get("/", |ctx: Context| async move { ctx.text("ok").await });
+    TestClient::new(app).get("/").send().await.assert_text("ok");
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "testing")]
+
+use serde_json::json;
+use ultimo::openapi::OpenApiSpec;
+use ultimo::testing::TestClient;
+use ultimo::Ultimo;
+
+fn spec() -> OpenApiSpec {
+    serde_json::from_value(json!({
+        "openapi": "3.0.0",
+        "info": { "title": "Mock API", "version": "1.0.0" },
+        "paths": {
+            "/users": {
+                "get": {
+                    "responses": {
+                        "200": {
+                            "description": "List users",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "type": "array", "items": { "$ref": "#/components/schemas/User" } }
+                                }
+                            }
+                        }
+                    }
+                },
+                "post": {
+                    "responses": {
+                        "201": {
+                            "description": "Created",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/User" },
+                                    "example": { "id": 1, "name": "Alice" }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "/users/{id}": {
+                "delete": {
+                    "responses": { "204": { "description": "Deleted" } }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "User": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "number" },
+                        "name": { "type": "string" }
+                    }
+                }
+            }
+        }
+    }))
+    .unwrap()
+}
+
+fn app() -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.mock_from_spec(spec());
+    app
+}
+
+#[tokio::test]
+async fn serves_documented_example() {
+    let res = TestClient::new(app()).post("/users").send().await;
+    assert_eq!(res.status(), 201);
+    assert_eq!(
+        res.json::<serde_json::Value>(),
+        json!({ "id": 1, "name": "Alice" })
+    );
+}
+
+#[tokio::test]
+async fn derives_sample_from_schema_when_no_example() {
+    let res = TestClient::new(app()).get("/users").send().await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        res.json::<serde_json::Value>(),
+        json!([{ "id": 0, "name": "string" }])
+    );
+}
+
+#[tokio::test]
+async fn path_templates_become_route_params() {
+    let res = TestClient::new(app()).delete("/users/42").send().await;
+    assert_eq!(res.status(), 204);
+    assert!(res.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn undocumented_routes_are_404() {
+    let res = TestClient::new(app()).get("/posts").send().await;
+    assert_eq!(res.status(), 404);
+}
@@ -0,0 +1,183 @@
+#![cfg(feature = "testing")]
+
+use std::path::PathBuf;
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo};
+
+const BOUNDARY: &str = "X-ULTIMO-BOUNDARY";
+
+/// Build a multipart body from `(name, filename, content)` parts.
+fn form(parts: &[(&str, Option<&str>, &str)]) -> String {
+    let mut body = String::new();
+    for (name, filename, content) in parts {
+        body.push_str(&format!("--{}\r\n", BOUNDARY));
+        match filename {
+            Some(f) => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: text/plain\r\n\r\n",
+                name, f
+            )),
+            None => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                name
+            )),
+        }
+        body.push_str(content);
+        body.push_str("\r\n");
+    }
+    body.push_str(&format!("--{}--\r\n", BOUNDARY));
+    body
+}
+
+fn app(dir: PathBuf) -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.post("/upload", move |ctx: Context| {
+        let dir = dir.clone();
+        async move {
+            let saved = ctx
+                .req
+                .multipart()
+                .await?
+                .max_file_size(16)
+                .max_total_size(24)
+                .save_all(&dir)
+                .await?;
+            let files: Vec<_> = saved
+                .iter()
+                .map(|f| {
+                    serde_json::json!({
+                        "field": f.field_name,
+                        "name": f.original_name,
+                        "size": f.size,
+                        "type": f.content_type,
+                        "stored": f.path.file_name().unwrap().to_string_lossy(),
+                    })
+                })
+                .collect();
+            ctx.json(files).await
+        }
+    });
+    app
+}
+
+async fn upload(dir: &tempfile::TempDir, body: String) -> ultimo::testing::TestResponse {
+    TestClient::new(app(dir.path().to_path_buf()))
+        .post("/upload")
+        .header(
+            "Content-Type",
+            &format!("multipart/form-data; boundary={}", BOUNDARY),
+        )
+        .body(body)
+        .send()
+        .await
+}
+
+fn stored_files(dir: &tempfile::TempDir) -> Vec<PathBuf> {
+    std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect()
+}
+
+#[tokio::test]
+async fn saves_files_and_skips_plain_fields() {
+    let dir = tempfile::tempdir().unwrap();
+    let res = upload(
+        &dir,
+        form(&[
+            ("title", None, "holiday"),
+            ("doc", Some("notes.txt"), "hello world"),
+        ]),
+    )
+    .await;
+    assert_eq!(res.status(), 200, "{}", res.text());
+
+    let files: Vec<serde_json::Value> = res.json();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0]["field"], "doc");
+    assert_eq!(files[0]["name"], "notes.txt");
+    assert_eq!(files[0]["size"], 11);
+    assert_eq!(files[0]["type"], "text/plain");
+    assert!(files[0]["stored"].as_str().unwrap().ends_with("_notes.txt"));
+
+    let stored = stored_files(&dir);
+    assert_eq!(stored.len(), 1);
+    assert_eq!(std::fs::read_to_string(&stored[0]).unwrap(), "hello world");
+}
+
+#[tokio::test]
+async fn rejects_path_traversal_in_filename() {
+    let dir = tempfile::tempdir().unwrap();
+    let res = upload(&dir, form(&[("doc", Some("../escape.txt"), "x")])).await;
+    assert_eq!(res.status(), 400);
+    assert!(stored_files(&dir).is_empty());
+}
+
+#[tokio::test]
+async fn oversized_file_is_413_and_removed() {
+    let dir = tempfile::tempdir().unwrap();
+    let res = upload(&dir, form(&[("doc", Some("big.txt"), &"a".repeat(17))])).await;
+    assert_eq!(res.status(), 413);
+    assert!(stored_files(&dir).is_empty());
+}
+
+#[tokio::test]
+async fn total_cap_removes_earlier_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let res = upload(
+        &dir,
+        form(&[
+            ("a", Some("a.txt"), &"a".repeat(16)),
+            ("b", Some("b.txt"), &"b".repeat(16)),
+        ]),
+    )
+    .await;
+    assert_eq!(res.status(), 413);
+    assert!(stored_files(&dir).is_empty());
+}
+
+#[tokio::test]
+async fn missing_boundary_is_400() {
+    let dir = tempfile::tempdir().unwrap();
+    let res = TestClient::new(app(dir.path().to_path_buf()))
+        .post("/upload")
+        .header("Content-Type", "multipart/form-data")
+        .body("irrelevant")
+        .send()
+        .await;
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn field_cap_limits_in_memory_reads() {
+    let mut app = Ultimo::new_without_defaults();
+    app.post("/fields", |ctx: Context| async move {
+        let mut multipart = ctx.req.multipart().await?.max_field_size(8);
+        let mut fields = Vec::new();
+        while let Some(field) = multipart.next_field().await? {
+            let name = field.name().unwrap_or_default().to_string();
+            fields.push(serde_json::json!({ name: field.text().await? }));
+        }
+        ctx.json(fields).await
+    });
+    let client = TestClient::new(app);
+    let send = |body: String| {
+        client
+            .post("/fields")
+            .header(
+                "Content-Type",
+                &format!("multipart/form-data; boundary={}", BOUNDARY),
+            )
+            .body(body)
+            .send()
+    };
+
+    let res = send(form(&[("a", None, "12345678"), ("b", None, "ok")])).await;
+    assert_eq!(res.status(), 200, "{}", res.text());
+    assert_eq!(
+        res.json::<serde_json::Value>(),
+        serde_json::json!([{ "a": "12345678" }, { "b": "ok" }])
+    );
+
+    let res = send(form(&[("a", None, "123456789")])).await;
+    assert_eq!(res.status(), 413);
+}
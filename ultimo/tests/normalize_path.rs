@@ -0,0 +1,89 @@
+#![cfg(feature = "testing")]
+
+use ultimo::middleware::builtin::{normalize_path, NormalizePath, NormalizePathMode};
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo};
+
+fn app(middleware: ultimo::middleware::BoxedMiddleware) -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(middleware);
+    app.get("/api/users", |ctx: Context| async move {
+        ctx.text(ctx.req.path().to_string()).await
+    });
+    app.get("/users/:id", |ctx: Context| async move {
+        let path = ctx.req.path().to_string();
+        let id = ctx.req.param("id")?.to_string();
+        ctx.text(format!("{} {}", path, id)).await
+    });
+    app
+}
+
+#[tokio::test]
+async fn rewrites_doubled_interior_slashes() {
+    let res = TestClient::new(app(normalize_path()))
+        .get("/users//123")
+        .send()
+        .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text(), "/users/123 123");
+}
+
+#[tokio::test]
+async fn rewrites_leading_double_slashes() {
+    let res = TestClient::new(app(normalize_path()))
+        .get("//api//users")
+        .send()
+        .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text(), "/api/users");
+}
+
+#[tokio::test]
+async fn trailing_slash_kept_unless_trimmed() {
+    let res = TestClient::new(app(normalize_path()))
+        .get("/api/users/")
+        .send()
+        .await;
+    assert_eq!(res.text(), "/api/users/");
+
+    let trimmed = NormalizePath::new().trim_trailing_slash(true).build();
+    let res = TestClient::new(app(trimmed))
+        .get("/api/users/")
+        .send()
+        .await;
+    assert_eq!(res.text(), "/api/users");
+}
+
+#[tokio::test]
+async fn redirect_mode_issues_308_with_query() {
+    let redirect = NormalizePath::new()
+        .mode(NormalizePathMode::Redirect)
+        .trim_trailing_slash(true)
+        .build();
+    let res = TestClient::new(app(redirect))
+        .get("/users//123/?tab=posts")
+        .send()
+        .await;
+    assert_eq!(res.status(), 308);
+    assert_eq!(res.header("location"), Some("/users/123?tab=posts"));
+}
+
+#[tokio::test]
+async fn canonical_paths_pass_through() {
+    let redirect = NormalizePath::new()
+        .mode(NormalizePathMode::Redirect)
+        .build();
+    let res = TestClient::new(app(redirect))
+        .get("/api/users")
+        .send()
+        .await;
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn runs_only_for_matched_routes() {
+    let mut strict = app(NormalizePath::new().trim_trailing_slash(true).build());
+    strict.trailing_slash(ultimo::router::TrailingSlash::Strict);
+    let res = TestClient::new(strict).get("/api/users/").send().await;
+    assert_eq!(res.status(), 404);
+}
@@ -1,8 +1,10 @@
 #![cfg(feature = "testing")]
 
+use std::time::Duration;
 use ultimo::middleware::builtin::{rate_limiter, RateLimitKey, RateLimiter};
+use ultimo::response::Response;
 use ultimo::testing::TestClient;
-use ultimo::{Context, Ultimo};
+use ultimo::{Context, Ultimo, UltimoError};
 
 #[tokio::test]
 async fn rate_limiter_allows_within_limit() {
@@ -77,3 +79,67 @@ async fn rate_limiter_per_header_key() {
     let res = client.get("/").header("X-API-Key", "client-b").send().await;
     assert_eq!(res.status(), 200);
 }
+
+#[tokio::test]
+async fn rate_limiter_builder_with_custom_key() {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(
+        RateLimiter::default()
+            .max_requests(1)
+            .per(Duration::from_secs(60))
+            .key_by(|ctx| ctx.req.header("x-tenant"))
+            .build(),
+    );
+    app.get("/", |ctx: Context| async move { ctx.text("ok").await });
+
+    let client = TestClient::new(app);
+
+    let res = client.get("/").header("X-Tenant", "a").send().await;
+    assert_eq!(res.status(), 200);
+    let res = client.get("/").header("X-Tenant", "a").send().await;
+    assert_eq!(res.status(), 429);
+
+    // Another tenant has its own bucket
+    let res = client.get("/").header("X-Tenant", "b").send().await;
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn rate_limiter_reports_limit_and_remaining() {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(RateLimiter::new(2, 60).key(RateLimitKey::Global).build());
+    app.get("/", |ctx: Context| async move { ctx.text("ok").await });
+
+    let client = TestClient::new(app);
+
+    let res = client.get("/").send().await;
+    assert_eq!(res.header("x-ratelimit-limit"), Some("2"));
+    assert_eq!(res.header("x-ratelimit-remaining"), Some("1"));
+
+    let res = client.get("/").send().await;
+    assert_eq!(res.header("x-ratelimit-remaining"), Some("0"));
+
+    // 2 per 60s refills one token every 30s
+    let res = client.get("/").send().await;
+    assert_eq!(res.status(), 429);
+    assert_eq!(res.header("x-ratelimit-remaining"), Some("0"));
+    let retry_after: u64 = res.header("retry-after").unwrap().parse().unwrap();
+    assert!(
+        (29..=30).contains(&retry_after),
+        "retry-after {retry_after}"
+    );
+}
+
+#[tokio::test]
+async fn rate_limit_headers_survive_handler_errors() {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(RateLimiter::new(2, 60).key(RateLimitKey::Global).build());
+    app.get("/", |_ctx: Context| async move {
+        Err::<Response, _>(UltimoError::BadRequest("nope".to_string()))
+    });
+
+    let res = TestClient::new(app).get("/").send().await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(res.header("x-ratelimit-limit"), Some("2"));
+    assert_eq!(res.header("x-ratelimit-remaining"), Some("1"));
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "testing")]
+
+use ultimo::middleware::builtin::request_id;
+use ultimo::testing::TestClient;
+use ultimo::{Context, Ultimo};
+
+fn client() -> TestClient {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(request_id());
+    app.get("/id", |ctx: Context| async move {
+        let id = ctx.get("request_id").await.unwrap_or_default();
+        ctx.text(id).await
+    });
+    TestClient::new(app)
+}
+
+#[tokio::test]
+async fn generates_and_echoes_an_id() {
+    let res = client().get("/id").send().await;
+    let id = res.text();
+    assert!(uuid::Uuid::parse_str(&id).is_ok(), "not a uuid: {}", id);
+    res.assert_header("x-request-id", &id);
+
+    let other = client().get("/id").send().await.text();
+    assert_ne!(id, other);
+}
+
+#[tokio::test]
+async fn reuses_incoming_id() {
+    client()
+        .get("/id")
+        .header("x-request-id", "upstream-42")
+        .send()
+        .await
+        .assert_text("upstream-42")
+        .assert_header("x-request-id", "upstream-42");
+}
+
+#[tokio::test]
+async fn replaces_unusable_incoming_id() {
+    for bad in ["", "has spaces", &"x".repeat(129)] {
+        let res = client().get("/id").header("x-request-id", bad).send().await;
+        let id = res.text();
+        assert!(
+            uuid::Uuid::parse_str(&id).is_ok(),
+            "{:?} kept as {}",
+            bad,
+            id
+        );
+        res.assert_header("x-request-id", &id);
+    }
+}
+
+#[tokio::test]
+async fn error_responses_carry_the_id() {
+    let mut app = Ultimo::new_without_defaults();
+    app.use_middleware(request_id());
+    app.get("/fail", |_ctx: Context| async move {
+        Err::<ultimo::response::Response, _>(ultimo::UltimoError::BadRequest("nope".to_string()))
+    });
+
+    TestClient::new(app)
+        .get("/fail")
+        .header("x-request-id", "upstream-7")
+        .send()
+        .await
+        .assert_status(400)
+        .assert_header("x-request-id", "upstream-7");
+}
@@ -8,6 +8,7 @@ use http_body_util::{BodyExt, Full};
 use hyper::Request as HyperRequest;
 use tempfile::TempDir;
 use ultimo::prelude::*;
+use ultimo::response::CacheControl;
 
 fn empty() -> Full<Bytes> {
     Full::new(Bytes::new())
@@ -117,6 +118,76 @@ async fn etag_is_set_and_304_on_match() {
     assert_eq!(res2.status(), 304);
 }
 
+#[tokio::test]
+async fn last_modified_is_set_and_304_if_unmodified_since() {
+    let dir = TempDir::new().unwrap();
+    write_fixture(&dir, "app.js", b"console.log(1)").await;
+
+    let mut app = Ultimo::new_without_defaults();
+    app.serve_static("/assets", dir.path());
+
+    let get = |since: Option<&str>| {
+        let mut req = HyperRequest::builder().uri("/assets/app.js");
+        if let Some(since) = since {
+            req = req.header("if-modified-since", since);
+        }
+        req.body(empty()).unwrap()
+    };
+    let res = app.oneshot(get(None)).await;
+    assert_eq!(res.status(), 200);
+    assert!(res.headers().get("cache-control").is_none());
+    let last_modified = res.headers()["last-modified"].to_str().unwrap().to_string();
+
+    let res = app.oneshot(get(Some(&last_modified))).await;
+    assert_eq!(res.status(), 304);
+    assert_eq!(res.headers()["last-modified"], last_modified.as_str());
+    assert!(res
+        .into_body()
+        .collect()
+        .await
+        .unwrap()
+        .to_bytes()
+        .is_empty());
+
+    let res = app
+        .oneshot(get(Some("Thu, 01 Jan 1970 00:00:00 GMT")))
+        .await;
+    assert_eq!(res.status(), 200);
+    let res = app.oneshot(get(Some("not a date"))).await;
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn serve_static_with_cache_sets_cache_control() {
+    let dir = TempDir::new().unwrap();
+    write_fixture(&dir, "logo.svg", b"<svg/>").await;
+
+    let mut app = Ultimo::new_without_defaults();
+    app.serve_static_with_cache(
+        "/assets",
+        dir.path(),
+        CacheControl::new().public().max_age(3600),
+    );
+
+    let req = HyperRequest::builder()
+        .uri("/assets/logo.svg")
+        .body(empty())
+        .unwrap();
+    let res = app.oneshot(req).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["cache-control"], "public, max-age=3600");
+    let etag = res.headers()["etag"].clone();
+
+    let req = HyperRequest::builder()
+        .uri("/assets/logo.svg")
+        .header("if-none-match", etag)
+        .body(empty())
+        .unwrap();
+    let res = app.oneshot(req).await;
+    assert_eq!(res.status(), 304);
+    assert_eq!(res.headers()["cache-control"], "public, max-age=3600");
+}
+
 #[tokio::test]
 async fn nested_path_is_served() {
     let dir = TempDir::new().unwrap();
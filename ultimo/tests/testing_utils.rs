@@ -50,6 +50,41 @@ async fn assert_status_panics_on_mismatch() {
     client.get("/hello").send().await.assert_status(404);
 }
 
+#[tokio::test]
+async fn assert_json_eq_ignores_key_order() {
+    let client = TestClient::new(app());
+    client
+        .post("/echo")
+        .json(&serde_json::json!({ "a": 1, "b": { "c": [true] } }))
+        .send()
+        .await
+        .assert_header("content-type", "application/json")
+        .assert_json_eq(serde_json::json!({ "b": { "c": [true] }, "a": 1 }));
+}
+
+#[tokio::test]
+#[should_panic(expected = r#"at $.user.name: expected "bo", got "al""#)]
+async fn assert_json_eq_panics_with_path() {
+    let client = TestClient::new(app());
+    client
+        .post("/echo")
+        .json(&serde_json::json!({ "user": { "name": "al" } }))
+        .send()
+        .await
+        .assert_json_eq(serde_json::json!({ "user": { "name": "bo" } }));
+}
+
+#[tokio::test]
+#[should_panic(expected = "expected header x-missing: 1, got None")]
+async fn assert_header_panics_with_actual_value() {
+    let client = TestClient::new(app());
+    client
+        .get("/hello")
+        .send()
+        .await
+        .assert_header("x-missing", "1");
+}
+
 // ---- Task 6: macros ----
 #[tokio::test]
 async fn macros_work() {
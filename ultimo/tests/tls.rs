@@ -0,0 +1,203 @@
+//! Tests for serving HTTPS with `listen_tls` (feature `tls`).
+#![cfg(feature = "tls")]
+
+use std::net::SocketAddr;
+use std::time::Duration;
+use ultimo::tls::ClientAuth;
+use ultimo::{Context, Ultimo};
+
+const CERT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls-cert.pem");
+const KEY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls-key.pem");
+const CLIENT_CA: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/tls-client-ca.pem"
+);
+const CLIENT_CERT: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/tls-client-cert.pem"
+);
+const CLIENT_KEY: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/tls-client-key.pem"
+);
+
+fn app(http2: bool) -> Ultimo {
+    let mut app = Ultimo::new_without_defaults();
+    app.http2(http2);
+    app.get(
+        "/ping",
+        |ctx: Context| async move { ctx.text("pong").await },
+    );
+    app
+}
+
+async fn serve_tls(http2: bool) -> SocketAddr {
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::spawn(app(http2).listen_on_tls(listener, CERT, KEY));
+    addr
+}
+
+/// A client that trusts the self-signed fixture certificate.
+fn client() -> reqwest::Client {
+    let cert = reqwest::Certificate::from_pem(&std::fs::read(CERT).unwrap()).unwrap();
+    reqwest::Client::builder()
+        .use_native_tls()
+        .add_root_certificate(cert)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn serves_https() {
+    let addr = serve_tls(false).await;
+    let res = client()
+        .get(format!("https://localhost:{}/ping", addr.port()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.version(), reqwest::Version::HTTP_11);
+    assert_eq!(res.text().await.unwrap(), "pong");
+}
+
+#[tokio::test]
+async fn negotiates_h2_over_alpn_when_http2_is_enabled() {
+    let addr = serve_tls(true).await;
+    let res = client()
+        .get(format!("https://localhost:{}/ping", addr.port()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.version(), reqwest::Version::HTTP_2);
+    assert_eq!(res.text().await.unwrap(), "pong");
+}
+
+#[tokio::test]
+async fn plain_http_and_failed_handshakes_do_not_stop_the_server() {
+    let addr = serve_tls(false).await;
+    assert!(reqwest::get(format!("http://{}/ping", addr)).await.is_err());
+
+    // An untrusted client aborts its handshake.
+    let untrusted = reqwest::Client::builder().use_native_tls().build().unwrap();
+    assert!(untrusted
+        .get(format!("https://localhost:{}/ping", addr.port()))
+        .send()
+        .await
+        .is_err());
+
+    let res = client()
+        .get(format!("https://localhost:{}/ping", addr.port()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "pong");
+}
+
+#[tokio::test]
+async fn invalid_certificate_files_fail_before_serving() {
+    let (listener, _) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    let err = app(false)
+        .listen_on_tls(listener, "does-not-exist.pem", KEY)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does-not-exist.pem"), "{}", err);
+}
+
+#[tokio::test]
+async fn listen_tls_with_shutdown_returns_when_signalled() {
+    let server = app(false).listen_tls_with_shutdown(
+        "127.0.0.1:0",
+        CERT,
+        KEY,
+        tokio::time::sleep(Duration::from_millis(50)),
+    );
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server did not shut down")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn stalled_handshakes_do_not_hold_up_shutdown() {
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let mut app = app(false);
+    app.shutdown_timeout(Duration::from_secs(30));
+    let server = tokio::spawn(app.listen_on_tls_with_shutdown(listener, CERT, KEY, async {
+        stopped.await.ok();
+    }));
+
+    // Connect but never send a ClientHello.
+    let _stalled = tokio::net::TcpStream::connect(addr).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    stop.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("shutdown waited for the stalled handshake")
+        .unwrap()
+        .unwrap();
+}
+
+/// Serve `/whoami`, answering with the client certificate's CN and SANs.
+async fn serve_mtls(mode: ClientAuth) -> SocketAddr {
+    let mut app = Ultimo::new_without_defaults();
+    app.tls_client_auth(CLIENT_CA, mode);
+    app.get("/whoami", |ctx: Context| async move {
+        let who = match ctx.client_cert() {
+            Some(cert) => format!(
+                "{} {}",
+                cert.common_name().unwrap_or("-"),
+                cert.dns_names().join(",")
+            ),
+            None => "anonymous".to_string(),
+        };
+        ctx.text(who).await
+    });
+    let (listener, addr) = Ultimo::bind("127.0.0.1:0").await.unwrap();
+    tokio::spawn(app.listen_on_tls(listener, CERT, KEY));
+    addr
+}
+
+/// [`client`] presenting the fixture client certificate.
+fn client_with_cert() -> reqwest::Client {
+    let cert = reqwest::Certificate::from_pem(&std::fs::read(CERT).unwrap()).unwrap();
+    let identity = reqwest::Identity::from_pkcs8_pem(
+        &std::fs::read(CLIENT_CERT).unwrap(),
+        &std::fs::read(CLIENT_KEY).unwrap(),
+    )
+    .unwrap();
+    reqwest::Client::builder()
+        .use_native_tls()
+        .add_root_certificate(cert)
+        .identity(identity)
+        .build()
+        .unwrap()
+}
+
+async fn whoami(client: &reqwest::Client, addr: SocketAddr) -> reqwest::Result<String> {
+    client
+        .get(format!("https://localhost:{}/whoami", addr.port()))
+        .send()
+        .await?
+        .text()
+        .await
+}
+
+#[tokio::test]
+async fn required_client_auth_exposes_the_certificate() {
+    let addr = serve_mtls(ClientAuth::Required).await;
+    assert_eq!(
+        whoami(&client_with_cert(), addr).await.unwrap(),
+        "client-1 client-1.example.com"
+    );
+    assert!(whoami(&client(), addr).await.is_err());
+}
+
+#[tokio::test]
+async fn optional_client_auth_admits_clients_without_a_certificate() {
+    let addr = serve_mtls(ClientAuth::Optional).await;
+    assert_eq!(
+        whoami(&client_with_cert(), addr).await.unwrap(),
+        "client-1 client-1.example.com"
+    );
+    assert_eq!(whoami(&client(), addr).await.unwrap(), "anonymous");
+}
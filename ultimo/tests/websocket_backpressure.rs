@@ -176,7 +176,7 @@ mod websocket_backpressure_tests {
         assert!(ws.send("message").await.is_ok());
 
         // Close should fail when buffer is full
-        let result = ws.close(Some(1000), Some("Normal closure")).await;
+        let result = ws.close(CloseCode::Normal, "Normal closure").await;
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
 
@@ -184,7 +184,7 @@ mod websocket_backpressure_tests {
         let _ = rx.recv().await;
 
         // Close should work now
-        assert!(ws.close(Some(1000), Some("Normal closure")).await.is_ok());
+        assert!(ws.close(CloseCode::Normal, "Normal closure").await.is_ok());
     }
 
     #[tokio::test]
@@ -305,4 +305,152 @@ mod websocket_backpressure_tests {
         };
         assert!(ws.send_json(&data3).await.is_ok());
     }
+
+    struct FloodHandler {
+        accepted: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl ultimo::websocket::WebSocketHandler for FloodHandler {
+        type Data = ();
+
+        async fn on_open(&self, ws: &WebSocket<()>) {
+            // Flood until the bounded queue pushes back.
+            for i in 0..1000 {
+                match ws.send(format!("flood {}", i)).await {
+                    Ok(()) => {
+                        self.accepted
+                            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    }
+                    Err(e) => {
+                        assert_eq!(e.kind(), std::io::ErrorKind::WouldBlock);
+                        break;
+                    }
+                }
+            }
+        }
+
+        async fn on_message(&self, _ws: &WebSocket<()>, _msg: Message) {}
+
+        async fn on_drain(&self, ws: &WebSocket<()>) {
+            ws.send("drained").await.ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flood_is_capped_and_on_drain_fires() {
+        use futures_util::StreamExt;
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let accepted = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut app = ultimo::Ultimo::new_without_defaults();
+        let config = ultimo::websocket::WebSocketConfig {
+            max_write_queue_size: 16,
+            ..Default::default()
+        };
+        app.websocket_with_config(
+            "/ws",
+            FloodHandler {
+                accepted: accepted.clone(),
+            },
+            config,
+        );
+        tokio::spawn(async move {
+            app.listen(&format!("127.0.0.1:{}", port)).await.ok();
+        });
+        time::sleep(Duration::from_millis(100)).await;
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/ws", port))
+            .await
+            .unwrap();
+
+        let mut flooded = 0;
+        time::timeout(Duration::from_secs(2), async {
+            while let Some(Ok(msg)) = ws.next().await {
+                match msg {
+                    WsMessage::Text(text) if text == "drained" => return,
+                    WsMessage::Text(_) => flooded += 1,
+                    _ => {}
+                }
+            }
+            panic!("connection closed before on_drain");
+        })
+        .await
+        .expect("on_drain was never called");
+
+        // Nothing beyond the queue cap was buffered, and all of it was delivered.
+        assert_eq!(accepted.load(std::sync::atomic::Ordering::SeqCst), 16);
+        assert_eq!(flooded, 16);
+    }
+
+    struct EchoHandler {
+        drained: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl ultimo::websocket::WebSocketHandler for EchoHandler {
+        type Data = ();
+
+        async fn on_message(&self, ws: &WebSocket<()>, msg: Message) {
+            if let Message::Text(text) = msg {
+                ws.send(text).await.unwrap();
+            }
+        }
+
+        async fn on_drain(&self, _ws: &WebSocket<()>) {
+            self.drained
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_on_drain_needs_a_refused_send() {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        // A one-slot queue is full after every send, but none is refused.
+        let drained = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut app = ultimo::Ultimo::new_without_defaults();
+        let config = ultimo::websocket::WebSocketConfig {
+            max_write_queue_size: 1,
+            ..Default::default()
+        };
+        app.websocket_with_config(
+            "/ws",
+            EchoHandler {
+                drained: drained.clone(),
+            },
+            config,
+        );
+        tokio::spawn(async move {
+            app.listen(&format!("127.0.0.1:{}", port)).await.ok();
+        });
+        time::sleep(Duration::from_millis(100)).await;
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/ws", port))
+            .await
+            .unwrap();
+
+        for i in 0..5 {
+            let text = format!("ping {}", i);
+            ws.send(WsMessage::Text(text.clone())).await.unwrap();
+            let echoed = time::timeout(Duration::from_secs(2), ws.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            assert_eq!(echoed, WsMessage::Text(text));
+        }
+        time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(drained.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
 }
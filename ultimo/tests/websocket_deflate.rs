@@ -0,0 +1,215 @@
+//! Tests for `permessage-deflate` (RFC 7692) negotiation and framing
+
+#[cfg(feature = "websocket-deflate")]
+mod websocket_deflate_tests {
+    use bytes::{Bytes, BytesMut};
+    use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio::time::Duration;
+    use ultimo::websocket::test_helpers::*;
+    use ultimo::websocket::{Message, WebSocketConfig, WebSocketHandler};
+
+    struct Echo;
+
+    #[async_trait::async_trait]
+    impl WebSocketHandler for Echo {
+        type Data = ();
+
+        async fn on_message(&self, ws: &WebSocket<()>, msg: Message) {
+            if let Message::Text(text) = msg {
+                ws.send(text).await.ok();
+            }
+        }
+    }
+
+    async fn start_server(compression: bool) -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let mut app = ultimo::Ultimo::new_without_defaults();
+        app.websocket_with_config(
+            "/ws",
+            Echo,
+            WebSocketConfig {
+                compression,
+                ..Default::default()
+            },
+        );
+        tokio::spawn(async move {
+            app.listen(&format!("127.0.0.1:{}", port)).await.ok();
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        port
+    }
+
+    /// Handshake over a raw socket; returns the response head and the stream.
+    async fn raw_connect(port: u16, extensions: Option<&str>) -> (String, TcpStream, BytesMut) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let mut request = String::from(
+            "GET /ws HTTP/1.1\r\nHost: 127.0.0.1\r\nUpgrade: websocket\r\n\
+             Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+             Sec-WebSocket-Version: 13\r\n",
+        );
+        if let Some(extensions) = extensions {
+            request.push_str(&format!("Sec-WebSocket-Extensions: {}\r\n", extensions));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut buf = BytesMut::new();
+        let head_end = loop {
+            stream.read_buf(&mut buf).await.unwrap();
+            if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i + 4;
+            }
+        };
+        let head = String::from_utf8(buf.split_to(head_end).to_vec()).unwrap();
+        assert!(head.starts_with("HTTP/1.1 101"));
+        (head.to_ascii_lowercase(), stream, buf)
+    }
+
+    /// Next frame from the server with its RSV1 bit, or `None` once closed.
+    async fn read_frame(stream: &mut TcpStream, buf: &mut BytesMut) -> Option<(bool, Frame)> {
+        loop {
+            let rsv1 = buf.first().is_some_and(|b| b & 0x40 != 0);
+            if let Some(frame) = Frame::parse(buf).unwrap() {
+                return Some((rsv1, frame));
+            }
+            if stream.read_buf(buf).await.unwrap_or(0) == 0 {
+                return None;
+            }
+        }
+    }
+
+    /// The code of the close frame the server answers with.
+    async fn close_code(stream: &mut TcpStream, buf: &mut BytesMut) -> u16 {
+        let (_, frame) = read_frame(stream, buf).await.expect("a close frame");
+        assert_eq!(frame.opcode, OpCode::Close);
+        u16::from_be_bytes([frame.payload[0], frame.payload[1]])
+    }
+
+    /// Encode a masked client frame, optionally flagged as compressed.
+    fn client_frame(mut frame: Frame, rsv1: bool) -> Bytes {
+        frame.mask = Some([0x12, 0x34, 0x56, 0x78]);
+        let mut encoded = BytesMut::from(frame.encode().as_ref());
+        if rsv1 {
+            encoded[0] |= 0x40;
+        }
+        encoded.freeze()
+    }
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut compressor = Compress::new(Compression::default(), false);
+        let mut out = Vec::with_capacity(data.len() + 64);
+        compressor
+            .compress_vec(data, &mut out, FlushCompress::Sync)
+            .unwrap();
+        assert!(out.ends_with(&[0, 0, 0xff, 0xff]));
+        out.truncate(out.len() - 4);
+        out
+    }
+
+    fn inflate(data: &[u8], len: usize) -> Vec<u8> {
+        let mut input = data.to_vec();
+        input.extend_from_slice(&[0, 0, 0xff, 0xff]);
+        let mut out = Vec::with_capacity(len + 64);
+        Decompress::new(false)
+            .decompress_vec(&input, &mut out, FlushDecompress::Sync)
+            .unwrap();
+        out
+    }
+
+    #[tokio::test]
+    async fn test_compressed_round_trip() {
+        let port = start_server(true).await;
+        let (head, mut stream, mut buf) =
+            raw_connect(port, Some("permessage-deflate; client_max_window_bits")).await;
+        assert!(head.contains("sec-websocket-extensions: permessage-deflate"));
+
+        let text = "compress me please ".repeat(200);
+        let frame = Frame {
+            fin: true,
+            opcode: OpCode::Text,
+            mask: None,
+            payload: Bytes::from(deflate(text.as_bytes())),
+        };
+        stream.write_all(&client_frame(frame, true)).await.unwrap();
+
+        let (rsv1, reply) = read_frame(&mut stream, &mut buf).await.unwrap();
+        assert!(rsv1, "echo should be compressed");
+        assert_eq!(reply.opcode, OpCode::Text);
+        assert!(reply.payload.len() < text.len());
+        assert_eq!(inflate(&reply.payload, text.len()), text.as_bytes());
+
+        // Tiny messages that don't shrink go out uncompressed.
+        stream
+            .write_all(&client_frame(Frame::text("hi"), false))
+            .await
+            .unwrap();
+        let (rsv1, reply) = read_frame(&mut stream, &mut buf).await.unwrap();
+        assert!(!rsv1);
+        assert_eq!(reply.payload, Bytes::from("hi"));
+    }
+
+    #[tokio::test]
+    async fn test_offer_declined_when_compression_disabled() {
+        let port = start_server(false).await;
+        let (head, _, _) = raw_connect(port, Some("permessage-deflate")).await;
+        assert!(!head.contains("sec-websocket-extensions"));
+    }
+
+    #[tokio::test]
+    async fn test_without_offer_rsv1_is_a_protocol_error() {
+        let port = start_server(true).await;
+        let (head, mut stream, mut buf) = raw_connect(port, None).await;
+        assert!(!head.contains("sec-websocket-extensions"));
+
+        let frame = Frame {
+            fin: true,
+            opcode: OpCode::Text,
+            mask: None,
+            payload: Bytes::from(deflate(b"hello hello hello")),
+        };
+        stream.write_all(&client_frame(frame, true)).await.unwrap();
+
+        assert!(read_frame(&mut stream, &mut buf).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_inflation_is_capped_by_max_message_size() {
+        let port = start_server(true).await;
+        let (_, mut stream, mut buf) = raw_connect(port, Some("permessage-deflate")).await;
+
+        // 128 MiB of zeros deflates to well under one frame.
+        let bomb = deflate(&vec![0u8; 128 * 1024 * 1024]);
+        assert!(bomb.len() < WebSocketConfig::default().max_frame_size);
+        let frame = Frame {
+            fin: true,
+            opcode: OpCode::Binary,
+            mask: None,
+            payload: Bytes::from(bomb),
+        };
+        stream.write_all(&client_frame(frame, true)).await.unwrap();
+
+        assert_eq!(close_code(&mut stream, &mut buf).await, 1009);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_deflate_data_closes_with_1007() {
+        let port = start_server(true).await;
+        let (_, mut stream, mut buf) = raw_connect(port, Some("permessage-deflate")).await;
+
+        // 0xff starts a block with the reserved BTYPE 11.
+        let frame = Frame {
+            fin: true,
+            opcode: OpCode::Text,
+            mask: None,
+            payload: Bytes::from_static(&[0xff, 0xff, 0xff]),
+        };
+        stream.write_all(&client_frame(frame, true)).await.unwrap();
+
+        assert_eq!(close_code(&mut stream, &mut buf).await, 1007);
+    }
+}
@@ -179,3 +179,107 @@ mod websocket_fragmentation_tests {
         assert_eq!(result, "Hello 世界 🌍");
     }
 }
+
+#[cfg(feature = "websocket")]
+mod websocket_message_limit_tests {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::time::Duration;
+    use tokio_tungstenite::tungstenite::protocol::frame::coding::{CloseCode, Data, OpCode};
+    use tokio_tungstenite::tungstenite::protocol::frame::Frame as WsFrame;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+    use ultimo::websocket::test_helpers::*;
+    use ultimo::websocket::{WebSocketConfig, WebSocketHandler};
+
+    /// Records `on_close` codes.
+    struct RecordClose(tokio::sync::mpsc::UnboundedSender<ultimo::websocket::CloseCode>);
+
+    #[async_trait::async_trait]
+    impl WebSocketHandler for RecordClose {
+        type Data = ();
+
+        async fn on_message(&self, _ws: &WebSocket<()>, msg: Message) {
+            assert!(
+                matches!(msg, Message::Close(_)),
+                "unexpected message: {:?}",
+                msg
+            );
+        }
+
+        async fn on_close(
+            &self,
+            _ws: &WebSocket<()>,
+            code: ultimo::websocket::CloseCode,
+            _reason: &str,
+        ) {
+            self.0.send(code).ok();
+        }
+    }
+
+    /// Send `frames` raw and return the close code the server answers with.
+    async fn close_code_for(frames: Vec<WsFrame>) -> (CloseCode, ultimo::websocket::CloseCode) {
+        let (closes_tx, mut closes_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut app = ultimo::Ultimo::new_without_defaults();
+        app.websocket_with_config(
+            "/ws",
+            RecordClose(closes_tx),
+            WebSocketConfig {
+                max_message_size: 1024,
+                ..Default::default()
+            },
+        );
+        let (listener, addr) = ultimo::Ultimo::bind("127.0.0.1:0").await.unwrap();
+        tokio::spawn(app.listen_on(listener));
+
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+            .await
+            .unwrap();
+        for frame in frames {
+            client.send(WsMessage::Frame(frame)).await.unwrap();
+        }
+        let wire = match tokio::time::timeout(Duration::from_secs(2), client.next()).await {
+            Ok(Some(Ok(WsMessage::Close(Some(frame))))) => frame.code,
+            other => panic!("expected a close frame, got {:?}", other),
+        };
+        (wire, closes_rx.recv().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_utf8_text_closes_with_1007() {
+        let frame = WsFrame::message(vec![b'h', b'i', 0xff, 0xfe], OpCode::Data(Data::Text), true);
+        let (wire, on_close) = close_code_for(vec![frame]).await;
+        assert_eq!(wire, CloseCode::Invalid);
+        assert_eq!(on_close, ultimo::websocket::CloseCode::InvalidPayload);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_utf8_split_across_fragments_closes_with_1007() {
+        let frames = vec![
+            WsFrame::message(b"ok ".to_vec(), OpCode::Data(Data::Text), false),
+            WsFrame::message(vec![0xc3], OpCode::Data(Data::Continue), false),
+            WsFrame::message(b"!".to_vec(), OpCode::Data(Data::Continue), true),
+        ];
+        let (wire, on_close) = close_code_for(frames).await;
+        assert_eq!(wire, CloseCode::Invalid);
+        assert_eq!(on_close, ultimo::websocket::CloseCode::InvalidPayload);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_fragmented_message_closes_with_1009() {
+        // Each fragment fits the 1024-byte limit; together they don't.
+        let frames = vec![
+            WsFrame::message(vec![b'a'; 600], OpCode::Data(Data::Text), false),
+            WsFrame::message(vec![b'a'; 600], OpCode::Data(Data::Continue), true),
+        ];
+        let (wire, on_close) = close_code_for(frames).await;
+        assert_eq!(wire, CloseCode::Size);
+        assert_eq!(on_close, ultimo::websocket::CloseCode::MessageTooBig);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_single_frame_closes_with_1009() {
+        let frame = WsFrame::message(vec![0; 2048], OpCode::Data(Data::Binary), true);
+        let (wire, on_close) = close_code_for(vec![frame]).await;
+        assert_eq!(wire, CloseCode::Size);
+        assert_eq!(on_close, ultimo::websocket::CloseCode::MessageTooBig);
+    }
+}
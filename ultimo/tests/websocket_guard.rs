@@ -0,0 +1,157 @@
+//! WebSocket upgrade guard tests
+//!
+//! A guard either accepts the handshake (attaching typed connection data) or
+//! rejects it with a response that must reach the client verbatim.
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message as WsMessage};
+use ultimo::prelude::*;
+use ultimo::response::ResponseBuilder;
+use ultimo::websocket::{Message, WebSocket, WebSocketHandler};
+
+struct ChatHandler;
+
+#[async_trait::async_trait]
+impl WebSocketHandler for ChatHandler {
+    type Data = String;
+
+    async fn on_message(&self, ws: &WebSocket<Self::Data>, msg: Message) {
+        if let Message::Text(text) = msg {
+            ws.send(format!("{}: {}", ws.data(), text)).await.ok();
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+async fn start_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+
+    let mut app = Ultimo::new_without_defaults();
+    app.websocket_route("/ws")
+        .guard(
+            |req| match req.headers().get("x-user").and_then(|v| v.to_str().ok()) {
+                Some(user) => Ok(user.to_string()),
+                None => Err(ResponseBuilder::new()
+                    .status(403)
+                    .header("X-Reason", "anonymous")
+                    .json(&json!({"error": "login required"}))
+                    .and_then(|b| b.build())
+                    .unwrap()),
+            },
+        )
+        .handler(ChatHandler);
+
+    tokio::spawn(async move {
+        app.listen(&format!("127.0.0.1:{}", port)).await.ok();
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    port
+}
+
+#[tokio::test]
+async fn accepted_upgrade_attaches_guard_data() {
+    let port = start_server().await;
+    let mut request = format!("ws://127.0.0.1:{}/ws", port)
+        .into_client_request()
+        .unwrap();
+    request
+        .headers_mut()
+        .insert("x-user", "alice".parse().unwrap());
+
+    let (mut ws, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+    assert_eq!(response.status(), 101);
+
+    ws.send(WsMessage::Text("hi".to_string())).await.unwrap();
+    let reply = tokio::time::timeout(tokio::time::Duration::from_secs(1), async {
+        loop {
+            if let Some(Ok(WsMessage::Text(text))) = ws.next().await {
+                return text;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(reply, "alice: hi");
+}
+
+#[tokio::test]
+async fn rejected_upgrade_sends_guard_response_verbatim() {
+    let port = start_server().await;
+    let err = tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/ws", port))
+        .await
+        .expect_err("guard should reject the handshake");
+
+    let WsError::Http(response) = err else {
+        panic!("expected HTTP rejection, got {:?}", err);
+    };
+    assert_eq!(response.status(), 403);
+    assert_eq!(response.headers()["x-reason"], "anonymous");
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let body: serde_json::Value =
+        serde_json::from_slice(response.body().as_deref().unwrap()).unwrap();
+    assert_eq!(body, json!({"error": "login required"}));
+}
+
+struct GreetHandler;
+
+#[async_trait::async_trait]
+impl WebSocketHandler for GreetHandler {
+    type Data = String;
+
+    async fn on_open(&self, ws: &WebSocket<Self::Data>) {
+        let queue = ws.config().max_write_queue_size;
+        ws.send(format!("hello {} (queue {})", ws.data(), queue))
+            .await
+            .ok();
+    }
+
+    async fn on_message(&self, _ws: &WebSocket<Self::Data>, _msg: Message) {}
+}
+
+#[tokio::test]
+#[allow(clippy::result_large_err)]
+async fn guard_with_config_builds_data_from_query_param() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+
+    let mut app = Ultimo::new_without_defaults();
+    let config = ultimo::websocket::WebSocketConfig {
+        max_write_queue_size: 8,
+        ..Default::default()
+    };
+    app.websocket_route("/ws")
+        .guard(|req| {
+            let name = req
+                .uri()
+                .query()
+                .and_then(|q| q.split('&').find_map(|kv| kv.strip_prefix("name=")))
+                .unwrap_or("anonymous");
+            Ok(name.to_string())
+        })
+        .config(config)
+        .handler(GreetHandler);
+    tokio::spawn(async move {
+        app.listen(&format!("127.0.0.1:{}", port)).await.ok();
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let (mut ws, _) =
+        tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/ws?name=bob", port))
+            .await
+            .unwrap();
+    let greeting = tokio::time::timeout(tokio::time::Duration::from_secs(1), async {
+        loop {
+            if let Some(Ok(WsMessage::Text(text))) = ws.next().await {
+                return text;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(greeting, "hello bob (queue 8)");
+}
@@ -290,18 +290,58 @@ mod websocket_tests {
             default_config(),
         );
 
-        ws.close(Some(1000), Some("Normal closure")).await.unwrap();
+        ws.close(CloseCode::Normal, "Normal closure").await.unwrap();
 
         let msg = rx.recv().await.unwrap();
         match msg {
             Message::Close(Some(close_frame)) => {
-                assert_eq!(close_frame.code, 1000);
+                assert_eq!(close_frame.code, CloseCode::Normal);
                 assert_eq!(close_frame.reason, "Normal closure");
             }
             _ => panic!("Expected close message"),
         }
     }
 
+    #[tokio::test]
+    async fn test_websocket_close_sanitizes_its_frame() {
+        let channel_manager = std::sync::Arc::new(ChannelManager::new());
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1000);
+
+        let ws: WebSocket<()> = create_websocket(
+            (),
+            tx,
+            channel_manager,
+            uuid::Uuid::new_v4(),
+            None,
+            default_config(),
+        );
+
+        // No status means an empty payload, reason and all.
+        ws.close(CloseCode::NoStatus, "ignored").await.unwrap();
+        assert!(matches!(rx.recv().await.unwrap(), Message::Close(None)));
+
+        // Codes reserved for reporting never reach the queue.
+        for code in [
+            CloseCode::Abnormal,
+            CloseCode::Other(1000),
+            CloseCode::Other(1004),
+            CloseCode::Other(1015),
+        ] {
+            let err = ws.close(code, "").await.unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+        assert!(rx.try_recv().is_err());
+
+        // The reason is cut to 123 bytes on a character boundary.
+        ws.close(CloseCode::Normal, &"é".repeat(100)).await.unwrap();
+        match rx.recv().await.unwrap() {
+            Message::Close(Some(close_frame)) => {
+                assert_eq!(close_frame.reason, "é".repeat(61));
+            }
+            _ => panic!("Expected close message"),
+        }
+    }
+
     #[tokio::test]
     async fn test_websocket_is_writable() {
         let channel_manager = std::sync::Arc::new(ChannelManager::new());
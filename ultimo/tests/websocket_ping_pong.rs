@@ -201,4 +201,175 @@ mod websocket_ping_pong_tests {
         assert_eq!(ws.config().ping_interval, Some(2));
         assert_eq!(ws.config().ping_timeout, 5);
     }
+
+    // --- Live connection: automatic pong replies and heartbeat ---
+
+    use bytes::BytesMut;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio::time::{timeout, Duration};
+    use ultimo::websocket::{Message, WebSocketHandler};
+
+    /// Replies to every message it sees with that message's kind.
+    struct KindEcho;
+
+    #[async_trait::async_trait]
+    impl WebSocketHandler for KindEcho {
+        type Data = ();
+
+        async fn on_message(&self, ws: &WebSocket<()>, msg: Message) {
+            let kind = match msg {
+                Message::Text(text) => format!("text:{}", text),
+                Message::Binary(_) => "binary".to_string(),
+                Message::Ping(_) => "ping".to_string(),
+                Message::Pong(_) => "pong".to_string(),
+                Message::Close(_) => return,
+            };
+            ws.send(kind).await.ok();
+        }
+    }
+
+    async fn start_server(config: WebSocketConfig) -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let mut app = ultimo::Ultimo::new_without_defaults();
+        app.websocket_with_config("/ws", KindEcho, config);
+        tokio::spawn(async move {
+            app.listen(&format!("127.0.0.1:{}", port)).await.ok();
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        port
+    }
+
+    /// Perform the handshake over a raw socket so frames can be sent verbatim.
+    async fn raw_connect(port: u16) -> (TcpStream, BytesMut) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream
+            .write_all(
+                b"GET /ws HTTP/1.1\r\nHost: 127.0.0.1\r\nUpgrade: websocket\r\n\
+                  Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                  Sec-WebSocket-Version: 13\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut buf = BytesMut::new();
+        let head_end = loop {
+            stream.read_buf(&mut buf).await.unwrap();
+            if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i + 4;
+            }
+        };
+        let head = buf.split_to(head_end);
+        assert!(head.starts_with(b"HTTP/1.1 101"));
+        (stream, buf)
+    }
+
+    /// Next frame from the server, or `None` once the connection is closed.
+    async fn read_frame(stream: &mut TcpStream, buf: &mut BytesMut) -> Option<Frame> {
+        loop {
+            if let Some(frame) = Frame::parse(buf).unwrap() {
+                return Some(frame);
+            }
+            if stream.read_buf(buf).await.unwrap_or(0) == 0 {
+                return None;
+            }
+        }
+    }
+
+    /// Encode a client frame (clients must mask).
+    fn masked(mut frame: Frame) -> Bytes {
+        frame.mask = Some([0x12, 0x34, 0x56, 0x78]);
+        frame.encode()
+    }
+
+    #[tokio::test]
+    async fn test_inbound_ping_gets_matching_pong_and_skips_handler() {
+        let port = start_server(WebSocketConfig {
+            ping_interval: None,
+            ..Default::default()
+        })
+        .await;
+        let (mut stream, mut buf) = raw_connect(port).await;
+
+        stream
+            .write_all(&masked(Frame::ping(Bytes::from("hb-1"))))
+            .await
+            .unwrap();
+        stream
+            .write_all(&masked(Frame::text("after")))
+            .await
+            .unwrap();
+
+        let pong = read_frame(&mut stream, &mut buf).await.unwrap();
+        assert_eq!(pong.opcode, OpCode::Pong);
+        assert_eq!(pong.payload, Bytes::from("hb-1"));
+
+        // The handler saw only the text message, never the ping.
+        let reply = read_frame(&mut stream, &mut buf).await.unwrap();
+        assert_eq!(reply.opcode, OpCode::Text);
+        assert_eq!(reply.payload, Bytes::from("text:after"));
+    }
+
+    #[tokio::test]
+    async fn test_server_pings_and_drops_silent_peer() {
+        let port = start_server(WebSocketConfig {
+            ping_interval: Some(1),
+            ping_timeout: 1,
+            ..Default::default()
+        })
+        .await;
+        let (mut stream, mut buf) = raw_connect(port).await;
+
+        let ping = timeout(Duration::from_secs(3), read_frame(&mut stream, &mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ping.opcode, OpCode::Ping);
+
+        // Never answer: the server closes the socket without a close frame.
+        let next = timeout(Duration::from_secs(3), async {
+            loop {
+                match read_frame(&mut stream, &mut buf).await {
+                    Some(frame) if frame.opcode == OpCode::Ping => continue,
+                    other => return other,
+                }
+            }
+        })
+        .await
+        .expect("silent peer was not dropped");
+        assert!(next.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_answered_pings_keep_connection_open() {
+        let port = start_server(WebSocketConfig {
+            ping_interval: Some(1),
+            ping_timeout: 1,
+            ..Default::default()
+        })
+        .await;
+        let (mut stream, mut buf) = raw_connect(port).await;
+
+        // Answer three heartbeats, well past the timeout.
+        for _ in 0..3 {
+            let ping = timeout(Duration::from_secs(3), read_frame(&mut stream, &mut buf))
+                .await
+                .unwrap()
+                .expect("connection dropped despite pongs");
+            assert_eq!(ping.opcode, OpCode::Ping);
+            stream
+                .write_all(&masked(Frame::pong(ping.payload)))
+                .await
+                .unwrap();
+        }
+
+        stream
+            .write_all(&masked(Frame::text("alive")))
+            .await
+            .unwrap();
+        let reply = read_frame(&mut stream, &mut buf).await.unwrap();
+        assert_eq!(reply.payload, Bytes::from("text:alive"));
+    }
 }
@@ -235,3 +235,87 @@ async fn test_websocket_binary_messages() {
         assert_eq!(text, "echo: ping");
     }
 }
+
+#[derive(Clone)]
+struct LobbyHandler;
+
+#[async_trait::async_trait]
+impl WebSocketHandler for LobbyHandler {
+    type Data = ();
+
+    async fn on_open(&self, ws: &WebSocket<Self::Data>) {
+        ws.subscribe("lobby").await.ok();
+        ws.send("joined").await.ok();
+    }
+
+    async fn on_message(&self, _ws: &WebSocket<Self::Data>, _msg: Message) {}
+}
+
+#[tokio::test]
+async fn test_http_handler_publishes_to_websocket_channel() {
+    let port = find_available_port().await;
+
+    let mut app = Ultimo::new();
+    app.websocket("/ws", LobbyHandler);
+    let channels = app.channels();
+    app.post("/broadcast", move |ctx: Context| {
+        let channels = channels.clone();
+        async move {
+            let text = ctx.req.text().await?;
+            let sent = channels.publish("lobby", Message::Text(text)).await?;
+            ctx.json(json!({ "delivered": sent })).await
+        }
+    });
+    tokio::spawn(async move {
+        app.listen(&format!("127.0.0.1:{}", port)).await.ok();
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/ws", port))
+        .await
+        .expect("Failed to connect");
+    let timeout = tokio::time::Duration::from_millis(500);
+    assert_eq!(next_text_frame(&mut ws, timeout).await, "joined");
+
+    let response = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{}/broadcast", port))
+        .body("webhook fired")
+        .send()
+        .await
+        .expect("Failed to make HTTP request");
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["delivered"], 1);
+
+    assert_eq!(next_text_frame(&mut ws, timeout).await, "webhook fired");
+}
+
+#[tokio::test]
+async fn test_route_listing_marks_websocket_routes() {
+    let mut app = Ultimo::new_without_defaults();
+    app.websocket("/ws/chat", TestHandler);
+    app.get(
+        "/api/health",
+        |c: Context| async move { c.text("ok").await },
+    );
+    app.websocket("/ws/alerts", TestHandler);
+    app.enable_route_listing("/__routes");
+
+    let req = hyper::Request::get("/__routes")
+        .body(http_body_util::Full::default())
+        .unwrap();
+    let res = app.oneshot(req).await;
+    let body = http_body_util::BodyExt::collect(res.into_body())
+        .await
+        .unwrap()
+        .to_bytes();
+    let routes: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        routes,
+        serde_json::json!([
+            { "method": "GET", "path": "/api/health", "websocket": false },
+            { "method": "GET", "path": "/__routes", "websocket": false },
+            { "method": "GET", "path": "/ws/alerts", "websocket": true },
+            { "method": "GET", "path": "/ws/chat", "websocket": true },
+        ])
+    );
+}
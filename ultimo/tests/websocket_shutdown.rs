@@ -40,13 +40,15 @@ mod websocket_shutdown_tests {
         );
 
         // Close the connection
-        ws.close(Some(1000), Some("Test closure")).await.unwrap();
+        ws.close(ultimo::websocket::CloseCode::Normal, "Test closure")
+            .await
+            .unwrap();
 
         // Verify close message was sent
         let msg = rx.recv().await.unwrap();
         match msg {
             Message::Close(Some(close_frame)) => {
-                assert_eq!(close_frame.code, 1000);
+                assert_eq!(close_frame.code, ultimo::websocket::CloseCode::Normal);
                 assert_eq!(close_frame.reason, "Test closure");
             }
             _ => panic!("Expected close message"),
@@ -54,7 +56,7 @@ mod websocket_shutdown_tests {
     }
 
     #[tokio::test]
-    async fn test_close_without_code() {
+    async fn test_close_with_application_code() {
         let channel_manager = Arc::new(ChannelManager::new());
         let (tx, mut rx) = tokio::sync::mpsc::channel(1000);
 
@@ -67,12 +69,17 @@ mod websocket_shutdown_tests {
             Arc::new(WebSocketConfig::default()),
         );
 
-        ws.close(None, None).await.unwrap();
+        ws.close(ultimo::websocket::CloseCode::Other(4000), "custom")
+            .await
+            .unwrap();
 
         let msg = rx.recv().await.unwrap();
         match msg {
-            Message::Close(None) => {}
-            _ => panic!("Expected close message without frame"),
+            Message::Close(Some(close_frame)) => {
+                assert_eq!(close_frame.code, ultimo::websocket::CloseCode::Other(4000));
+                assert_eq!(close_frame.reason, "custom");
+            }
+            _ => panic!("Expected close message"),
         }
     }
 
@@ -118,7 +125,7 @@ mod websocket_shutdown_tests {
         let message = Message::from_frame(close_frame).unwrap();
         match message {
             Message::Close(Some(cf)) => {
-                assert_eq!(cf.code, 1000);
+                assert_eq!(cf.code, ultimo::websocket::CloseCode::Normal);
                 assert_eq!(cf.reason, "Test reason");
             }
             _ => panic!("Expected close message"),
@@ -144,7 +151,7 @@ mod websocket_shutdown_tests {
 
         // Broadcast close message to all
         let close_msg = Message::Close(Some(CloseFrame {
-            code: 1001,
+            code: ultimo::websocket::CloseCode::GoingAway,
             reason: "Server shutting down".to_string(),
         }));
 
@@ -159,7 +166,7 @@ mod websocket_shutdown_tests {
         for msg in [msg1, msg2, msg3] {
             match msg {
                 Message::Close(Some(cf)) => {
-                    assert_eq!(cf.code, 1001);
+                    assert_eq!(cf.code, ultimo::websocket::CloseCode::GoingAway);
                     assert_eq!(cf.reason, "Server shutting down");
                 }
                 _ => panic!("Expected close message"),
@@ -228,12 +235,14 @@ mod websocket_shutdown_tests {
             Arc::new(WebSocketConfig::default()),
         );
 
-        ws.close(Some(1000), Some("")).await.unwrap();
+        ws.close(ultimo::websocket::CloseCode::Normal, "")
+            .await
+            .unwrap();
 
         let msg = rx.recv().await.unwrap();
         match msg {
             Message::Close(Some(close_frame)) => {
-                assert_eq!(close_frame.code, 1000);
+                assert_eq!(close_frame.code, ultimo::websocket::CloseCode::Normal);
                 assert_eq!(close_frame.reason, "");
             }
             _ => panic!("Expected close message"),
@@ -244,7 +253,7 @@ mod websocket_shutdown_tests {
     async fn test_close_frame_not_fragmented() {
         // Close frames should never be fragmented
         let close_msg = Message::Close(Some(CloseFrame {
-            code: 1000,
+            code: ultimo::websocket::CloseCode::Normal,
             reason: "A".repeat(1000), // Large reason
         }));
 
@@ -252,4 +261,148 @@ mod websocket_shutdown_tests {
         assert_eq!(frames.len(), 1); // Should still be one frame
         assert_eq!(frames[0].opcode, OpCode::Close);
     }
+
+    // --- Live server: listen_with_shutdown closes open sockets ---
+
+    use futures_util::StreamExt;
+    use std::time::Duration;
+    use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+    use ultimo::websocket::WebSocketHandler;
+
+    /// Records the close code the handler was told about.
+    struct RecordClose(tokio::sync::mpsc::UnboundedSender<ultimo::websocket::CloseCode>);
+
+    #[async_trait::async_trait]
+    impl WebSocketHandler for RecordClose {
+        type Data = ();
+
+        async fn on_message(&self, _ws: &WebSocket<()>, _msg: Message) {}
+
+        async fn on_close(
+            &self,
+            _ws: &WebSocket<()>,
+            code: ultimo::websocket::CloseCode,
+            _reason: &str,
+        ) {
+            self.0.send(code).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_listen_with_shutdown_sends_going_away() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let (codes_tx, mut codes_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut app = ultimo::Ultimo::new_without_defaults();
+        app.websocket("/ws", RecordClose(codes_tx));
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(async move {
+            app.listen_with_shutdown(&format!("127.0.0.1:{}", port), async {
+                stop_rx.await.ok();
+            })
+            .await
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (mut client, _) =
+            tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/ws", port))
+                .await
+                .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        stop_tx.send(()).unwrap();
+
+        match tokio::time::timeout(Duration::from_secs(2), client.next()).await {
+            Ok(Some(Ok(WsMessage::Close(Some(frame))))) => {
+                assert_eq!(frame.code, CloseCode::Away);
+            }
+            other => panic!("expected a 1001 close frame, got {:?}", other),
+        }
+        assert_eq!(
+            codes_rx.recv().await,
+            Some(ultimo::websocket::CloseCode::GoingAway)
+        );
+        tokio::time::timeout(Duration::from_secs(2), server)
+            .await
+            .expect("listen_with_shutdown did not return")
+            .unwrap()
+            .unwrap();
+    }
+
+    /// Closes the connection when told "bye"; records `on_close` codes.
+    struct CloseOnBye(tokio::sync::mpsc::UnboundedSender<(ultimo::websocket::CloseCode, String)>);
+
+    #[async_trait::async_trait]
+    impl WebSocketHandler for CloseOnBye {
+        type Data = ();
+
+        async fn on_message(&self, ws: &WebSocket<()>, msg: Message) {
+            if let Message::Text(text) = msg {
+                if text == "bye" {
+                    ws.close(ultimo::websocket::CloseCode::PolicyViolation, "bye")
+                        .await
+                        .unwrap();
+                }
+            }
+        }
+
+        async fn on_close(
+            &self,
+            _ws: &WebSocket<()>,
+            code: ultimo::websocket::CloseCode,
+            reason: &str,
+        ) {
+            self.0.send((code, reason.to_string())).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_close_and_peer_close_reach_on_close() {
+        use futures_util::SinkExt;
+        use tokio_tungstenite::tungstenite::protocol::CloseFrame as WsCloseFrame;
+
+        let (closes_tx, mut closes_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut app = ultimo::Ultimo::new_without_defaults();
+        app.websocket("/ws", CloseOnBye(closes_tx));
+        let (listener, addr) = ultimo::Ultimo::bind("127.0.0.1:0").await.unwrap();
+        tokio::spawn(app.listen_on(listener));
+        let url = format!("ws://{}/ws", addr);
+
+        // Server-initiated: the client gets the typed code on the wire.
+        let (mut client, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        client.send(WsMessage::Text("bye".into())).await.unwrap();
+        match tokio::time::timeout(Duration::from_secs(2), client.next()).await {
+            Ok(Some(Ok(WsMessage::Close(Some(frame))))) => {
+                assert_eq!(frame.code, CloseCode::Policy);
+                assert_eq!(frame.reason, "bye");
+            }
+            other => panic!("expected a 1008 close frame, got {:?}", other),
+        }
+        assert_eq!(
+            closes_rx.recv().await,
+            Some((
+                ultimo::websocket::CloseCode::PolicyViolation,
+                "bye".to_string()
+            ))
+        );
+
+        // Client-initiated: on_close gets the peer's code and reason.
+        let (mut client, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        client
+            .close(Some(WsCloseFrame {
+                code: CloseCode::Library(4001),
+                reason: "logout".into(),
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            closes_rx.recv().await,
+            Some((
+                ultimo::websocket::CloseCode::Other(4001),
+                "logout".to_string()
+            ))
+        );
+    }
 }
@@ -0,0 +1,194 @@
+//! Subprotocol-based WebSocket handler dispatch
+//!
+//! One endpoint serves different handlers depending on the negotiated
+//! `Sec-WebSocket-Protocol`.
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message as WsMessage};
+use ultimo::prelude::*;
+use ultimo::response::ResponseBuilder;
+use ultimo::websocket::{
+    Message, WebSocket, WebSocketConfig, WebSocketHandler, WebSocketProtocols,
+};
+
+/// Replies to every text message with `"<prefix>: <text>"`.
+struct PrefixHandler(&'static str);
+
+#[async_trait::async_trait]
+impl WebSocketHandler for PrefixHandler {
+    type Data = ();
+
+    async fn on_message(&self, ws: &WebSocket<Self::Data>, msg: Message) {
+        if let Message::Text(text) = msg {
+            ws.send(format!("{}: {}", self.0, text)).await.ok();
+        }
+    }
+}
+
+/// Replies with `"<protocol> as <user>: <text>"`, the user coming from the guard.
+struct UserHandler(&'static str);
+
+#[async_trait::async_trait]
+impl WebSocketHandler for UserHandler {
+    type Data = String;
+
+    async fn on_message(&self, ws: &WebSocket<Self::Data>, msg: Message) {
+        if let Message::Text(text) = msg {
+            ws.send(format!("{} as {}: {}", self.0, ws.data(), text))
+                .await
+                .ok();
+        }
+    }
+}
+
+async fn serve(app: Ultimo) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+    tokio::spawn(async move {
+        app.listen(&format!("127.0.0.1:{}", port)).await.ok();
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    port
+}
+
+async fn start_server(with_default: bool) -> u16 {
+    let mut protocols = WebSocketProtocols::new()
+        .protocol("chat", PrefixHandler("chat"))
+        .protocol("admin", PrefixHandler("admin"));
+    if with_default {
+        protocols = protocols.default_handler(PrefixHandler("default"));
+    }
+
+    let mut app = Ultimo::new_without_defaults();
+    app.websocket_route("/ws").protocols(protocols);
+    serve(app).await
+}
+
+type Client =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Connect offering `protocols`, returning the socket and the confirmed protocol.
+async fn connect(
+    port: u16,
+    protocols: Option<&str>,
+) -> std::result::Result<(Client, Option<String>), WsError> {
+    let mut request = format!("ws://127.0.0.1:{}/ws", port)
+        .into_client_request()
+        .unwrap();
+    if let Some(protocols) = protocols {
+        request
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", protocols.parse().unwrap());
+    }
+    let (ws, response) = tokio_tungstenite::connect_async(request).await?;
+    let confirmed = response
+        .headers()
+        .get("sec-websocket-protocol")
+        .map(|v| v.to_str().unwrap().to_string());
+    Ok((ws, confirmed))
+}
+
+async fn round_trip(ws: &mut Client, text: &str) -> String {
+    ws.send(WsMessage::Text(text.to_string())).await.unwrap();
+    tokio::time::timeout(tokio::time::Duration::from_secs(1), async {
+        loop {
+            if let Some(Ok(WsMessage::Text(reply))) = ws.next().await {
+                return reply;
+            }
+        }
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn dispatches_by_negotiated_protocol() {
+    let port = start_server(false).await;
+
+    let (mut chat, confirmed) = connect(port, Some("chat")).await.unwrap();
+    assert_eq!(confirmed.as_deref(), Some("chat"));
+    assert_eq!(round_trip(&mut chat, "hi").await, "chat: hi");
+
+    let (mut admin, confirmed) = connect(port, Some("admin")).await.unwrap();
+    assert_eq!(confirmed.as_deref(), Some("admin"));
+    assert_eq!(round_trip(&mut admin, "hi").await, "admin: hi");
+}
+
+#[tokio::test]
+async fn first_supported_client_offer_wins() {
+    let port = start_server(false).await;
+    let (mut ws, confirmed) = connect(port, Some("graphql-ws,admin,chat")).await.unwrap();
+    assert_eq!(confirmed.as_deref(), Some("admin"));
+    assert_eq!(round_trip(&mut ws, "x").await, "admin: x");
+}
+
+#[tokio::test]
+async fn no_offer_uses_default_handler() {
+    let port = start_server(true).await;
+    let (mut ws, confirmed) = connect(port, None).await.unwrap();
+    assert_eq!(confirmed, None);
+    assert_eq!(round_trip(&mut ws, "x").await, "default: x");
+}
+
+#[tokio::test]
+async fn no_offer_without_default_fails_handshake() {
+    let port = start_server(false).await;
+    match connect(port, None).await {
+        Err(WsError::Http(response)) => assert_eq!(response.status(), 400),
+        other => panic!(
+            "expected 400 handshake failure, got {:?}",
+            other.map(|_| ())
+        ),
+    }
+}
+
+#[tokio::test]
+async fn unsupported_offer_fails_handshake() {
+    let port = start_server(true).await;
+    match connect(port, Some("graphql-ws")).await {
+        Err(WsError::Http(response)) => assert_eq!(response.status(), 400),
+        other => panic!(
+            "expected 400 handshake failure, got {:?}",
+            other.map(|_| ())
+        ),
+    }
+}
+
+#[tokio::test]
+#[allow(clippy::result_large_err)]
+async fn protocols_share_the_guard_and_config() {
+    let mut app = Ultimo::new_without_defaults();
+    app.websocket_route("/ws")
+        .guard(|req| match req.uri().query() {
+            Some(query) => Ok(query.trim_start_matches("user=").to_string()),
+            None => Err(ResponseBuilder::new().status(401).build().unwrap()),
+        })
+        .config(WebSocketConfig {
+            subprotocols: vec!["ignored".to_string()],
+            ..Default::default()
+        })
+        .protocols(
+            WebSocketProtocols::new()
+                .protocol("chat", UserHandler("chat"))
+                .protocol("admin", UserHandler("admin")),
+        );
+    let port = serve(app).await;
+
+    let mut request = format!("ws://127.0.0.1:{}/ws?user=ada", port)
+        .into_client_request()
+        .unwrap();
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", "ignored,admin".parse().unwrap());
+    let (mut ws, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+    assert_eq!(response.headers()["sec-websocket-protocol"], "admin");
+    assert_eq!(round_trip(&mut ws, "hi").await, "admin as ada: hi");
+
+    match connect(port, Some("chat")).await {
+        Err(WsError::Http(response)) => assert_eq!(response.status(), 401),
+        other => panic!("expected the guard's 401, got {:?}", other.map(|_| ())),
+    }
+}